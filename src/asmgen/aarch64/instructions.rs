@@ -1,4 +1,9 @@
-use crate::{common::Width, ir::nodes};
+use std::fmt;
+
+use crate::{
+    common::{Signedness, SourceLocation, Width},
+    ir::nodes,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FunctionArgumentRegister {
@@ -49,10 +54,11 @@ pub enum RegisterKind {
     FramePointer,                               // x29
     LinkRegister,                               //x30
     StackPointer,                               // sp
+    Vector(usize),                              // v0 - v31, as s or d
 }
 
 impl FunctionArgumentRegister {
-    fn to_gp_num(&self) -> usize {
+    fn to_gp_num(self) -> usize {
         match self {
             Self::X0 => 0,
             Self::X1 => 1,
@@ -66,13 +72,29 @@ impl FunctionArgumentRegister {
     }
 }
 
+impl CorruptibleRegister {
+    fn to_gp_num(self) -> usize {
+        match self {
+            Self::X9 => 9,
+            Self::X10 => 10,
+            Self::X11 => 11,
+            Self::X12 => 12,
+            Self::X13 => 13,
+            Self::X14 => 14,
+            Self::X15 => 15,
+        }
+    }
+}
+
 impl RegisterKind {
-    fn to_gp_num(&self) -> Option<usize> {
+    fn to_gp_num(self) -> Option<usize> {
         match self {
             Self::FunctionArgument(fa) => Some(fa.to_gp_num()),
+            Self::Corruptuble(cr) => Some(cr.to_gp_num()),
             Self::StackPointer => None,
             Self::FramePointer => Some(29),
             Self::LinkRegister => Some(30),
+            Self::Vector(number) => Some(number),
             _ => todo!(),
         }
     }
@@ -96,6 +118,27 @@ impl Register {
         Register { kind: self.kind, width }
     }
 
+    /// The widest view of the register, the one spills are made with
+    pub fn widest(&self) -> Self {
+        if self.is_floating() {
+            self.align(Width::Double)
+        } else {
+            self.align(Width::Long)
+        }
+    }
+
+    pub fn is_floating(&self) -> bool {
+        matches!(self.kind, RegisterKind::Vector(_))
+    }
+
+    pub fn v(number: usize, width: Width) -> Self {
+        assert!(number < 32 && width.is_floating());
+        Register {
+            kind: RegisterKind::Vector(number),
+            width,
+        }
+    }
+
     pub fn x0(width: Width) -> Self {
         Register {
             kind: RegisterKind::FunctionArgument(FunctionArgumentRegister::X0),
@@ -210,9 +253,13 @@ impl Register {
     }
 }
 
+#[derive(Clone)]
 pub enum AddressingMode {
     BaseRegister(Register),
     Offset((Register, i64)),
+    PageOffset((Register, Symbol)),
+    // Global offset table entry of a symbol, which may live in another image
+    GotPageOffset((Register, Symbol)),
     PreIndexed((Register, i64)),
     PostIndexed((Register, i64)),
 }
@@ -221,6 +268,9 @@ impl AddressingMode {
     pub fn stack_offset(off: i64) -> AddressingMode {
         AddressingMode::Offset((Register::stack_pointer(), off))
     }
+    pub fn frame_offset(off: i64) -> AddressingMode {
+        AddressingMode::Offset((Register::frame_pointer(), off))
+    }
     pub fn pre_indexed(off: i64) -> AddressingMode {
         AddressingMode::PreIndexed((Register::stack_pointer(), off))
     }
@@ -235,6 +285,7 @@ pub enum RValue {
     SymbolOffset(Symbol),
 }
 
+#[derive(Clone)]
 pub struct Symbol(pub String);
 pub struct Label(pub String);
 
@@ -250,11 +301,13 @@ impl From<(nodes::Label, &str)> for Label {
 pub enum CondBranch {
     Equal,
     NotEqual,
+    SignedGreaterEqual,
+    SignedGreaterThan,
 }
 pub enum Branch {
     Unconditional(Label),
-    BranchLink(Label),
-    BranchLinkRegister(Register),
+    Link(Label),
+    LinkRegister(Register),
     Return,
     Cond((CondBranch, Label)),
 }
@@ -268,16 +321,24 @@ impl Branch {
         Branch::Cond((CondBranch::NotEqual, label.into()))
     }
 
+    pub fn cond_ge(label: impl Into<Label>) -> Branch {
+        Branch::Cond((CondBranch::SignedGreaterEqual, label.into()))
+    }
+
+    pub fn cond_gt(label: impl Into<Label>) -> Branch {
+        Branch::Cond((CondBranch::SignedGreaterThan, label.into()))
+    }
+
     pub fn uncond(label: impl Into<Label>) -> Branch {
         Branch::Unconditional(label.into())
     }
 
     pub fn branch_link(label: impl Into<Label>) -> Branch {
-        Branch::BranchLink(label.into())
+        Branch::Link(label.into())
     }
 
     pub fn branch_link_register(reg: Register) -> Branch {
-        Branch::BranchLinkRegister(reg)
+        Branch::LinkRegister(reg)
     }
 }
 
@@ -285,19 +346,55 @@ pub enum ArithOp {
     Add,
     Sub,
     Mul,
-    Div,
+    SDiv,
+    UDiv,
+    Lsl,
+    Asr,
+    Lsr,
+    And,
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
 }
 
 impl ArithOp {
     pub fn try_from_nodes_op(op: nodes::Op) -> Self {
         match op {
-            nodes::Op::Div => ArithOp::Div,
+            nodes::Op::Div => ArithOp::SDiv,
+            nodes::Op::UDiv => ArithOp::UDiv,
             nodes::Op::Plus => ArithOp::Add,
             nodes::Op::Mul => ArithOp::Mul,
             nodes::Op::Minus => ArithOp::Sub,
+            nodes::Op::ShiftLeft => ArithOp::Lsl,
+            nodes::Op::ShiftRight => ArithOp::Asr,
+            nodes::Op::UShiftRight => ArithOp::Lsr,
             _ => todo!(),
         }
     }
+
+    pub fn try_from_float_op(op: nodes::Op) -> Self {
+        match op {
+            nodes::Op::Div => ArithOp::FDiv,
+            nodes::Op::Plus => ArithOp::FAdd,
+            nodes::Op::Mul => ArithOp::FMul,
+            nodes::Op::Minus => ArithOp::FSub,
+            _ => panic!("{:?} is not a floating operation", op),
+        }
+    }
+
+    /// Multiplication and division have no immediate form on aarch64,
+    /// add and sub only encode 12 bit unsigned immediates
+    pub fn takes_immediate(&self, immediate: i64) -> bool {
+        match self {
+            Self::Mul | Self::SDiv | Self::UDiv => false,
+            Self::Add | Self::Sub => (0..4096).contains(&immediate),
+            Self::Lsl | Self::Asr | Self::Lsr => true,
+            // Only masks of the low bits are encoded here
+            Self::And => immediate > 0 && immediate & (immediate + 1) == 0,
+            Self::FAdd | Self::FSub | Self::FMul | Self::FDiv => false,
+        }
+    }
 }
 
 pub struct Arith {
@@ -312,29 +409,72 @@ pub enum ConditionalCode {
     Ne,
     SignedLessThan,
     SignedGreaterThan,
+    SignedLessEqual,
+    SignedGreaterEqual,
+    UnsignedLower,
+    UnsignedHigher,
+    UnsignedLowerSame,
+    UnsignedHigherSame,
+    // lt and le are also true for unordered floating comparisons
+    FloatLessThan,
+    FloatLessEqual,
 }
 
 impl ConditionalCode {
     pub fn try_from_nodes_op(op: nodes::Op) -> Self {
         match op {
             nodes::Op::Eq => ConditionalCode::Eq,
+            nodes::Op::Ne => ConditionalCode::Ne,
             nodes::Op::Lt => ConditionalCode::SignedLessThan,
-            nodes::Op::Gt => ConditionalCode::SignedGreaterThan, // Todo: Add unsigned
+            nodes::Op::Gt => ConditionalCode::SignedGreaterThan,
+            nodes::Op::Le => ConditionalCode::SignedLessEqual,
+            nodes::Op::Ge => ConditionalCode::SignedGreaterEqual,
+            nodes::Op::ULt => ConditionalCode::UnsignedLower,
+            nodes::Op::UGt => ConditionalCode::UnsignedHigher,
+            nodes::Op::ULe => ConditionalCode::UnsignedLowerSame,
+            nodes::Op::UGe => ConditionalCode::UnsignedHigherSame,
             _ => todo!(),
         }
     }
+
+    pub fn try_from_float_op(op: nodes::Op) -> Self {
+        match op {
+            nodes::Op::Lt => ConditionalCode::FloatLessThan,
+            nodes::Op::Le => ConditionalCode::FloatLessEqual,
+            _ => Self::try_from_nodes_op(op),
+        }
+    }
 }
 
 pub enum Section {
     Text,
     TextCstring,
+    TextConst,
+    /// Read only once the dynamic linker has filled in the addresses
+    DataConst,
+    Data,
 }
 
 pub enum Directive {
     Section(Section),
     Extern(String),
     Global(String),
-    AsciiCString(String),
+    /// Bytes already escaped for the assembler, without a terminating zero
+    Ascii(String),
+    Align(u8),
+    Byte(u8),
+    Short(u16),
+    Long(u32),
+    Quad(u64),
+    /// Address of a symbol, named as in the source, plus a byte offset
+    QuadSymbol(String, i64),
+    Zero(usize),
+    /// Zeroed memory of `size` bytes in `__DATA,__bss`, taking no space in the object file
+    ZeroFill { name: String, size: usize, align: u8 },
+    /// The source file, already escaped, that `Loc` lines refer to
+    File(String),
+    /// Source position of the instructions that follow, for the line table
+    Loc(SourceLocation),
 }
 
 pub enum Instruction {
@@ -358,6 +498,13 @@ pub enum Instruction {
         dest: Register,
         operand: RValue,
     },
+    // movz / movk of a 16 bit chunk
+    MovWide {
+        keep: bool,
+        dest: Register,
+        immediate: u16,
+        shift: u8,
+    },
 
     Cmp {
         left: Register,
@@ -370,6 +517,8 @@ pub enum Instruction {
 
     Load {
         width: Width,
+        // How the loaded value is extended to the destination register
+        signedness: Signedness,
         dest: Register,
         operand: AddressingMode,
     },
@@ -380,28 +529,63 @@ pub enum Instruction {
         operand: AddressingMode,
     },
 
+    // sxtb/sxth/sxtw, uxtb/uxth and a w register mov for words
+    Extend {
+        signedness: Signedness,
+        from: Width,
+        dest: Register,
+        source: Register,
+    },
+
+    // scvtf/ucvtf, fcvtzs/fcvtzu and fcvt, by register classes
+    Convert {
+        signedness: Signedness,
+        dest: Register,
+        source: Register,
+    },
+
     Branch(Branch),
     Arith(Arith),
+    // dest = minuend - left * right, what is left over by a division
+    Msub {
+        dest: Register,
+        left: Register,
+        right: Register,
+        minuend: Register,
+    },
     AdressPage {
         dest: Register,
         symbol: Symbol,
     },
+    AdressGotPage {
+        dest: Register,
+        symbol: Symbol,
+    },
 }
 
 impl ArithOp {
     pub fn to_instr_string(&self) -> &str {
         match self {
             Self::Add => "add",
-            Self::Div => "div",
+            Self::SDiv => "sdiv",
+            Self::UDiv => "udiv",
             Self::Mul => "mul",
             Self::Sub => "sub",
+            Self::Lsl => "lsl",
+            Self::Asr => "asr",
+            Self::Lsr => "lsr",
+            Self::And => "and",
+            Self::FAdd => "fadd",
+            Self::FSub => "fsub",
+            Self::FMul => "fmul",
+            Self::FDiv => "fdiv",
         }
     }
 }
 
-impl Register {
-    pub fn to_string(&self) -> String {
-        match self.kind {
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = match self.kind {
             RegisterKind::StackPointer => "sp".into(),
             _ => {
                 let prefix = match self.width {
@@ -409,32 +593,39 @@ impl Register {
                     Width::Short => "w",
                     Width::Word => "w",
                     Width::Long => "x",
+                    Width::Single => "s",
+                    Width::Double => "d",
                 };
                 let num = self.kind.to_gp_num().unwrap();
                 format!("{}{}", prefix, num)
             }
-        }
+        };
+        f.write_str(&text)
     }
 }
 
-impl RValue {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for RValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = match self {
             Self::Immediate(c) => c.to_string(),
             Self::Register(r) => r.to_string(),
             Self::SymbolOffset(symb) => format!("{}@PAGEOFF", symb.0),
-        }
+        };
+        f.write_str(&text)
     }
 }
 
-impl AddressingMode {
-    fn to_string(&self) -> String {
-        match self {
-            Self::BaseRegister(br) => format!("[{}]", br.to_string()),
-            Self::Offset((br, off)) => format!("[{}, {}]", br.to_string(), off),
-            Self::PreIndexed((br, off)) => format!("[{}, {}]!", br.to_string(), off),
-            Self::PostIndexed((br, off)) => format!("[{}], {}", br.to_string(), off),
-        }
+impl fmt::Display for AddressingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = match self {
+            Self::BaseRegister(br) => format!("[{}]", br),
+            Self::Offset((br, off)) => format!("[{}, {}]", br, off),
+            Self::PageOffset((br, symb)) => format!("[{}, {}@PAGEOFF]", br, symb.0),
+            Self::GotPageOffset((br, symb)) => format!("[{}, {}@GOTPAGEOFF]", br, symb.0),
+            Self::PreIndexed((br, off)) => format!("[{}, {}]!", br, off),
+            Self::PostIndexed((br, off)) => format!("[{}], {}", br, off),
+        };
+        f.write_str(&text)
     }
 }
 
@@ -445,13 +636,21 @@ impl ConditionalCode {
             Self::Ne => "ne",
             Self::SignedGreaterThan => "gt",
             Self::SignedLessThan => "lt",
+            Self::SignedGreaterEqual => "ge",
+            Self::SignedLessEqual => "le",
+            Self::UnsignedHigher => "hi",
+            Self::UnsignedLower => "lo",
+            Self::UnsignedHigherSame => "hs",
+            Self::UnsignedLowerSame => "ls",
+            Self::FloatLessThan => "mi",
+            Self::FloatLessEqual => "ls",
         }
     }
 }
 
-impl Branch {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for Branch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = match self {
             Self::Cond((CondBranch::Equal, label)) => {
                 format!("beq {}", label.0)
             }
@@ -459,55 +658,108 @@ impl Branch {
             Self::Cond((CondBranch::NotEqual, label)) => {
                 format!("bne {}", label.0)
             }
-            Self::BranchLink(label) => {
+            Self::Cond((CondBranch::SignedGreaterEqual, label)) => {
+                format!("bge {}", label.0)
+            }
+            Self::Cond((CondBranch::SignedGreaterThan, label)) => {
+                format!("bgt {}", label.0)
+            }
+            Self::Link(label) => {
                 format!("bl {}", label.0)
             }
-            Self::BranchLinkRegister(reg) => {
-                format!("bl {}", reg.to_string())
+            Self::LinkRegister(reg) => {
+                format!("blr {}", reg)
             }
             Self::Return => "ret".into(),
             Self::Unconditional(label) => {
                 format!("b {}", label.0)
             }
-        }
+        };
+        f.write_str(&text)
     }
 }
 
-impl Directive {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = match self {
             Self::Extern(symbol_name) => format!(".extern _{}", symbol_name),
             Self::Global(symbol_name) => format!(".globl _{}", symbol_name),
-            Self::AsciiCString(symbol_name) => format!(".asciz \"{}\"", symbol_name),
+            Self::Ascii(bytes) => format!(".ascii \"{}\"", bytes),
             Self::Section(Section::Text) => ".section __TEXT,__text".into(),
             Self::Section(Section::TextCstring) => ".section __TEXT,__cstring".into(),
-        }
+            Self::Section(Section::TextConst) => ".section __TEXT,__const".into(),
+            Self::Section(Section::DataConst) => ".section __DATA,__const".into(),
+            Self::Section(Section::Data) => ".section __DATA,__data".into(),
+            Self::Align(power) => format!(".p2align {}", power),
+            Self::Byte(value) => format!(".byte {:#x}", value),
+            Self::Short(value) => format!(".short {:#x}", value),
+            Self::Long(value) => format!(".long {:#x}", value),
+            Self::Quad(value) => format!(".quad {:#x}", value),
+            Self::QuadSymbol(symbol_name, 0) => format!(".quad _{}", symbol_name),
+            Self::QuadSymbol(symbol_name, offset) => format!(".quad _{}{:+}", symbol_name, offset),
+            Self::Zero(size) => format!(".zero {}", size),
+            Self::ZeroFill { name, size, align } => {
+                format!(".zerofill __DATA,__bss,_{},{},{}", name, size, align)
+            }
+            Self::File(name) => format!(".file 1 \"{}\"", name),
+            Self::Loc(location) => format!(".loc 1 {} {}", location.line, location.column),
+        };
+        f.write_str(&text)
     }
 }
 
-impl Instruction {
-    pub fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = match self {
             Self::Mov { dest, operand } => {
-                format!("mov {}, {}", dest.to_string(), operand.to_string())
+                let instruction_name = match operand {
+                    RValue::Register(source) if source.is_floating() || dest.is_floating() => {
+                        "fmov"
+                    }
+                    _ => "mov",
+                };
+                format!(
+                    "{} {}, {}",
+                    instruction_name,
+                    dest,
+                    operand
+                )
+            }
+            Self::MovWide {
+                keep,
+                dest,
+                immediate,
+                shift,
+            } => {
+                format!(
+                    "{} {}, {}, lsl {}",
+                    if *keep { "movk" } else { "movz" },
+                    dest,
+                    immediate,
+                    shift
+                )
             }
             Self::Load {
                 width,
+                signedness,
                 dest,
                 operand,
             } => {
-                let instruction_name = match width {
-                    Width::Byte => "ldrb",
-                    Width::Short => "ldrh",
-                    Width::Word => "ldr",
-                    Width::Long => "ldr",
+                let instruction_name = match (width, signedness) {
+                    (Width::Byte, Signedness::Signed) => "ldrsb",
+                    (Width::Byte, Signedness::Unsigned) => "ldrb",
+                    (Width::Short, Signedness::Signed) => "ldrsh",
+                    (Width::Short, Signedness::Unsigned) => "ldrh",
+                    (Width::Word, Signedness::Signed) if dest.width == Width::Long => "ldrsw",
+                    (Width::Word, _) => "ldr",
+                    (Width::Long | Width::Single | Width::Double, _) => "ldr",
                 };
 
                 format!(
                     "{} {}, {}",
                     instruction_name,
-                    dest.to_string(),
-                    operand.to_string()
+                    dest,
+                    operand
                 )
             }
 
@@ -520,61 +772,121 @@ impl Instruction {
                     Width::Byte => "strb",
                     Width::Short => "strh",
                     Width::Word => "str",
-                    Width::Long => "str",
+                    Width::Long | Width::Single | Width::Double => "str",
                 };
 
                 format!(
                     "{} {}, {}",
                     instruction_name,
-                    dest.to_string(),
-                    operand.to_string()
+                    dest,
+                    operand
                 )
             }
 
-            Self::Cmp { left, right } => {
-                format!("cmp {}, {}", left.to_string(), right.to_string())
+            Self::Extend {
+                signedness,
+                from,
+                dest,
+                source,
+            } => {
+                let source = source.align(Width::Word);
+                // Zero extensions write a w register, which clears the upper half
+                let (instruction_name, dest) = match (signedness, from) {
+                    (Signedness::Signed, Width::Byte) => ("sxtb", *dest),
+                    (Signedness::Signed, Width::Short) => ("sxth", *dest),
+                    (Signedness::Signed, Width::Word) => ("sxtw", dest.align(Width::Long)),
+                    (Signedness::Unsigned, Width::Byte) => ("uxtb", dest.align(Width::Word)),
+                    (Signedness::Unsigned, Width::Short) => ("uxth", dest.align(Width::Word)),
+                    (Signedness::Unsigned, Width::Word) => ("mov", dest.align(Width::Word)),
+                    (_, Width::Long | Width::Single | Width::Double) => {
+                        panic!("Cannot extend a {:?}", from)
+                    }
+                };
+                format!(
+                    "{} {}, {}",
+                    instruction_name,
+                    dest,
+                    source
+                )
             }
 
+            Self::Convert {
+                signedness,
+                dest,
+                source,
+            } => {
+                let instruction_name = match (source.is_floating(), dest.is_floating(), signedness) {
+                    (true, true, _) => "fcvt",
+                    (false, true, Signedness::Signed) => "scvtf",
+                    (false, true, Signedness::Unsigned) => "ucvtf",
+                    (true, false, Signedness::Signed) => "fcvtzs",
+                    (true, false, Signedness::Unsigned) => "fcvtzu",
+                    (false, false, _) => panic!("Not a floating conversion"),
+                };
+                format!(
+                    "{} {}, {}",
+                    instruction_name,
+                    dest,
+                    source
+                )
+            }
+
+            Self::Cmp { left, right } => match right {
+                // The only immediate fcmp takes
+                RValue::Immediate(0) if left.is_floating() => {
+                    format!("fcmp {}, #0.0", left)
+                }
+                _ => {
+                    let instruction_name = if left.is_floating() { "fcmp" } else { "cmp" };
+                    format!("{} {}, {}", instruction_name, left, right)
+                }
+            },
+
             Self::Arith(ar) => {
                 let arith_instr = ar.op.to_instr_string();
                 format!(
                     "{} {}, {}, {}",
                     arith_instr,
-                    ar.dest.to_string(),
-                    ar.left.to_string(),
-                    ar.right.to_string()
+                    ar.dest,
+                    ar.left,
+                    ar.right
                 )
             }
 
+            Self::Msub {
+                dest,
+                left,
+                right,
+                minuend,
+            } => format!(
+                "msub {}, {}, {}, {}",
+                dest,
+                left,
+                right,
+                minuend
+            ),
             Self::CondSet { dest, cond } => {
-                format!("cset {}, {}", dest.to_string(), cond.to_string())
+                format!("cset {}, {}", dest, cond.to_string())
             }
             Self::Label(lab) => {
                 format!("{}:", lab)
             }
             Self::Branch(b) => b.to_string(),
             Self::StorePair { r1, r2, addressing } => {
-                format!(
-                    "stp {}, {}, {}",
-                    r1.to_string(),
-                    r2.to_string(),
-                    addressing.to_string()
-                )
+                format!("stp {}, {}, {}", r1, r2, addressing)
             }
             Self::LoadPair { r1, r2, addressing } => {
-                format!(
-                    "ldp {}, {}, {}",
-                    r1.to_string(),
-                    r2.to_string(),
-                    addressing.to_string()
-                )
+                format!("ldp {}, {}, {}", r1, r2, addressing)
             }
             Self::AdressPage { dest, symbol } => {
-                format!("adrp {}, {}@PAGE", dest.to_string(), symbol.0)
+                format!("adrp {}, {}@PAGE", dest, symbol.0)
+            }
+            Self::AdressGotPage { dest, symbol } => {
+                format!("adrp {}, {}@GOTPAGE", dest, symbol.0)
             }
             Self::Directive(dir) => dir.to_string(),
             Self::Comment(c) => format!("// {}", c),
-            _ => todo!(),
-        }
+        };
+        f.write_str(&text)
     }
 }
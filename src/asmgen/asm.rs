@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use crate::asmgen::aarch64::instructions;
@@ -10,9 +10,13 @@ use crate::asmgen::regalloc;
 use crate::asmgen::regalloc::LinearScanRegisterAlloc;
 use crate::asmgen::regalloc::Location;
 use crate::asmgen::regalloc::analyze_lifetimes;
-use crate::asmgen::lookup_table::{SymbolAddress, SymbolLookup};
+use crate::asmgen::lookup_table::SymbolLookup;
+use crate::common::ARGUMENT_REGISTERS;
+use crate::common::Abi;
+use crate::common::Signedness;
 use crate::common::StorageClass;
 use crate::common::Width;
+use crate::ir::Cfg;
 use crate::ir::IrTextRepr;
 use crate::ir::nodes;
 use crate::ir::nodes::Address;
@@ -36,6 +40,7 @@ fn load_if_needed(instructions: &mut Vec<Instruction>, loc: regalloc::Location,
         regalloc::Location::Spill(stack_off) => {
             instructions.push(Instruction::Load {
                     width: spill_load_register.width,
+                    signedness: Signedness::Unsigned,
                     dest: spill_load_register,
                     operand: instructions::AddressingMode::stack_offset(stack_off + dynamic_offset),
             });
@@ -44,6 +49,159 @@ fn load_if_needed(instructions: &mut Vec<Instruction>, loc: regalloc::Location,
     }
 }
 
+/// `mov` only takes immediates that fit a single instruction,
+/// anything wider is built 16 bits at a time with movz/movk.
+fn mov_immediate(instructions: &mut Vec<Instruction>, dest: Register, value: i64) {
+    if (-0x10000..0x10000).contains(&value) {
+        instructions.push(Instruction::Mov {
+            dest,
+            operand: RValue::Immediate(value),
+        });
+        return;
+    }
+
+    let bits = match dest.width {
+        Width::Long => value as u64,
+        _ => value as u32 as u64,
+    };
+    let mut keep = false;
+    for shift in (0..64).step_by(16) {
+        let chunk = ((bits >> shift) & 0xFFFF) as u16;
+        if chunk == 0 && (keep || bits >> shift != 0) {
+            continue;
+        }
+        instructions.push(Instruction::MovWide {
+            keep,
+            dest,
+            immediate: chunk,
+            shift: shift as u8,
+        });
+        keep = true;
+    }
+}
+
+/// Builds a constant operand in a register. Numbers are moved in, floating
+/// constants come from the constant pool and symbols are addressed
+/// directly for data of the unit or through the GOT otherwise.
+fn load_constant(instructions: &mut Vec<Instruction>, lookup: &SymbolLookup, dest: Register, constant: &nodes::AddressConstant) {
+    match constant {
+        nodes::AddressConstant::Numeric(nc) => mov_immediate(instructions, dest, *nc),
+        nodes::AddressConstant::Floating(bits) => {
+            let page_register = scratch_registers(Width::Long)[1];
+            let fl_label_str = format!("fl{}", lookup.floating_literal(*bits, dest.width));
+
+            instructions.push(Instruction::AdressPage {
+                dest: page_register,
+                symbol: Symbol(fl_label_str.clone()),
+            });
+            instructions.push(Instruction::Load {
+                width: dest.width,
+                signedness: Signedness::Unsigned,
+                dest,
+                operand: instructions::AddressingMode::PageOffset((page_register, Symbol(fl_label_str))),
+            });
+        }
+        nodes::AddressConstant::Symbol(name) => {
+            let dest = dest.widest();
+            let symbol_name = format!("_{}", name);
+
+            // Data of the unit is at a fixed distance from the code, anything
+            // else is reached through the GOT as extern symbols may live in a dylib
+            if lookup.is_data(name) {
+                instructions.push(Instruction::AdressPage {
+                    dest,
+                    symbol: Symbol(symbol_name.clone()),
+                });
+                instructions.push(Instruction::Arith(instructions::Arith {
+                    op: instructions::ArithOp::Add,
+                    dest,
+                    left: dest,
+                    right: instructions::RValue::SymbolOffset(Symbol(symbol_name)),
+                }));
+            } else {
+                instructions.push(Instruction::AdressGotPage {
+                    dest,
+                    symbol: Symbol(symbol_name.clone()),
+                });
+                instructions.push(Instruction::Load {
+                    width: Width::Long,
+                    signedness: Signedness::Unsigned,
+                    dest,
+                    operand: instructions::AddressingMode::GotPageOffset((dest, Symbol(symbol_name))),
+                });
+            }
+        }
+    }
+}
+
+/// Scratch registers of the register class a width lives in, outside of
+/// the argument registers the allocator hands out
+fn scratch_registers(width: Width) -> [Register; 3] {
+    if width.is_floating() {
+        [Register::v(16, width), Register::v(17, width), Register::v(18, width)]
+    } else {
+        [Register::x10(width), Register::x11(width), Register::x12(width)]
+    }
+}
+
+/// Integer and floating point arguments are numbered separately,
+/// each class starting from its first register
+fn argument_register(number: usize, width: Width) -> Register {
+    assert!(number < ARGUMENT_REGISTERS, "Further arguments are passed on the stack");
+    if width.is_floating() {
+        return Register::v(number, width);
+    }
+    match number {
+        0 => Register::x0(width),
+        1 => Register::x1(width),
+        2 => Register::x2(width),
+        3 => Register::x3(width),
+        4 => Register::x4(width),
+        5 => Register::x5(width),
+        6 => Register::x6(width),
+        _ => Register::x7(width),
+    }
+}
+
+/// Where an argument is passed
+#[derive(Debug, Clone, Copy)]
+enum ArgumentLocation {
+    Register(Register),
+    /// Offset from the stack pointer at the call, which the callee finds
+    /// right above its frame record
+    Stack(i64),
+}
+
+/// Where the arguments of a call go, given their width and whether they
+/// are variadic, and the bytes the ones on the stack take. The first
+/// eight of each class take registers, the rest go on the stack in order:
+/// in 8 byte slots under AAPCS64, at their natural alignment on Apple's
+/// arm64, which also puts every variadic argument in an 8 byte slot.
+fn argument_locations(arguments: impl IntoIterator<Item = (Width, bool)>, abi: Abi) -> (Vec<ArgumentLocation>, usize) {
+    let (mut general_count, mut floating_count) = (0, 0);
+    let mut stack_size: usize = 0;
+    let locations = arguments
+        .into_iter()
+        .map(|(width, is_variadic)| {
+            let counter = if width.is_floating() { &mut floating_count } else { &mut general_count };
+            let apple_variadic = is_variadic && abi == Abi::Apple;
+            if *counter < ARGUMENT_REGISTERS && !apple_variadic {
+                *counter += 1;
+                return ArgumentLocation::Register(argument_register(*counter - 1, width));
+            }
+            let slot_size = if abi == Abi::Apple && !is_variadic { width.to_bytes() } else { 8 };
+            let offset = stack_size.next_multiple_of(slot_size);
+            stack_size = offset + slot_size;
+            ArgumentLocation::Stack(offset as i64)
+        })
+        .collect();
+    (locations, stack_size)
+}
+
+fn return_register(width: Width) -> Register {
+    argument_register(0, width)
+}
+
 fn empty_register(loc: regalloc::Location, spill_load_register: Register) -> Register {
     match loc {
         regalloc::Location::Reg(r) => r.align(spill_load_register.width),
@@ -73,6 +231,106 @@ fn store_if_needed(instructions: &mut Vec<Instruction>, loc: regalloc::Location,
     }
 }
 
+/// Stack slots of the allocas, right below the frame record. Gives the
+/// offset of each from the frame pointer and the bytes they take together.
+fn frame_slots(body: &[nodes::Ssa]) -> (HashMap<Address, i64>, usize) {
+    let mut slots = HashMap::new();
+    let mut size = 0;
+    for ssa in body {
+        if let nodes::Ssa::Alloca { dest, size: slot_size, align } = ssa {
+            size = (size + slot_size).next_multiple_of(*align);
+            slots.insert(dest.clone(), -(size as i64));
+        }
+    }
+    (slots, size.next_multiple_of(16))
+}
+
+/// Slots only ever used as the address of a load or store. These are
+/// reached through the frame pointer and never need a register.
+fn direct_slots(body: &[nodes::Ssa], slots: &HashMap<Address, i64>) -> HashSet<Address> {
+    let mut escaping = HashSet::new();
+    for ssa in body {
+        let through_memory = match ssa {
+            nodes::Ssa::Load { address, .. }
+            | nodes::Ssa::VaStart { list: address }
+            | nodes::Ssa::VaArg { list: address, .. } => Some(address),
+            nodes::Ssa::Store { address, value, .. } if address != value => Some(address),
+            _ => None,
+        };
+        for used in ssa.used_addresses() {
+            if Some(used) != through_memory && slots.contains_key(used) {
+                escaping.insert(used.clone());
+            }
+        }
+    }
+    slots.keys().filter(|slot| !escaping.contains(*slot)).cloned().collect()
+}
+
+/// Loads whose only use is the sign extension right after them. These
+/// become a single ldrsb, ldrsh or ldrsw into the extension's destination.
+fn sign_extending_loads(body: &[nodes::Ssa]) -> HashSet<usize> {
+    let mut uses: HashMap<&Address, usize> = HashMap::new();
+    for ssa in body {
+        for used in ssa.used_addresses() {
+            *uses.entry(used).or_default() += 1;
+        }
+    }
+    body.windows(2)
+        .enumerate()
+        .filter(|(_, pair)| match pair {
+            [
+                nodes::Ssa::Load { dest, width, .. },
+                nodes::Ssa::Extend { signed: true, source, from, .. },
+            ] => source == dest && from == width && uses.get(dest) == Some(&1),
+            _ => false,
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// dest = frame pointer + offset, for a slot below the frame record
+fn frame_address(instructions: &mut Vec<Instruction>, dest: Register, offset: i64) {
+    let right = if instructions::ArithOp::Sub.takes_immediate(-offset) {
+        RValue::Immediate(-offset)
+    } else {
+        mov_immediate(instructions, dest, -offset);
+        dest.rvalue()
+    };
+    instructions.push(Instruction::Arith(instructions::Arith {
+        op: instructions::ArithOp::Sub,
+        dest,
+        left: Register::frame_pointer(),
+        right,
+    }));
+}
+
+/// Where a load or store goes. Slots are addressed from the frame pointer
+/// directly as long as an unscaled offset reaches them, 256 bytes down.
+fn memory_operand(
+    instructions: &mut Vec<Instruction>,
+    allocator: &LinearScanRegisterAlloc,
+    slots: &HashMap<Address, i64>,
+    address: &Address,
+    idx: usize,
+) -> instructions::AddressingMode {
+    let base = scratch_registers(Width::Long)[1];
+    match (slots.get(address), address) {
+        (Some(&offset), _) if offset >= -256 => instructions::AddressingMode::frame_offset(offset),
+        (Some(&offset), _) => {
+            frame_address(instructions, base, offset);
+            base.addressing_mode()
+        }
+        (None, Address::Constant(nodes::AddressConstant::Numeric(nc))) => {
+            mov_immediate(instructions, base, *nc);
+            base.addressing_mode()
+        }
+        (None, address) => {
+            let address_loc = allocator.location_of(address, idx).unwrap();
+            load_if_needed(instructions, address_loc, base, 0).addressing_mode()
+        }
+    }
+}
+
 fn alloc_stack(instructions: &mut Vec<Instruction>, bytes: usize) {
     instructions.push(instructions::Instruction::Arith(instructions::Arith {
         op: instructions::ArithOp::Sub,
@@ -101,10 +359,11 @@ fn alloc_stack_spills(instructions: &mut Vec<Instruction>, regs: &[Register]) ->
     }));
 
     for (idx, reg) in regs.iter().enumerate() {
-        instructions.push(Instruction::Comment(format!("Spilling {} which is in use", reg.to_string())));
+        instructions.push(Instruction::Comment(format!("Spilling {} which is in use", reg)));
+        let reg = reg.widest();
         instructions.push(Instruction::Store {
-            width: Width::Long,
-            source: reg.align(Width::Long),
+            width: reg.width,
+            source: reg,
             operand: instructions::AddressingMode::stack_offset(8 * idx as i64),
         });
     }
@@ -116,10 +375,12 @@ fn alloc_stack_spills(instructions: &mut Vec<Instruction>, regs: &[Register]) ->
 fn pop_stack_spills(instructions: &mut Vec<Instruction>, regs: &[Register]) {
     let stack_space = (regs.len() * 8).next_multiple_of(16);
     for (idx, reg) in regs.iter().enumerate() {
-        instructions.push(Instruction::Comment(format!("Popping {} which was in use", reg.to_string())));
+        instructions.push(Instruction::Comment(format!("Popping {} which was in use", reg)));
+        let reg = reg.widest();
         instructions.push(Instruction::Load {
-            width: Width::Long,
-            dest: reg.align(Width::Long),
+            width: reg.width,
+            signedness: Signedness::Unsigned,
+            dest: reg,
             operand: instructions::AddressingMode::stack_offset(8 * idx as i64),
         });
     }
@@ -133,82 +394,293 @@ fn pop_stack_spills(instructions: &mut Vec<Instruction>, regs: &[Register]) {
     }));
 }
 
-fn handle_param(instructions: &mut Vec<Instruction>, allocator: &LinearScanRegisterAlloc, param: &nodes::FunctionParameter, idx: usize, scratch_register_1: Register, dynamic_offset: i64) {
-    instructions.push(Instruction::Comment(param.to_ir_string()));
-    assert!(!param.is_variadic);
-    let width = param.width;
-    let arg_reg = match param.number {
-        0 => Register::x0(width),
-        1 => Register::x1(width),
-        2 => Register::x2(width),
-        _ => todo!(),
-    };
+/// Moves the arguments of a call into their registers. Arguments already
+/// in registers are read from ones other arguments may go to, so they are
+/// moved as a parallel copy. Spilled and constant arguments come after,
+/// once no move reads their registers.
+fn move_arguments(instructions: &mut Vec<Instruction>, allocator: &LinearScanRegisterAlloc, lookup: &SymbolLookup, params: &[(Register, &nodes::FunctionParameter)], idx: usize, dynamic_offset: i64) {
+    let mut register_moves = vec![];
+    let mut others = vec![];
+    for &(arg_reg, param) in params {
+        instructions.push(Instruction::Comment(param.to_ir_string()));
+        let width = param.width;
+        let param_loc = match param.value {
+            Address::Constant(_) => None,
+            _ => Some(allocator.location_of(&param.value, idx).unwrap()),
+        };
+        match param_loc {
+            Some(regalloc::Location::Reg(param_reg)) => register_moves.push((arg_reg, param_reg.align(width))),
+            _ => others.push((arg_reg, param, param_loc)),
+        }
+    }
 
-    match param.value {
-        Address::Constant(nodes::AddressConstant::Numeric(nc)) => {
-            instructions.push(Instruction::Mov {
-                dest: arg_reg,
-                operand: RValue::Immediate(nc)
-            });
-        },
-        _ => {
-            let scratch_register_1 = scratch_register_1.align(width);
-            let param_loc = allocator.location_of(&param.value, idx).unwrap();
-            let param_reg = load_if_needed(instructions, param_loc, scratch_register_1, dynamic_offset);
+    parallel_copy(instructions, register_moves);
+    for (arg_reg, param, param_loc) in others {
+        match (&param.value, param_loc) {
+            (Address::Constant(constant), _) => load_constant(instructions, lookup, arg_reg, constant),
+            (_, Some(loc)) => {
+                load_if_needed(instructions, loc, arg_reg, dynamic_offset);
+            }
+            (_, None) => unreachable!("Arguments other than constants have a location"),
+        }
+    }
+}
 
-            // Maybe check if arg_reg is empty here?
-            if arg_reg != param_reg {
+/// Makes register to register moves as if all at once: a register is only
+/// overwritten once no move left reads it. Moves that all wait on each
+/// other form cycles, which are broken by saving one register to scratch.
+fn parallel_copy(instructions: &mut Vec<Instruction>, mut moves: Vec<(Register, Register)>) {
+    moves.retain(|(dest, source)| dest.widest() != source.widest());
+    while !moves.is_empty() {
+        let is_read = |register: &Register| moves.iter().any(|(_, source)| source.widest() == register.widest());
+        match moves.iter().position(|(dest, _)| !is_read(dest)) {
+            Some(ready) => {
+                let (dest, source) = moves.remove(ready);
+                instructions.push(Instruction::Mov {
+                    dest,
+                    operand: source.rvalue(),
+                });
+            }
+            None => {
+                let blocked = moves[0].0.widest();
+                let scratch = scratch_registers(blocked.width)[1].widest();
                 instructions.push(Instruction::Mov {
-                    dest: arg_reg,
-                    operand: param_reg.rvalue(),
+                    dest: scratch,
+                    operand: blocked.rvalue(),
                 });
+                for (_, source) in moves.iter_mut().filter(|(_, source)| source.widest() == blocked) {
+                    *source = scratch.align(source.width);
+                }
             }
         }
     }
-
 }
 
 
-fn handle_variadic_params(instructions: &mut Vec<Instruction>, allocator: &LinearScanRegisterAlloc, idx: usize, params: &[&nodes::FunctionParameter], scratch_register_1: Register, dynamic_offset: i64) -> usize {
-    const APPLE_VARARG_SLOT_SIZE: usize = 8;
-    let allocated = (APPLE_VARARG_SLOT_SIZE * params.len()).next_multiple_of(16);
+/// Stores the arguments a call passes on the stack to an area allocated
+/// below the spills, and gives the bytes it takes
+fn store_stack_arguments(instructions: &mut Vec<Instruction>, allocator: &LinearScanRegisterAlloc, lookup: &SymbolLookup, idx: usize, params: &[(i64, &nodes::FunctionParameter)], stack_size: usize, dynamic_offset: i64) -> usize {
+    let allocated = stack_size.next_multiple_of(16);
+    if allocated == 0 {
+        return 0;
+    }
 
     alloc_stack(instructions, allocated);
 
-    for (param_idx, param) in params.iter().enumerate() {
+    for &(offset, param) in params {
         instructions.push(Instruction::Comment(param.to_ir_string()));
-        let param_loc =  allocator.location_of(&param.value, idx).unwrap();
-        let width = param.width;
+        let scratch_register_1 = scratch_registers(param.width)[0];
+        let param_reg = if let Address::Constant(constant) = &param.value {
+            load_constant(instructions, lookup, scratch_register_1, constant);
+            scratch_register_1
+        } else {
+            let param_loc = allocator.location_of(&param.value, idx).unwrap();
+            load_if_needed(instructions, param_loc, scratch_register_1, dynamic_offset + allocated as i64)
+        };
 
-        let scratch_register_1 = scratch_register_1.align(width);
-        let param_reg = load_if_needed(instructions, param_loc, scratch_register_1, dynamic_offset + allocated as i64);
+        // Variadic arguments are stored as the whole register, filling their slot
+        let source = if param.is_variadic { param_reg.widest() } else { param_reg };
+        instructions.push(Instruction::Store {
+            width: source.width,
+            source,
+            operand: instructions::AddressingMode::stack_offset(offset)
+        });
+    }
 
-        if let Address::Constant(nodes::AddressConstant::Numeric(nc)) =  param.value {
-            instructions.push(Instruction::Mov {
-                dest: param_reg.align(Width::Long),
-                operand: RValue::Immediate(nc)
-            });
-        }
+    allocated
+}
 
+/// Registers of each class a variadic function saves for `va_arg` under
+/// AAPCS64, 8 bytes for each general and 16 for each floating one
+const SAVED_REGISTERS: i64 = 8;
+const GENERAL_SAVE_AREA: i64 = 8 * SAVED_REGISTERS;
+const FLOATING_SAVE_AREA: i64 = 16 * SAVED_REGISTERS;
+
+/// Stores the argument registers to the register save area of AAPCS64,
+/// right below the slots: x0-x7 at the top, d0-d7 below them. A scratch
+/// register addresses the area, as all of x0-x7 may hold arguments.
+fn save_argument_registers(instructions: &mut Vec<Instruction>, slots_size: usize) {
+    let base = scratch_registers(Width::Long)[0];
+    frame_address(instructions, base, -(slots_size as i64 + GENERAL_SAVE_AREA + FLOATING_SAVE_AREA));
+    let general = [
+        Register::x0(Width::Long),
+        Register::x1(Width::Long),
+        Register::x2(Width::Long),
+        Register::x3(Width::Long),
+        Register::x4(Width::Long),
+        Register::x5(Width::Long),
+        Register::x6(Width::Long),
+        Register::x7(Width::Long),
+    ];
+    for (pair, registers) in general.chunks(2).enumerate() {
+        instructions.push(Instruction::StorePair {
+            r1: registers[0],
+            r2: registers[1],
+            addressing: instructions::AddressingMode::Offset((base, FLOATING_SAVE_AREA + 16 * pair as i64)),
+        });
+    }
+    for number in 0..SAVED_REGISTERS as usize {
         instructions.push(Instruction::Store {
-            width: Width::Long,
-            source: param_reg.align(Width::Long),
-            operand: instructions::AddressingMode::stack_offset(8 * param_idx as i64)
+            width: Width::Double,
+            source: Register::v(number, Width::Double),
+            operand: instructions::AddressingMode::Offset((base, 16 * number as i64)),
         });
     }
+}
 
-    allocated
+/// dest = address of the `va_list` a variadic instruction works on
+fn list_address(
+    instructions: &mut Vec<Instruction>,
+    allocator: &LinearScanRegisterAlloc,
+    lookup: &SymbolLookup,
+    slots: &HashMap<Address, i64>,
+    list: &Address,
+    idx: usize,
+    dest: Register,
+) {
+    match (slots.get(list), list) {
+        (Some(&offset), _) => frame_address(instructions, dest, offset),
+        (None, Address::Constant(constant)) => load_constant(instructions, lookup, dest, constant),
+        (None, list) => {
+            let list_loc = allocator.location_of(list, idx).unwrap();
+            let list_reg = load_if_needed(instructions, list_loc, dest, 0);
+            if list_reg != dest {
+                instructions.push(Instruction::Mov {
+                    dest,
+                    operand: list_reg.rvalue(),
+                });
+            }
+        }
+    }
+}
+
+/// Fills the `va_list` of AAPCS64 at `list`. The register arguments left
+/// after the named parameters are at negative offsets from the tops of
+/// the save areas, the stacked ones right above the frame record, past
+/// the named parameters passed on the stack.
+fn aapcs64_va_start(
+    instructions: &mut Vec<Instruction>,
+    list_reg: Register,
+    slots_size: usize,
+    parameters: &[(String, Width)],
+    named_stack_size: usize,
+) {
+    let value = scratch_registers(Width::Long)[0];
+    let named_floating = parameters.iter().filter(|(_, width)| width.is_floating()).count() as i64;
+    let named_general = parameters.len() as i64 - named_floating;
+    let field = |offset| instructions::AddressingMode::Offset((list_reg, offset));
+
+    instructions.push(Instruction::Arith(instructions::Arith {
+        op: instructions::ArithOp::Add,
+        dest: value,
+        left: Register::frame_pointer(),
+        right: RValue::Immediate(16 + named_stack_size as i64),
+    }));
+    instructions.push(Instruction::Store { width: Width::Long, source: value, operand: field(0) });
+    frame_address(instructions, value, -(slots_size as i64));
+    instructions.push(Instruction::Store { width: Width::Long, source: value, operand: field(8) });
+    frame_address(instructions, value, -(slots_size as i64 + GENERAL_SAVE_AREA));
+    instructions.push(Instruction::Store { width: Width::Long, source: value, operand: field(16) });
+
+    let offset = value.align(Width::Word);
+    mov_immediate(instructions, offset, -(SAVED_REGISTERS - named_general.min(SAVED_REGISTERS)) * 8);
+    instructions.push(Instruction::Store { width: Width::Word, source: offset, operand: field(24) });
+    mov_immediate(instructions, offset, -(SAVED_REGISTERS - named_floating.min(SAVED_REGISTERS)) * 16);
+    instructions.push(Instruction::Store { width: Width::Word, source: offset, operand: field(28) });
+}
+
+/// `va_arg` of AAPCS64. The argument is in the save area of its class
+/// while the offset stays negative, and on the stack once it is used up.
+/// Leaves the address of the argument in `address` and moves the list
+/// past it.
+fn aapcs64_va_arg(
+    instructions: &mut Vec<Instruction>,
+    list_reg: Register,
+    address: Register,
+    width: Width,
+    labels: (String, String),
+) {
+    let next = scratch_registers(Width::Long)[0];
+    let (on_stack, done) = labels;
+    // Offset and top of the save area in the va_list, then the slot size
+    let (offset_field, top_field, register_size) = if width.is_floating() {
+        (28, 16, 16)
+    } else {
+        (24, 8, 8)
+    };
+    let field = |offset| instructions::AddressingMode::Offset((list_reg, offset));
+
+    instructions.push(Instruction::Load {
+        width: Width::Word,
+        signedness: Signedness::Signed,
+        dest: address,
+        operand: field(offset_field),
+    });
+    instructions.push(Instruction::Cmp {
+        left: address.align(Width::Word),
+        right: RValue::Immediate(0),
+    });
+    instructions.push(Instruction::Branch(instructions::Branch::cond_ge(instructions::Label(on_stack.clone()))));
+    instructions.push(Instruction::Arith(instructions::Arith {
+        op: instructions::ArithOp::Add,
+        dest: next.align(Width::Word),
+        left: address.align(Width::Word),
+        right: RValue::Immediate(register_size),
+    }));
+    instructions.push(Instruction::Store {
+        width: Width::Word,
+        source: next.align(Width::Word),
+        operand: field(offset_field),
+    });
+    instructions.push(Instruction::Cmp {
+        left: next.align(Width::Word),
+        right: RValue::Immediate(0),
+    });
+    instructions.push(Instruction::Branch(instructions::Branch::cond_gt(instructions::Label(on_stack.clone()))));
+    instructions.push(Instruction::Load {
+        width: Width::Long,
+        signedness: Signedness::Unsigned,
+        dest: next,
+        operand: field(top_field),
+    });
+    instructions.push(Instruction::Arith(instructions::Arith {
+        op: instructions::ArithOp::Add,
+        dest: address,
+        left: next,
+        right: address.rvalue(),
+    }));
+    instructions.push(Instruction::Branch(instructions::Branch::uncond(instructions::Label(done.clone()))));
+
+    instructions.push(Instruction::Label(on_stack));
+    instructions.push(Instruction::Load {
+        width: Width::Long,
+        signedness: Signedness::Unsigned,
+        dest: address,
+        operand: field(0),
+    });
+    instructions.push(Instruction::Arith(instructions::Arith {
+        op: instructions::ArithOp::Add,
+        dest: next,
+        left: address,
+        right: RValue::Immediate(8),
+    }));
+    instructions.push(Instruction::Store {
+        width: Width::Long,
+        source: next,
+        operand: field(0),
+    });
+    instructions.push(Instruction::Label(done));
 }
 
-fn generate_precolor(parameters: &[(String, Width)], body_len: usize) -> HashMap<Address, regalloc::Allocation> {
+/// Parameters passed in registers stay there, the ones on the stack are
+/// allocated like any other value
+fn generate_precolor(parameters: &[(String, Width)], locations: &[ArgumentLocation], body_len: usize) -> HashMap<Address, regalloc::Allocation> {
     let mut hm = HashMap::new();
-    for (p_idx, p) in parameters.iter().enumerate() {
-        let loc = match p_idx {
-            0 => Location::Reg(Register::x0(p.1)),
-            1 => Location::Reg(Register::x1(p.1)),
-            2 => Location::Reg(Register::x2(p.1)),
-            _ => todo!()
+    for (p, location) in parameters.iter().zip(locations) {
+        let ArgumentLocation::Register(register) = *location else {
+            continue;
         };
+        let loc = Location::Reg(register);
         let alloc = regalloc::Allocation {
             loc,
             lifetime: regalloc::Lifetime {
@@ -222,15 +694,30 @@ fn generate_precolor(parameters: &[(String, Width)], body_len: usize) -> HashMap
 }
 
 fn body_to_asm(
-    block: &[nodes::Ssa],
+    cfg: &Cfg,
     func_name: &str,
     parameters: &[(String, Width)],
     lookup: &SymbolLookup,
+    abi: Abi,
 ) -> Vec<instructions::Instruction> {
+    let (parameter_locations, named_stack_size) =
+        argument_locations(parameters.iter().map(|(_, width)| (*width, false)), abi);
+    let (register_parameters, stack_parameters): (Vec<_>, Vec<_>) = parameters
+        .iter()
+        .zip(&parameter_locations)
+        .partition(|(_, location)| matches!(location, ArgumentLocation::Register(_)));
+    let names = |parameters: &[(&(String, Width), &ArgumentLocation)]| -> Vec<String> {
+        parameters.iter().map(|((name, _), _)| name.clone()).collect()
+    };
 
-    let parameter_names: Vec<String> = parameters.iter().map(|x| x.0.clone()).collect();
-
-    let lifetimes = analyze_lifetimes(block, &parameter_names);
+    // Blocks are emitted in layout order, each from its label to its terminator
+    let linear = cfg.to_linear();
+    let block: &Vec<nodes::Ssa> = &linear.iter().map(|instruction| instruction.ssa.clone()).collect();
+    let mut lifetimes = analyze_lifetimes(cfg, block, &names(&register_parameters), &names(&stack_parameters));
+    let (slots, slots_size) = frame_slots(block);
+    let direct_slots = direct_slots(block, &slots);
+    lifetimes.retain(|address, _| !direct_slots.contains(address));
+    let sign_extending_loads = sign_extending_loads(block);
 
     let mut allocator = LinearScanRegisterAlloc::new(vec![
         Register::x0(Width::Long),
@@ -238,82 +725,90 @@ fn body_to_asm(
         Register::x2(Width::Long),
         Register::x3(Width::Long),
         Register::x4(Width::Long),
+        Register::x5(Width::Long),
+        Register::x6(Width::Long),
+        Register::x7(Width::Long),
+        Register::v(0, Width::Double),
+        Register::v(1, Width::Double),
+        Register::v(2, Width::Double),
+        Register::v(3, Width::Double),
+        Register::v(4, Width::Double),
+        Register::v(5, Width::Double),
+        Register::v(6, Width::Double),
+        Register::v(7, Width::Double),
     ], 
-        generate_precolor(parameters, block.len())
+        generate_precolor(parameters, &parameter_locations, block.len())
     );
 
-    allocator.linear_scan(&lifetimes);
-
-    let stack_size = allocator.stack_size();
+    allocator.linear_scan(
+        &lifetimes,
+        &regalloc::floating_addresses(block, parameters),
+        &regalloc::spill_weights(cfg),
+    );
 
-    let scratch_register_1 = Register::x5(Width::Long);
-    let scratch_register_2 = Register::x6(Width::Long);
-    let scratch_register_3 = Register::x7(Width::Long);
+    // Spills sit at the bottom of the frame, the slots above them. Under
+    // AAPCS64 the register save area of va_start goes in between.
+    let saves_registers = abi == Abi::Aapcs64 && block.iter().any(|ssa| matches!(ssa, nodes::Ssa::VaStart { .. }));
+    let save_area_size = if saves_registers {
+        (GENERAL_SAVE_AREA + FLOATING_SAVE_AREA) as usize
+    } else {
+        0
+    };
+    let stack_size = slots_size + save_area_size + allocator.stack_size();
 
     let mut result = vec![];
 
     alloc_stack(&mut result, stack_size);
+    if saves_registers {
+        save_argument_registers(&mut result, slots_size);
+    }
+    // Parameters the caller passed on the stack are right above the frame record
+    for ((name, width), location) in &stack_parameters {
+        let (ArgumentLocation::Stack(offset), Some(loc)) =
+            (location, allocator.location_of(&Address::source_count(name.clone(), 0), 0))
+        else {
+            continue;
+        };
+        let dest_reg = empty_register(loc, scratch_registers(*width)[0]);
+        result.push(Instruction::Load {
+            width: *width,
+            signedness: Signedness::Unsigned,
+            dest: dest_reg,
+            operand: instructions::AddressingMode::frame_offset(16 + offset),
+        });
+        store_if_needed(&mut result, loc, dest_reg);
+    }
 
-    for (idx, b) in block.iter().enumerate() {
+    // Locations go to the line table where they change and at the start of
+    // each block. Instructions without one stay with the line before them.
+    let mut last_location = None;
+    for (idx, instruction) in linear.iter().enumerate() {
+        let b = &instruction.ssa;
+        if let nodes::Ssa::Label(_) = b {
+            last_location = None;
+        }
+        if let Some(location) = instruction.location && last_location != Some(location) {
+            last_location = Some(location);
+            result.push(Instruction::Directive(instructions::Directive::Loc(location)));
+        }
         result.push( Instruction::Comment( b.to_ir_string() ) );
+        if idx > 0 && sign_extending_loads.contains(&(idx - 1)) {
+            continue;
+        }
         match b {
             nodes::Ssa::Assignment {
                 dest,
                 source,
                 width,
             } => {
-                if let nodes::Address::Constant(nodes::AddressConstant::Numeric(num_const)) = source
-                {
-                    let dest_loc = allocator.location_of(dest, idx).unwrap();
-                    match dest_loc {
-                        regalloc::Location::Reg(r) => {
-                            result.push(Instruction::Mov {
-                                dest: r.align(*width),
-                                operand: instructions::RValue::Immediate(*num_const),
-                            });
-                        },
-                        regalloc::Location::Spill(_) => {
-                            result.push(Instruction::Mov {
-                                dest: scratch_register_1.align(*width),
-                                operand: instructions::RValue::Immediate(*num_const),
-                            });
-
-                            store_if_needed(&mut result, dest_loc, scratch_register_1.align(*width));
-                        }
-                    }
-                } else if let nodes::Address::Constant(nodes::AddressConstant::StringLiteral(sl)) =
-                    source
-                {
-
-                    let scratch_register_1 = scratch_register_1.align(*width);
+                if let nodes::Address::Constant(constant) = source {
                     let dest_loc = allocator.location_of(dest, idx).unwrap();
-                    let dest_reg = empty_register(dest_loc, scratch_register_1);
-
-                    let sl_label = lookup
-                        .get(&nodes::Address::Constant(
-                            nodes::AddressConstant::StringLiteral(sl.clone()),
-                        ))
-                        .unwrap();
-                    let sl_label_str = match sl_label.address {
-                        SymbolAddress::StringLiteral(sl_count) => format!("sl{}", sl_count),
-                        _ => panic!(),
-                    };
-
-                    result.push(Instruction::AdressPage {
-                        dest: dest_reg,
-                        symbol: Symbol(sl_label_str.clone()),
-                    });
-                    result.push(Instruction::Arith(instructions::Arith {
-                        op: instructions::ArithOp::Add,
-                        dest: dest_reg,
-                        left: dest_reg,
-                        right: instructions::RValue::SymbolOffset(Symbol(sl_label_str.clone())),
-                    }));
+                    let dest_reg = empty_register(dest_loc, scratch_registers(*width)[0]);
+                    load_constant(&mut result, lookup, dest_reg, constant);
                     store_if_needed(&mut result, dest_loc, dest_reg);
-
                 } else {
-                    let scratch_register_1 = scratch_register_1.align(*width);
-                    let scratch_register_2 = scratch_register_2.align(*width);
+                    let scratch_register_1 = scratch_registers(*width)[0];
+                    let scratch_register_2 = scratch_registers(*width)[1];
                     let dest_loc = allocator.location_of(dest, idx).unwrap();
                     let source_loc = allocator.location_of(source, idx).unwrap();
 
@@ -328,33 +823,265 @@ fn body_to_asm(
                     store_if_needed(&mut result, dest_loc, dest_reg.align(*width));
                 }
             }
+            nodes::Ssa::Extend {
+                signed,
+                dest,
+                source,
+                from,
+                width,
+            } => {
+                let signedness = if *signed { Signedness::Signed } else { Signedness::Unsigned };
+                let dest_loc = allocator.location_of(dest, idx).unwrap();
+                let dest_reg = empty_register(dest_loc, scratch_registers(*width)[0]);
+
+                if let Address::Constant(nodes::AddressConstant::Numeric(nc)) = source {
+                    mov_immediate(&mut result, dest_reg, from.extend(*nc, signedness));
+                } else {
+                    let source_loc = allocator.location_of(source, idx).unwrap();
+                    let source_reg = load_if_needed(&mut result, source_loc, scratch_registers(*from)[1], 0);
+                    result.push(Instruction::Extend {
+                        signedness,
+                        from: *from,
+                        dest: dest_reg,
+                        source: source_reg,
+                    });
+                }
+
+                store_if_needed(&mut result, dest_loc, dest_reg);
+            }
+            nodes::Ssa::Truncate {
+                dest,
+                source,
+                width,
+            } => {
+                let dest_loc = allocator.location_of(dest, idx).unwrap();
+                let dest_reg = empty_register(dest_loc, scratch_registers(*width)[0]);
+
+                if let Address::Constant(nodes::AddressConstant::Numeric(nc)) = source {
+                    mov_immediate(&mut result, dest_reg, width.extend(*nc, Signedness::Unsigned));
+                } else {
+                    // Low bits of a spilled value come first in memory, so the
+                    // narrow load already truncates
+                    let source_loc = allocator.location_of(source, idx).unwrap();
+                    let source_reg = load_if_needed(&mut result, source_loc, scratch_registers(*width)[1], 0);
+                    match width {
+                        Width::Word => result.push(Instruction::Mov {
+                            dest: dest_reg,
+                            operand: source_reg.rvalue(),
+                        }),
+                        Width::Short | Width::Byte => result.push(Instruction::Arith(instructions::Arith {
+                            op: instructions::ArithOp::And,
+                            dest: dest_reg,
+                            left: source_reg,
+                            right: RValue::Immediate(width.extend(-1, Signedness::Unsigned)),
+                        })),
+                        Width::Long | Width::Single | Width::Double => {
+                            panic!("Cannot truncate to a {:?}", width)
+                        }
+                    }
+                }
+
+                store_if_needed(&mut result, dest_loc, dest_reg);
+            }
+            nodes::Ssa::Convert {
+                signed,
+                dest,
+                source,
+                from,
+                width,
+            } => {
+                let signedness = if *signed { Signedness::Signed } else { Signedness::Unsigned };
+                let dest_loc = allocator.location_of(dest, idx).unwrap();
+                let dest_reg = empty_register(dest_loc, scratch_registers(*width)[0]);
+
+                let source_reg = if let Address::Constant(nodes::AddressConstant::Numeric(nc)) = source {
+                    mov_immediate(&mut result, scratch_registers(*from)[1], *nc);
+                    scratch_registers(*from)[1]
+                } else {
+                    let source_loc = allocator.location_of(source, idx).unwrap();
+                    load_if_needed(&mut result, source_loc, scratch_registers(*from)[1], 0)
+                };
+                result.push(Instruction::Convert {
+                    signedness,
+                    dest: dest_reg,
+                    source: source_reg,
+                });
+
+                store_if_needed(&mut result, dest_loc, dest_reg);
+            }
+            nodes::Ssa::VaStart { list } if abi == Abi::Aapcs64 => {
+                let list_reg = scratch_registers(Width::Long)[1];
+                list_address(&mut result, &allocator, lookup, &slots, list, idx, list_reg);
+                aapcs64_va_start(&mut result, list_reg, slots_size, parameters, named_stack_size);
+            }
+            nodes::Ssa::VaStart { list } => {
+                // Variadic arguments follow the named ones passed on the
+                // stack, right above the frame record
+                let first = scratch_registers(Width::Long)[0];
+                result.push(Instruction::Arith(instructions::Arith {
+                    op: instructions::ArithOp::Add,
+                    dest: first,
+                    left: Register::frame_pointer(),
+                    right: RValue::Immediate(16 + named_stack_size.next_multiple_of(8) as i64),
+                }));
+                let operand = memory_operand(&mut result, &allocator, &slots, list, idx);
+                result.push(Instruction::Store {
+                    width: Width::Long,
+                    source: first,
+                    operand,
+                });
+            }
+            nodes::Ssa::VaArg { dest, list, width } => {
+                let [next, list_reg, current] = scratch_registers(Width::Long);
+                if abi == Abi::Aapcs64 {
+                    list_address(&mut result, &allocator, lookup, &slots, list, idx, list_reg);
+                    let labels = (
+                        format!("L_{}_va_arg_{}_stack", func_name, idx),
+                        format!("L_{}_va_arg_{}_done", func_name, idx),
+                    );
+                    aapcs64_va_arg(&mut result, list_reg, current, *width, labels);
+                } else {
+                    // The list moves past the argument before it is read, as
+                    // the destination may take the register of its address
+                    let operand = memory_operand(&mut result, &allocator, &slots, list, idx);
+                    result.push(Instruction::Load {
+                        width: Width::Long,
+                        signedness: Signedness::Unsigned,
+                        dest: current,
+                        operand: operand.clone(),
+                    });
+                    result.push(Instruction::Arith(instructions::Arith {
+                        op: instructions::ArithOp::Add,
+                        dest: next,
+                        left: current,
+                        right: RValue::Immediate(8),
+                    }));
+                    result.push(Instruction::Store {
+                        width: Width::Long,
+                        source: next,
+                        operand,
+                    });
+                }
+                let dest_loc = allocator.location_of(dest, idx).unwrap();
+                let dest_reg = empty_register(dest_loc, scratch_registers(*width)[0]);
+                result.push(Instruction::Load {
+                    width: *width,
+                    signedness: Signedness::Unsigned,
+                    dest: dest_reg,
+                    operand: current.addressing_mode(),
+                });
+                store_if_needed(&mut result, dest_loc, dest_reg);
+            }
+            nodes::Ssa::Alloca { dest, .. } => {
+                if direct_slots.contains(dest) {
+                    continue;
+                }
+                let dest_loc = allocator.location_of(dest, idx).unwrap();
+                let dest_reg = empty_register(dest_loc, scratch_registers(Width::Long)[0]);
+                frame_address(&mut result, dest_reg, slots[dest]);
+                store_if_needed(&mut result, dest_loc, dest_reg);
+            }
+            nodes::Ssa::Load {
+                dest,
+                address,
+                width,
+            } => {
+                // A load folded with its sign extension writes the extension's destination
+                let (dest_loc, dest_width, signedness) = match block.get(idx + 1) {
+                    Some(nodes::Ssa::Extend { dest, width, .. }) if sign_extending_loads.contains(&idx) => {
+                        (allocator.location_of(dest, idx + 1).unwrap(), *width, Signedness::Signed)
+                    }
+                    _ => (allocator.location_of(dest, idx).unwrap(), *width, Signedness::Unsigned),
+                };
+                let dest_reg = empty_register(dest_loc, scratch_registers(dest_width)[0]);
+                let operand = memory_operand(&mut result, &allocator, &slots, address, idx);
+                result.push(Instruction::Load {
+                    width: *width,
+                    signedness,
+                    dest: dest_reg,
+                    operand,
+                });
+                store_if_needed(&mut result, dest_loc, dest_reg);
+            }
+            nodes::Ssa::Store {
+                address,
+                value,
+                width,
+            } => {
+                let value_reg = if let Address::Constant(constant) = value {
+                    load_constant(&mut result, lookup, scratch_registers(*width)[0], constant);
+                    scratch_registers(*width)[0]
+                } else {
+                    let value_loc = allocator.location_of(value, idx).unwrap();
+                    load_if_needed(&mut result, value_loc, scratch_registers(*width)[0], 0)
+                };
+                let operand = memory_operand(&mut result, &allocator, &slots, address, idx);
+                result.push(Instruction::Store {
+                    width: *width,
+                    source: value_reg,
+                    operand,
+                });
+            }
             nodes::Ssa::Quadriplet(quad) => {
                 let width = quad.width;
 
-                let scratch_register_1 = scratch_register_1.align(width);
-                let scratch_register_2 = scratch_register_2.align(width);
-                let scratch_register_3 = scratch_register_3.align(width);
+                let scratch_register_1 = scratch_registers(width)[0];
+                let scratch_register_2 = scratch_registers(width)[1];
+                let scratch_register_3 = scratch_registers(width)[2];
 
-                let left_loc = allocator.location_of(&quad.left, idx).unwrap();
                 let right_loc = allocator.location_of(quad.right.as_ref().unwrap(), idx);
                 let dest_loc = allocator.location_of(&quad.dest, idx).unwrap();
 
-                let rvalue = if let Some(right_loc) =  right_loc {
-                    let right_reg = load_if_needed(&mut result, right_loc, scratch_register_2, 0);
-                    right_reg.rvalue()
-                } else {
-                    if let Address::Constant(nodes::AddressConstant::Numeric(nc)) = quad.right.as_ref().unwrap() {
-                        RValue::Immediate(*nc)
-                    } else {
-                        panic!("WTF?")
+                let rvalue = match (right_loc, quad.right.as_ref().unwrap()) {
+                    (Some(right_loc), _) => {
+                        let right_reg = load_if_needed(&mut result, right_loc, scratch_register_2, 0);
+                        right_reg.rvalue()
+                    }
+                    (None, Address::Constant(nodes::AddressConstant::Numeric(nc))) => RValue::Immediate(*nc),
+                    (None, Address::Constant(constant)) => {
+                        load_constant(&mut result, lookup, scratch_register_2, constant);
+                        scratch_register_2.rvalue()
                     }
+                    (None, _) => unreachable!("Operands other than constants have a location"),
                 };
 
-                let left_reg = load_if_needed(&mut result, left_loc, scratch_register_1, 0);
+                // Copy elimination can leave a constant on the left
+                let left_reg = match &quad.left {
+                    Address::Constant(constant) => {
+                        load_constant(&mut result, lookup, scratch_register_1, constant);
+                        scratch_register_1
+                    }
+                    left => {
+                        let left_loc = allocator.location_of(left, idx).unwrap();
+                        load_if_needed(&mut result, left_loc, scratch_register_1, 0)
+                    }
+                };
+
+                if quad.op.is_cmp() && width.is_floating() {
+                    // The flags of fcmp are set into a general purpose word
+                    let dest_reg = empty_register(dest_loc, scratch_registers(Width::Word)[2]);
+                    result.push(Instruction::Cmp {
+                        left: left_reg,
+                        right: rvalue,
+                    });
+                    result.push(Instruction::CondSet {
+                        dest: dest_reg,
+                        cond: instructions::ConditionalCode::try_from_float_op(quad.op),
+                    });
+                    store_if_needed(&mut result, dest_loc, dest_reg);
+                    continue;
+                }
 
                 let dest_reg = empty_register(dest_loc, scratch_register_3);
 
                 if quad.op.is_cmp() {
+                    let rvalue = match rvalue {
+                        RValue::Immediate(nc) if !(0..4096).contains(&nc) => {
+                            mov_immediate(&mut result, scratch_register_2, nc);
+                            scratch_register_2.rvalue()
+                        }
+                        _ => rvalue,
+                    };
                     let cond_op = instructions::ConditionalCode::try_from_nodes_op(quad.op);
                     result.push(Instruction::Cmp {
                         left: left_reg,
@@ -364,8 +1091,46 @@ fn body_to_asm(
                         dest: dest_reg,
                         cond: cond_op,
                     });
+                } else if let nodes::Op::Rem | nodes::Op::URem = quad.op {
+                    // There is no remainder instruction, it is left - left / right * right
+                    let divide = if let nodes::Op::Rem = quad.op {
+                        instructions::ArithOp::SDiv
+                    } else {
+                        instructions::ArithOp::UDiv
+                    };
+                    let right_reg = match rvalue {
+                        RValue::Register(right_reg) => right_reg,
+                        RValue::Immediate(nc) => {
+                            mov_immediate(&mut result, scratch_register_2, nc);
+                            scratch_register_2
+                        }
+                        RValue::SymbolOffset(_) => unreachable!("Symbols are not quadriplet operands"),
+                    };
+                    result.push(Instruction::Arith(instructions::Arith {
+                        op: divide,
+                        dest: scratch_register_3,
+                        left: left_reg,
+                        right: right_reg.rvalue(),
+                    }));
+                    result.push(Instruction::Msub {
+                        dest: dest_reg,
+                        left: scratch_register_3,
+                        right: right_reg,
+                        minuend: left_reg,
+                    });
                 } else {
-                    let mod_op = instructions::ArithOp::try_from_nodes_op(quad.op);
+                    let mod_op = if width.is_floating() {
+                        instructions::ArithOp::try_from_float_op(quad.op)
+                    } else {
+                        instructions::ArithOp::try_from_nodes_op(quad.op)
+                    };
+                    let rvalue = match rvalue {
+                        RValue::Immediate(nc) if !mod_op.takes_immediate(nc) => {
+                            mov_immediate(&mut result, scratch_register_2, nc);
+                            scratch_register_2.rvalue()
+                        }
+                        _ => rvalue,
+                    };
                     result.push(Instruction::Arith(instructions::Arith {
                         op: mod_op,
                         dest: dest_reg,
@@ -385,45 +1150,49 @@ fn body_to_asm(
                 false_target,
                 width
             } => {
-                let scratch_register_1 = scratch_register_1.align(*width);
-                if let Address::Constant(nodes::AddressConstant::Numeric(nc)) = cond {
-                    result.push(Instruction::Mov { dest: scratch_register_1, operand: instructions::RValue::Immediate(*nc) });
-                    result.push(Instruction::Cmp {
-                        left: scratch_register_1,
-                        right: instructions::RValue::Immediate(1),
-                    });
-                } else {
-                    let cond_loc = allocator.location_of(&cond, idx).unwrap();
-                    let cond_register = load_if_needed(&mut result, cond_loc, scratch_register_1, 0);
-
-                    result.push(Instruction::Cmp {
-                        left: cond_register,
-                        right: instructions::RValue::Immediate(1),
-                    });
+                // Any value other than zero takes the true target
+                let constant = match cond {
+                    Address::Constant(nodes::AddressConstant::Numeric(nc)) => Some(*nc != 0),
+                    Address::Constant(nodes::AddressConstant::Floating(bits)) => {
+                        Some(f64::from_bits(*bits) != 0.0)
+                    }
+                    _ => None,
+                };
+                if let Some(taken) = constant {
+                    let target = if taken { true_target } else { false_target };
+                    result.push(Instruction::Branch(instructions::Branch::uncond((
+                        target.clone(),
+                        func_name,
+                    ))));
+                    continue;
                 }
 
-                result.push(Instruction::Branch(instructions::Branch::cond_eq((
+                let cond_loc = allocator.location_of(cond, idx).unwrap();
+                let cond_register = load_if_needed(&mut result, cond_loc, scratch_registers(*width)[0], 0);
+                result.push(Instruction::Cmp {
+                    left: cond_register,
+                    right: instructions::RValue::Immediate(0),
+                });
+                result.push(Instruction::Branch(instructions::Branch::cond_not_eq((
                     true_target.clone(),
                     func_name,
                 ))));
-                result.push(Instruction::Branch(instructions::Branch::cond_not_eq((
+                result.push(Instruction::Branch(instructions::Branch::cond_eq((
                     false_target.clone(),
                     func_name,
                 ))));
             }
             nodes::Ssa::Return { value } => {
                 if let Some((val, width)) = value {
-                    let scratch_register_1 = scratch_register_1.align(*width);
-                    if let nodes::Address::Constant(nodes::AddressConstant::Numeric(nc)) = val {
-                        result.push(Instruction::Mov {
-                            dest: Register::x0(*width), // dont care about contents at this point
-                            operand: instructions::RValue::Immediate(*nc)
-                        });
+                    let scratch_register_1 = scratch_registers(*width)[0];
+                    if let nodes::Address::Constant(constant) = val {
+                        // dont care about contents of x0 at this point
+                        load_constant(&mut result, lookup, return_register(*width), constant);
                     } else {
-                        let val_loc = allocator.location_of(&val, idx).unwrap();
+                        let val_loc = allocator.location_of(val, idx).unwrap();
                         let val_register = load_if_needed(&mut result, val_loc, scratch_register_1, 0);
                         result.push(Instruction::Mov {
-                            dest: Register::x0(*width), // dont care about contents at this point
+                            dest: return_register(*width), // dont care about contents at this point
                             operand: val_register.rvalue(),
                         });
                     }
@@ -452,69 +1221,82 @@ fn body_to_asm(
                 parameters
             } => {
 
-                let non_variadic_parameters = parameters.iter().filter(|x| !x.is_variadic).collect::<Vec<_>>();
-                let variadic_parameters = parameters.iter().filter(|x| x.is_variadic).collect::<Vec<_>>();
+                let (locations, stack_size) = argument_locations(parameters.iter().map(|p| (p.width, p.is_variadic)), abi);
+                let mut register_parameters = vec![];
+                let mut stack_parameters = vec![];
+                for (location, parameter) in locations.into_iter().zip(parameters) {
+                    match location {
+                        ArgumentLocation::Register(register) => register_parameters.push((register, parameter)),
+                        ArgumentLocation::Stack(offset) => stack_parameters.push((offset, parameter)),
+                    }
+                }
 
                 let used_registers = allocator.used_registers_at(idx);
 
                 let dynamic_stack_offset = alloc_stack_spills(&mut result, &used_registers) as i64;
 
-                let allocated_variadic = if !variadic_parameters.is_empty() {
-                    handle_variadic_params(&mut result, &allocator, idx, &variadic_parameters, scratch_register_1, dynamic_stack_offset)
+                // The pointer of an indirect call is moved out of the way
+                // before the argument registers get overwritten
+                let function_pointer = if let nodes::Address::Constant(_) = func {
+                    None
                 } else {
-                    0
+                    let function_register = scratch_registers(Width::Long)[2];
+                    let func_loc = allocator.location_of(func, idx).unwrap();
+                    let func_reg = load_if_needed(&mut result, func_loc, function_register, dynamic_stack_offset);
+                    if func_reg != function_register {
+                        result.push(Instruction::Mov {
+                            dest: function_register,
+                            operand: func_reg.rvalue(),
+                        });
+                    }
+                    Some(function_register)
                 };
 
-                for p in non_variadic_parameters.iter() {
-                    handle_param(&mut result, &allocator, p, idx, scratch_register_1, dynamic_stack_offset) 
-                }
+                let allocated_arguments = store_stack_arguments(&mut result, &allocator, lookup, idx, &stack_parameters, stack_size, dynamic_stack_offset);
 
-                match func {
-                    nodes::Address::CompilerTemp(_) => {
-                        let scratch_register_1 = scratch_register_1.align(Width::Long);
-
-                        let func_loc = allocator.location_of(func, idx).unwrap();
-                        let func_reg = load_if_needed(&mut result, func_loc, scratch_register_1, dynamic_stack_offset);
+                // Spilled arguments are read past the argument area below the spills
+                move_arguments(&mut result, &allocator, lookup, &register_parameters, idx, dynamic_stack_offset + allocated_arguments as i64);
 
+                match (func, function_pointer) {
+                    (_, Some(function_register)) => {
                         result.push(Instruction::Branch(
-                            instructions::Branch::branch_link_register(func_reg),
+                            instructions::Branch::branch_link_register(function_register),
                         ));
                     }
-                    nodes::Address::Source(source) => {
+                    (nodes::Address::Constant(nodes::AddressConstant::Symbol(name)), None) => {
                         result.push(Instruction::Branch(instructions::Branch::branch_link(
-                            instructions::Label(format!("_{}", source.0)),
+                            instructions::Label(format!("_{}", name)),
                         )));
                     }
-                    nodes::Address::Constant(_) => {
-                        panic!("Cannot call a constant! Unless maybe blr?");
+                    (_, None) => {
+                        panic!("Cannot call a constant!");
                     }
                 }
 
                 if let Some((_, width)) = dest {
-                    let scratch_register_1 = scratch_register_1.align(*width);
+                    let scratch_register_1 = scratch_registers(*width)[0];
 
                     result.push(Instruction::Mov{
                         dest: scratch_register_1,
-                        operand: Register::x0(*width).rvalue()
+                        operand: return_register(*width).rvalue()
                     });
                 }
 
-                if allocated_variadic > 0 {
-                    result.push(Instruction::Comment(format!("Variadic parameters pop")));
-                    pop_stack(&mut result, allocated_variadic);
+                if allocated_arguments > 0 {
+                    result.push(Instruction::Comment("Stack arguments pop".into()));
+                    pop_stack(&mut result, allocated_arguments);
                 }
 
                 pop_stack_spills(&mut result, &used_registers);
 
                 if let Some((val, width)) = dest {
-                    let scratch_register_1 = scratch_register_1.align(*width);
+                    let scratch_register_1 = scratch_registers(*width)[0];
                     let val_loc = allocator.location_of(val, idx).unwrap();
                     store_if_needed(&mut result, val_loc, scratch_register_1);
                 }
             }
 
             nodes::Ssa::Phi(_) => panic!("Phi functions should be eliminated at this stage!"),
-            _ => todo!(),
         }
     }
 
@@ -529,10 +1311,11 @@ fn body_to_asm(
 }
 
 pub fn convert_function_body_ir_to_asm(
-    ir: &[nodes::Ssa],
+    ir: &Cfg,
     func_name: &str,
     parameters: &[(String, Width)],
     global_lookup: &SymbolLookup,
+    abi: Abi,
 ) -> Vec<instructions::Instruction> {
     let lookup = global_lookup;
 
@@ -548,7 +1331,7 @@ pub fn convert_function_body_ir_to_asm(
         operand: instructions::RValue::Register(Register::stack_pointer()),
     });
 
-    let asm = body_to_asm(ir, func_name, parameters, lookup);
+    let asm = body_to_asm(ir, func_name, parameters, lookup, abi);
     instructions.extend(asm);
 
     instructions.push(instructions::Instruction::LoadPair {
@@ -565,12 +1348,13 @@ pub fn convert_function_body_ir_to_asm(
 pub fn convert_function_to_asm(
     fd: &nodes::FunctionDef,
     lookup: &SymbolLookup,
+    abi: Abi,
 ) -> Vec<instructions::Instruction> {
     let mut instructions = vec![];
     instructions.push(instructions::Instruction::Label(
         "_".to_owned() + fd.name.as_str(),
     ));
-    instructions.extend(convert_function_body_ir_to_asm(&fd.body, &fd.name, &fd.parameters, lookup));
+    instructions.extend(convert_function_body_ir_to_asm(&fd.body, &fd.name, &fd.parameters, lookup, abi));
 
     instructions
 }
@@ -596,13 +1380,109 @@ pub fn convert_declaration_to_asm(
     instructions
 }
 
-pub fn convert_unit_to_asm(unit: &[nodes::ToplevelItem]) -> Vec<instructions::Instruction> {
+/// A string literal the linker may merge with the equal ones of other units
+fn is_c_string(data: &nodes::DataDef) -> bool {
+    let bytes: Vec<u8> = data
+        .inits
+        .iter()
+        .flat_map(|init| match init {
+            nodes::DataInit::Bytes(bytes) => bytes.clone(),
+            nodes::DataInit::Values(Width::Byte, values) => values
+                .iter()
+                .map(|value| match value {
+                    nodes::AddressConstant::Numeric(n) => *n as u8,
+                    _ => 1,
+                })
+                .collect(),
+            _ => vec![1],
+        })
+        .collect();
+    data.constant && data.linkage == nodes::Linkage::Local && bytes.iter().position(|&b| b == 0) == Some(bytes.len() - 1)
+}
+
+/// Constant data goes in a read only section, `__DATA,__const` when the
+/// dynamic linker has addresses to fill in, and zeroed data takes no space
+pub fn convert_data_to_asm(data: &nodes::DataDef) -> Vec<instructions::Instruction> {
+    let mut instructions = vec![];
+    let align = data.align.max(1).trailing_zeros() as u8;
+    if data.linkage == nodes::Linkage::Export {
+        instructions.push(Instruction::Directive(instructions::Directive::Global(data.name.clone())));
+    }
+    if !data.constant && data.is_zero() {
+        instructions.push(Instruction::Directive(instructions::Directive::ZeroFill {
+            name: data.name.clone(),
+            size: data.size().max(1),
+            align,
+        }));
+        return instructions;
+    }
+
+    let section = if is_c_string(data) {
+        instructions::Section::TextCstring
+    } else if !data.constant {
+        instructions::Section::Data
+    } else if data.has_symbols() {
+        instructions::Section::DataConst
+    } else {
+        instructions::Section::TextConst
+    };
+    instructions.push(Instruction::Directive(instructions::Directive::Section(section)));
+    instructions.push(Instruction::Directive(instructions::Directive::Align(align)));
+    instructions.push(Instruction::Label(format!("_{}", data.name)));
+
+    for init in &data.inits {
+        match init {
+            nodes::DataInit::Bytes(bytes) => instructions.push(Instruction::Directive(
+                instructions::Directive::Ascii(crate::ir::escape_bytes(bytes)),
+            )),
+            nodes::DataInit::Values(width, values) => {
+                for value in values {
+                    let directive = match (value, width) {
+                        (nodes::AddressConstant::Symbol(name), _) => {
+                            instructions::Directive::QuadSymbol(name.to_string(), 0)
+                        }
+                        (nodes::AddressConstant::Floating(bits), Width::Single) => {
+                            instructions::Directive::Long((f64::from_bits(*bits) as f32).to_bits())
+                        }
+                        (nodes::AddressConstant::Floating(bits), _) => instructions::Directive::Quad(*bits),
+                        (nodes::AddressConstant::Numeric(n), Width::Byte) => instructions::Directive::Byte(*n as u8),
+                        (nodes::AddressConstant::Numeric(n), Width::Short) => {
+                            instructions::Directive::Short(*n as u16)
+                        }
+                        (nodes::AddressConstant::Numeric(n), Width::Word) => instructions::Directive::Long(*n as u32),
+                        (nodes::AddressConstant::Numeric(n), Width::Single) => {
+                            instructions::Directive::Long((*n as f32).to_bits())
+                        }
+                        (nodes::AddressConstant::Numeric(n), Width::Long) => instructions::Directive::Quad(*n as u64),
+                        (nodes::AddressConstant::Numeric(n), Width::Double) => {
+                            instructions::Directive::Quad((*n as f64).to_bits())
+                        }
+                    };
+                    instructions.push(Instruction::Directive(directive));
+                }
+            }
+            nodes::DataInit::Address(name, offset) => instructions.push(Instruction::Directive(
+                instructions::Directive::QuadSymbol(name.to_string(), *offset),
+            )),
+            nodes::DataInit::Zero(size) => {
+                instructions.push(Instruction::Directive(instructions::Directive::Zero(*size)))
+            }
+        }
+    }
+    instructions
+}
+
+/// `source_file` is the file the source locations of the instructions are in
+pub fn convert_unit_to_asm(unit: &[nodes::ToplevelItem], source_file: &str, abi: Abi) -> Vec<instructions::Instruction> {
     let lookup = SymbolLookup::global_from_unit(unit);
     let mut instructions = vec![];
 
     instructions.push(Instruction::Directive(instructions::Directive::Section(
         instructions::Section::Text,
     )));
+    instructions.push(Instruction::Directive(instructions::Directive::File(
+        crate::ir::escape_bytes(source_file.as_bytes()),
+    )));
 
     for tl in unit {
         match tl {
@@ -615,24 +1495,35 @@ pub fn convert_unit_to_asm(unit: &[nodes::ToplevelItem]) -> Vec<instructions::In
                     f.name.clone(),
                 )));
             }
+            nodes::ToplevelItem::Data(_) => {}
         }
     }
 
     for tl in unit {
         if let nodes::ToplevelItem::Function(f) = tl {
-            instructions.extend(convert_function_to_asm(f, &lookup))
+            instructions.extend(convert_function_to_asm(f, &lookup, abi))
+        }
+    }
+
+    for tl in unit {
+        if let nodes::ToplevelItem::Data(data) = tl {
+            instructions.extend(convert_data_to_asm(data))
         }
     }
 
     instructions.push(Instruction::Directive(instructions::Directive::Section(
-        instructions::Section::TextCstring,
+        instructions::Section::TextConst,
     )));
 
-    for (counter, strlit) in lookup.string_literals_iter() {
-        instructions.push(instructions::Instruction::Label(format!("sl{}", counter)));
-        instructions.push(instructions::Instruction::Directive(
-            instructions::Directive::AsciiCString(strlit.to_owned()),
-        ));
+    for (counter, value, width) in lookup.floating_literals_iter() {
+        let (alignment, data) = match width {
+            Width::Single => (2, instructions::Directive::Long((value as f32).to_bits())),
+            Width::Double => (3, instructions::Directive::Quad(value.to_bits())),
+            _ => panic!("Not a floating width {:?}", width),
+        };
+        instructions.push(Instruction::Directive(instructions::Directive::Align(alignment)));
+        instructions.push(instructions::Instruction::Label(format!("fl{}", counter)));
+        instructions.push(instructions::Instruction::Directive(data));
     }
 
     instructions
@@ -646,3 +1537,144 @@ pub fn asm_into_text(instr: &[instructions::Instruction]) -> String {
     }
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{asm_into_text, convert_unit_to_asm};
+    use crate::{common::Abi, ir, opt};
+
+    /// `first` reads one variadic int after one named one
+    const VARIADIC: &str = "function w first (w %count) out_of_ssa {
+@start_function_first:
+\t%args.addr.0 =l alloca 32 align 8
+\tvastart %args.addr.0
+\t%_t2 =w vaarg %args.addr.0
+\treturn w %_t2
+}
+
+function w main () out_of_ssa {
+@start_function_main:
+\t%_t2 =w call $first with (param0 w #1, vparam1 w #42)
+\treturn w %_t2
+}";
+
+    /// `last` has a named parameter more than there are argument registers,
+    /// so both it and the variadic argument are passed on the stack
+    const STACKED: &str = "function w last (w %a, w %b, w %c, w %d, w %e, w %f, w %g, w %h, w %i) out_of_ssa {
+@start_function_last:
+\t%args.addr.0 =l alloca 32 align 8
+\tvastart %args.addr.0
+\t%_t2 =w vaarg %args.addr.0
+\treturn w %_t2
+}
+
+function w main () out_of_ssa {
+@start_function_main:
+\t%_t2 =w call $last with (param0 w #1, param1 w #2, param2 w #3, param3 w #4, param4 w #5, param5 w #6, \
+param6 w #7, param7 w #8, param8 w #9, vparam9 w #10)
+\treturn w %_t2
+}";
+
+    fn assembly(abi: Abi) -> Vec<String> {
+        assembly_of(VARIADIC, abi)
+    }
+
+    fn assembly_of(source: &str, abi: Abi) -> Vec<String> {
+        let unit = opt::run_o1(&ir::from_text(source).unwrap(), true).unwrap();
+        asm_into_text(&convert_unit_to_asm(&unit, "variadic.c", abi))
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn contains_in_order(asm: &[String], expected: &[&str]) -> bool {
+        let mut lines = asm.iter();
+        expected.iter().all(|line| lines.any(|l| l == line))
+    }
+
+    #[test]
+    fn aapcs64_va_start() {
+        // The save area is below the 32 bytes of the va_list, and one
+        // general register is taken by `count`
+        let asm = assembly(Abi::Aapcs64);
+        assert!(contains_in_order(&asm, &[
+            "sub sp, sp, 224",
+            "sub x10, x29, 224",
+            "stp x0, x1, [x10, 128]",
+            "stp x6, x7, [x10, 176]",
+            "str d0, [x10, 0]",
+            "str d7, [x10, 112]",
+            "sub x11, x29, 32",
+            "add x10, x29, 16",
+            "str x10, [x11, 0]",
+            "sub x10, x29, 32",
+            "str x10, [x11, 8]",
+            "sub x10, x29, 96",
+            "str x10, [x11, 16]",
+            "mov w10, -56",
+            "str w10, [x11, 24]",
+            "mov w10, -128",
+            "str w10, [x11, 28]",
+        ]));
+    }
+
+    #[test]
+    fn aapcs64_va_arg() {
+        let asm = assembly(Abi::Aapcs64);
+        assert!(contains_in_order(&asm, &[
+            "ldrsw x12, [x11, 24]",
+            "cmp w12, 0",
+            "bge L_first_va_arg_3_stack",
+            "add w10, w12, 8",
+            "str w10, [x11, 24]",
+            "cmp w10, 0",
+            "bgt L_first_va_arg_3_stack",
+            "ldr x10, [x11, 8]",
+            "add x12, x10, x12",
+            "b L_first_va_arg_3_done",
+            "L_first_va_arg_3_stack:",
+            "ldr x12, [x11, 0]",
+            "add x10, x12, 8",
+            "str x10, [x11, 0]",
+            "L_first_va_arg_3_done:",
+        ]));
+        let done = asm.iter().position(|line| line == "L_first_va_arg_3_done:").unwrap();
+        assert!(asm[done + 1].starts_with("ldr w") && asm[done + 1].ends_with(", [x12]"));
+    }
+
+    #[test]
+    fn aapcs64_stacked_arguments() {
+        // `i` and the variadic argument take a slot each, and va_start
+        // starts the stacked arguments past `i` with the general registers
+        // used up
+        let asm = assembly_of(STACKED, Abi::Aapcs64);
+        assert!(contains_in_order(&asm, &[
+            "ldr w10, [x29, 16]",
+            "add x10, x29, 24",
+            "str x10, [x11, 0]",
+            "mov w10, 0",
+            "str w10, [x11, 24]",
+            "mov w10, -128",
+            "str w10, [x11, 28]",
+        ]));
+        assert!(contains_in_order(&asm, &[
+            "mov w10, 9",
+            "str w10, [sp, 0]",
+            "mov w10, 10",
+            "str x10, [sp, 8]",
+            "mov w7, 8",
+            "bl _last",
+        ]));
+    }
+
+    #[test]
+    fn variadic_arguments_by_abi() {
+        // Apple puts the variadic argument in a stack slot of its own,
+        // AAPCS64 in the next register
+        let apple = assembly(Abi::Apple);
+        assert!(contains_in_order(&apple, &["sub sp, sp, 16", "mov w10, 42", "str x10, [sp, 0]", "mov w0, 1"]));
+        assert!(!apple.iter().any(|line| line.starts_with("stp x0, x1")));
+        let aapcs64 = assembly(Abi::Aapcs64);
+        assert!(contains_in_order(&aapcs64, &["mov w0, 1", "mov w1, 42", "bl _first"]));
+    }
+}
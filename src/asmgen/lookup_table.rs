@@ -1,112 +1,75 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::common::Width;
-use crate::ir::nodes::{self, AddressConstant};
-
-const STACK_ALIGN: usize = 16;
-
-#[derive(Debug, Clone)]
-pub enum SymbolAddress {
-    // VariableOffset(usize),
-    StringLiteral(usize),
-    SourceFunction(String),
-}
-
-#[derive(Debug, Clone)]
-pub struct SymbolInfo {
-    pub address: SymbolAddress,
-    pub width: Width,
-}
+use crate::ir::nodes;
 
 #[derive(Debug, Clone)]
 pub struct SymbolLookup {
-    lookup: HashMap<nodes::Address, SymbolInfo>,
+    // Floating constant pool, keyed by the bits of the f64 and the width it is loaded as
+    floating_literals: HashMap<(u64, Width), usize>,
+    // Data items defined in the unit, reached without going through the GOT
+    data: HashSet<String>,
 }
 
 impl SymbolLookup {
-    pub fn get(&self, key: &nodes::Address) -> Option<&SymbolInfo> {
-        self.lookup.get(key)
-    }
-
     pub fn global_from_unit(toplevels: &[nodes::ToplevelItem]) -> Self {
-        let mut lookup = HashMap::new();
-        let mut string_liter_count: usize = 0;
+        let mut floating_literals = HashMap::new();
+        let mut data = HashSet::new();
 
         for toplevel in toplevels {
             match toplevel {
-                nodes::ToplevelItem::Declaration(decl) => match decl {
-                    nodes::ToplevelDeclaration::Function {
-                        storage_class: _,
-                        name,
-                        return_width: _,
-                        parameters: _,
-                    } => {
-                        let addr = nodes::Address::source_count(name.clone(), 0);
-                        let info = SymbolInfo {
-                            address: SymbolAddress::SourceFunction(name.clone()),
-                            width: Width::Long,
-                        };
-                        lookup.insert(addr, info);
-                    }
-                },
+                nodes::ToplevelItem::Declaration(_) => {}
                 nodes::ToplevelItem::Function(func) => {
-                    let addr = nodes::Address::source_count(func.name.clone(), 0);
-                    let info = SymbolInfo {
-                        address: SymbolAddress::SourceFunction(func.name.clone()),
-                        width: Width::Long,
-                    };
-                    lookup.insert(addr, info);
-
-                    for b in func.body.iter() {
-                        match &b {
-                            nodes::Ssa::Assignment {
-                                dest: _,
-                                source,
-                                width: _,
-                            } => match &source {
-                                nodes::Address::CompilerTemp(_)
-                                | nodes::Address::Source(_)
-                                | nodes::Address::Constant(nodes::AddressConstant::Numeric(_)) => {}
-                                nodes::Address::Constant(
-                                    nodes::AddressConstant::StringLiteral(_),
-                                ) => {
-                                    let info = SymbolInfo {
-                                        address: SymbolAddress::StringLiteral(string_liter_count),
-                                        width: Width::Long,
-                                    };
-                                    lookup.insert(source.clone(), info);
-                                    string_liter_count += 1;
-                                }
-                            },
+                    let instructions = func.body.blocks().flat_map(|(_, block)| {
+                        block.body.iter().map(|i| &i.ssa).chain(std::iter::once(block.terminator()))
+                    });
+                    for ssa in instructions {
+                        // Floating constants are loaded from the pool wherever
+                        // they are an operand, at the width they are read as
+                        let constants: Vec<(&nodes::Address, Width)> = match ssa {
+                            nodes::Ssa::Assignment { source, width, .. } => vec![(source, *width)],
+                            nodes::Ssa::Store { value, width, .. } => vec![(value, *width)],
+                            nodes::Ssa::Return { value: Some((value, width)) } => vec![(value, *width)],
+                            nodes::Ssa::Quadriplet(quad) => [Some(&quad.left), quad.right.as_ref()]
+                                .into_iter()
+                                .flatten()
+                                .map(|address| (address, quad.width))
+                                .collect(),
+                            nodes::Ssa::Call { parameters, .. } => {
+                                parameters.iter().map(|p| (&p.value, p.width)).collect()
+                            }
                             _ => continue,
+                        };
+                        for (address, width) in constants {
+                            if let nodes::Address::Constant(nodes::AddressConstant::Floating(bits)) = address {
+                                let count = floating_literals.len();
+                                floating_literals.entry((*bits, width)).or_insert(count);
+                            }
                         }
                     }
                 }
+                nodes::ToplevelItem::Data(item) => {
+                    data.insert(item.name.clone());
+                }
             }
         }
-        SymbolLookup { lookup }
-    }
-
-    pub fn extend_with_global(self, global: Self) -> Self {
-        let mut joint_lookup = self.lookup;
-        joint_lookup.extend(global.lookup);
         SymbolLookup {
-            lookup: joint_lookup,
+            floating_literals,
+            data,
         }
     }
 
-    pub fn string_literals_iter(&self) -> impl Iterator<Item = (usize, &str)> {
-        self.lookup.iter().filter_map(|(addr, symbol_info)| {
-            if let SymbolAddress::StringLiteral(counter) = symbol_info.address {
-                match addr {
-                    nodes::Address::Constant(AddressConstant::StringLiteral(sl)) => {
-                        Some((counter, sl.as_str()))
-                    }
-                    _ => panic!("Not a string literal"),
-                }
-            } else {
-                None
-            }
-        })
+    pub fn is_data(&self, name: &str) -> bool {
+        self.data.contains(name)
+    }
+
+    pub fn floating_literal(&self, bits: u64, width: Width) -> usize {
+        self.floating_literals[&(bits, width)]
+    }
+
+    pub fn floating_literals_iter(&self) -> impl Iterator<Item = (usize, f64, Width)> {
+        self.floating_literals
+            .iter()
+            .map(|(&(bits, width), &counter)| (counter, f64::from_bits(bits), width))
     }
 }
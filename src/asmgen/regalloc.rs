@@ -1,10 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{asmgen::aarch64::instructions::Register, common::Width, ir::nodes::{self, Address, Ssa}};
+use crate::{asmgen::aarch64::instructions::Register, common::Width, ir::{Cfg, nodes::{Address, Ssa}}};
 
 pub fn alive_addresses_in_ssa(ssa: &Ssa) -> Vec<Address> {
     match ssa {
-        Ssa::Assignment { dest, source, width: _ } => {
+        Ssa::Assignment { dest, source, width: _ }
+        | Ssa::Extend { dest, source, .. }
+        | Ssa::Truncate { dest, source, .. }
+        | Ssa::Convert { dest, source, .. }
+        | Ssa::VaArg { dest, list: source, .. }
+        | Ssa::Load { dest, address: source, .. } => {
             if matches!(source, Address::Constant(_)) {
                 vec![dest.clone()]
             } else {
@@ -19,21 +24,33 @@ pub fn alive_addresses_in_ssa(ssa: &Ssa) -> Vec<Address> {
             if let Some(d) = dest {
                 res.push(d.0.clone());
             }
-            if matches!(func, Address::CompilerTemp(_)) {
+            if !matches!(func, Address::Constant(_)) {
                 res.push(func.clone());
             }
             for p in parameters {
-                res.push(p.value.clone());
+                if !matches!(p.value, Address::Constant(_)) {
+                    res.push(p.value.clone());
+                }
             }
             res
         },
+        Ssa::VaStart { list: Address::Constant(_) } => vec![],
+        Ssa::VaStart { list } => vec![list.clone()],
+        Ssa::Alloca { dest, .. } => vec![dest.clone()],
+        Ssa::Store { address, value, .. } => [address, value]
+            .into_iter()
+            .filter(|a| !matches!(a, Address::Constant(_)))
+            .cloned()
+            .collect(),
         Ssa::Jump(_) => vec![],
         Ssa::Label(_) => vec![],
         Ssa::Phi(_) => panic!("Phis should be eliminated at this point..."),
         Ssa::Quadriplet(q) => {
             let mut res = vec![];
             res.push(q.dest.clone());
-            res.push(q.left.clone());
+            if !matches!(q.left, Address::Constant(_)) {
+                res.push(q.left.clone());
+            }
             if let Some(qr) = &q.right && !matches!(qr, Address::Constant(_)){
                 res.push(qr.clone());
             }
@@ -61,18 +78,38 @@ impl Lifetime {
     }
 }
 
+/// Instructions of the function as `Cfg::to_linear` lays them out, from
+/// the label of each block to its terminator
+fn block_spans(cfg: &Cfg) -> HashMap<usize, Lifetime> {
+    let mut spans = HashMap::new();
+    let mut start = 0;
+    for (id, block) in cfg.blocks() {
+        let end = start + block.instructions().len();
+        spans.insert(id.index(), Lifetime { start, end });
+        start = end + 1;
+    }
+    spans
+}
+
 pub fn analyze_lifetimes(
+    cfg: &Cfg,
     body: &[Ssa],
-    parameters: &[String]
+    parameters: &[String],
+    stack_parameters: &[String],
 ) -> HashMap<Address, Lifetime> {
     let mut lifetimes = HashMap::new();
+    // Parameters passed on the stack are loaded on entry
+    for p in stack_parameters {
+        lifetimes.insert(Address::source_count(p.clone(), 0), Lifetime { start: 0, end: 0 });
+    }
 
     for (idx, b) in body.iter().enumerate() {
         let alive_here = alive_addresses_in_ssa(b);
         for a in alive_here.iter() {
+            // Parameters arrive in their precolored register as version 0
             let mut need_add = true;
             for p in parameters {
-                if let Some(svar)  = a.try_get_source() && svar == p {
+                if let Address::Source((svar, 0)) = a && svar.as_str() == p {
                     need_add = false;
                     break;
                 }
@@ -84,9 +121,88 @@ pub fn analyze_lifetimes(
         }
     }
 
+    extend_lifetimes_over_loops(cfg, &mut lifetimes);
+
     lifetimes
 }
 
+/// Values that enter a loop and are still alive inside it may be read again
+/// after the back edge, so they have to stay alive until the end of the loop
+fn extend_lifetimes_over_loops(cfg: &Cfg, lifetimes: &mut HashMap<Address, Lifetime>) {
+    let spans = block_spans(cfg);
+    let loops: Vec<(usize, usize)> = cfg
+        .loops()
+        .loops()
+        .iter()
+        .map(|l| {
+            let start = l.blocks.iter().map(|b| spans[b].start).min().unwrap();
+            let end = l.blocks.iter().map(|b| spans[b].end).max().unwrap();
+            (start, end)
+        })
+        .collect();
+
+    // Extending a lifetime over an inner loop may make it reach an outer one
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(start, end) in loops.iter() {
+            for lifetime in lifetimes.values_mut() {
+                if lifetime.start < start && lifetime.end >= start && lifetime.end < end {
+                    lifetime.end = end;
+                    changed = true;
+                }
+            }
+        }
+    }
+}
+
+/// Each use of a value inside a loop counts this much more than one outside of it
+const LOOP_WEIGHT: usize = 10;
+
+/// What keeping each address in memory costs: the number of its uses,
+/// uses inside loops weighing `LOOP_WEIGHT` times more per nesting level
+pub fn spill_weights(cfg: &Cfg) -> HashMap<Address, usize> {
+    let loops = cfg.loops();
+    let mut weights: HashMap<Address, usize> = HashMap::new();
+    for (id, block) in cfg.blocks() {
+        let weight = LOOP_WEIGHT.saturating_pow(loops.depth(id.index()) as u32);
+        for instruction in block.instructions() {
+            for address in alive_addresses_in_ssa(&instruction.ssa) {
+                let total = weights.entry(address).or_default();
+                *total = total.saturating_add(weight);
+            }
+        }
+    }
+    weights
+}
+
+/// Addresses that live in the floating point registers,
+/// everything else is allocated to general purpose ones
+pub fn floating_addresses(body: &[Ssa], parameters: &[(String, Width)]) -> HashSet<Address> {
+    let mut floating: HashSet<Address> = parameters
+        .iter()
+        .filter(|(_, width)| width.is_floating())
+        .map(|(name, _)| Address::source_count(name.clone(), 0))
+        .collect();
+
+    for ssa in body {
+        let definition = match ssa {
+            Ssa::Assignment { dest, source: _, width }
+            | Ssa::Convert { dest, width, .. }
+            | Ssa::VaArg { dest, width, .. }
+            | Ssa::Load { dest, width, .. } => Some((dest, *width)),
+            Ssa::Quadriplet(q) if !q.op.is_cmp() => Some((&q.dest, q.width)),
+            Ssa::Call { dest: Some((dest, width)), .. } => Some((dest, *width)),
+            _ => None,
+        };
+        if let Some((dest, width)) = definition && width.is_floating() {
+            floating.insert(dest.clone());
+        }
+    }
+
+    floating
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Location {
     Reg(Register),
@@ -128,7 +244,7 @@ impl LinearScanRegisterAlloc {
     }
 
     pub fn stack_size(&self) -> usize {
-        self.next_spill_slot.abs() as usize
+        self.next_spill_slot.unsigned_abs() as usize
     }
 
     pub fn used_registers_at(&self, instr_idx: usize) -> Vec<Register> {
@@ -162,7 +278,22 @@ impl LinearScanRegisterAlloc {
         }
     }
 
-    pub fn linear_scan(&mut self, lifetimes: &HashMap<Address, Lifetime>) {
+    fn next_spill_location(&mut self) -> Location {
+        let spill_off = self.next_spill_slot;
+        self.next_spill_slot += 8; // assuming 8-byte slots
+        Location::Spill(spill_off)
+    }
+
+    /// When every register is taken, the cheapest of the values alive at
+    /// that point according to `weights` goes to memory
+    pub fn linear_scan(
+        &mut self,
+        lifetimes: &HashMap<Address, Lifetime>,
+        floating: &HashSet<Address>,
+        weights: &HashMap<Address, usize>,
+    ) {
+        let weight = |address: &Address| weights.get(address).copied().unwrap_or_default();
+
         // Sort intervals by start time
         let mut intervals: Vec<_> = lifetimes.iter().collect();
         intervals.sort_by_key(|(_, lt)| lt.start);
@@ -174,8 +305,10 @@ impl LinearScanRegisterAlloc {
             active.retain(|(_, lt, _)| lt.end >= lifetime.start);
 
             // try allocate register
+            let is_floating = floating.contains(addr);
             if let Some(&reg) = self.available_regs
                 .iter()
+                .filter(|r| r.is_floating() == is_floating)
                 .find(|r| !active.iter().any(|(_, _, ar)| ar == *r))
             {
                 self.allocations.insert(addr.clone(), Allocation {
@@ -184,13 +317,35 @@ impl LinearScanRegisterAlloc {
                 });
                 active.push((addr.clone(), *lifetime, reg));
             } else {
-                // Spill this variable
-                let spill_off = self.next_spill_slot;
-                self.next_spill_slot += 8; // assuming 8-byte slots
-                self.allocations.insert(addr.clone(), Allocation {
-                    loc: Location::Spill(spill_off),
-                    lifetime: *lifetime,
-                });
+                let cheapest = active
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, _, reg))| reg.is_floating() == is_floating)
+                    .min_by_key(|(_, (active_addr, _, _))| weight(active_addr))
+                    .map(|(position, (active_addr, _, _))| (position, weight(active_addr)));
+
+                match cheapest {
+                    // Allocations hold for a whole lifetime and no code is generated
+                    // yet, so the active value can still live in memory all along
+                    Some((position, cheapest_weight)) if cheapest_weight < weight(addr) => {
+                        let (spilled, _, reg) = active.remove(position);
+                        let loc = self.next_spill_location();
+                        self.allocations.get_mut(&spilled).unwrap().loc = loc;
+                        self.allocations.insert(addr.clone(), Allocation {
+                            loc: Location::Reg(reg),
+                            lifetime: *lifetime,
+                        });
+                        active.push((addr.clone(), *lifetime, reg));
+                    }
+                    _ => {
+                        // Spill this variable
+                        let loc = self.next_spill_location();
+                        self.allocations.insert(addr.clone(), Allocation {
+                            loc,
+                            lifetime: *lifetime,
+                        });
+                    }
+                }
             }
         }
 
@@ -1,25 +1,94 @@
-use crate::semantic_analysis::{SymbolKind, SymbolType};
+use std::fmt;
+
+use crate::semantic_analysis::SymbolType;
+
+/// Arguments of each class, integer and floating point, passed in registers,
+/// x0-x7 and v0-v7. Further ones are passed on the stack.
+pub const ARGUMENT_REGISTERS: usize = 8;
+
+/// Calling convention for variadic functions, the part of the arm64 ABI
+/// the platforms differ in
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Abi {
+    /// Every variadic argument in an 8 byte stack slot, `va_list` is a
+    /// `char *` walking them
+    Apple,
+    /// Variadic arguments in registers like the fixed ones. `va_start`
+    /// reads them from a register save area the function fills on entry.
+    Aapcs64,
+}
+
+/// Position in the source file, lines and columns start at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum StorageClass {
     Auto,
     Extern,
+    /// Stored for the whole program, and only visible in its unit
+    Static,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signedness {
+    Signed,
+    Unsigned,
+}
+
+impl Signedness {
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Self::Signed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Width {
     Word,
     Long,
     Short,
     Byte,
+    Single,
+    Double,
 }
 
 impl Width {
     pub fn from_type(symbol: &SymbolType) -> Self {
         match symbol {
-            SymbolType::Int => Self::Word,
-            SymbolType::Char => Self::Byte,
-            SymbolType::Pointer(_) => Self::Long,
+            SymbolType::Char(_) => Self::Byte,
+            SymbolType::Short(_) => Self::Short,
+            SymbolType::Int(_) => Self::Word,
+            SymbolType::Long(_) | SymbolType::LongLong(_) => Self::Long,
+            SymbolType::Float => Self::Single,
+            SymbolType::Double => Self::Double,
+            // Arrays and structs are handled through their address
+            SymbolType::Pointer(_) | SymbolType::Array(..) | SymbolType::Struct(_) => Self::Long,
+            SymbolType::Void => panic!("Void has no value"),
+            SymbolType::Function { .. } => panic!("Functions are only used through pointers"),
+        }
+    }
+
+    /// Keeps the low bits of `value` that fit this width and
+    /// sign or zero extends them back to 64 bits
+    pub fn extend(&self, value: i64, signedness: Signedness) -> i64 {
+        let bits = self.to_bytes() * 8;
+        if bits == 64 {
+            return value;
+        }
+        let truncated = value & ((1 << bits) - 1);
+        if signedness.is_signed() && truncated >> (bits - 1) == 1 {
+            truncated - (1 << bits)
+        } else {
+            truncated
         }
     }
 
@@ -27,8 +96,12 @@ impl Width {
         match self {
             Self::Byte => 1,
             Self::Short => 2,
-            Self::Word => 4,
-            Self::Long => 8,
+            Self::Word | Self::Single => 4,
+            Self::Long | Self::Double => 8,
         }
     }
+
+    pub fn is_floating(&self) -> bool {
+        matches!(self, Self::Single | Self::Double)
+    }
 }
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+
+use crate::{
+    common::SourceLocation,
+    ir::{DominatorTree, IrTextRepr, LoopForest, nodes},
+};
+
+/// Identifies a block for the whole life of a function: blocks keep their
+/// id when others are added or removed, and ids are never handed out twice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(usize);
+
+impl BlockId {
+    /// Position of the block in the vectors analyses keep per block,
+    /// up to `Cfg::id_bound`
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+fn is_terminator(ssa: &nodes::Ssa) -> bool {
+    matches!(
+        ssa,
+        nodes::Ssa::Jump(_) | nodes::Ssa::Branch { .. } | nodes::Ssa::Return { .. }
+    )
+}
+
+/// Labels a terminator goes to, in order
+fn targets(terminator: &nodes::Ssa) -> Vec<&nodes::Label> {
+    match terminator {
+        nodes::Ssa::Jump(target) => vec![target],
+        nodes::Ssa::Branch {
+            true_target,
+            false_target,
+            ..
+        } => vec![true_target, false_target],
+        _ => vec![],
+    }
+}
+
+/// A basic block: phis taking their values on entry, straight-line
+/// instructions, then the jump, branch or return leaving it
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub label: nodes::Label,
+    pub phis: Vec<nodes::PhiFunction>,
+    /// Neither labels, phis nor terminators
+    pub body: Vec<nodes::Instruction>,
+    // Changed through the graph only, which keeps the edges up to date
+    terminator: nodes::Instruction,
+}
+
+impl Block {
+    pub fn terminator(&self) -> &nodes::Ssa {
+        &self.terminator.ssa
+    }
+
+    pub fn terminator_location(&self) -> Option<SourceLocation> {
+        self.terminator.location
+    }
+
+    /// Operands of the terminator. Its targets are set with `Cfg::set_terminator`.
+    pub fn terminator_operands_mut(&mut self) -> Vec<&mut nodes::Address> {
+        self.terminator.ssa.used_addresses_mut()
+    }
+
+    /// Everything the block runs, phis first and the terminator last.
+    /// Phis merge values from several places and have no location.
+    pub fn instructions(&self) -> Vec<nodes::Instruction> {
+        self.phis
+            .iter()
+            .cloned()
+            .map(|phi| nodes::Ssa::Phi(phi).into())
+            .chain(self.body.iter().cloned())
+            .chain(std::iter::once(self.terminator.clone()))
+            .collect()
+    }
+
+    /// Takes back instructions as `instructions` gives them, once a pass
+    /// rewrote them without touching the terminator targets
+    pub fn set_instructions(&mut self, instructions: Vec<nodes::Instruction>) {
+        let mut phis = vec![];
+        let mut body = vec![];
+        for instruction in instructions {
+            match instruction.ssa {
+                nodes::Ssa::Phi(phi) => phis.push(phi),
+                ref ssa if is_terminator(ssa) => self.terminator = instruction,
+                _ => body.push(instruction),
+            }
+        }
+        self.phis = phis;
+        self.body = body;
+    }
+}
+
+/// Control flow graph of a function body. Blocks are kept in the order
+/// they are laid out in, the entry block first, with the edges between
+/// them. Also hands out the temporaries and labels passes add, so that
+/// they never clash with the ones already there.
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    // By id, None for the blocks removed
+    blocks: Vec<Option<Block>>,
+    layout: Vec<BlockId>,
+    labels: HashMap<nodes::Label, BlockId>,
+    successors: Vec<Vec<BlockId>>,
+    predecessors: Vec<Vec<BlockId>>,
+    next_temp: usize,
+    next_label: usize,
+}
+
+impl Cfg {
+    /// Splits instructions into blocks, each starting with a label and
+    /// ending in a jump, branch or return, with phis before anything
+    /// else. Lists everything breaking these rules otherwise.
+    pub fn from_linear(instructions: Vec<nodes::Instruction>) -> Result<Self, Vec<String>> {
+        let mut errors = vec![];
+        let mut cfg = Cfg {
+            blocks: vec![],
+            layout: vec![],
+            labels: HashMap::new(),
+            successors: vec![],
+            predecessors: vec![],
+            next_temp: 0,
+            next_label: 0,
+        };
+
+        let mut instructions = instructions.into_iter().peekable();
+        if !matches!(instructions.peek().map(|i| &i.ssa), Some(nodes::Ssa::Label(_))) {
+            return Err(vec!["function body does not start with a label".into()]);
+        }
+
+        while let Some(nodes::Instruction {
+            ssa: nodes::Ssa::Label(label),
+            ..
+        }) = instructions.next()
+        {
+            let name = label.to_ir_string();
+            let mut block = Block {
+                label,
+                phis: vec![],
+                body: vec![],
+                terminator: nodes::Ssa::Return { value: None }.into(),
+            };
+            let mut terminated = false;
+            let mut continues = false;
+            let mut phi_after_instructions = false;
+            while let Some(instruction) = instructions.next_if(|i| !matches!(i.ssa, nodes::Ssa::Label(_))) {
+                match instruction.ssa {
+                    _ if terminated => continues = true,
+                    nodes::Ssa::Phi(phi) => {
+                        phi_after_instructions |= !block.body.is_empty();
+                        block.phis.push(phi);
+                    }
+                    ref ssa if is_terminator(ssa) => {
+                        block.terminator = instruction;
+                        terminated = true;
+                    }
+                    _ => block.body.push(instruction),
+                }
+            }
+            if !terminated {
+                errors.push(format!("block @{} does not end with a terminator", name));
+            }
+            if continues {
+                errors.push(format!(
+                    "block @{} continues after its terminator `{}`",
+                    name,
+                    block.terminator.ssa.to_ir_string().trim()
+                ));
+            }
+            if phi_after_instructions {
+                errors.push(format!("block @{} has a phi after other instructions", name));
+            }
+
+            let id = BlockId(cfg.blocks.len());
+            if cfg.labels.insert(block.label.clone(), id).is_some() {
+                errors.push(format!("label @{} is defined twice", name));
+            }
+            cfg.blocks.push(Some(block));
+            cfg.layout.push(id);
+            cfg.successors.push(vec![]);
+            cfg.predecessors.push(vec![]);
+        }
+
+        for id in cfg.layout.clone() {
+            let block = cfg.block(id);
+            let unknown: Vec<String> = targets(&block.terminator.ssa)
+                .into_iter()
+                .filter(|target| !cfg.labels.contains_key(target))
+                .map(|target| {
+                    format!(
+                        "block @{} jumps to unknown label @{}",
+                        block.label.to_ir_string(),
+                        target.to_ir_string()
+                    )
+                })
+                .collect();
+            if unknown.is_empty() {
+                cfg.connect(id);
+            }
+            errors.extend(unknown);
+        }
+
+        for instruction in cfg.to_linear() {
+            match instruction.ssa {
+                nodes::Ssa::Label(nodes::Label::CompilerTemp(n)) => {
+                    cfg.next_label = cfg.next_label.max(n + 1)
+                }
+                ssa => {
+                    for address in ssa.defined_address().into_iter().chain(ssa.used_addresses()) {
+                        if let nodes::Address::CompilerTemp(n) = address {
+                            cfg.next_temp = cfg.next_temp.max(n + 1);
+                        }
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(cfg)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Blocks one after the other, each with its label first
+    pub fn to_linear(&self) -> Vec<nodes::Instruction> {
+        let mut out = vec![];
+        for (_, block) in self.blocks() {
+            out.push(nodes::Ssa::Label(block.label.clone()).into());
+            out.extend(block.instructions());
+        }
+        out
+    }
+
+    /// Adds the edges leaving a block from its terminator
+    fn connect(&mut self, id: BlockId) {
+        let successors: Vec<BlockId> = targets(&self.block(id).terminator.ssa)
+            .into_iter()
+            .map(|target| self.labels[target])
+            .collect();
+        for &successor in &successors {
+            let predecessors = &mut self.predecessors[successor.0];
+            if !predecessors.contains(&id) {
+                predecessors.push(id);
+            }
+        }
+        self.successors[id.0] = successors;
+    }
+
+    fn disconnect(&mut self, id: BlockId) {
+        for successor in std::mem::take(&mut self.successors[id.0]) {
+            self.predecessors[successor.0].retain(|&p| p != id);
+        }
+    }
+
+    /// The block control enters the function at. It keeps the first id.
+    pub fn entry(&self) -> BlockId {
+        BlockId(0)
+    }
+
+    /// Ids of the blocks in layout order
+    pub fn layout(&self) -> &[BlockId] {
+        &self.layout
+    }
+
+    /// Blocks in layout order
+    pub fn blocks(&self) -> impl Iterator<Item = (BlockId, &Block)> {
+        self.layout.iter().map(|&id| (id, self.block(id)))
+    }
+
+    /// Blocks in layout order, to change their phis and instructions
+    pub fn blocks_mut(&mut self) -> impl Iterator<Item = (BlockId, &mut Block)> {
+        let mut by_id: Vec<Option<&mut Block>> = self.blocks.iter_mut().map(Option::as_mut).collect();
+        self.layout.iter().map(move |&id| (id, by_id[id.0].take().unwrap()))
+    }
+
+    pub fn block(&self, id: BlockId) -> &Block {
+        self.blocks[id.0].as_ref().expect("Block was removed")
+    }
+
+    pub fn block_mut(&mut self, id: BlockId) -> &mut Block {
+        self.blocks[id.0].as_mut().expect("Block was removed")
+    }
+
+    /// Block of an index the analyses working on plain graphs give back
+    pub fn block_by_index(&self, index: usize) -> &Block {
+        self.block(BlockId(index))
+    }
+
+    pub fn block_of(&self, label: &nodes::Label) -> Option<BlockId> {
+        self.labels.get(label).copied()
+    }
+
+    /// Number of ids handed out so far, removed blocks included
+    pub fn id_bound(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Blocks the terminator of `id` goes to, true target first
+    pub fn successors(&self, id: BlockId) -> &[BlockId] {
+        &self.successors[id.0]
+    }
+
+    /// Blocks going to `id`, each once
+    pub fn predecessors(&self, id: BlockId) -> &[BlockId] {
+        &self.predecessors[id.0]
+    }
+
+    /// Replaces the terminator of a block and the edges leaving it, keeping
+    /// its location. The targets must be blocks of the graph.
+    pub fn set_terminator(&mut self, id: BlockId, terminator: nodes::Ssa) {
+        assert!(is_terminator(&terminator), "Not a terminator");
+        self.disconnect(id);
+        self.block_mut(id).terminator.ssa = terminator;
+        self.connect(id);
+    }
+
+    /// Adds a block with a new label right after `after` in the layout
+    pub fn insert_block_after(
+        &mut self,
+        after: BlockId,
+        body: Vec<nodes::Instruction>,
+        terminator: nodes::Instruction,
+    ) -> BlockId {
+        let id = BlockId(self.blocks.len());
+        let label = nodes::Label::compiler_temp(self.next_label);
+        self.next_label += 1;
+        self.labels.insert(label.clone(), id);
+        self.blocks.push(Some(Block {
+            label,
+            phis: vec![],
+            body,
+            terminator: nodes::Instruction::new(nodes::Ssa::Return { value: None }, terminator.location),
+        }));
+        self.successors.push(vec![]);
+        self.predecessors.push(vec![]);
+        let position = self.layout.iter().position(|&b| b == after).expect("Block was removed");
+        self.layout.insert(position + 1, id);
+        self.set_terminator(id, terminator.ssa);
+        id
+    }
+
+    /// Drops the blocks control cannot reach from the entry
+    pub fn remove_unreachable(&mut self) {
+        let mut reachable = vec![false; self.id_bound()];
+        let mut worklist = vec![self.entry()];
+        reachable[self.entry().0] = true;
+        while let Some(id) = worklist.pop() {
+            for &successor in &self.successors[id.0] {
+                if !reachable[successor.0] {
+                    reachable[successor.0] = true;
+                    worklist.push(successor);
+                }
+            }
+        }
+
+        for id in self.layout.clone() {
+            if reachable[id.0] {
+                continue;
+            }
+            self.disconnect(id);
+            let block = self.blocks[id.0].take().unwrap();
+            self.labels.remove(&block.label);
+        }
+        self.layout.retain(|id| reachable[id.0]);
+    }
+
+    /// A temporary no instruction uses yet
+    pub fn fresh_temp(&mut self) -> nodes::Address {
+        self.next_temp += 1;
+        nodes::Address::compiler_temp(self.next_temp - 1)
+    }
+
+    /// Successors by block index, for the analyses working on plain graphs
+    pub fn successor_graph(&self) -> Vec<Vec<usize>> {
+        self.successors
+            .iter()
+            .map(|successors| successors.iter().map(|s| s.0).collect())
+            .collect()
+    }
+
+    pub fn dominators(&self) -> DominatorTree {
+        DominatorTree::dominators(&self.successor_graph())
+    }
+
+    pub fn post_dominators(&self) -> DominatorTree {
+        DominatorTree::post_dominators(&self.successor_graph())
+    }
+
+    pub fn loops(&self) -> LoopForest {
+        let successors = self.successor_graph();
+        LoopForest::new(&successors, &DominatorTree::dominators(&successors))
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    common::Width,
+    ir::{BlockId, Cfg, nodes},
+};
+
+/// SSA construction after Braun et al., "Simple and Efficient Construction
+/// of Static Single Assignment Form". Every block knows the last version
+/// of each variable it writes, reads of other variables look the value
+/// up in the predecessors, placing a phi where several of them meet.
+struct Construction {
+    cfg: Cfg,
+    parameters: HashSet<String>,
+    widths: HashMap<String, Width>,
+    versions: HashMap<String, usize>,
+    // Last version a block writes of a variable
+    exit_values: HashMap<BlockId, HashMap<String, nodes::Address>>,
+    // Version of a variable when entering a block
+    entry_values: HashMap<(String, BlockId), nodes::Address>,
+    // Variables read before any write get zero, defined in the entry block
+    undefined: Vec<nodes::Ssa>,
+}
+
+impl Construction {
+    fn new_version(&mut self, name: &str) -> nodes::Address {
+        let version = self.versions.entry(name.to_owned()).or_default();
+        *version += 1;
+        nodes::Address::source_count(name.to_owned(), *version)
+    }
+
+    fn read_at_exit(&mut self, name: &str, block: BlockId) -> nodes::Address {
+        match self.exit_values.get(&block).and_then(|values| values.get(name)) {
+            Some(address) => address.clone(),
+            None => self.read_at_entry(name, block),
+        }
+    }
+
+    fn read_at_entry(&mut self, name: &str, block: BlockId) -> nodes::Address {
+        let key = (name.to_owned(), block);
+        if let Some(address) = self.entry_values.get(&key) {
+            return address.clone();
+        }
+
+        let predecessors = self.cfg.predecessors(block).to_vec();
+        let value = match predecessors.as_slice() {
+            [] if self.parameters.contains(name) => nodes::Address::source_count(name.to_owned(), 0),
+            [] => {
+                let dest = self.new_version(name);
+                let width = self.widths[name];
+                let zero = if width.is_floating() {
+                    nodes::Address::constant_f64(0.0)
+                } else {
+                    nodes::Address::constant_i64(0)
+                };
+                self.undefined.push(nodes::Ssa::Assignment {
+                    dest: dest.clone(),
+                    source: zero,
+                    width,
+                });
+                dest
+            }
+            [predecessor] => self.read_at_exit(name, *predecessor),
+            _ => {
+                // The phi is the value before its operands are read, which
+                // ends the lookup around loops
+                let dest = self.new_version(name);
+                self.entry_values.insert(key.clone(), dest.clone());
+                let merging = predecessors
+                    .iter()
+                    .map(|&predecessor| {
+                        (
+                            self.read_at_exit(name, predecessor),
+                            self.cfg.block(predecessor).label.clone(),
+                        )
+                    })
+                    .collect();
+                self.cfg.block_mut(block).phis.push(nodes::PhiFunction {
+                    dest: dest.clone(),
+                    width: self.widths[name],
+                    merging,
+                });
+                dest
+            }
+        };
+        self.entry_values.insert(key, value.clone());
+        value
+    }
+
+    /// Gives every write a new version, so that the value a block leaves
+    /// behind is known before any read is looked up
+    fn number_definitions(&mut self) {
+        for id in self.cfg.layout().to_vec() {
+            let mut body = std::mem::take(&mut self.cfg.block_mut(id).body);
+            for ssa in body.iter_mut().map(|instruction| &mut instruction.ssa) {
+                if let Some(dest) = ssa.defined_address_mut()
+                    && let Some(name) = dest.try_get_source().map(String::from)
+                {
+                    *dest = self.new_version(&name);
+                    self.exit_values.entry(id).or_default().insert(name, dest.clone());
+                }
+            }
+            self.cfg.block_mut(id).body = body;
+        }
+    }
+
+    fn rename_uses(&mut self) {
+        for id in self.cfg.layout().to_vec() {
+            let mut block = self.cfg.block(id).clone();
+            let mut current: HashMap<String, nodes::Address> = HashMap::new();
+            for ssa in block.body.iter_mut().map(|instruction| &mut instruction.ssa) {
+                for operand in ssa.used_addresses_mut() {
+                    if let Some(name) = operand.try_get_source().map(String::from) {
+                        *operand = match current.get(&name) {
+                            Some(address) => address.clone(),
+                            None => self.read_at_entry(&name, id),
+                        };
+                    }
+                }
+                if let Some(dest) = ssa.defined_address()
+                    && let Some(name) = dest.try_get_source()
+                {
+                    current.insert(name.to_owned(), dest.clone());
+                }
+            }
+            for operand in block.terminator_operands_mut() {
+                if let Some(name) = operand.try_get_source().map(String::from) {
+                    *operand = match current.get(&name) {
+                        Some(address) => address.clone(),
+                        None => self.read_at_entry(&name, id),
+                    };
+                }
+            }
+            // Reads looking further up may have placed phis in this block
+            block.phis = std::mem::take(&mut self.cfg.block_mut(id).phis);
+            *self.cfg.block_mut(id) = block;
+        }
+    }
+
+    /// Versions are handed out while looking up reads, they are numbered
+    /// again in body order. Parameters keep version 0 for their value on entry.
+    fn renumber(&mut self) {
+        let mut versions: HashMap<String, usize> = self
+            .parameters
+            .iter()
+            .map(|name| (name.clone(), 1))
+            .collect();
+        let mut renamed: HashMap<nodes::Address, nodes::Address> = HashMap::new();
+
+        let phis = self.cfg.blocks().map(|(_, block)| block.phis.iter().map(|phi| &phi.dest));
+        let undefined = self.undefined.iter().filter_map(|ssa| ssa.defined_address());
+        let definitions = self.cfg.blocks().map(|(_, block)| {
+            block.body.iter().filter_map(|instruction| instruction.ssa.defined_address())
+        });
+        let mut ordered: Vec<&nodes::Address> = vec![];
+        for (idx, (phis, definitions)) in phis.zip(definitions).enumerate() {
+            ordered.extend(phis);
+            if idx == 0 {
+                ordered.extend(undefined.clone());
+            }
+            ordered.extend(definitions);
+        }
+        for address in ordered {
+            if let Some(name) = address.try_get_source() {
+                let version = versions.entry(name.to_owned()).or_default();
+                renamed.insert(
+                    address.clone(),
+                    nodes::Address::source_count(name.to_owned(), *version),
+                );
+                *version += 1;
+            }
+        }
+
+        let rename = |address: &mut nodes::Address| {
+            if let Some(new) = renamed.get(address) {
+                *address = new.clone();
+            }
+        };
+        for (_, block) in self.cfg.blocks_mut() {
+            for phi in &mut block.phis {
+                rename(&mut phi.dest);
+                phi.merging.iter_mut().for_each(|(address, _)| rename(address));
+            }
+            block.terminator_operands_mut().into_iter().for_each(rename);
+        }
+        for (_, block) in self.cfg.blocks_mut() {
+            for ssa in block.body.iter_mut().map(|instruction| &mut instruction.ssa) {
+                ssa.defined_address_mut().into_iter().for_each(rename);
+                ssa.used_addresses_mut().into_iter().for_each(rename);
+            }
+        }
+        for ssa in self.undefined.iter_mut() {
+            ssa.defined_address_mut().into_iter().for_each(rename);
+        }
+    }
+
+    /// The graph in SSA form, the values of undefined reads set first
+    /// thing, and the versions those values are
+    fn into_cfg(mut self) -> (Cfg, HashSet<nodes::Address>) {
+        let entry = self.cfg.entry();
+        let versions = self.undefined.iter().filter_map(|ssa| ssa.defined_address()).cloned().collect();
+        let undefined = self.undefined.into_iter().map(nodes::Instruction::from);
+        self.cfg.block_mut(entry).body.splice(0..0, undefined);
+        (self.cfg, versions)
+    }
+}
+
+/// Removes phis merging a single value besides themselves, the lookup
+/// of a value through the predecessors places one at every join even
+/// where no other definition reaches. Their uses get the value instead.
+pub fn remove_trivial_phis(cfg: &mut Cfg) {
+    let mut replacements: HashMap<nodes::Address, nodes::Address> = HashMap::new();
+    let resolve = |replacements: &HashMap<nodes::Address, nodes::Address>, address: &nodes::Address| {
+        let mut address = address;
+        while let Some(replacement) = replacements.get(address) {
+            address = replacement;
+        }
+        address.clone()
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (_, block) in cfg.blocks_mut() {
+            block.phis.retain(|phi| {
+                let mut values = phi
+                    .merging
+                    .iter()
+                    .map(|(address, _)| resolve(&replacements, address))
+                    .filter(|address| *address != phi.dest);
+                let Some(first) = values.next() else {
+                    return true;
+                };
+                if values.all(|address| address == first) {
+                    replacements.insert(phi.dest.clone(), first);
+                    changed = true;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+    }
+
+    for (_, block) in cfg.blocks_mut() {
+        for operand in block.terminator_operands_mut() {
+            *operand = resolve(&replacements, operand);
+        }
+        let phis = block.phis.iter_mut().flat_map(|phi| {
+            phi.merging.iter_mut().map(|(address, _)| address)
+        });
+        let uses = block.body.iter_mut().flat_map(|instruction| instruction.ssa.used_addresses_mut());
+        for operand in phis.chain(uses) {
+            *operand = resolve(&replacements, operand);
+        }
+    }
+}
+
+/// Width of every source variable, taken from the instructions moving it
+fn variable_widths(function: &nodes::FunctionDef) -> HashMap<String, Width> {
+    let mut widths: HashMap<String, Width> = function.parameters.iter().cloned().collect();
+    for instruction in function.body.blocks().flat_map(|(_, block)| &block.body) {
+        match &instruction.ssa {
+            nodes::Ssa::Assignment {
+                dest,
+                source,
+                width,
+            } => {
+                for name in [dest, source].into_iter().filter_map(|a| a.try_get_source()) {
+                    widths.insert(name.to_owned(), *width);
+                }
+            }
+            // Stack slots of the variables kept in memory
+            nodes::Ssa::Alloca { dest, .. } => {
+                if let Some(name) = dest.try_get_source() {
+                    widths.insert(name.to_owned(), Width::Long);
+                }
+            }
+            _ => (),
+        }
+    }
+    widths
+}
+
+fn construct_function(function: &nodes::FunctionDef) -> (nodes::FunctionDef, HashSet<nodes::Address>) {
+    let mut cfg = function.body.clone();
+    cfg.remove_unreachable();
+    let mut construction = Construction {
+        cfg,
+        exit_values: HashMap::new(),
+        parameters: function.parameters.iter().map(|(name, _)| name.clone()).collect(),
+        widths: variable_widths(function),
+        versions: HashMap::new(),
+        entry_values: HashMap::new(),
+        undefined: vec![],
+    };
+    construction.number_definitions();
+    construction.rename_uses();
+    remove_trivial_phis(&mut construction.cfg);
+    construction.renumber();
+
+    let (body, undefined) = construction.into_cfg();
+    let function = nodes::FunctionDef {
+        body,
+        is_ssa: true,
+        ..function.clone()
+    };
+    (function, undefined)
+}
+
+/// Versions that variables read before any write take, by function
+pub type UndefinedValues = HashMap<String, HashSet<nodes::Address>>;
+
+/// Puts functions lowered with mutable source variables into SSA form.
+/// Phis are only placed where a variable is read after several
+/// definitions meet, and blocks that cannot be reached are dropped.
+/// Reads of uninitialized variables are given zero, the versions holding
+/// it are returned alongside.
+pub fn construct_ssa(unit: &[nodes::ToplevelItem]) -> (Vec<nodes::ToplevelItem>, UndefinedValues) {
+    let mut undefined = UndefinedValues::new();
+    let unit = unit
+        .iter()
+        .map(|item| match item {
+            nodes::ToplevelItem::Function(function) => {
+                let (function, values) = construct_function(function);
+                undefined.insert(function.name.clone(), values);
+                nodes::ToplevelItem::Function(function)
+            }
+            nodes::ToplevelItem::Declaration(_) | nodes::ToplevelItem::Data(_) => item.clone(),
+        })
+        .collect();
+    (unit, undefined)
+}
@@ -0,0 +1,196 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    common::{SourceLocation, StorageClass},
+    ir::{UndefinedValues, nodes},
+    semantic_analysis::{Diagnostic, Scope, Symbol, SymbolKind, SymbolTableRef},
+};
+
+/// Def-use chains of the source variables of a function in SSA form.
+/// Phi functions only merge versions of one variable, so their operands
+/// are kept apart from the reads of the program.
+#[derive(Default)]
+struct DefUse<'a> {
+    /// Values read by instructions and terminators, in layout order
+    reads: Vec<(&'a nodes::Address, Option<SourceLocation>)>,
+    /// Values written by instructions, in layout order
+    writes: Vec<(&'a nodes::Address, Option<SourceLocation>)>,
+    /// Values each phi merges
+    phis: HashMap<&'a nodes::Address, Vec<&'a nodes::Address>>,
+}
+
+impl<'a> DefUse<'a> {
+    fn new(function: &'a nodes::FunctionDef, undefined: &HashSet<nodes::Address>) -> Self {
+        let mut chains = Self::default();
+        for (_, block) in function.body.blocks() {
+            for phi in &block.phis {
+                let merging = phi.merging.iter().map(|(address, _)| address).collect();
+                chains.phis.insert(&phi.dest, merging);
+            }
+            let body = block.body.iter().map(|instruction| (&instruction.ssa, instruction.location));
+            let terminator = (block.terminator(), block.terminator_location());
+            for (ssa, location) in body.chain(std::iter::once(terminator)) {
+                let sources = ssa.used_addresses().into_iter().filter(|address| address.try_get_source().is_some());
+                chains.reads.extend(sources.map(|address| (address, location)));
+                if let Some(dest) = ssa.defined_address()
+                    && dest.try_get_source().is_some()
+                    && !undefined.contains(dest)
+                {
+                    chains.writes.push((dest, location));
+                }
+            }
+        }
+        chains
+    }
+
+    /// Values a read can see, through the phis merging them
+    fn live(&self) -> HashSet<&'a nodes::Address> {
+        let mut live = HashSet::new();
+        let mut pending: Vec<&nodes::Address> = self.reads.iter().map(|(address, _)| *address).collect();
+        while let Some(address) = pending.pop() {
+            if live.insert(address) {
+                pending.extend(self.phis.get(address).into_iter().flatten());
+            }
+        }
+        live
+    }
+
+    /// Phis one of whose paths carries an undefined value
+    fn maybe_undefined(&self, undefined: &HashSet<nodes::Address>) -> HashSet<&'a nodes::Address> {
+        let mut maybe = HashSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (&dest, merging) in &self.phis {
+                if !maybe.contains(dest)
+                    && merging.iter().any(|address| undefined.contains(*address) || maybe.contains(address))
+                {
+                    maybe.insert(dest);
+                    changed = true;
+                }
+            }
+        }
+        maybe
+    }
+}
+
+/// Variables kept in a stack slot because their address is taken. They
+/// are read and written through memory, which the analysis does not follow.
+fn slot_variables(function: &nodes::FunctionDef) -> HashSet<String> {
+    function
+        .body
+        .blocks()
+        .flat_map(|(_, block)| &block.body)
+        .filter_map(|instruction| match &instruction.ssa {
+            nodes::Ssa::Alloca { dest, .. } => dest.try_get_source()?.strip_suffix(".addr").map(String::from),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Variables declared in a function body, by their name in the IR
+fn function_variables(scope: &Rc<RefCell<Scope>>, variables: &mut HashMap<String, Symbol>) {
+    for symbol in scope.borrow().symbols.values() {
+        if matches!(symbol.kind, SymbolKind::Variable { .. })
+            && matches!(symbol.storage_class, StorageClass::Auto)
+        {
+            variables
+                .entry(symbol.ir_name.clone())
+                .or_insert_with(|| symbol.clone());
+        }
+    }
+    for child in &scope.borrow().children {
+        function_variables(child, variables);
+    }
+}
+
+fn check_function<'a>(
+    function: &'a nodes::FunctionDef,
+    undefined: &HashSet<nodes::Address>,
+    scope: &Rc<RefCell<Scope>>,
+) -> Vec<Diagnostic> {
+    let chains = DefUse::new(function, undefined);
+    let live = chains.live();
+    let maybe_undefined = chains.maybe_undefined(undefined);
+    let parameters: HashSet<&str> = function.parameters.iter().map(|(name, _)| name.as_str()).collect();
+    let slots = slot_variables(function);
+
+    let mut variables = HashMap::new();
+    function_variables(scope, &mut variables);
+    let mut variables: Vec<(String, Symbol)> = variables.into_iter().collect();
+    variables.sort_by_key(|(_, symbol)| (symbol.location.line, symbol.location.column));
+
+    let mut diagnostics = vec![];
+    for (ir_name, symbol) in variables {
+        let of_variable = |values: &[(&'a nodes::Address, Option<SourceLocation>)]| -> Vec<_> {
+            let name = Some(ir_name.as_str());
+            values.iter().filter(|(address, _)| address.try_get_source() == name).copied().collect()
+        };
+        let reads = of_variable(&chains.reads);
+        let writes = of_variable(&chains.writes);
+        let mut warn = |message: String, location: Option<SourceLocation>| {
+            diagnostics.push(
+                Diagnostic::warning(Some(function.name.clone()), message).at(location.unwrap_or(symbol.location)),
+            );
+        };
+        if parameters.contains(ir_name.as_str()) && reads.is_empty() {
+            warn(format!("unused parameter '{}'", symbol.name), None);
+            continue;
+        } else if slots.contains(&ir_name) {
+            continue;
+        } else if reads.is_empty() && writes.is_empty() {
+            warn(format!("unused variable '{}'", symbol.name), None);
+            continue;
+        } else if reads.is_empty() {
+            warn(format!("variable '{}' set but not used", symbol.name), None);
+            continue;
+        }
+
+        for (value, location) in reads {
+            if undefined.contains(value) {
+                warn(format!("variable '{}' is used uninitialized", symbol.name), location);
+            } else if maybe_undefined.contains(value) {
+                warn(format!("variable '{}' may be used uninitialized", symbol.name), location);
+            }
+        }
+        for (value, location) in writes {
+            if !live.contains(value) {
+                warn(format!("value assigned to '{}' is never read", symbol.name), location);
+            }
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.location.map(|location| (location.line, location.column)));
+    diagnostics
+}
+
+/// Warns about reads of uninitialized variables, unused variables and
+/// parameters, and values that are written but never read. Reads of
+/// uninitialized variables are the ones SSA construction gave an
+/// undefined value, the others are found through the def-use chains.
+pub fn check_variables(
+    unit: &[nodes::ToplevelItem],
+    undefined: &UndefinedValues,
+    symbol_table: SymbolTableRef,
+) -> Vec<Diagnostic> {
+    unit.iter()
+        .filter_map(|item| match item {
+            nodes::ToplevelItem::Function(function) => Some(function),
+            nodes::ToplevelItem::Declaration(_) | nodes::ToplevelItem::Data(_) => None,
+        })
+        .flat_map(|function| {
+            let table = symbol_table.borrow();
+            // The symbol of a defined function is located at its definition
+            let defined_at = table.global_scope.borrow().symbols[&function.name].location;
+            let scope = table.function_scope(&function.name, defined_at);
+            check_function(
+                function,
+                &undefined[&function.name],
+                &scope.expect("Every lowered function has a body scope"),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,243 @@
+/// Dominator tree of a control flow graph given by the successors of each
+/// block, block 0 being the entry. Computed with the iterative algorithm of
+/// Cooper, Harvey and Kennedy, "A Simple, Fast Dominance Algorithm".
+///
+/// Built on the reversed graph, from every block without successors, it
+/// is the post-dominator tree, and the frontiers are the post-dominance
+/// frontiers, the branches a block is control dependent on.
+pub struct DominatorTree {
+    // None for the roots and the blocks the roots do not reach
+    immediate_dominators: Vec<Option<usize>>,
+    reachable: Vec<bool>,
+    children: Vec<Vec<usize>>,
+    // Position of a block when entering and leaving it in a walk of the tree
+    enter: Vec<usize>,
+    exit: Vec<usize>,
+    frontiers: Vec<Vec<usize>>,
+}
+
+/// Blocks of the graph in postorder from `root`
+fn postorder(successors: &[Vec<usize>], root: usize) -> Vec<usize> {
+    let mut visited = vec![false; successors.len()];
+    let mut order = vec![];
+    let mut stack = vec![(root, 0)];
+    visited[root] = true;
+    while let Some((block, next)) = stack.pop() {
+        match successors[block].get(next) {
+            Some(&successor) => {
+                stack.push((block, next + 1));
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            }
+            None => order.push(block),
+        }
+    }
+    order
+}
+
+impl DominatorTree {
+    pub fn dominators(successors: &[Vec<usize>]) -> Self {
+        let roots = if successors.is_empty() { vec![] } else { vec![0] };
+        Self::build(successors, &roots)
+    }
+
+    pub fn post_dominators(successors: &[Vec<usize>]) -> Self {
+        let mut reversed = vec![vec![]; successors.len()];
+        for (block, block_successors) in successors.iter().enumerate() {
+            for &successor in block_successors {
+                reversed[successor].push(block);
+            }
+        }
+        let exits: Vec<usize> = (0..successors.len())
+            .filter(|&block| successors[block].is_empty())
+            .collect();
+        Self::build(&reversed, &exits)
+    }
+
+    /// A virtual block placed before the roots makes a single tree of them
+    fn build(successors: &[Vec<usize>], roots: &[usize]) -> Self {
+        let count = successors.len();
+        let virtual_root = count;
+        let mut graph = successors.to_vec();
+        graph.push(roots.to_vec());
+
+        let mut predecessors = vec![vec![]; count + 1];
+        for (block, block_successors) in graph.iter().enumerate() {
+            for &successor in block_successors {
+                predecessors[successor].push(block);
+            }
+        }
+
+        let order = postorder(&graph, virtual_root);
+        let mut number = vec![None; count + 1];
+        for (position, &block) in order.iter().enumerate() {
+            number[block] = Some(position);
+        }
+
+        let mut idom: Vec<Option<usize>> = vec![None; count + 1];
+        idom[virtual_root] = Some(virtual_root);
+        let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+            while a != b {
+                while number[a] < number[b] {
+                    a = idom[a].unwrap();
+                }
+                while number[b] < number[a] {
+                    b = idom[b].unwrap();
+                }
+            }
+            a
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in order.iter().rev().filter(|&&block| block != virtual_root) {
+                let new = predecessors[block]
+                    .iter()
+                    .filter(|&&p| idom[p].is_some())
+                    .copied()
+                    .reduce(|a, b| intersect(&idom, a, b));
+                if new.is_some() && idom[block] != new {
+                    idom[block] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        let reachable: Vec<bool> = idom[..count].iter().map(Option::is_some).collect();
+        let immediate_dominators: Vec<Option<usize>> = idom[..count]
+            .iter()
+            .map(|&d| d.filter(|&d| d != virtual_root))
+            .collect();
+
+        let mut children = vec![vec![]; count + 1];
+        for (block, parent) in idom[..count].iter().enumerate() {
+            if let Some(parent) = *parent {
+                children[parent].push(block);
+            }
+        }
+
+        let (mut enter, mut exit) = (vec![0; count + 1], vec![0; count + 1]);
+        let mut position = 0;
+        let mut stack = vec![(virtual_root, false)];
+        while let Some((block, done)) = stack.pop() {
+            position += 1;
+            if done {
+                exit[block] = position;
+                continue;
+            }
+            enter[block] = position;
+            stack.push((block, true));
+            stack.extend(children[block].iter().map(|&child| (child, false)));
+        }
+
+        // A block is in the frontier of the blocks on the way up the tree
+        // from its predecessors to its immediate dominator
+        let mut frontiers = vec![vec![]; count];
+        for block in (0..count).filter(|&block| reachable[block]) {
+            let block_predecessors: Vec<usize> = predecessors[block]
+                .iter()
+                .copied()
+                .filter(|&p| idom[p].is_some())
+                .collect();
+            if block_predecessors.len() < 2 {
+                continue;
+            }
+            for predecessor in block_predecessors {
+                let mut runner = predecessor;
+                while Some(runner) != idom[block] && runner != virtual_root {
+                    let frontier: &mut Vec<usize> = &mut frontiers[runner];
+                    if !frontier.contains(&block) {
+                        frontier.push(block);
+                    }
+                    runner = idom[runner].unwrap();
+                }
+            }
+        }
+
+        children.truncate(count);
+        enter.truncate(count);
+        exit.truncate(count);
+        Self {
+            immediate_dominators,
+            reachable,
+            children,
+            enter,
+            exit,
+            frontiers,
+        }
+    }
+
+    /// Closest block dominating `block` besides itself
+    pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+        self.immediate_dominators[block]
+    }
+
+    /// Whether every path to `block` goes through `dominator`, a block
+    /// dominates itself. Blocks that cannot be reached are not dominated.
+    pub fn dominates(&self, dominator: usize, block: usize) -> bool {
+        self.reachable[dominator]
+            && self.reachable[block]
+            && self.enter[dominator] <= self.enter[block]
+            && self.exit[block] <= self.exit[dominator]
+    }
+
+    pub fn is_reachable(&self, block: usize) -> bool {
+        self.reachable[block]
+    }
+
+    /// Blocks `block` is the immediate dominator of
+    pub fn children(&self, block: usize) -> &[usize] {
+        &self.children[block]
+    }
+
+    /// Blocks where the dominance of `block` ends: they have a predecessor
+    /// it dominates without being strictly dominated by it
+    pub fn frontier(&self, block: usize) -> &[usize] {
+        &self.frontiers[block]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DominatorTree;
+
+    // 0 -> 1, a diamond 1 -> 2 | 3 -> 4 looping back from 4 to 1, and
+    // the exit 5. Nothing reaches 6.
+    fn graph() -> Vec<Vec<usize>> {
+        vec![vec![1], vec![2, 3], vec![4], vec![4], vec![1, 5], vec![], vec![5]]
+    }
+
+    #[test]
+    fn dominators() {
+        let tree = DominatorTree::dominators(&graph());
+        let idoms: Vec<Option<usize>> = (0..7).map(|b| tree.immediate_dominator(b)).collect();
+        assert_eq!(idoms, [None, Some(0), Some(1), Some(1), Some(1), Some(4), None]);
+        assert_eq!(tree.children(1), [2, 3, 4]);
+        assert!(tree.dominates(1, 5) && tree.dominates(4, 4) && !tree.dominates(2, 4));
+        assert!(!tree.is_reachable(6) && !tree.dominates(0, 6));
+
+        assert_eq!(tree.frontier(0), [] as [usize; 0]);
+        assert_eq!(tree.frontier(1), [1]);
+        assert_eq!(tree.frontier(2), [4]);
+        assert_eq!(tree.frontier(3), [4]);
+        assert_eq!(tree.frontier(4), [1]);
+    }
+
+    #[test]
+    fn post_dominators() {
+        let tree = DominatorTree::post_dominators(&graph());
+        let ipdoms: Vec<Option<usize>> = (0..7).map(|b| tree.immediate_dominator(b)).collect();
+        assert_eq!(ipdoms, [Some(1), Some(4), Some(4), Some(4), Some(5), None, Some(5)]);
+        assert!(tree.dominates(5, 0) && !tree.dominates(2, 1));
+
+        // Blocks are control dependent on the branches of their frontier
+        assert_eq!(tree.frontier(2), [1]);
+        assert_eq!(tree.frontier(3), [1]);
+        assert_eq!(tree.frontier(1), [4]);
+        assert_eq!(tree.frontier(4), [4]);
+        assert_eq!(tree.frontier(5), [] as [usize; 0]);
+    }
+}
@@ -1,126 +1,114 @@
 use std::fs;
 
-use crate::ir::{IrTextRepr, ir_to_basic_blocks, nodes};
+use crate::ir::{Cfg, IrTextRepr, nodes};
+
+/// Dominators, frontier and loops of every block, shown under its label
+fn block_analyses(cfg: &Cfg) -> Vec<String> {
+    let dominators = cfg.dominators();
+    let post_dominators = cfg.post_dominators();
+    let loops = cfg.loops();
+    let label = |idx: usize| format!("@{}", cfg.block_by_index(idx).label.to_ir_string());
+
+    cfg.layout()
+        .iter()
+        .map(|id| {
+            let idx = id.index();
+            let mut facts = vec![];
+            if let Some(dominator) = dominators.immediate_dominator(idx) {
+                facts.push(format!("idom {}", label(dominator)));
+            }
+            if let Some(post_dominator) = post_dominators.immediate_dominator(idx) {
+                facts.push(format!("ipdom {}", label(post_dominator)));
+            }
+            if !dominators.frontier(idx).is_empty() {
+                let frontier: Vec<String> = dominators.frontier(idx).iter().map(|&b| label(b)).collect();
+                facts.push(format!("frontier {}", frontier.join(" ")));
+            }
+            if loops.depth(idx) > 0 {
+                facts.push(format!("loop depth {}", loops.depth(idx)));
+            }
+            if let Some(header) = loops.loops().iter().find(|l| l.header == idx) {
+                let preheader = header.preheader.map_or("none".to_owned(), label);
+                facts.push(format!("loop header, preheader {}", preheader));
+            }
+            facts.join(", ")
+        })
+        .collect()
+}
 
 pub fn graphviz_function(declaration: &nodes::FunctionDef) -> String {
-    let blocks = ir_to_basic_blocks(&declaration.body);
+    let cfg = &declaration.body;
+    let analyses = block_analyses(cfg);
     let mut res = String::new();
     res.push_str("digraph SSA {\n");
     res.push_str("rankdir=TB;\n");
     res.push_str("node [shape=rectangle, fontname=\"Courier\"];\n");
     let sep = "<BR ALIGN=\"LEFT\"/>    ";
-    for block in blocks.iter() {
-        let label = &block[0];
-        if let nodes::Ssa::Label(lab) = label {
-            res.push_str(&lab.to_ir_string());
-            res.push(' ');
-            res.push('[');
-            res.push_str(&format!("label=<{}", lab.to_ir_string()));
+    for ((_, block), facts) in cfg.blocks().zip(&analyses) {
+        let label = block.label.to_ir_string();
+        res.push_str(&format!("{} [label=<{}", label, label));
+        res.push_str(sep);
+        if !facts.is_empty() {
+            res.push_str(&format!("<I>{}</I>", facts));
             res.push_str(sep);
         }
 
         let body = block
+            .instructions()
             .iter()
-            .skip(1)
             .map(|x| {
-                html_escape::encode_safe_to_string(&x.to_ir_string(), &mut String::new()).to_owned()
+                html_escape::encode_safe_to_string(x.to_ir_string(), &mut String::new()).to_owned()
             })
             .collect::<Vec<_>>()
             .join(sep);
         res.push_str(&body);
         res.push_str(sep);
-        res.push('>');
-        res.push(']');
-        res.push(';');
-
-        res.push('\n');
+        res.push_str(">];\n");
     }
 
     // Control Flow
-    let mut prev_block = None;
-    for block in blocks.iter() {
-        let label = &block[0];
-        let mut lab_ir = String::new();
-        if let nodes::Ssa::Label(lab) = label {
-            lab_ir = lab.to_ir_string();
-        }
-        let mut ended_jump = false;
-        for i in block.iter().skip(1) {
-            match i {
-                nodes::Ssa::Jump(j) => {
-                    res.push_str(&format!("{} -> {};\n", lab_ir, j.to_ir_string()));
-                    ended_jump = true;
-                }
-                nodes::Ssa::Branch {
-                    cond: _,
-                    true_target,
-                    false_target,
-                    width: _,
-                } => {
-                    res.push_str(&format!(
-                        "{} -> {}[label=\"true\"];\n",
-                        lab_ir,
-                        true_target.to_ir_string()
-                    ));
-                    res.push_str(&format!(
-                        "{} -> {}[label=\"false\"];\n",
-                        lab_ir,
-                        false_target.to_ir_string()
-                    ));
-                    ended_jump = true;
-                }
-                _ => (),
-            }
-        }
-
-        if let Some(pb) = &prev_block {
-            res.push_str(&format!("{} -> {};\n", pb, lab_ir));
-        }
-        if !ended_jump {
-            prev_block = Some(lab_ir);
-        } else {
-            prev_block = None;
+    for (id, block) in cfg.blocks() {
+        let label = block.label.to_ir_string();
+        let successors = cfg.successors(id);
+        let kinds: &[&str] = match successors.len() {
+            2 => &["[label=\"true\"]", "[label=\"false\"]"],
+            _ => &[""],
+        };
+        for (successor, kind) in successors.iter().zip(kinds) {
+            res.push_str(&format!(
+                "{} -> {}{};\n",
+                label,
+                cfg.block(*successor).label.to_ir_string(),
+                kind
+            ));
         }
     }
 
     // Data flow (phi)
     res.push_str("edge [color=red, style=dashed, constraint=false];\n");
 
-    for block in blocks.iter() {
-        let label = &block[0];
-        let mut lab_ir = String::new();
-        if let nodes::Ssa::Label(lab) = label {
-            lab_ir = lab.to_ir_string();
-        }
-        for i in block.iter().skip(1) {
-            match i {
-                nodes::Ssa::Phi(phi) => {
-                    for (addr, lab) in &phi.merging {
-                        res.push_str(&format!(
-                            "{} -> {}[label=\"{} -> phi\"];\n",
-                            lab.to_ir_string(),
-                            lab_ir,
-                            addr.to_ir_string()
-                        ));
-                    }
-                }
-                _ => (),
+    for (_, block) in cfg.blocks() {
+        for phi in &block.phis {
+            for (addr, lab) in &phi.merging {
+                res.push_str(&format!(
+                    "{} -> {}[label=\"{} -> phi\"];\n",
+                    lab.to_ir_string(),
+                    block.label.to_ir_string(),
+                    addr.to_ir_string()
+                ));
             }
         }
     }
 
-    res.push_str("}");
+    res.push('}');
     res
 }
 
 pub fn graphviz_unit(unit: &[nodes::ToplevelItem], dir: &str) {
     for i in unit {
-        match i {
-            nodes::ToplevelItem::Function(f) => {
-                let u = &graphviz_function(f);
-                fs::write(format!("{}/{}.dot", dir, f.name), u).unwrap();
-            }
-            _ => (),
+        if let nodes::ToplevelItem::Function(f) = i {
+            let u = &graphviz_function(f);
+            fs::write(format!("{}/{}.dot", dir, f.name), u).unwrap();
         }
     }
 }
@@ -0,0 +1,929 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    common::{Signedness, Width},
+    ir::{BlockId, IrTextRepr, nodes},
+};
+
+#[derive(Error, Debug)]
+pub enum InterpretError {
+    #[error("program has no main function")]
+    NoMain,
+    #[error("call to '{0}', which is neither defined nor a builtin")]
+    UnknownFunction(String),
+    #[error("call through {0:#x}, which is not the address of a function")]
+    NotAFunction(u64),
+    #[error("in function '{function}': {message}")]
+    Runtime { function: String, message: String },
+    #[error("invalid memory access at {0:#x}")]
+    InvalidAccess(u64),
+    #[error("call stack deeper than {MAX_CALL_DEPTH} calls")]
+    StackOverflow,
+}
+
+type Result<T> = std::result::Result<T, InterpretError>;
+
+const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Functions get fake addresses below the memory, so they can be called
+/// through pointers and printed with %p
+const FUNCTION_BASE: u64 = 0x1000;
+const MEMORY_BASE: u64 = 0x10000;
+const STACK_BASE: u64 = 0x1_0000_0000;
+
+/// What main gets as argv[0]
+const PROGRAM_NAME: &[u8] = b"a.out";
+
+/// What a program printed and the exit code main returned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Execution {
+    pub stdout: Vec<u8>,
+    pub exit_code: i32,
+}
+
+/// Byte addressed memory. Data items and malloc take the heap, which is
+/// never freed. Stack slots and variadic arguments go on the stack, where
+/// each call releases what it pushed when it returns.
+#[derive(Default)]
+struct Memory {
+    heap: Vec<u8>,
+    stack: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+enum Region {
+    Heap,
+    Stack,
+}
+
+impl Memory {
+    fn allocate(&mut self, size: usize) -> u64 {
+        // Keep every allocation 16 byte aligned, like malloc does
+        let start = self.heap.len().next_multiple_of(16);
+        self.heap.resize(start + size.max(1), 0);
+        MEMORY_BASE + start as u64
+    }
+
+    fn push(&mut self, size: usize) -> u64 {
+        let start = self.stack.len().next_multiple_of(16);
+        self.stack.resize(start + size.max(1), 0);
+        STACK_BASE + start as u64
+    }
+
+    /// How much of the stack is in use, for `release` to go back to
+    fn stack_mark(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn release(&mut self, mark: usize) {
+        self.stack.truncate(mark);
+    }
+
+    fn bytes(&self, region: Region) -> &[u8] {
+        match region {
+            Region::Heap => &self.heap,
+            Region::Stack => &self.stack,
+        }
+    }
+
+    fn bytes_mut(&mut self, region: Region) -> &mut [u8] {
+        match region {
+            Region::Heap => &mut self.heap,
+            Region::Stack => &mut self.stack,
+        }
+    }
+
+    /// Region holding `size` bytes at `address`, and their offset in it
+    fn offset(&self, address: u64, size: usize) -> Result<(Region, usize)> {
+        let (region, base) = if address >= STACK_BASE {
+            (Region::Stack, STACK_BASE)
+        } else {
+            (Region::Heap, MEMORY_BASE)
+        };
+        address
+            .checked_sub(base)
+            .map(|offset| offset as usize)
+            .filter(|offset| offset + size <= self.bytes(region).len())
+            .map(|offset| (region, offset))
+            .ok_or(InterpretError::InvalidAccess(address))
+    }
+
+    fn load(&self, address: u64, width: Width) -> Result<u64> {
+        let size = width.to_bytes();
+        let (region, offset) = self.offset(address, size)?;
+        let mut bytes = [0; 8];
+        bytes[..size].copy_from_slice(&self.bytes(region)[offset..offset + size]);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn store(&mut self, address: u64, width: Width, value: u64) -> Result<()> {
+        let size = width.to_bytes();
+        let (region, offset) = self.offset(address, size)?;
+        self.bytes_mut(region)[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+        Ok(())
+    }
+
+    fn c_string(&self, address: u64) -> Result<Vec<u8>> {
+        let (region, offset) = self.offset(address, 1)?;
+        let bytes = &self.bytes(region)[offset..];
+        match bytes.iter().position(|&b| b == 0) {
+            Some(length) => Ok(bytes[..length].to_vec()),
+            None => Err(InterpretError::InvalidAccess(address)),
+        }
+    }
+}
+
+fn signed(value: u64, width: Width) -> i64 {
+    width.extend(value as i64, Signedness::Signed)
+}
+
+fn unsigned(value: u64, width: Width) -> u64 {
+    width.extend(value as i64, Signedness::Unsigned) as u64
+}
+
+/// Values are kept as raw bits: integers zero extended from their width,
+/// floats as the bits of an f32 or f64
+fn to_float(value: u64, width: Width) -> f64 {
+    match width {
+        Width::Single => f32::from_bits(value as u32) as f64,
+        _ => f64::from_bits(value),
+    }
+}
+
+fn from_float(value: f64, width: Width) -> u64 {
+    match width {
+        Width::Single => (value as f32).to_bits() as u64,
+        _ => value.to_bits(),
+    }
+}
+
+/// What a call returned, read with the width of its destination
+fn returned(value: Option<u64>, width: Width) -> u64 {
+    let value = value.unwrap_or(0);
+    if width.is_floating() {
+        value
+    } else {
+        unsigned(value, width)
+    }
+}
+
+/// Local state of one function call
+struct Frame<'a> {
+    function: &'a nodes::FunctionDef,
+    values: HashMap<nodes::Address, u64>,
+    /// Where the variadic arguments of the call were stored
+    variadic_area: u64,
+    /// Block being run and the position of its next instruction, the
+    /// terminator coming after the body
+    block: BlockId,
+    position: usize,
+    /// Where the result of the call the frame is waiting on goes
+    waiting: Option<(&'a nodes::Address, Width)>,
+    /// Stack in use before the call, what it leaves on return
+    stack_mark: usize,
+}
+
+fn unit_functions(unit: &[nodes::ToplevelItem]) -> Vec<&nodes::FunctionDef> {
+    unit.iter()
+        .filter_map(|item| match item {
+            nodes::ToplevelItem::Function(f) => Some(f),
+            nodes::ToplevelItem::Declaration(_) | nodes::ToplevelItem::Data(_) => None,
+        })
+        .collect()
+}
+
+struct Interpreter<'a> {
+    functions: Vec<&'a nodes::FunctionDef>,
+    /// Names of the defined and declared functions, by address
+    symbols: Vec<String>,
+    /// Where each data item of the unit lives in memory
+    data: HashMap<String, u64>,
+    memory: Memory,
+    stdout: Vec<u8>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(unit: &'a [nodes::ToplevelItem]) -> Self {
+        let symbols = unit
+            .iter()
+            .filter_map(|item| match item {
+                nodes::ToplevelItem::Function(f) => Some(f.name.clone()),
+                nodes::ToplevelItem::Declaration(d) => Some(d.name()),
+                nodes::ToplevelItem::Data(_) => None,
+            })
+            .collect();
+        let mut interpreter = Self {
+            functions: unit_functions(unit),
+            symbols,
+            data: HashMap::new(),
+            memory: Memory::default(),
+            stdout: vec![],
+        };
+        interpreter.load_data(unit);
+        interpreter
+    }
+
+    /// Lays the data items out in memory before the program starts. They
+    /// are all placed first, as initializers may take each other's address.
+    fn load_data(&mut self, unit: &[nodes::ToplevelItem]) {
+        let items: Vec<&nodes::DataDef> = unit
+            .iter()
+            .filter_map(|item| match item {
+                nodes::ToplevelItem::Data(data) => Some(data),
+                _ => None,
+            })
+            .collect();
+        for data in &items {
+            let address = self.memory.allocate(data.size());
+            self.data.insert(data.name.clone(), address);
+        }
+        for data in items {
+            let mut offset = (self.data[&data.name] - MEMORY_BASE) as usize;
+            for init in &data.inits {
+                match init {
+                    nodes::DataInit::Bytes(bytes) => {
+                        self.memory.heap[offset..offset + bytes.len()].copy_from_slice(bytes);
+                    }
+                    nodes::DataInit::Values(width, values) => {
+                        for (idx, value) in values.iter().enumerate() {
+                            let value = match value {
+                                nodes::AddressConstant::Numeric(n) => *n as u64,
+                                nodes::AddressConstant::Floating(bits) => from_float(f64::from_bits(*bits), *width),
+                                nodes::AddressConstant::Symbol(name) => self.symbol_address(name).unwrap_or(0),
+                            };
+                            let start = offset + idx * width.to_bytes();
+                            self.memory.heap[start..start + width.to_bytes()]
+                                .copy_from_slice(&value.to_le_bytes()[..width.to_bytes()]);
+                        }
+                    }
+                    nodes::DataInit::Address(name, displacement) => {
+                        let value = self.symbol_address(name).unwrap_or(0).wrapping_add_signed(*displacement);
+                        self.memory.heap[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+                    }
+                    nodes::DataInit::Zero(_) => {}
+                }
+                offset += init.size();
+            }
+        }
+    }
+
+    /// argc and argv of a run without arguments, main ignores them when
+    /// it takes no parameters
+    fn main_arguments(&mut self) -> Result<[(u64, Width, bool); 2]> {
+        let name = self.memory.allocate(PROGRAM_NAME.len() + 1);
+        for (idx, &byte) in PROGRAM_NAME.iter().enumerate() {
+            self.memory.store(name + idx as u64, Width::Byte, byte as u64)?;
+        }
+        // argv ends with a null pointer, which allocate already zeroed
+        let argv = self.memory.allocate(16);
+        self.memory.store(argv, Width::Long, name)?;
+        Ok([(1, Width::Word, false), (argv, Width::Long, false)])
+    }
+
+    fn function_address(&self, name: &str) -> Option<u64> {
+        self.symbols
+            .iter()
+            .position(|symbol| symbol == name)
+            .map(|idx| FUNCTION_BASE + 8 * idx as u64)
+    }
+
+    fn symbol_address(&self, name: &str) -> Option<u64> {
+        self.data.get(name).copied().or_else(|| self.function_address(name))
+    }
+
+    fn runtime_error(frame: &Frame, message: String) -> InterpretError {
+        InterpretError::Runtime {
+            function: frame.function.name.clone(),
+            message,
+        }
+    }
+
+    /// Value of an operand read with `width`
+    fn read(&mut self, frame: &Frame, address: &nodes::Address, width: Width) -> Result<u64> {
+        let value = match address {
+            nodes::Address::Constant(nodes::AddressConstant::Numeric(n)) => *n as u64,
+            nodes::Address::Constant(nodes::AddressConstant::Floating(bits)) => {
+                return Ok(from_float(f64::from_bits(*bits), width));
+            }
+            nodes::Address::Constant(nodes::AddressConstant::Symbol(name)) => self.symbol_address(name).unwrap_or(0),
+            _ => *frame.values.get(address).ok_or_else(|| {
+                Self::runtime_error(frame, format!("{} is read before it is defined", address.to_ir_string()))
+            })?,
+        };
+        Ok(if width.is_floating() {
+            value
+        } else {
+            unsigned(value, width)
+        })
+    }
+
+    fn quadriplet(&mut self, frame: &Frame, quad: &nodes::Quadriplet) -> Result<u64> {
+        let Some(right) = &quad.right else {
+            return Err(Self::runtime_error(frame, format!("`{}` has no right operand", quad.to_ir_string())));
+        };
+        let left = self.read(frame, &quad.left, quad.width)?;
+        let right = self.read(frame, right, quad.width)?;
+
+        if quad.width.is_floating() {
+            let (left, right) = (to_float(left, quad.width), to_float(right, quad.width));
+            let result = match quad.op {
+                nodes::Op::Plus => left + right,
+                nodes::Op::Minus => left - right,
+                nodes::Op::Mul => left * right,
+                nodes::Op::Div | nodes::Op::UDiv => left / right,
+                nodes::Op::Eq => return Ok((left == right) as u64),
+                nodes::Op::Ne => return Ok((left != right) as u64),
+                nodes::Op::Lt | nodes::Op::ULt => return Ok((left < right) as u64),
+                nodes::Op::Le | nodes::Op::ULe => return Ok((left <= right) as u64),
+                nodes::Op::Gt | nodes::Op::UGt => return Ok((left > right) as u64),
+                nodes::Op::Ge | nodes::Op::UGe => return Ok((left >= right) as u64),
+                nodes::Op::ShiftLeft | nodes::Op::ShiftRight | nodes::Op::UShiftRight => {
+                    return Err(Self::runtime_error(
+                        frame,
+                        format!("`{}` shifts a floating value", quad.to_ir_string()),
+                    ));
+                }
+                nodes::Op::Rem | nodes::Op::URem => {
+                    return Err(Self::runtime_error(
+                        frame,
+                        format!("`{}` takes the remainder of a floating value", quad.to_ir_string()),
+                    ));
+                }
+            };
+            return Ok(from_float(result, quad.width));
+        }
+
+        let Some(result) = quad.op.apply_constant(left as i64, right as i64, quad.width) else {
+            return Err(Self::runtime_error(frame, "division by zero".into()));
+        };
+        Ok(unsigned(result as u64, quad.width))
+    }
+
+    fn convert(source: u64, signed_integer: bool, from: Width, width: Width) -> u64 {
+        match (from.is_floating(), width.is_floating()) {
+            (true, true) => from_float(to_float(source, from), width),
+            (false, _) if signed_integer => from_float(signed(source, from) as f64, width),
+            (false, _) => from_float(unsigned(source, from) as f64, width),
+            (true, false) if signed_integer => unsigned(to_float(source, from) as i64 as u64, width),
+            (true, false) => unsigned(to_float(source, from) as u64, width),
+        }
+    }
+
+    /// Runs one instruction, returning where control goes next when it
+    /// leaves the block
+    fn step(&mut self, frame: &mut Frame<'a>, ssa: &'a nodes::Ssa) -> Result<Option<Flow<'a>>> {
+        let (dest, value) = match ssa {
+            nodes::Ssa::Quadriplet(quad) => {
+                let value = self.quadriplet(frame, quad)?;
+                (&quad.dest, value)
+            }
+            nodes::Ssa::Assignment { dest, source, width } => (dest, self.read(frame, source, *width)?),
+            nodes::Ssa::Extend {
+                signed: is_signed,
+                dest,
+                source,
+                from,
+                width,
+            } => {
+                let source = self.read(frame, source, *from)?;
+                let value = if *is_signed {
+                    signed(source, *from) as u64
+                } else {
+                    source
+                };
+                (dest, unsigned(value, *width))
+            }
+            nodes::Ssa::Truncate { dest, source, width } => {
+                // The source is wider, reading it with the narrow width truncates
+                (dest, self.read(frame, source, *width)?)
+            }
+            nodes::Ssa::Convert {
+                signed,
+                dest,
+                source,
+                from,
+                width,
+            } => {
+                let source = self.read(frame, source, *from)?;
+                (dest, Self::convert(source, *signed, *from, *width))
+            }
+            nodes::Ssa::VaStart { list } => {
+                let list = self.read(frame, list, Width::Long)?;
+                self.memory.store(list, Width::Long, frame.variadic_area)?;
+                return Ok(None);
+            }
+            nodes::Ssa::VaArg { dest, list, width } => {
+                let list = self.read(frame, list, Width::Long)?;
+                let current = self.memory.load(list, Width::Long)?;
+                self.memory.store(list, Width::Long, current + 8)?;
+                (dest, self.memory.load(current, *width)?)
+            }
+            nodes::Ssa::Alloca { dest, size, .. } => (dest, self.memory.push(*size)),
+            nodes::Ssa::Load {
+                dest,
+                address,
+                width,
+            } => {
+                let address = self.read(frame, address, Width::Long)?;
+                (dest, self.memory.load(address, *width)?)
+            }
+            nodes::Ssa::Store {
+                address,
+                value,
+                width,
+            } => {
+                let address = self.read(frame, address, Width::Long)?;
+                let value = self.read(frame, value, *width)?;
+                self.memory.store(address, *width, value)?;
+                return Ok(None);
+            }
+            nodes::Ssa::Call {
+                parameters,
+                dest,
+                func,
+                num_params: _,
+            } => {
+                let mut arguments = vec![];
+                for parameter in parameters {
+                    let value = self.read(frame, &parameter.value, parameter.width)?;
+                    arguments.push((parameter, value));
+                }
+                arguments.sort_by_key(|(parameter, _)| parameter.number);
+                let arguments: Vec<(u64, Width, bool)> = arguments
+                    .into_iter()
+                    .map(|(p, value)| (value, p.width, p.is_variadic))
+                    .collect();
+
+                let name = match func {
+                    nodes::Address::Constant(nodes::AddressConstant::Symbol(name)) => name.to_string(),
+                    _ => {
+                        let address = self.read(frame, func, Width::Long)?;
+                        address
+                            .checked_sub(FUNCTION_BASE)
+                            .filter(|offset| offset % 8 == 0)
+                            .and_then(|offset| self.symbols.get((offset / 8) as usize))
+                            .cloned()
+                            .ok_or(InterpretError::NotAFunction(address))?
+                    }
+                };
+
+                // Defined functions get a frame of their own, builtins run here
+                if let Some(&function) = self.functions.iter().find(|f| f.name == name) {
+                    frame.waiting = dest.as_ref().map(|(dest, width)| (dest, *width));
+                    return Ok(Some(Flow::Call(function, arguments)));
+                }
+                let result = self.builtin(&name, &arguments)?;
+                match dest {
+                    Some((dest, width)) => (dest, returned(result, *width)),
+                    None => return Ok(None),
+                }
+            }
+            nodes::Ssa::Return { value } => {
+                let value = match value {
+                    Some((address, width)) => Some(self.read(frame, address, *width)?),
+                    None => None,
+                };
+                return Ok(Some(Flow::Return(value)));
+            }
+            nodes::Ssa::Jump(label) => return Ok(Some(Flow::Jump(label.clone()))),
+            nodes::Ssa::Branch {
+                width,
+                cond,
+                true_target,
+                false_target,
+            } => {
+                let cond = self.read(frame, cond, *width)?;
+                let taken = if width.is_floating() {
+                    to_float(cond, *width) != 0.0
+                } else {
+                    cond != 0
+                };
+                let target = if taken { true_target } else { false_target };
+                return Ok(Some(Flow::Jump(target.clone())));
+            }
+            // Phis run when their block is entered
+            nodes::Ssa::Phi(_) | nodes::Ssa::Label(_) => return Ok(None),
+        };
+        frame.values.insert(dest.clone(), value);
+        Ok(None)
+    }
+
+    /// Sets all phis at the start of a block at once, from the operands
+    /// of the block control came from
+    fn enter_block(&mut self, frame: &mut Frame<'a>, from: Option<&nodes::Label>) -> Result<()> {
+        let block = frame.function.body.block(frame.block);
+        let mut values = vec![];
+        for phi in &block.phis {
+            let operand = phi
+                .merging
+                .iter()
+                .find(|(_, label)| Some(label) == from)
+                .ok_or_else(|| {
+                    Self::runtime_error(
+                        frame,
+                        format!(
+                            "phi of {} has no operand for @{}",
+                            phi.dest.to_ir_string(),
+                            from.map_or("entry".into(), |label| label.to_ir_string())
+                        ),
+                    )
+                })?;
+            values.push((&phi.dest, self.read(frame, &operand.0, phi.width)?));
+        }
+        for (dest, value) in values {
+            frame.values.insert(dest.clone(), value);
+        }
+        Ok(())
+    }
+
+    /// Frame of a call to `function`, about to run its entry block
+    fn enter(&mut self, function: &'a nodes::FunctionDef, arguments: &[(u64, Width, bool)]) -> Result<Frame<'a>> {
+        // Named parameters are bound to the first version of their
+        // variable, variadic ones get 8 byte slots in memory
+        let mut frame = Frame {
+            function,
+            values: HashMap::new(),
+            variadic_area: 0,
+            block: function.body.entry(),
+            position: 0,
+            waiting: None,
+            stack_mark: self.memory.stack_mark(),
+        };
+        let (named, variadic): (Vec<_>, Vec<_>) = arguments.iter().partition(|(_, _, is_variadic)| !is_variadic);
+        for ((name, _), &(value, _, _)) in function.parameters.iter().zip(named) {
+            frame
+                .values
+                .insert(nodes::Address::source_count(name.clone(), 0), value);
+        }
+        if !variadic.is_empty() {
+            frame.variadic_area = self.memory.push(8 * variadic.len());
+            for (idx, &(value, width, _)) in variadic.into_iter().enumerate() {
+                let value = if width.is_floating() {
+                    from_float(to_float(value, width), Width::Double)
+                } else {
+                    value
+                };
+                self.memory.store(frame.variadic_area + 8 * idx as u64, Width::Long, value)?;
+            }
+        }
+
+        self.enter_block(&mut frame, None)?;
+        Ok(frame)
+    }
+
+    /// Runs `function` to completion. Calls push a frame on a stack of
+    /// their own rather than recursing, so deep recursion in the program
+    /// does not grow the stack of the interpreter.
+    fn call(&mut self, function: &'a nodes::FunctionDef, arguments: &[(u64, Width, bool)]) -> Result<Option<u64>> {
+        let mut frames = vec![self.enter(function, arguments)?];
+        loop {
+            let frame = frames.last_mut().expect("Frames are popped on return only");
+            let block = frame.function.body.block(frame.block);
+            let ssa = block.body.get(frame.position).map_or_else(|| block.terminator(), |i| &i.ssa);
+            frame.position += 1;
+
+            match self.step(frame, ssa)? {
+                None => {}
+                Some(Flow::Jump(target)) => {
+                    frame.block = frame.function.body.block_of(&target).ok_or_else(|| {
+                        Self::runtime_error(frame, format!("jump to unknown label @{}", target.to_ir_string()))
+                    })?;
+                    frame.position = 0;
+                    self.enter_block(frame, Some(&block.label))?;
+                }
+                Some(Flow::Call(function, arguments)) => {
+                    if frames.len() == MAX_CALL_DEPTH {
+                        return Err(InterpretError::StackOverflow);
+                    }
+                    let callee = self.enter(function, &arguments)?;
+                    frames.push(callee);
+                }
+                Some(Flow::Return(value)) => {
+                    let callee = frames.pop().expect("The returning frame is on the stack");
+                    self.memory.release(callee.stack_mark);
+                    let Some(caller) = frames.last_mut() else {
+                        return Ok(value);
+                    };
+                    if let Some((dest, width)) = caller.waiting.take() {
+                        caller.values.insert(dest.clone(), returned(value, width));
+                    }
+                }
+            }
+        }
+    }
+
+    fn builtin(&mut self, name: &str, arguments: &[(u64, Width, bool)]) -> Result<Option<u64>> {
+        let argument = |idx: usize| arguments.get(idx).map_or(0, |(value, _, _)| *value);
+        match name {
+            "printf" => {
+                let format = self.memory.c_string(argument(0))?;
+                let output = self.printf(&format, &arguments[1.min(arguments.len())..])?;
+                self.stdout.extend_from_slice(&output);
+                Ok(Some(output.len() as u64))
+            }
+            "puts" => {
+                let string = self.memory.c_string(argument(0))?;
+                self.stdout.extend_from_slice(&string);
+                self.stdout.push(b'\n');
+                Ok(Some(1))
+            }
+            "putchar" => {
+                self.stdout.push(argument(0) as u8);
+                Ok(Some(argument(0) & 0xff))
+            }
+            "malloc" => Ok(Some(self.memory.allocate(argument(0) as usize))),
+            // Allocations are never reused, so there is nothing to release
+            "free" => Ok(None),
+            _ => Err(InterpretError::UnknownFunction(name.to_string())),
+        }
+    }
+
+    /// Formats like C's printf, for the conversions programs commonly use
+    fn printf(&self, format: &[u8], arguments: &[(u64, Width, bool)]) -> Result<Vec<u8>> {
+        let mut output = vec![];
+        let mut arguments = arguments.iter().map(|(value, width, _)| (*value, *width));
+        let mut next_argument = || arguments.next().unwrap_or((0, Width::Long));
+        let mut chars = format.iter().copied().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != b'%' {
+                output.push(c);
+                continue;
+            }
+
+            let mut spec = Spec::default();
+            while let Some(&flag) = chars.peek() {
+                match flag {
+                    b'-' => spec.left = true,
+                    b'+' => spec.plus = true,
+                    b' ' => spec.space = true,
+                    b'#' => spec.alternate = true,
+                    b'0' => spec.zero = true,
+                    _ => break,
+                }
+                chars.next();
+            }
+            if chars.peek() == Some(&b'*') {
+                chars.next();
+                let width = signed(next_argument().0, Width::Word);
+                spec.left |= width < 0;
+                spec.width = width.unsigned_abs() as usize;
+            } else {
+                while let Some(digit) = chars.peek().and_then(|&d| (d as char).to_digit(10)) {
+                    spec.width = spec.width * 10 + digit as usize;
+                    chars.next();
+                }
+            }
+            if chars.peek() == Some(&b'.') {
+                chars.next();
+                let mut precision = 0;
+                if chars.peek() == Some(&b'*') {
+                    chars.next();
+                    precision = signed(next_argument().0, Width::Word).max(0) as usize;
+                } else {
+                    while let Some(digit) = chars.peek().and_then(|&d| (d as char).to_digit(10)) {
+                        precision = precision * 10 + digit as usize;
+                        chars.next();
+                    }
+                }
+                spec.precision = Some(precision);
+            }
+
+            let mut length = Width::Word;
+            while let Some(&modifier) = chars.peek() {
+                length = match modifier {
+                    b'h' if length == Width::Short => Width::Byte,
+                    b'h' => Width::Short,
+                    b'l' | b'z' | b'j' | b't' | b'L' => Width::Long,
+                    _ => break,
+                };
+                chars.next();
+            }
+
+            let Some(conversion) = chars.next() else {
+                output.push(b'%');
+                break;
+            };
+            let body = match conversion {
+                b'%' => {
+                    output.push(b'%');
+                    continue;
+                }
+                b'd' | b'i' => {
+                    let value = signed(next_argument().0, length);
+                    let sign = if value < 0 {
+                        "-"
+                    } else if spec.plus {
+                        "+"
+                    } else if spec.space {
+                        " "
+                    } else {
+                        ""
+                    };
+                    spec.integer(sign, value.unsigned_abs().to_string(), "")
+                }
+                b'u' | b'x' | b'X' | b'o' => {
+                    let value = unsigned(next_argument().0, length);
+                    let (digits, prefix) = match conversion {
+                        b'u' => (value.to_string(), ""),
+                        b'x' => (format!("{value:x}"), "0x"),
+                        b'X' => (format!("{value:X}"), "0X"),
+                        _ => (format!("{value:o}"), "0"),
+                    };
+                    let prefix = if spec.alternate && value != 0 { prefix } else { "" };
+                    spec.integer("", digits, prefix)
+                }
+                b'c' => spec.pad(vec![next_argument().0 as u8], false),
+                b's' => {
+                    let address = next_argument().0;
+                    let mut string = if address == 0 {
+                        b"(null)".to_vec()
+                    } else {
+                        self.memory.c_string(address)?
+                    };
+                    if let Some(precision) = spec.precision {
+                        string.truncate(precision);
+                    }
+                    spec.pad(string, false)
+                }
+                b'p' => spec.pad(format!("{:#x}", next_argument().0).into_bytes(), false),
+                b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => {
+                    let (value, width) = next_argument();
+                    spec.floating(to_float(value, width), conversion)
+                }
+                // %n and anything unknown are left out
+                b'n' => continue,
+                other => vec![b'%', other],
+            };
+            output.extend(body);
+        }
+        Ok(output)
+    }
+}
+
+/// Where control goes after an instruction that leaves the block
+enum Flow<'a> {
+    Jump(nodes::Label),
+    Return(Option<u64>),
+    /// Into a defined function, with the value, width and variadic flag
+    /// of each argument
+    Call(&'a nodes::FunctionDef, Vec<(u64, Width, bool)>),
+}
+
+/// Flags, field width and precision of a printf conversion
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    /// Pads to the field width, with zeros after the sign and prefix
+    /// when `numeric` and the 0 flag is set
+    fn pad(&self, body: Vec<u8>, numeric: bool) -> Vec<u8> {
+        if body.len() >= self.width {
+            return body;
+        }
+        let fill = self.width - body.len();
+        if self.left {
+            let mut padded = body;
+            padded.resize(self.width, b' ');
+            padded
+        } else if numeric && self.zero {
+            let sign = body
+                .iter()
+                .take_while(|&&c| matches!(c, b'-' | b'+' | b' '))
+                .count();
+            let prefix = if body[sign..].starts_with(b"0x") || body[sign..].starts_with(b"0X") {
+                sign + 2
+            } else {
+                sign
+            };
+            let mut padded = body[..prefix].to_vec();
+            padded.resize(prefix + fill, b'0');
+            padded.extend_from_slice(&body[prefix..]);
+            padded
+        } else {
+            let mut padded = vec![b' '; fill];
+            padded.extend(body);
+            padded
+        }
+    }
+
+    fn integer(&self, sign: &str, digits: String, prefix: &str) -> Vec<u8> {
+        let digits = match self.precision {
+            // A zero precision prints nothing for zero
+            Some(0) if digits == "0" => String::new(),
+            Some(precision) => format!("{digits:0>precision$}"),
+            None => digits,
+        };
+        // The octal prefix is only needed without a leading zero already
+        let prefix = if prefix == "0" && digits.starts_with('0') { "" } else { prefix };
+        self.pad(
+            format!("{sign}{prefix}{digits}").into_bytes(),
+            self.precision.is_none(),
+        )
+    }
+
+    fn floating(&self, value: f64, conversion: u8) -> Vec<u8> {
+        let sign = if value.is_sign_negative() && !value.is_nan() {
+            "-"
+        } else if self.plus {
+            "+"
+        } else if self.space {
+            " "
+        } else {
+            ""
+        };
+        let upper = conversion.is_ascii_uppercase();
+        let magnitude = value.abs();
+        let body = if !value.is_finite() {
+            let body = if value.is_nan() { "nan" } else { "inf" };
+            let body = if upper { body.to_uppercase() } else { body.into() };
+            return self.pad(format!("{sign}{body}").into_bytes(), false);
+        } else {
+            let precision = self.precision.unwrap_or(6);
+            match conversion.to_ascii_lowercase() {
+                b'f' => format!("{magnitude:.precision$}"),
+                b'e' => exponential(magnitude, precision),
+                _ => {
+                    // %g picks %e or %f by the exponent, then drops
+                    // trailing zeros unless # is given
+                    let precision = precision.max(1);
+                    let exponent: i32 = exponential(magnitude, precision - 1)
+                        .rsplit('e')
+                        .next()
+                        .and_then(|e| e.parse().ok())
+                        .unwrap_or(0);
+                    let body = if exponent < -4 || exponent >= precision as i32 {
+                        exponential(magnitude, precision - 1)
+                    } else {
+                        let decimals = (precision as i32 - 1 - exponent) as usize;
+                        format!("{magnitude:.decimals$}")
+                    };
+                    if self.alternate {
+                        body
+                    } else {
+                        strip_zeros(&body)
+                    }
+                }
+            }
+        };
+        let body = if self.alternate && self.precision == Some(0) && !body.contains('.') {
+            format!("{body}.")
+        } else {
+            body
+        };
+        let body = if upper { body.to_uppercase() } else { body };
+        self.pad(format!("{sign}{body}").into_bytes(), true)
+    }
+}
+
+/// C's %e: one digit, the decimals and a signed exponent of at least
+/// two digits
+fn exponential(value: f64, precision: usize) -> String {
+    let formatted = format!("{value:.precision$e}");
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{mantissa}e{sign}{:02}", exponent.abs())
+}
+
+fn strip_zeros(body: &str) -> String {
+    let (mantissa, exponent) = match body.find('e') {
+        Some(idx) => body.split_at(idx),
+        None => (body, ""),
+    };
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    format!("{mantissa}{exponent}")
+}
+
+/// Runs a whole program from its main function, with printf, puts,
+/// putchar and malloc built in for the externs it declares
+pub fn interpret(unit: &[nodes::ToplevelItem]) -> Result<Execution> {
+    let mut interpreter = Interpreter::new(unit);
+    let main = interpreter
+        .functions
+        .iter()
+        .find(|f| f.name == "main")
+        .copied()
+        .ok_or(InterpretError::NoMain)?;
+    let arguments = interpreter.main_arguments()?;
+    let exit_code = interpreter.call(main, &arguments)?;
+    Ok(Execution {
+        stdout: interpreter.stdout,
+        // Falling off the end of main returns 0
+        exit_code: exit_code.map_or(0, |code| signed(code, Width::Word) as i32),
+    })
+}
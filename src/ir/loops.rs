@@ -0,0 +1,157 @@
+use crate::ir::dominance::DominatorTree;
+
+/// A natural loop: the header and every block that reaches one of the
+/// latches, the blocks jumping back to the header, without going through
+/// the header. Back edges to the same header make a single loop.
+pub struct Loop {
+    pub header: usize,
+    /// Blocks of the loop in order, the header and nested loops included
+    pub blocks: Vec<usize>,
+    pub latches: Vec<usize>,
+    /// Innermost loop containing this one, as an index of `LoopForest::loops`
+    pub parent: Option<usize>,
+    /// 1 for loops that are not nested in another one
+    pub depth: usize,
+    /// Only block entering the loop from outside, when it goes nowhere
+    /// but the header. Code hoisted out of the loop goes there.
+    pub preheader: Option<usize>,
+}
+
+/// Natural loops of a control flow graph given by the successors of each
+/// block. Loops entered at several blocks (irreducible control flow) have
+/// no header dominating them and are not found.
+pub struct LoopForest {
+    loops: Vec<Loop>,
+    depths: Vec<usize>,
+}
+
+impl LoopForest {
+    pub fn new(successors: &[Vec<usize>], dominators: &DominatorTree) -> Self {
+        let mut predecessors = vec![vec![]; successors.len()];
+        for (block, block_successors) in successors.iter().enumerate() {
+            for &successor in block_successors {
+                predecessors[successor].push(block);
+            }
+        }
+
+        // An edge to a block dominating its source goes back to a loop header
+        let mut loops: Vec<Loop> = vec![];
+        for (block, block_successors) in successors.iter().enumerate() {
+            for &header in block_successors {
+                if !dominators.dominates(header, block) {
+                    continue;
+                }
+                match loops.iter_mut().find(|l| l.header == header) {
+                    Some(l) if !l.latches.contains(&block) => l.latches.push(block),
+                    Some(_) => (),
+                    None => loops.push(Loop {
+                        header,
+                        blocks: vec![],
+                        latches: vec![block],
+                        parent: None,
+                        depth: 1,
+                        preheader: None,
+                    }),
+                }
+            }
+        }
+
+        for l in &mut loops {
+            let mut in_loop = vec![false; successors.len()];
+            in_loop[l.header] = true;
+            let mut worklist = l.latches.clone();
+            while let Some(block) = worklist.pop() {
+                if in_loop[block] {
+                    continue;
+                }
+                in_loop[block] = true;
+                worklist.extend(
+                    predecessors[block]
+                        .iter()
+                        .filter(|&&p| dominators.is_reachable(p)),
+                );
+            }
+            l.blocks = (0..successors.len()).filter(|&block| in_loop[block]).collect();
+
+            let mut entries = predecessors[l.header].iter().filter(|p| !in_loop[**p]);
+            l.preheader = match (entries.next(), entries.next()) {
+                (Some(&entry), None) if successors[entry] == [l.header] => Some(entry),
+                _ => None,
+            };
+        }
+
+        // Loops are nested in the smallest other loop containing their header
+        for idx in 0..loops.len() {
+            loops[idx].parent = (0..loops.len())
+                .filter(|&other| other != idx && loops[other].blocks.contains(&loops[idx].header))
+                .min_by_key(|&other| loops[other].blocks.len());
+        }
+        for idx in 0..loops.len() {
+            let mut depth = 1;
+            let mut parent = loops[idx].parent;
+            while let Some(p) = parent {
+                depth += 1;
+                parent = loops[p].parent;
+            }
+            loops[idx].depth = depth;
+        }
+
+        let mut depths = vec![0; successors.len()];
+        for l in &loops {
+            for &block in &l.blocks {
+                depths[block] = depths[block].max(l.depth);
+            }
+        }
+
+        Self { loops, depths }
+    }
+
+    pub fn loops(&self) -> &[Loop] {
+        &self.loops
+    }
+
+    /// Number of loops `block` is in, 0 outside of loops
+    pub fn depth(&self, block: usize) -> usize {
+        self.depths[block]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoopForest;
+    use crate::ir::dominance::DominatorTree;
+
+    fn forest(successors: &[Vec<usize>]) -> LoopForest {
+        LoopForest::new(successors, &DominatorTree::dominators(successors))
+    }
+
+    #[test]
+    fn nested_loops() {
+        // 0 enters the loop at 1, whose body 2 enters the loop at 3 with
+        // body 4. The outer loop goes back from 5 and leaves to 6.
+        let successors = vec![vec![1], vec![2, 6], vec![3], vec![4, 5], vec![3], vec![1], vec![]];
+        let forest = forest(&successors);
+        let [inner, outer] = forest.loops() else {
+            panic!("expected two loops");
+        };
+
+        assert_eq!((outer.header, &outer.latches, &outer.blocks), (1, &vec![5], &vec![1, 2, 3, 4, 5]));
+        assert_eq!((outer.parent, outer.depth, outer.preheader), (None, 1, Some(0)));
+        assert_eq!((inner.header, &inner.latches, &inner.blocks), (3, &vec![4], &vec![3, 4]));
+        assert_eq!((inner.parent, inner.depth, inner.preheader), (Some(1), 2, Some(2)));
+
+        let depths: Vec<usize> = (0..7).map(|b| forest.depth(b)).collect();
+        assert_eq!(depths, [0, 1, 1, 2, 2, 1, 0]);
+    }
+
+    #[test]
+    fn loop_without_preheader() {
+        // The entry branches to the loop 1 and past it
+        let forest = forest(&[vec![1, 2], vec![1, 2], vec![]]);
+        let [single] = forest.loops() else {
+            panic!("expected one loop");
+        };
+        assert_eq!((single.header, &single.latches, &single.blocks), (1, &vec![1], &vec![1]));
+        assert_eq!(single.preheader, None);
+    }
+}
@@ -0,0 +1,1877 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::common::{Signedness, SourceLocation, StorageClass, Width};
+use crate::ir::{Cfg, unescape};
+use crate::ir::nodes::{self, Address, FunctionDef, Label, ToplevelItem};
+use crate::semantic_analysis::{
+    ExpressionTypes, InitialValue, Symbol, SymbolKind, SymbolType, align_of, compound_falls_through,
+    flatten, number_literal_type,
+};
+use crate::{parsing::ast, semantic_analysis::SymbolTableRef};
+
+#[derive(Debug, Clone)]
+struct State {
+    var_count: Rc<RefCell<usize>>,
+    label_count: Rc<RefCell<usize>>,
+    return_type: Option<SymbolType>,
+    is_variadic: bool,
+    /// Names of the variables whose address is taken, see `address_taken`
+    address_taken: Rc<HashSet<String>>,
+    /// Stack slots of the function, allocated on entry
+    allocas: Rc<RefCell<Vec<nodes::Ssa>>>,
+    data: Rc<RefCell<UnitData>>,
+    /// Types the type checker annotated the expressions with
+    types: Rc<ExpressionTypes>,
+    /// Where the temporaries copying variables read them, finer than the
+    /// statement the reads are in
+    reads: Rc<RefCell<HashMap<usize, SourceLocation>>>,
+}
+
+impl State {
+    fn new(
+        return_type: SymbolType,
+        is_variadic: bool,
+        address_taken: HashSet<String>,
+        data: Rc<RefCell<UnitData>>,
+        types: Rc<ExpressionTypes>,
+    ) -> Self {
+        Self {
+            return_type: Some(return_type),
+            is_variadic,
+            var_count: Rc::new(RefCell::new(0)),
+            label_count: Rc::new(RefCell::new(0)),
+            address_taken: Rc::new(address_taken),
+            allocas: Rc::new(RefCell::new(vec![])),
+            data,
+            types,
+            reads: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Instructions located at their statement, and the reads of
+    /// variables at the identifier they read
+    fn located(&self, ssas: Vec<nodes::Ssa>, location: SourceLocation) -> Vec<nodes::Instruction> {
+        let reads = self.reads.borrow();
+        ssas.into_iter()
+            .map(|ssa| {
+                let read = match &ssa {
+                    nodes::Ssa::Assignment {
+                        dest: nodes::Address::CompilerTemp(temp),
+                        source: nodes::Address::Source(_),
+                        ..
+                    } => reads.get(temp).copied(),
+                    _ => None,
+                };
+                nodes::Instruction::new(ssa, Some(read.unwrap_or(location)))
+            })
+            .collect()
+    }
+
+    /// Arrays and structs are always in memory, scalars when their
+    /// address is taken
+    fn in_slot(&self, symbol: &Symbol) -> bool {
+        matches!(symbol.kind, SymbolKind::Variable { .. })
+            && matches!(symbol.storage_class, StorageClass::Auto)
+            && (symbol.type_info.is_aggregate() || self.address_taken.contains(&symbol.name))
+    }
+
+    /// Address of a new temporary holding the address of a data item
+    fn symbol_address(&self, name: Rc<String>) -> nodes::Ssa {
+        let address = nodes::Ssa::Assignment {
+            dest: nodes::Address::compiler_temp(self.var_count()),
+            source: nodes::Address::constant(nodes::AddressConstant::Symbol(name)),
+            width: Width::Long,
+        };
+        self.inc_var_cnt();
+        address
+    }
+
+    fn allocate_slot(&self, symbol: &Symbol) {
+        self.allocas.borrow_mut().push(nodes::Ssa::Alloca {
+            dest: slot(&symbol.ir_name),
+            size: symbol.type_info.size(),
+            align: symbol.type_info.alignment(),
+        });
+    }
+
+    fn last_var(&self) -> usize {
+        *self.var_count.borrow() - 1
+    }
+
+    fn var_count(&self) -> usize {
+        *self.var_count.borrow()
+    }
+
+    fn label_count(&self) -> usize {
+        *self.label_count.borrow()
+    }
+
+    fn inc_var_cnt(&self) {
+        *self.var_count.borrow_mut() += 1;
+    }
+
+    fn inc_label_cnt(&self) {
+        *self.label_count.borrow_mut() += 1;
+    }
+}
+
+/// Data items the functions of the unit refer to besides the globals,
+/// put after everything else
+#[derive(Debug, Default)]
+struct UnitData {
+    items: Vec<nodes::DataDef>,
+}
+
+impl UnitData {
+    /// Name of a new constant data item holding the bytes of a string
+    /// literal and its terminating zero
+    fn string_literal(&mut self, literal: &str) -> Rc<String> {
+        let name = format!("str.{}", self.items.len());
+        self.items.push(nodes::DataDef {
+            name: name.clone(),
+            linkage: nodes::Linkage::Local,
+            align: 1,
+            constant: true,
+            inits: vec![
+                nodes::DataInit::Bytes(unescape(literal)),
+                nodes::DataInit::Values(Width::Byte, vec![nodes::AddressConstant::Numeric(0)]),
+            ],
+        });
+        Rc::new(name)
+    }
+}
+
+/// Variables of the file scope, and the ones declared `extern` or `static`
+/// anywhere, live in data items rather than in the frame of a function
+fn is_global(symbol_table: &SymbolTableRef, symbol: &Symbol) -> bool {
+    matches!(symbol.kind, SymbolKind::Variable { .. })
+        && (matches!(symbol.storage_class, StorageClass::Extern | StorageClass::Static)
+            || symbol_table.borrow().is_file_scope(&symbol.name))
+}
+
+/// Source variables are mutable before SSA construction, every read and
+/// write of one goes to the same address
+fn variable(ir_name: String) -> Address {
+    Address::source_count(ir_name, 0)
+}
+
+/// Stack slot of a variable whose address is taken, reads and writes of
+/// the variable load from and store to it
+fn slot(ir_name: &str) -> Address {
+    Address::source_count(format!("{ir_name}.addr"), 0)
+}
+
+/// Collects the names of the variables `&` is applied to. Shadowing is not
+/// told apart: every variable of such a name gets a stack slot, mem2reg
+/// promotes the ones that did not need it.
+fn address_taken(statement: &ast::Statement, names: &mut HashSet<String>) {
+    match statement {
+        ast::Statement::Declaration(decl) => {
+            for declarator in &decl.declarators {
+                if let ast::Declarator::InitDeclarator(id) = declarator {
+                    for value in id.value.expressions() {
+                        address_taken_in(value, names);
+                    }
+                }
+            }
+        }
+        ast::Statement::ExpressionStatement(es) => address_taken_in(&es.expression, names),
+        ast::Statement::ReturnStatement(rs) => address_taken_in(&rs.expression, names),
+        ast::Statement::IfStatement(ifs) => {
+            address_taken_in(&ifs.condition.expression, names);
+            address_taken(&ifs.body, names);
+            if let Some(else_body) = &ifs.else_body {
+                address_taken(else_body, names);
+            }
+        }
+        ast::Statement::WhileStatement(ws) => {
+            address_taken_in(&ws.condition.expression, names);
+            address_taken(&ws.body, names);
+        }
+        ast::Statement::CompoundStatement(cs) => {
+            for item in &cs.items {
+                address_taken(item, names);
+            }
+        }
+    }
+}
+
+fn address_taken_in(expression: &ast::Expression, names: &mut HashSet<String>) {
+    match &expression.kind {
+        ast::ExpressionKind::Pointer(pe) => {
+            let mut argument = pe.argument.as_ref();
+            while let ast::ExpressionKind::Parenthesized(inner) = &argument.kind {
+                argument = &inner.expression;
+            }
+            if let (ast::PointerOp::AddressOf, ast::ExpressionKind::Identifier(id)) = (&pe.op, &argument.kind) {
+                names.insert(id.0.clone());
+            }
+            address_taken_in(&pe.argument, names);
+        }
+        ast::ExpressionKind::Binary(bin) => {
+            address_taken_in(&bin.left, names);
+            address_taken_in(&bin.right, names);
+        }
+        ast::ExpressionKind::Call(ce) => {
+            address_taken_in(&ce.function, names);
+            for argument in &ce.arguments {
+                address_taken_in(argument, names);
+            }
+        }
+        ast::ExpressionKind::Parenthesized(pe) => address_taken_in(&pe.expression, names),
+        ast::ExpressionKind::Assignment(asn) => {
+            match &asn.lvalue {
+                ast::LValue::Identifier(_) => (),
+                ast::LValue::Dereference(pointer) => address_taken_in(pointer, names),
+                ast::LValue::Subscript(subscript) => {
+                    address_taken_in(&subscript.array, names);
+                    address_taken_in(&subscript.index, names);
+                }
+                ast::LValue::Member(member) => address_taken_in(&member.object, names),
+            }
+            address_taken_in(&asn.rvalue, names);
+        }
+        ast::ExpressionKind::Subscript(subscript) => {
+            address_taken_in(&subscript.array, names);
+            address_taken_in(&subscript.index, names);
+        }
+        ast::ExpressionKind::Member(member) => address_taken_in(&member.object, names),
+        ast::ExpressionKind::Cast(cast) => address_taken_in(&cast.value, names),
+        // The variadic macros work on the address of their `va_list`
+        ast::ExpressionKind::Variadic(variadic) => match variadic {
+            ast::VariadicExpression::Start { list }
+            | ast::VariadicExpression::Arg { list, .. }
+            | ast::VariadicExpression::End { list } => {
+                names.insert(list.0.clone());
+            }
+            ast::VariadicExpression::Copy { dest, source } => {
+                names.insert(dest.0.clone());
+                names.insert(source.0.clone());
+            }
+        },
+        // The operand of sizeof is not evaluated
+        ast::ExpressionKind::SizeOf(_)
+        | ast::ExpressionKind::AlignOf(_)
+        | ast::ExpressionKind::Identifier(_)
+        | ast::ExpressionKind::StringLiteral(_)
+        | ast::ExpressionKind::NumberLiteral(_)
+        | ast::ExpressionKind::Empty => (),
+    }
+}
+
+/// Expressions lower to plain instructions, statements to instructions
+/// located at them
+trait SsaBuilder<T = nodes::Ssa> {
+    fn visit(&self, symbol_table: SymbolTableRef, state: &State) -> Vec<T>;
+}
+
+/// Computes the value an assignment stores to an object of `lvalue_type`.
+/// `current_value` reads the object, for compound assignments.
+fn apply_assignment_to_exp(
+    symbol_table: SymbolTableRef,
+    state: &State,
+    lvalue_type: &SymbolType,
+    current_value: impl FnOnce() -> Vec<nodes::Ssa>,
+    exp: &ast::Expression,
+    assigment_type: &ast::AssignmentType,
+) -> Vec<nodes::Ssa> {
+    let Some(op) = assigment_type.to_op() else {
+        return visit_as(exp, symbol_table, state, lvalue_type);
+    };
+
+    let exp_type = state.types.of(exp);
+    let operation_type = operation_type(&op, lvalue_type, &exp_type);
+
+    let mut new_ssas = current_value();
+    new_ssas.extend(convert(state, lvalue_type, &operation_type));
+    let left_temp = state.last_var();
+    new_ssas.extend(visit_as(exp, symbol_table, state, &operation_type));
+    // `p += n` and `p -= n` move by n pointees
+    if let Some(size) = pointee_size(&op, lvalue_type, &exp_type) {
+        new_ssas.extend(scale(state, nodes::Op::Mul, size));
+    }
+    let right_temp = state.last_var();
+
+    new_ssas.push(nodes::Ssa::Quadriplet(nodes::Quadriplet {
+        dest: nodes::Address::compiler_temp(state.var_count()),
+        op: nodes::Op::from_binop(&op, operation_type.signedness()),
+        left: nodes::Address::compiler_temp(left_temp),
+        right: Some(nodes::Address::compiler_temp(right_temp)),
+        width: Width::from_type(&operation_type),
+    }));
+    state.inc_var_cnt();
+    new_ssas.extend(convert(state, &operation_type, lvalue_type));
+    new_ssas
+}
+
+/// Size of the pointee a `+` or `-` with a pointer operand counts in, as
+/// pointer arithmetic is in elements rather than bytes. None for other
+/// operations and those without pointers.
+fn pointee_size(op: &ast::BinOp, left: &SymbolType, right: &SymbolType) -> Option<usize> {
+    if !matches!(op, ast::BinOp::Plus | ast::BinOp::Minus) {
+        return None;
+    }
+    match (left.decay(), right.decay()) {
+        (SymbolType::Pointer(pointee), _) | (_, SymbolType::Pointer(pointee)) => Some(pointee.size()),
+        _ => None,
+    }
+}
+
+/// Multiplies or divides the last computed value, a long, by `size`: an
+/// offset in elements becomes one in bytes, and a difference of addresses
+/// one in elements
+fn scale(state: &State, op: nodes::Op, size: usize) -> Vec<nodes::Ssa> {
+    if size == 1 {
+        return vec![];
+    }
+    let value = state.last_var();
+    let size_temp = state.var_count();
+    state.inc_var_cnt();
+    let scaled = state.var_count();
+    state.inc_var_cnt();
+    vec![
+        nodes::Ssa::Assignment {
+            dest: nodes::Address::compiler_temp(size_temp),
+            source: nodes::Address::constant_i64(size as i64),
+            width: Width::Long,
+        },
+        nodes::Ssa::Quadriplet(nodes::Quadriplet {
+            dest: nodes::Address::compiler_temp(scaled),
+            op,
+            left: nodes::Address::compiler_temp(value),
+            right: Some(nodes::Address::compiler_temp(size_temp)),
+            width: Width::Long,
+        }),
+    ]
+}
+
+/// Type the operands of a binary expression are converted to before the
+/// operation. The result of a shift has the promoted type of its left operand,
+/// everything else goes through the usual arithmetic conversions. Two pointers
+/// are subtracted as a signed long and compared as addresses, unsigned.
+fn operation_type(op: &ast::BinOp, left: &SymbolType, right: &SymbolType) -> SymbolType {
+    match op {
+        ast::BinOp::ShiftLeft | ast::BinOp::ShiftRight => left.promote(),
+        ast::BinOp::Minus => SymbolType::common_type(left, right),
+        _ if matches!((left.decay(), right.decay()), (SymbolType::Pointer(_), SymbolType::Pointer(_))) => {
+            SymbolType::Long(Signedness::Unsigned)
+        }
+        _ => SymbolType::common_type(left, right),
+    }
+}
+
+/// Converts the last computed value from `from` to `to`, extending or
+/// truncating it into a new temporary when the widths differ.
+/// Floating conversions go through a word at least, as aarch64 only
+/// converts between floating point and 32 or 64 bit integers.
+fn convert(state: &State, from: &SymbolType, to: &SymbolType) -> Vec<nodes::Ssa> {
+    match (from.is_floating(), to.is_floating()) {
+        (false, false) => convert_integer(state, from, to),
+        (false, true) => {
+            let word = SymbolType::Int(from.signedness());
+            let mut ssas = vec![];
+            if from.size() < word.size() {
+                ssas.extend(convert_integer(state, from, &word));
+            }
+            ssas.push(convert_floating(state, from, to, from.signedness()));
+            ssas
+        }
+        (true, false) => {
+            let word = SymbolType::Int(to.signedness());
+            let integer = if to.size() < word.size() { &word } else { to };
+            let mut ssas = vec![convert_floating(state, from, integer, to.signedness())];
+            ssas.extend(convert_integer(state, integer, to));
+            ssas
+        }
+        (true, true) if from == to => vec![],
+        (true, true) => vec![convert_floating(state, from, to, Signedness::Signed)],
+    }
+}
+
+fn convert_integer(state: &State, from: &SymbolType, to: &SymbolType) -> Vec<nodes::Ssa> {
+    let (from_width, width) = (Width::from_type(from), Width::from_type(to));
+    let dest = nodes::Address::compiler_temp(state.var_count());
+    let source = nodes::Address::compiler_temp(state.last_var());
+    let conversion = if from_width.to_bytes() < width.to_bytes() {
+        nodes::Ssa::Extend {
+            signed: from.signedness().is_signed(),
+            dest,
+            source,
+            from: from_width,
+            width,
+        }
+    } else if from_width.to_bytes() > width.to_bytes() {
+        nodes::Ssa::Truncate {
+            dest,
+            source,
+            width,
+        }
+    } else {
+        return vec![];
+    };
+    state.inc_var_cnt();
+    vec![conversion]
+}
+
+/// `signedness` is the one of the integer side of the conversion
+fn convert_floating(state: &State, from: &SymbolType, to: &SymbolType, signedness: Signedness) -> nodes::Ssa {
+    let from_width = match Width::from_type(from) {
+        Width::Byte | Width::Short => Width::Word,
+        from_width => from_width,
+    };
+    let conversion = nodes::Ssa::Convert {
+        signed: signedness.is_signed(),
+        dest: nodes::Address::compiler_temp(state.var_count()),
+        source: nodes::Address::compiler_temp(state.last_var()),
+        from: from_width,
+        width: Width::from_type(to),
+    };
+    state.inc_var_cnt();
+    conversion
+}
+
+/// Converts an integer constant of the source to `target`
+fn integer_constant(value: i64, target: &SymbolType) -> nodes::Address {
+    if target.is_floating() {
+        nodes::Address::constant_f64(value as f64)
+    } else {
+        nodes::Address::constant_i64(target.wrap(value))
+    }
+}
+
+/// Value of a literal converted to `target`, as `convert_constant` does
+fn number_constant(nl: &ast::NumberLiteral, target: &SymbolType) -> nodes::Address {
+    if !nl.is_floating() {
+        // The sign applies in the type of the literal, so `-1u` is UINT_MAX
+        let literal_type = number_literal_type(nl);
+        let value = literal_type.wrap(nl.value());
+        let float_value = match literal_type.signedness() {
+            Signedness::Signed => value as f64,
+            Signedness::Unsigned => value as u64 as f64,
+        };
+        return match target {
+            SymbolType::Float => nodes::Address::constant_f64(float_value as f32 as f64),
+            SymbolType::Double => nodes::Address::constant_f64(float_value),
+            _ => nodes::Address::constant_i64(target.wrap(value)),
+        };
+    }
+    let value = nl.float_value();
+    match target {
+        SymbolType::Float => nodes::Address::constant_f64(value as f32 as f64),
+        SymbolType::Double => nodes::Address::constant_f64(value),
+        _ => match target.signedness() {
+            Signedness::Signed => nodes::Address::constant_i64(target.wrap(value as i64)),
+            Signedness::Unsigned => nodes::Address::constant_i64(target.wrap(value as u64 as i64)),
+        },
+    }
+}
+
+/// Computes `expression` and converts its value to `target`.
+/// Constants are converted at compile time.
+fn visit_as(
+    expression: &ast::Expression,
+    symbol_table: SymbolTableRef,
+    state: &State,
+    target: &SymbolType,
+) -> Vec<nodes::Ssa> {
+    let constant = match &expression.kind {
+        ast::ExpressionKind::NumberLiteral(nl) => Some(number_constant(nl, target)),
+        ast::ExpressionKind::SizeOf(so) => {
+            Some(integer_constant(state.types.size_of(&symbol_table.borrow(), so) as i64, target))
+        }
+        ast::ExpressionKind::AlignOf(ao) => Some(integer_constant(align_of(&symbol_table.borrow(), ao) as i64, target)),
+        _ => None,
+    };
+    if let Some(source) = constant {
+        let constant = nodes::Ssa::Assignment {
+            dest: nodes::Address::compiler_temp(state.var_count()),
+            source,
+            width: Width::from_type(target),
+        };
+        state.inc_var_cnt();
+        return vec![constant];
+    }
+
+    let source_type = state.types.of(expression);
+    let mut ssas = expression.visit(symbol_table, state);
+    ssas.extend(convert(state, &source_type, target));
+    ssas
+}
+
+impl SsaBuilder for &ast::Expression {
+    fn visit(&self, symbol_table: SymbolTableRef, state: &State) -> Vec<nodes::Ssa> {
+        let mut nodes = vec![];
+        match &self.kind {
+            ast::ExpressionKind::Identifier(id)
+                if matches!(
+                    symbol_table.borrow().query(&id.0).map(|symbol| symbol.kind),
+                    Some(SymbolKind::Function { .. })
+                ) =>
+            {
+                nodes.push(nodes::Ssa::Assignment {
+                    dest: nodes::Address::compiler_temp(state.var_count()),
+                    source: nodes::Address::constant(nodes::AddressConstant::Symbol(Rc::new(
+                        id.0.clone(),
+                    ))),
+                    width: Width::Long,
+                });
+                state.inc_var_cnt();
+            }
+            // An array or struct is used through its address
+            ast::ExpressionKind::Identifier(_) | ast::ExpressionKind::Subscript(_) | ast::ExpressionKind::Member(_)
+                if state.types.of(self).is_aggregate() =>
+            {
+                nodes.extend(address_ssa(self, symbol_table, state));
+            }
+            ast::ExpressionKind::Subscript(_) | ast::ExpressionKind::Member(_) => {
+                let width = Width::from_type(&state.types.of(self));
+                nodes.extend(address_ssa(self, symbol_table, state));
+                nodes.push(nodes::Ssa::Load {
+                    dest: nodes::Address::compiler_temp(state.var_count()),
+                    address: nodes::Address::compiler_temp(state.last_var()),
+                    width,
+                });
+                state.inc_var_cnt();
+            }
+            ast::ExpressionKind::Identifier(id) => {
+                nodes.extend(variable_value(id, &symbol_table, state));
+                state.reads.borrow_mut().insert(state.last_var(), self.location);
+            }
+            ast::ExpressionKind::Binary(bin) => {
+                // Arrays are added to and compared as the pointer they decay to
+                let left_type = state.types.of(&bin.left).decay();
+                let right_type = state.types.of(&bin.right).decay();
+                let operation_type = operation_type(&bin.op, &left_type, &right_type);
+                let pointee_size = pointee_size(&bin.op, &left_type, &right_type);
+                let is_pointer = |t: &SymbolType| matches!(t, SymbolType::Pointer(_));
+
+                // The integer added to or subtracted from a pointer counts pointees
+                nodes.extend(visit_as(&bin.left, symbol_table.clone(), state, &operation_type));
+                if let Some(size) = pointee_size
+                    && !is_pointer(&left_type)
+                {
+                    nodes.extend(scale(state, nodes::Op::Mul, size));
+                }
+                let left_temp_id = state.last_var();
+                nodes.extend(visit_as(&bin.right, symbol_table.clone(), state, &operation_type));
+                if let Some(size) = pointee_size
+                    && !is_pointer(&right_type)
+                {
+                    nodes.extend(scale(state, nodes::Op::Mul, size));
+                }
+                let right_temp_id = state.last_var();
+
+                let op = nodes::Op::from_binop(&bin.op, operation_type.signedness());
+                nodes.push(nodes::Ssa::Quadriplet(nodes::Quadriplet {
+                    dest: nodes::Address::CompilerTemp(state.var_count()),
+                    op,
+                    left: nodes::Address::CompilerTemp(left_temp_id),
+                    right: Some(nodes::Address::CompilerTemp(right_temp_id)),
+                    width: Width::from_type(&operation_type),
+                }));
+                state.inc_var_cnt();
+
+                // The difference of two pointers counts the pointees between them
+                if let Some(size) = pointee_size
+                    && is_pointer(&left_type)
+                    && is_pointer(&right_type)
+                {
+                    nodes.extend(scale(state, nodes::Op::Div, size));
+                }
+
+                // Floating comparisons already yield a word
+                if op.is_cmp() && !operation_type.is_floating() {
+                    let result_type = state.types.of(self);
+                    nodes.extend(convert(state, &operation_type, &result_type));
+                }
+            }
+            ast::ExpressionKind::NumberLiteral(_)
+            | ast::ExpressionKind::SizeOf(_)
+            | ast::ExpressionKind::AlignOf(_) => {
+                let literal_type = state.types.of(self);
+                nodes.extend(visit_as(self, symbol_table, state, &literal_type));
+            }
+            ast::ExpressionKind::Call(ce) => {
+                let mut args_temps = vec![];
+                let callee_type = state.types.of(&ce.function);
+
+                let (return_type, parameters, is_variadic, has_prototype) =
+                    match callee_type.callee_function() {
+                        Some(SymbolType::Function {
+                            return_type,
+                            parameters,
+                            is_variadic,
+                            has_prototype,
+                        }) => (return_type.as_ref(), parameters, *is_variadic, *has_prototype),
+                        _ => panic!("Cannot call a variable"),
+                    };
+                if !has_prototype {
+                    // Arguments of a call without prototype are not checked
+                } else if !is_variadic && ce.arguments.len() != parameters.len() {
+                    panic!(
+                        "Wrong number of arguments, expected {} got {}",
+                        parameters.len(),
+                        ce.arguments.len()
+                    )
+                } else if is_variadic && ce.arguments.len() < parameters.len() {
+                    panic!(
+                        "Wrong number of arguments to variadic function, expected at least {} got {}",
+                        parameters.len(),
+                        ce.arguments.len()
+                    )
+                }
+
+                for (arg_idx, arg) in ce.arguments.iter().enumerate() {
+                    // Arguments without a parameter get the default argument promotions,
+                    // they are only passed on the stack after the `...` of a prototype
+                    let arg_type = match parameters.get(arg_idx) {
+                        Some(param) => param.clone(),
+                        None => state.types.of(arg).decay().default_promote(),
+                    };
+                    let arg_ssa = visit_as(arg, symbol_table.clone(), state, &arg_type);
+                    let arg_temp = state.last_var();
+                    nodes.extend(arg_ssa);
+                    args_temps.push((
+                        arg_temp,
+                        Width::from_type(&arg_type),
+                        has_prototype && parameters.get(arg_idx).is_none(),
+                    ))
+                }
+
+                // Functions named directly are called with bl, anything else is
+                // a function pointer value called through a register
+                let is_direct = ce.get_identifier().is_some_and(|id| {
+                    matches!(
+                        symbol_table.borrow().query(&id.0).map(|symbol| symbol.kind),
+                        Some(SymbolKind::Function { .. })
+                    )
+                });
+                let function_adress = match &ce.function.kind {
+                    ast::ExpressionKind::Identifier(id) if is_direct => {
+                        nodes::Address::constant(nodes::AddressConstant::Symbol(Rc::new(
+                            id.0.clone(),
+                        )))
+                    }
+                    _ => {
+                        let function_ssa = ce.function.as_ref().visit(symbol_table, state);
+                        let function_temp = state.last_var();
+                        nodes.extend(function_ssa);
+                        nodes::Address::CompilerTemp(function_temp)
+                    }
+                };
+
+                let mut params = vec![];
+
+                for (counter, &(index, width, is_variadic)) in args_temps.iter().enumerate() {
+                    params.push(nodes::FunctionParameter {
+                        value: nodes::Address::CompilerTemp(index),
+                        width,
+                        number: counter,
+                        is_variadic
+                    });
+                }
+
+                // A void call leaves no value behind
+                let dest = (*return_type != SymbolType::Void).then(|| {
+                    (
+                        nodes::Address::CompilerTemp(state.var_count()),
+                        Width::from_type(return_type),
+                    )
+                });
+
+                nodes.push(nodes::Ssa::Call {
+                    dest: dest.clone(),
+                    func: function_adress,
+                    num_params: ce.arguments.len(),
+                    parameters: params
+                });
+                if dest.is_some() {
+                    state.inc_var_cnt();
+                }
+            }
+            ast::ExpressionKind::Empty => (),
+            ast::ExpressionKind::StringLiteral(sl) => {
+                let name = state.data.borrow_mut().string_literal(&sl.0);
+                nodes.push(state.symbol_address(name));
+            }
+            ast::ExpressionKind::Assignment(ast) => match &ast.lvalue {
+                // Structs are copied, the assignment leaves the address of its object
+                lvalue if state.types.of(self).is_aggregate() => {
+                    let object_type = state.types.of(self);
+                    nodes.extend(lvalue_address(lvalue, symbol_table.clone(), state));
+                    let address = nodes::Address::compiler_temp(state.last_var());
+                    nodes.extend(ast.rvalue.as_ref().visit(symbol_table, state));
+                    let source = nodes::Address::compiler_temp(state.last_var());
+                    nodes.extend(copy(state, &address, &source, &object_type));
+                    nodes.push(nodes::Ssa::Assignment {
+                        dest: nodes::Address::compiler_temp(state.var_count()),
+                        source: address,
+                        width: Width::Long,
+                    });
+                    state.inc_var_cnt();
+                }
+                ast::LValue::Identifier(id) => {
+                    let symbol = symbol_table.borrow().query(&id.0).unwrap();
+                    let identifier_width = Width::from_type(&symbol.type_info);
+                    // The stored value stays the last temporary, as the value of the assignment
+                    let global_address = is_global(&symbol_table, &symbol).then(|| {
+                        nodes.push(state.symbol_address(Rc::new(symbol.ir_name.clone())));
+                        nodes::Address::compiler_temp(state.last_var())
+                    });
+                    let exp_ssas = apply_assignment_to_exp(
+                        symbol_table.clone(),
+                        state,
+                        &symbol.type_info,
+                        || variable_value(id, &symbol_table, state),
+                        ast.rvalue.as_ref(),
+                        &ast.atype,
+                    );
+                    nodes.extend(exp_ssas);
+
+                    let value = nodes::Address::CompilerTemp(state.last_var());
+                    nodes.push(if let Some(address) = global_address {
+                        nodes::Ssa::Store {
+                            address,
+                            value,
+                            width: identifier_width,
+                        }
+                    } else if state.in_slot(&symbol) {
+                        nodes::Ssa::Store {
+                            address: slot(&symbol.ir_name),
+                            value,
+                            width: identifier_width,
+                        }
+                    } else {
+                        nodes::Ssa::Assignment {
+                            dest: variable(symbol.ir_name),
+                            source: value,
+                            width: identifier_width,
+                        }
+                    });
+                }
+                // The address is computed once, compound assignments load through it
+                lvalue => {
+                    let object_type = state.types.of(self);
+                    let width = Width::from_type(&object_type);
+                    nodes.extend(lvalue_address(lvalue, symbol_table.clone(), state));
+                    let address = nodes::Address::compiler_temp(state.last_var());
+                    let load = || {
+                        let load = nodes::Ssa::Load {
+                            dest: nodes::Address::compiler_temp(state.var_count()),
+                            address: address.clone(),
+                            width,
+                        };
+                        state.inc_var_cnt();
+                        vec![load]
+                    };
+                    nodes.extend(apply_assignment_to_exp(
+                        symbol_table,
+                        state,
+                        &object_type,
+                        load,
+                        ast.rvalue.as_ref(),
+                        &ast.atype,
+                    ));
+                    nodes.push(nodes::Ssa::Store {
+                        address,
+                        value: nodes::Address::compiler_temp(state.last_var()),
+                        width,
+                    });
+                }
+            },
+            ast::ExpressionKind::Parenthesized(pe) => {
+                nodes.extend(pe.expression.as_ref().visit(symbol_table, state));
+            }
+            ast::ExpressionKind::Pointer(pe) => {
+                let pointer_type = state.types.of(self);
+                match pe.op {
+                    // `&f` and `*f` of a function are the same function pointer
+                    _ if state.types.passes_function_pointer(self) => {
+                        nodes.extend(pe.argument.as_ref().visit(symbol_table, state));
+                    }
+                    ast::PointerOp::AddressOf => {
+                        nodes.extend(address_ssa(&pe.argument, symbol_table, state));
+                    }
+                    ast::PointerOp::Dereference => {
+                        nodes.extend(pe.argument.as_ref().visit(symbol_table, state));
+                        // The address of an array or struct is its value
+                        if pointer_type.is_aggregate() {
+                            return nodes;
+                        }
+                        nodes.push(nodes::Ssa::Load {
+                            dest: nodes::Address::compiler_temp(state.var_count()),
+                            address: nodes::Address::compiler_temp(state.last_var()),
+                            width: Width::from_type(&pointer_type),
+                        });
+                        state.inc_var_cnt();
+                    }
+                }
+            }
+            ast::ExpressionKind::Cast(cast) => {
+                let cast_type = SymbolType::from_type_name(&symbol_table.borrow(), &cast.type_name);
+                // A cast to void only computes the value to discard it
+                if cast_type == SymbolType::Void {
+                    nodes.extend(cast.value.as_ref().visit(symbol_table, state));
+                } else {
+                    nodes.extend(visit_as(&cast.value, symbol_table, state, &cast_type));
+                }
+            }
+            ast::ExpressionKind::Variadic(variadic) => {
+                nodes.extend(variadic_ssa(variadic, symbol_table, state));
+            }
+        }
+        nodes
+    }
+}
+
+/// Value of a scalar variable, from memory or from its SSA variable
+fn variable_value(id: &ast::Identifier, symbol_table: &SymbolTableRef, state: &State) -> Vec<nodes::Ssa> {
+    let mut nodes = vec![];
+    let symbol = symbol_table.borrow().query(&id.0).unwrap();
+    let width = Width::from_type(&symbol.type_info);
+    let global_address = is_global(symbol_table, &symbol).then(|| {
+        nodes.push(state.symbol_address(Rc::new(symbol.ir_name.clone())));
+        nodes::Address::compiler_temp(state.last_var())
+    });
+    let dest = nodes::Address::compiler_temp(state.var_count());
+    nodes.push(if let Some(address) = global_address {
+        nodes::Ssa::Load { dest, address, width }
+    } else if state.in_slot(&symbol) {
+        nodes::Ssa::Load {
+            dest,
+            address: slot(&symbol.ir_name),
+            width,
+        }
+    } else {
+        nodes::Ssa::Assignment {
+            dest,
+            source: variable(symbol.ir_name),
+            width,
+        }
+    });
+    state.inc_var_cnt();
+    nodes
+}
+
+/// Address of a variable, the one of its symbol or of its slot
+fn variable_address(id: &ast::Identifier, symbol_table: &SymbolTableRef, state: &State) -> Vec<nodes::Ssa> {
+    let symbol = symbol_table.borrow().query(&id.0).unwrap();
+    if is_global(symbol_table, &symbol) {
+        return vec![state.symbol_address(Rc::new(symbol.ir_name))];
+    }
+    assert!(state.in_slot(&symbol), "Variables whose address is taken live in a slot");
+    let address = nodes::Ssa::Assignment {
+        dest: nodes::Address::compiler_temp(state.var_count()),
+        source: slot(&symbol.ir_name),
+        width: Width::Long,
+    };
+    state.inc_var_cnt();
+    vec![address]
+}
+
+/// Address of the object an assignment stores to
+fn lvalue_address(lvalue: &ast::LValue, symbol_table: SymbolTableRef, state: &State) -> Vec<nodes::Ssa> {
+    match lvalue {
+        ast::LValue::Identifier(id) => variable_address(id, &symbol_table, state),
+        ast::LValue::Dereference(pointer) => pointer.as_ref().visit(symbol_table, state),
+        ast::LValue::Subscript(subscript) => subscript_address(subscript, symbol_table, state),
+        ast::LValue::Member(member) => member_address(member, symbol_table, state),
+    }
+}
+
+/// Address of the object an `&` applies to
+fn address_ssa(
+    expression: &ast::Expression,
+    symbol_table: SymbolTableRef,
+    state: &State,
+) -> Vec<nodes::Ssa> {
+    match &expression.kind {
+        ast::ExpressionKind::Identifier(id) => variable_address(id, &symbol_table, state),
+        ast::ExpressionKind::Parenthesized(pe) => address_ssa(&pe.expression, symbol_table, state),
+        ast::ExpressionKind::Pointer(pe) if matches!(pe.op, ast::PointerOp::Dereference) => {
+            pe.argument.as_ref().visit(symbol_table, state)
+        }
+        ast::ExpressionKind::Subscript(subscript) => subscript_address(subscript, symbol_table, state),
+        ast::ExpressionKind::Member(member) => member_address(member, symbol_table, state),
+        _ => panic!("Cannot take the address of an rvalue"),
+    }
+}
+
+/// Address of `array[index]`, the pointer operand moved by `index`
+/// elements. Either operand may be the pointer, as `a[1]` is `1[a]`.
+fn subscript_address(
+    subscript: &ast::SubscriptExpression,
+    symbol_table: SymbolTableRef,
+    state: &State,
+) -> Vec<nodes::Ssa> {
+    let array_type = state.types.of(&subscript.array).decay();
+    let (pointer, index, pointer_type) = match array_type {
+        SymbolType::Pointer(_) => (&subscript.array, &subscript.index, array_type),
+        _ => {
+            let index_type = state.types.of(&subscript.index).decay();
+            (&subscript.index, &subscript.array, index_type)
+        }
+    };
+    let SymbolType::Pointer(element) = pointer_type else {
+        unreachable!("The type checker only accepts subscripts of pointers")
+    };
+
+    let mut nodes = pointer.as_ref().visit(symbol_table.clone(), state);
+    let base = state.last_var();
+    nodes.extend(visit_as(index, symbol_table, state, &SymbolType::Long(Signedness::Signed)));
+    nodes.extend(scale(state, nodes::Op::Mul, element.size()));
+    nodes.push(nodes::Ssa::Quadriplet(nodes::Quadriplet {
+        dest: nodes::Address::compiler_temp(state.var_count()),
+        op: nodes::Op::Plus,
+        left: nodes::Address::compiler_temp(base),
+        right: Some(nodes::Address::compiler_temp(state.last_var())),
+        width: Width::Long,
+    }));
+    state.inc_var_cnt();
+    nodes
+}
+
+/// Address of a member, at its offset from the struct. The value of the
+/// struct of a `.` is its address, like the pointer of a `->`.
+fn member_address(member: &ast::MemberExpression, symbol_table: SymbolTableRef, state: &State) -> Vec<nodes::Ssa> {
+    let struct_type = match state.types.of(&member.object) {
+        SymbolType::Pointer(pointee) => *pointee,
+        object_type => object_type,
+    };
+    let SymbolType::Struct(struct_type) = struct_type else {
+        unreachable!("The type checker only accepts members of structs")
+    };
+    let offset = struct_type
+        .member(&member.member.0)
+        .expect("The type checker only accepts declared members")
+        .offset;
+
+    let mut nodes = member.object.as_ref().visit(symbol_table, state);
+    let (add, _) = offset_address(state, &nodes::Address::compiler_temp(state.last_var()), offset);
+    nodes.extend(add);
+    nodes
+}
+
+/// Address `offset` bytes past `base`, in a new temporary unless the
+/// offset is zero
+fn offset_address(state: &State, base: &nodes::Address, offset: usize) -> (Vec<nodes::Ssa>, nodes::Address) {
+    if offset == 0 {
+        return (vec![], base.clone());
+    }
+    let address = nodes::Address::compiler_temp(state.var_count());
+    state.inc_var_cnt();
+    let add = nodes::Ssa::Quadriplet(nodes::Quadriplet {
+        dest: address.clone(),
+        op: nodes::Op::Plus,
+        left: base.clone(),
+        right: Some(nodes::Address::constant_i64(offset as i64)),
+        width: Width::Long,
+    });
+    (vec![add], address)
+}
+
+/// Splits the bytes from `start` to `end` of an object aligned to
+/// `alignment` into the widest accesses that keep to its alignment
+fn chunks(mut start: usize, end: usize, alignment: usize) -> Vec<(usize, Width)> {
+    let mut chunks = vec![];
+    while start < end {
+        let width = [Width::Long, Width::Word, Width::Short, Width::Byte]
+            .into_iter()
+            .find(|width| {
+                let size = width.to_bytes();
+                size <= alignment && start.is_multiple_of(size) && start + size <= end
+            })
+            .expect("Bytes are always aligned");
+        chunks.push((start, width));
+        start += width.to_bytes();
+    }
+    chunks
+}
+
+/// Copies an object of `object_type` from `source` to `dest`
+fn copy(
+    state: &State,
+    dest: &nodes::Address,
+    source: &nodes::Address,
+    object_type: &SymbolType,
+) -> Vec<nodes::Ssa> {
+    let mut nodes = vec![];
+    for (offset, width) in chunks(0, object_type.size(), object_type.alignment()) {
+        let (add, from) = offset_address(state, source, offset);
+        nodes.extend(add);
+        let value = nodes::Address::compiler_temp(state.var_count());
+        state.inc_var_cnt();
+        nodes.push(nodes::Ssa::Load {
+            dest: value.clone(),
+            address: from,
+            width,
+        });
+        let (add, to) = offset_address(state, dest, offset);
+        nodes.extend(add);
+        nodes.push(nodes::Ssa::Store { address: to, value, width });
+    }
+    nodes
+}
+
+/// Stores zero to the bytes from `start` to `end` of an object at `base`
+fn zero_fill(state: &State, base: &nodes::Address, start: usize, end: usize, alignment: usize) -> Vec<nodes::Ssa> {
+    let mut nodes = vec![];
+    for (offset, width) in chunks(start, end, alignment) {
+        let (add, address) = offset_address(state, base, offset);
+        nodes.extend(add);
+        nodes.push(nodes::Ssa::Store {
+            address,
+            value: nodes::Address::constant_i64(0),
+            width,
+        });
+    }
+    nodes
+}
+
+/// The `va_list` objects live in a slot, `va_start` and `va_arg` work on
+/// their address
+fn variadic_ssa(
+    variadic: &ast::VariadicExpression,
+    symbol_table: SymbolTableRef,
+    state: &State,
+) -> Vec<nodes::Ssa> {
+    let list_address = |list: &ast::Identifier| variable_address(list, &symbol_table, state);
+    match variadic {
+        ast::VariadicExpression::Start { list } => {
+            if !state.is_variadic {
+                panic!("va_start used in a function with fixed arguments");
+            }
+            let mut nodes = list_address(list);
+            nodes.push(nodes::Ssa::VaStart {
+                list: nodes::Address::compiler_temp(state.last_var()),
+            });
+            nodes
+        }
+        ast::VariadicExpression::Arg { list, type_name } => {
+            let value_type = SymbolType::from_type_name(&symbol_table.borrow(), type_name);
+            let mut nodes = list_address(list);
+            nodes.push(nodes::Ssa::VaArg {
+                dest: nodes::Address::compiler_temp(state.var_count()),
+                list: nodes::Address::compiler_temp(state.last_var()),
+                width: Width::from_type(&value_type),
+            });
+            state.inc_var_cnt();
+            nodes
+        }
+        ast::VariadicExpression::End { .. } => vec![],
+        ast::VariadicExpression::Copy { dest, source } => {
+            let mut nodes = list_address(dest);
+            let dest = nodes::Address::compiler_temp(state.last_var());
+            nodes.extend(list_address(source));
+            let source = nodes::Address::compiler_temp(state.last_var());
+            let va_list = SymbolType::try_from((&ast::DataType::VaList, &*symbol_table.borrow()))
+                .expect("va_list is a builtin type");
+            nodes.extend(copy(state, &dest, &source, &va_list));
+            nodes
+        }
+    }
+}
+
+/// Width a branch reads its condition with, that of the condition's type
+fn condition_width(condition: &ast::ParenthesizedExpression, state: &State) -> Width {
+    Width::from_type(&state.types.of(&condition.expression))
+}
+
+/// The condition and the jumps are located at the statement, labels are not
+impl SsaBuilder<nodes::Instruction> for &ast::IfStatement {
+    fn visit(&self, symbol_table: SymbolTableRef, state: &State) -> Vec<nodes::Instruction> {
+        let at = |ssa| nodes::Instruction::new(ssa, Some(self.location));
+        let mut out = vec![];
+
+        let expr_ssas = self
+            .condition
+            .expression
+            .as_ref()
+            .visit(symbol_table.clone(), state);
+
+        let true_label = nodes::Label::compiler_temp(state.label_count());
+        let false_label = nodes::Label::compiler_temp(state.label_count() + 1);
+        state.inc_label_cnt();
+        state.inc_label_cnt();
+
+        let width = condition_width(&self.condition, state);
+        out.extend(state.located(expr_ssas, self.location));
+        out.push(at(nodes::Ssa::Branch {
+            cond: nodes::Address::compiler_temp(state.last_var()),
+            true_target: true_label.clone(),
+            false_target: false_label.clone(),
+            width,
+        }));
+        out.push(nodes::Ssa::Label(true_label).into());
+        out.extend(self.body.as_ref().visit(symbol_table.clone(), state));
+
+        match self.else_body.as_ref() {
+            None => {
+                out.push(at(nodes::Ssa::Jump(false_label.clone())));
+                out.push(nodes::Ssa::Label(false_label).into());
+            }
+            Some(body) => {
+                let end_label = nodes::Label::compiler_temp(state.label_count());
+                state.inc_label_cnt();
+
+                out.push(at(nodes::Ssa::Jump(end_label.clone())));
+                out.push(nodes::Ssa::Label(false_label).into());
+                out.extend(body.as_ref().visit(symbol_table, state));
+                out.push(at(nodes::Ssa::Jump(end_label.clone())));
+                out.push(nodes::Ssa::Label(end_label).into());
+            }
+        }
+
+        out
+    }
+}
+
+/// The condition and the jumps are located at the statement, labels are not
+impl SsaBuilder<nodes::Instruction> for &ast::WhileStatement {
+    fn visit(&self, symbol_table: SymbolTableRef, state: &State) -> Vec<nodes::Instruction> {
+        let at = |ssa| nodes::Instruction::new(ssa, Some(self.location));
+        let mut out = vec![];
+
+        let cond_label = nodes::Label::compiler_temp(state.label_count());
+        let start_label = nodes::Label::compiler_temp(state.label_count() + 1);
+        let end_label = nodes::Label::compiler_temp(state.label_count() + 2);
+        state.inc_label_cnt();
+        state.inc_label_cnt();
+        state.inc_label_cnt();
+
+        out.push(at(nodes::Ssa::Jump(cond_label.clone())));
+        out.push(nodes::Ssa::Label(cond_label.clone()).into());
+
+        let expr_ssas = self
+            .condition
+            .expression
+            .as_ref()
+            .visit(symbol_table.clone(), state);
+        let width = condition_width(&self.condition, state);
+        out.extend(state.located(expr_ssas, self.location));
+        out.push(at(nodes::Ssa::Branch {
+            cond: nodes::Address::compiler_temp(state.last_var()),
+            true_target: start_label.clone(),
+            false_target: end_label.clone(),
+            width,
+        }));
+
+        out.push(nodes::Ssa::Label(start_label).into());
+        out.extend(self.body.as_ref().visit(symbol_table, state));
+        out.push(at(nodes::Ssa::Jump(cond_label)));
+        out.push(nodes::Ssa::Label(end_label).into());
+
+        out
+    }
+}
+
+/// Only declarators with an initializer compute anything. Variables
+/// whose address is taken also get their stack slot. Static ones are
+/// data items of the unit instead, initialized at compile time.
+fn declarator_ssa(
+    declarator: &ast::Declarator,
+    symbol_table: SymbolTableRef,
+    state: &State,
+) -> Vec<nodes::Ssa> {
+    if matches!(declarator, ast::Declarator::Abstract) {
+        return vec![];
+    }
+    let symbol = symbol_table.borrow().query(&declarator.get_identifier().0).unwrap();
+    // Declaring a function takes no storage, unlike a function pointer
+    if let SymbolKind::Function { .. } = symbol.kind {
+        return vec![];
+    }
+    if matches!(symbol.storage_class, StorageClass::Static) {
+        let definition = variable_data(
+            symbol.ir_name,
+            nodes::Linkage::Local,
+            &symbol.type_info,
+            declarator,
+            &symbol_table,
+            &state.data,
+            &state.types,
+        );
+        state.data.borrow_mut().items.push(definition);
+        return vec![];
+    }
+
+    match declarator {
+        ast::Declarator::InitDeclarator(id) => {
+            if symbol.type_info.is_aggregate() {
+                return aggregate_initializer_ssa(&symbol, &id.value, symbol_table, state);
+            }
+            let var_name = &symbol.ir_name;
+            let zero = ast::NumberLiteral("0".into());
+            let zero = ast::Expression::new(ast::ExpressionKind::NumberLiteral(zero), declarator.location());
+            let expr = id
+                .value
+                .scalar()
+                .expect("Initializers are checked by the type checker")
+                .unwrap_or(&zero);
+
+            let var_type = symbol.type_info.clone();
+            let width = Width::from_type(&var_type);
+
+            let mut expr_ssas = visit_as(expr, symbol_table, state, &var_type);
+            let last_id = state.last_var();
+
+            if state.in_slot(&symbol) {
+                state.allocate_slot(&symbol);
+                expr_ssas.push(nodes::Ssa::Store {
+                    address: slot(var_name),
+                    value: nodes::Address::compiler_temp(last_id),
+                    width,
+                });
+            } else {
+                expr_ssas.push(nodes::Ssa::Assignment {
+                    dest: variable(var_name.clone()),
+                    source: nodes::Address::compiler_temp(last_id),
+                    width,
+                });
+            }
+
+            expr_ssas
+        }
+        _ => {
+            if state.in_slot(&symbol) {
+                state.allocate_slot(&symbol);
+            }
+            vec![]
+        }
+    }
+}
+
+/// Stores the values of the initializer of a local array or struct to its
+/// slot, and zero to the bytes they leave out
+fn aggregate_initializer_ssa(
+    symbol: &Symbol,
+    initializer: &ast::Initializer,
+    symbol_table: SymbolTableRef,
+    state: &State,
+) -> Vec<nodes::Ssa> {
+    state.allocate_slot(symbol);
+    let object_type = &symbol.type_info;
+    let flat = {
+        let table = symbol_table.borrow();
+        flatten(&table, object_type, initializer, &mut |expression| {
+            Some(state.types.of(expression))
+        })
+    };
+
+    let base = slot(&symbol.ir_name);
+    let alignment = object_type.alignment();
+    let mut nodes = vec![];
+    let mut filled = 0;
+    for (&offset, value) in &flat.values {
+        nodes.extend(zero_fill(state, &base, filled, offset, alignment));
+        filled = offset + value.size();
+        match value {
+            InitialValue::Scalar(value_type, expression) => {
+                nodes.extend(visit_as(expression, symbol_table.clone(), state, value_type));
+                let value = nodes::Address::compiler_temp(state.last_var());
+                let (add, address) = offset_address(state, &base, offset);
+                nodes.extend(add);
+                nodes.push(nodes::Ssa::Store {
+                    address,
+                    value,
+                    width: Width::from_type(value_type),
+                });
+            }
+            InitialValue::Struct(value_type, expression) => {
+                nodes.extend(expression.visit(symbol_table.clone(), state));
+                let source = nodes::Address::compiler_temp(state.last_var());
+                let (add, address) = offset_address(state, &base, offset);
+                nodes.extend(add);
+                nodes.extend(copy(state, &address, &source, value_type));
+            }
+            InitialValue::String(bytes) => {
+                for (index, byte) in bytes.iter().enumerate() {
+                    let (add, address) = offset_address(state, &base, offset + index);
+                    nodes.extend(add);
+                    nodes.push(nodes::Ssa::Store {
+                        address,
+                        value: nodes::Address::constant_i64(*byte as i64),
+                        width: Width::Byte,
+                    });
+                }
+            }
+        }
+    }
+    nodes.extend(zero_fill(state, &base, filled, object_type.size(), alignment));
+    nodes
+}
+
+impl SsaBuilder<nodes::Instruction> for &ast::Statement {
+    fn visit(&self, symbol_table: SymbolTableRef, state: &State) -> Vec<nodes::Instruction> {
+        let ssas = match self {
+            ast::Statement::Declaration(decl) => decl
+                .declarators
+                .iter()
+                .flat_map(|declarator| declarator_ssa(declarator, symbol_table.clone(), state))
+                .collect(),
+            ast::Statement::ReturnStatement(rs) => {
+                if matches!(rs.expression.kind, ast::ExpressionKind::Empty) {
+                    vec![nodes::Ssa::Return { value: None }]
+                } else {
+                    let return_type = state.return_type.clone().unwrap();
+                    let mut expr_ssas =
+                        visit_as(&rs.expression, symbol_table, state, &return_type);
+                    let expression_res_var = state.last_var();
+                    expr_ssas.push(nodes::Ssa::Return {
+                        value: Some((
+                            nodes::Address::compiler_temp(expression_res_var),
+                            Width::from_type(&return_type),
+                        )),
+                    });
+                    state.inc_var_cnt();
+                    expr_ssas
+                }
+            }
+            ast::Statement::ExpressionStatement(es) => (&es.expression).visit(symbol_table, state),
+            ast::Statement::IfStatement(ifs) => return ifs.visit(symbol_table, state),
+            ast::Statement::WhileStatement(cs) => return cs.visit(symbol_table, state),
+            ast::Statement::CompoundStatement(cs) => return cs.visit(symbol_table, state),
+        };
+        state.located(ssas, self.location())
+    }
+}
+
+impl SsaBuilder<nodes::Instruction> for &ast::CompoundStatement {
+    fn visit(&self, symbol_table: SymbolTableRef, state: &State) -> Vec<nodes::Instruction> {
+
+        symbol_table.borrow_mut().compound_enter();
+
+        let mut ssas = vec![];
+
+        for statement in &self.items {
+            ssas.extend(statement.visit(symbol_table.clone(), state));
+        }
+
+        symbol_table.borrow_mut().compound_exit();
+
+        ssas
+    }
+
+}
+
+fn function_ssa(
+    fd: &ast::FunctionDefinition,
+    symbol_table: SymbolTableRef,
+    data: Rc<RefCell<UnitData>>,
+    types: Rc<ExpressionTypes>,
+) -> ToplevelItem {
+    let global_context = symbol_table.borrow().global_scope.clone();
+
+    let current_context = symbol_table.borrow().current_scope.clone();
+    let children = current_context.borrow().children[0].clone();
+    let symbols = &children.borrow().symbols;
+
+    let parameter_names: Vec<String> = fd
+        .declarator
+        .parameters()
+        .into_iter()
+        .filter_map(|param| match param {
+            ast::FunctionParameter::ParameterDeclaration(pd) => {
+                Some(pd.declarator.get_identifier().0)
+            }
+            ast::FunctionParameter::VariadicParameter => None,
+        })
+        .collect();
+
+    let parameters: Vec<_> = parameter_names
+        .iter()
+        .map(|name| {
+            let symbol = symbols.get(name).unwrap();
+            let dtype = &symbol.type_info;
+            let width = Width::from_type(dtype);
+            (symbol.name.clone(), width)
+        })
+        .collect();
+
+    let function_name = fd.declarator.get_identifier().0;
+    let function_symbol_type = &global_context.borrow().symbols[&function_name].type_info;
+    let return_width = match function_symbol_type {
+        SymbolType::Void => None,
+        return_type => Some(Width::from_type(return_type)),
+    };
+
+    let is_variadic = fd.declarator.function_declarator().is_variadic;
+
+    let mut address_taken_names = HashSet::new();
+    for item in &fd.body.items {
+        address_taken(item, &mut address_taken_names);
+    }
+    let state = State::new(
+        function_symbol_type.clone(),
+        is_variadic,
+        address_taken_names,
+        data,
+        types,
+    );
+
+    // Parameters whose address is taken are copied to their slot on entry
+    let mut parameter_stores = vec![];
+    for name in &parameter_names {
+        let symbol = symbols.get(name).unwrap();
+        if state.in_slot(symbol) {
+            state.allocate_slot(symbol);
+            parameter_stores.push(nodes::Ssa::Store {
+                address: slot(&symbol.ir_name),
+                value: variable(symbol.ir_name.clone()),
+                width: Width::from_type(&symbol.type_info),
+            });
+        }
+    }
+
+    let begin_label = Label::source(format!("start_function_{}", function_name));
+
+    // Reaching the end of main returns 0, C11 5.1.2.2.3. Other functions
+    // return without a value, which is only undefined if the caller uses it.
+    let implicit_return = if !compound_falls_through(&fd.body) {
+        vec![]
+    } else if function_name == "main" {
+        vec![nodes::Ssa::Return {
+            value: return_width.map(|width| (nodes::Address::constant_i64(0), width)),
+        }]
+    } else {
+        vec![nodes::Ssa::Return { value: None }]
+    };
+
+    // Only the statements have a location, what the function does around them has none
+    let function_body = (&fd.body).visit(symbol_table, &state);
+    let prologue = [vec![nodes::Ssa::Label(begin_label)], state.allocas.take(), parameter_stores].concat();
+    let body = prologue
+        .into_iter()
+        .map(nodes::Instruction::from)
+        .chain(function_body)
+        .chain(implicit_return.into_iter().map(nodes::Instruction::from))
+        .collect();
+
+    ToplevelItem::Function(FunctionDef {
+        name: function_name,
+        parameters,
+        body: Cfg::from_linear(terminate_blocks(body, return_width))
+            .expect("Lowered blocks are terminated"),
+        return_width,
+        // Source variables are assigned as often as the program does
+        is_ssa: false,
+    })
+}
+
+/// Makes every block end in exactly one jump, branch or return. Blocks
+/// falling into the next label jump to it, instructions after a
+/// `return` never run and are dropped. A block left open at the end of
+/// the function is not reachable by falling off its end, as the implicit
+/// return covers that, and gets a return of zero.
+fn terminate_blocks(body: Vec<nodes::Instruction>, return_width: Option<Width>) -> Vec<nodes::Instruction> {
+    let mut terminated = true;
+    let mut out: Vec<nodes::Instruction> = vec![];
+    for instruction in body {
+        match &instruction.ssa {
+            nodes::Ssa::Label(label) => {
+                if !terminated {
+                    out.push(nodes::Ssa::Jump(label.clone()).into());
+                }
+                out.push(instruction);
+                terminated = false;
+            }
+            _ if terminated => {}
+            nodes::Ssa::Jump(_) | nodes::Ssa::Branch { .. } | nodes::Ssa::Return { .. } => {
+                out.push(instruction);
+                terminated = true;
+            }
+            _ => out.push(instruction),
+        }
+    }
+    if !terminated {
+        let value = return_width.map(|width| {
+            let zero = if width.is_floating() {
+                nodes::Address::constant_f64(0.0)
+            } else {
+                nodes::Address::constant_i64(0)
+            };
+            (zero, width)
+        });
+        out.push(nodes::Ssa::Return { value }.into());
+    }
+    out
+}
+
+/// Converts a constant of type `from` to `to`, as `convert` does at run time
+fn convert_constant(
+    constant: nodes::AddressConstant,
+    from: &SymbolType,
+    to: &SymbolType,
+) -> nodes::AddressConstant {
+    let floating = |value: f64| match to {
+        SymbolType::Float => nodes::AddressConstant::Floating((value as f32 as f64).to_bits()),
+        _ => nodes::AddressConstant::Floating(value.to_bits()),
+    };
+    match constant {
+        nodes::AddressConstant::Numeric(n) if to.is_floating() => match from.signedness() {
+            Signedness::Signed => floating(n as f64),
+            Signedness::Unsigned => floating(n as u64 as f64),
+        },
+        nodes::AddressConstant::Numeric(n) => nodes::AddressConstant::Numeric(to.wrap(n)),
+        nodes::AddressConstant::Floating(bits) if to.is_floating() => floating(f64::from_bits(bits)),
+        nodes::AddressConstant::Floating(bits) => match to.signedness() {
+            Signedness::Signed => nodes::AddressConstant::Numeric(to.wrap(f64::from_bits(bits) as i64)),
+            Signedness::Unsigned => nodes::AddressConstant::Numeric(to.wrap(f64::from_bits(bits) as u64 as i64)),
+        },
+        symbol @ nodes::AddressConstant::Symbol(_) => symbol,
+    }
+}
+
+/// Data holding the value of a scalar with static storage, which C11
+/// 6.7.9p4 requires to be a constant expression, converted to `target`
+fn scalar_data(
+    expression: &ast::Expression,
+    symbol_table: &SymbolTableRef,
+    data: &RefCell<UnitData>,
+    types: &ExpressionTypes,
+    target: &SymbolType,
+) -> nodes::DataInit {
+    match address_initializer(expression, symbol_table, data, types) {
+        Some((symbol, 0)) => nodes::DataInit::Values(Width::Long, vec![nodes::AddressConstant::Symbol(symbol)]),
+        Some((symbol, offset)) => nodes::DataInit::Address(symbol, offset),
+        None => nodes::DataInit::Values(
+            Width::from_type(target),
+            vec![constant_initializer(expression, symbol_table, types, target)],
+        ),
+    }
+}
+
+/// Symbol and byte offset of an address constant, C11 6.6p9, or None for
+/// an arithmetic constant
+fn address_initializer(
+    expression: &ast::Expression,
+    symbol_table: &SymbolTableRef,
+    data: &RefCell<UnitData>,
+    types: &ExpressionTypes,
+) -> Option<(Rc<String>, i64)> {
+    let expression_type = types.of(expression);
+    match &expression.kind {
+        ast::ExpressionKind::StringLiteral(sl) => Some((data.borrow_mut().string_literal(&sl.0), 0)),
+        ast::ExpressionKind::Identifier(id) if expression_type.callee_function().is_some() => {
+            Some((Rc::new(id.0.clone()), 0))
+        }
+        // An array decays to the address of its first element
+        ast::ExpressionKind::Identifier(_) | ast::ExpressionKind::Subscript(_) | ast::ExpressionKind::Member(_)
+            if matches!(expression_type, SymbolType::Array(..)) =>
+        {
+            object_address(expression, symbol_table, data, types)
+        }
+        ast::ExpressionKind::Parenthesized(pe) => address_initializer(&pe.expression, symbol_table, data, types),
+        ast::ExpressionKind::Cast(cast) => address_initializer(&cast.value, symbol_table, data, types),
+        ast::ExpressionKind::Pointer(pe) if types.passes_function_pointer(expression) => {
+            address_initializer(&pe.argument, symbol_table, data, types)
+        }
+        ast::ExpressionKind::Pointer(pe) if matches!(pe.op, ast::PointerOp::AddressOf) => {
+            object_address(&pe.argument, symbol_table, data, types)
+        }
+        ast::ExpressionKind::Binary(bin) if matches!(bin.op, ast::BinOp::Plus | ast::BinOp::Minus) => {
+            let (address, integer, sign) = match address_initializer(&bin.left, symbol_table, data, types) {
+                Some(address) if matches!(bin.op, ast::BinOp::Minus) => (address, &bin.right, -1),
+                Some(address) => (address, &bin.right, 1),
+                None => (address_initializer(&bin.right, symbol_table, data, types)?, &bin.left, 1),
+            };
+            // The integer counts pointees, or bytes once the address is cast to an integer
+            let size = match expression_type {
+                SymbolType::Pointer(pointee) => pointee.size() as i64,
+                _ => 1,
+            };
+            let (symbol, offset) = address;
+            Some((symbol, offset + sign * size * integer_initializer(integer, symbol_table, types)))
+        }
+        _ => None,
+    }
+}
+
+/// Symbol and byte offset of an lvalue designating an object with static
+/// storage, or part of one
+fn object_address(
+    expression: &ast::Expression,
+    symbol_table: &SymbolTableRef,
+    data: &RefCell<UnitData>,
+    types: &ExpressionTypes,
+) -> Option<(Rc<String>, i64)> {
+    match &expression.kind {
+        ast::ExpressionKind::Identifier(id) => {
+            let symbol = symbol_table.borrow().query(&id.0).unwrap();
+            Some((Rc::new(symbol.ir_name), 0))
+        }
+        ast::ExpressionKind::Parenthesized(pe) => object_address(&pe.expression, symbol_table, data, types),
+        ast::ExpressionKind::Subscript(subscript) => {
+            let array_type = types.of(&subscript.array).decay();
+            let (pointer, index) = match array_type {
+                SymbolType::Pointer(_) => (&subscript.array, &subscript.index),
+                _ => (&subscript.index, &subscript.array),
+            };
+            let element_size = types.of(expression).size() as i64;
+            let (symbol, offset) = address_initializer(pointer, symbol_table, data, types)?;
+            Some((symbol, offset + element_size * integer_initializer(index, symbol_table, types)))
+        }
+        ast::ExpressionKind::Member(member) => {
+            let object_type = types.of(&member.object);
+            let (symbol, offset, struct_type) = match object_type {
+                SymbolType::Pointer(pointee) if member.through_pointer => {
+                    let (symbol, offset) = address_initializer(&member.object, symbol_table, data, types)?;
+                    (symbol, offset, *pointee)
+                }
+                object_type => {
+                    let (symbol, offset) = object_address(&member.object, symbol_table, data, types)?;
+                    (symbol, offset, object_type)
+                }
+            };
+            let SymbolType::Struct(struct_type) = struct_type else {
+                unreachable!("The type checker only accepts members of structs")
+            };
+            let member = struct_type
+                .member(&member.member.0)
+                .expect("The type checker only accepts declared members");
+            Some((symbol, offset + member.offset as i64))
+        }
+        ast::ExpressionKind::Pointer(pe) if matches!(pe.op, ast::PointerOp::Dereference) => {
+            address_initializer(&pe.argument, symbol_table, data, types)
+        }
+        _ => None,
+    }
+}
+
+/// Value of an integer constant expression, as a long
+fn integer_initializer(expression: &ast::Expression, symbol_table: &SymbolTableRef, types: &ExpressionTypes) -> i64 {
+    match constant_initializer(expression, symbol_table, types, &SymbolType::Long(Signedness::Signed)) {
+        nodes::AddressConstant::Numeric(value) => value,
+        _ => unreachable!("The type checker only accepts integer offsets"),
+    }
+}
+
+/// Value of an arithmetic constant expression converted to `target`
+fn constant_initializer(
+    expression: &ast::Expression,
+    symbol_table: &SymbolTableRef,
+    types: &ExpressionTypes,
+    target: &SymbolType,
+) -> nodes::AddressConstant {
+    let source_type = types.of(expression);
+    let constant = |address: nodes::Address| match address {
+        nodes::Address::Constant(constant) => constant,
+        _ => unreachable!("Literals lower to constants"),
+    };
+    let value = match &expression.kind {
+        ast::ExpressionKind::NumberLiteral(nl) => constant(number_constant(nl, &source_type)),
+        ast::ExpressionKind::SizeOf(so) => {
+            constant(integer_constant(types.size_of(&symbol_table.borrow(), so) as i64, &source_type))
+        }
+        ast::ExpressionKind::AlignOf(ao) => constant(integer_constant(align_of(&symbol_table.borrow(), ao) as i64, &source_type)),
+        ast::ExpressionKind::Parenthesized(pe) => {
+            constant_initializer(&pe.expression, symbol_table, types, &source_type)
+        }
+        ast::ExpressionKind::Cast(cast) => constant_initializer(&cast.value, symbol_table, types, &source_type),
+        ast::ExpressionKind::Binary(bin) => {
+            let (left_type, right_type) = (types.of(&bin.left), types.of(&bin.right));
+            let operation_type = operation_type(&bin.op, &left_type, &right_type);
+            let left = constant_initializer(&bin.left, symbol_table, types, &operation_type);
+            let right = constant_initializer(&bin.right, symbol_table, types, &operation_type);
+            let op = nodes::Op::from_binop(&bin.op, operation_type.signedness());
+            let result = match (left, right) {
+                (nodes::AddressConstant::Numeric(left), nodes::AddressConstant::Numeric(right)) => op
+                    .apply_constant(left, right, Width::from_type(&operation_type))
+                    .map(|result| nodes::AddressConstant::Numeric(operation_type.wrap(result)))
+                    .expect("The type checker rejects division by zero in constants"),
+                (nodes::AddressConstant::Floating(left), nodes::AddressConstant::Floating(right)) => {
+                    let (left, right) = (f64::from_bits(left), f64::from_bits(right));
+                    let compared = |result: bool| nodes::AddressConstant::Numeric(result as i64);
+                    match op {
+                        nodes::Op::Plus => nodes::AddressConstant::Floating((left + right).to_bits()),
+                        nodes::Op::Minus => nodes::AddressConstant::Floating((left - right).to_bits()),
+                        nodes::Op::Mul => nodes::AddressConstant::Floating((left * right).to_bits()),
+                        nodes::Op::Div | nodes::Op::UDiv => nodes::AddressConstant::Floating((left / right).to_bits()),
+                        nodes::Op::Eq => compared(left == right),
+                        nodes::Op::Ne => compared(left != right),
+                        nodes::Op::Lt | nodes::Op::ULt => compared(left < right),
+                        nodes::Op::Le | nodes::Op::ULe => compared(left <= right),
+                        nodes::Op::Gt | nodes::Op::UGt => compared(left > right),
+                        nodes::Op::Ge | nodes::Op::UGe => compared(left >= right),
+                        _ => panic!("Invalid operation on floating constants"),
+                    }
+                }
+                _ => unreachable!("The type checker only accepts constant initializers"),
+            };
+            // Comparisons give an int, whatever type they compare in
+            let result_type = if op.is_cmp() { &source_type } else { &operation_type };
+            return convert_constant(result, result_type, target);
+        }
+        _ => unreachable!("The type checker only accepts constant initializers"),
+    };
+    convert_constant(value, &source_type, target)
+}
+
+/// File scope declarations: functions are declared, variables other than
+/// `extern` ones are defined in a data item, local to the unit for `static`
+/// ones. A variable without an initializer is a tentative definition,
+/// zeroed unless defined later.
+fn declaration_ssa(
+    dec: &ast::Declaration,
+    symbol_table: &SymbolTableRef,
+    data: &RefCell<UnitData>,
+    types: &ExpressionTypes,
+) -> Vec<ToplevelItem> {
+    let symbol_type = SymbolType::try_from((&dec.dtype, &*symbol_table.borrow())).unwrap();
+    dec.declarators
+        .iter()
+        .filter_map(|declarator| {
+            let decl_type = SymbolType::try_from((declarator, &symbol_type, &*symbol_table.borrow())).unwrap();
+            Some(match decl_type {
+                SymbolType::Function {
+                    return_type,
+                    parameters,
+                    ..
+                } => ToplevelItem::Declaration(nodes::ToplevelDeclaration::Function {
+                    storage_class: dec.storage_class,
+                    name: declarator.get_identifier().0,
+                    return_width: match *return_type {
+                        SymbolType::Void => None,
+                        return_type => Some(Width::from_type(&return_type)),
+                    },
+                    parameters: parameters.iter().map(Width::from_type).collect(),
+                }),
+                _ if matches!(dec.storage_class, StorageClass::Extern) => return None,
+                // The type checker gives arrays declared with `[]` the length of their initializer
+                _ => ToplevelItem::Data(variable_data(
+                    declarator.get_identifier().0,
+                    match dec.storage_class {
+                        StorageClass::Static => nodes::Linkage::Local,
+                        StorageClass::Auto | StorageClass::Extern => nodes::Linkage::Export,
+                    },
+                    &symbol_table.borrow().query(&declarator.get_identifier().0).unwrap().type_info,
+                    declarator,
+                    symbol_table,
+                    data,
+                    types,
+                )),
+            })
+        })
+        .collect()
+}
+
+/// Data item of a variable with static storage, holding its initializer
+/// or zero
+fn variable_data(
+    name: String,
+    linkage: nodes::Linkage,
+    decl_type: &SymbolType,
+    declarator: &ast::Declarator,
+    symbol_table: &SymbolTableRef,
+    data: &RefCell<UnitData>,
+    types: &ExpressionTypes,
+) -> nodes::DataDef {
+    let inits = match declarator {
+        ast::Declarator::InitDeclarator(id) if decl_type.is_aggregate() => {
+            let flat = {
+                let table = symbol_table.borrow();
+                flatten(&table, decl_type, &id.value, &mut |expression| Some(types.of(expression)))
+            };
+            let mut inits = vec![];
+            let mut filled = 0;
+            for (offset, value) in flat.values {
+                if offset > filled {
+                    inits.push(nodes::DataInit::Zero(offset - filled));
+                }
+                filled = offset + value.size();
+                inits.push(match value {
+                    InitialValue::Scalar(value_type, expression) => {
+                        scalar_data(expression, symbol_table, data, types, &value_type)
+                    }
+                    InitialValue::String(bytes) => nodes::DataInit::Bytes(bytes),
+                    InitialValue::Struct(..) => {
+                        unreachable!("The type checker only accepts constant initializers")
+                    }
+                });
+            }
+            if filled < decl_type.size() {
+                inits.push(nodes::DataInit::Zero(decl_type.size() - filled));
+            }
+            inits
+        }
+        ast::Declarator::InitDeclarator(id) => {
+            let scalar = id.value.scalar();
+            vec![match scalar.expect("Initializers are checked by the type checker") {
+                Some(value) => scalar_data(value, symbol_table, data, types, decl_type),
+                None => nodes::DataInit::Values(
+                    Width::from_type(decl_type),
+                    vec![convert_constant(
+                        nodes::AddressConstant::Numeric(0),
+                        &SymbolType::Int(Signedness::Signed),
+                        decl_type,
+                    )],
+                ),
+            }]
+        }
+        _ => vec![nodes::DataInit::Zero(decl_type.size())],
+    };
+    nodes::DataDef {
+        name,
+        linkage,
+        align: decl_type.alignment(),
+        constant: false,
+        inits,
+    }
+}
+
+/// Lowers the translation unit to IR where source variables are still
+/// mutable, see `construct_ssa` for the SSA form the optimizer takes
+pub fn lower_unit(
+    unit: &ast::TranslationUnit,
+    symbol_table: SymbolTableRef,
+    types: ExpressionTypes,
+) -> Vec<crate::ir::nodes::ToplevelItem> {
+    let types = Rc::new(types);
+    let mut toplevels: Vec<ToplevelItem> = vec![];
+    let data = Rc::new(RefCell::new(UnitData::default()));
+    for i in unit.items.iter() {
+        match i {
+            ast::TopLevelItem::FunctionDefinition(fd) => {
+                let context = symbol_table
+                    .borrow()
+                    .function_scope(&fd.declarator.get_identifier().0, fd.declarator.location())
+                    .unwrap();
+                toplevels.push(function_ssa(
+                    fd,
+                    symbol_table.borrow().new_with_scope(context).borrow().fake_parent(),
+                    data.clone(),
+                    types.clone(),
+                ));
+            }
+            ast::TopLevelItem::Declaration(dec) => {
+                for item in declaration_ssa(dec, &symbol_table, &data, &types) {
+                    define(&mut toplevels, item);
+                }
+            }
+        }
+    }
+
+    toplevels.extend(data.take().items.into_iter().map(ToplevelItem::Data));
+    toplevels
+}
+
+/// Adds a global to the unit. A variable is defined once: a definition
+/// with an initializer replaces a tentative one, further tentative
+/// definitions add nothing.
+fn define(toplevels: &mut Vec<ToplevelItem>, item: ToplevelItem) {
+    let ToplevelItem::Data(definition) = &item else {
+        return toplevels.push(item);
+    };
+    let previous = toplevels.iter_mut().find_map(|toplevel| match toplevel {
+        ToplevelItem::Data(previous) if previous.name == definition.name => Some(previous),
+        _ => None,
+    });
+    let is_tentative = |data: &nodes::DataDef| matches!(data.inits.as_slice(), [nodes::DataInit::Zero(_)]);
+    match previous {
+        None => toplevels.push(item),
+        Some(previous) if is_tentative(previous) && !is_tentative(definition) => *previous = definition.clone(),
+        Some(_) => (),
+    }
+}
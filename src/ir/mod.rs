@@ -1,12 +1,24 @@
-mod blocks;
+mod cfg;
+mod construct;
+mod def_use;
+mod dominance;
 mod graphviz;
+mod interpret;
+mod loops;
+mod lower;
 pub mod nodes;
-mod ssa;
+mod parse;
 mod text;
+mod verify;
 
-pub use blocks::{
-    BasicBlock, block_label, extract_phi_functions, ir_to_basic_blocks, ir_to_basic_blocks_labeled,
-};
+pub use cfg::{Block, BlockId, Cfg};
+pub use construct::{UndefinedValues, construct_ssa, remove_trivial_phis};
+pub use def_use::check_variables;
+pub use dominance::DominatorTree;
 pub use graphviz::graphviz_unit;
-pub use ssa::build_ssa;
-pub use text::{IrTextRepr, into_text};
+pub use interpret::{Execution, InterpretError, interpret};
+pub use loops::{Loop, LoopForest};
+pub use lower::lower_unit;
+pub use parse::{IrParseError, from_text};
+pub use text::{IrTextRepr, escape_bytes, into_text, unescape};
+pub use verify::{VerifyError, verify_function};
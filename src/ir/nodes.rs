@@ -1,7 +1,8 @@
 use std::rc::Rc;
 
 use crate::{
-    common::{StorageClass, Width},
+    common::{Signedness, SourceLocation, StorageClass, Width},
+    ir::Cfg,
     parsing::ast,
 };
 
@@ -11,44 +12,125 @@ pub enum Op {
     Mul,
     Minus,
     Div,
+    UDiv,
+    // Remainder of the division truncating towards zero
+    Rem,
+    URem,
+    ShiftLeft,
+    // Arithmetic (sign-filling) right shift
+    ShiftRight,
+    // Logical (zero-filling) right shift
+    UShiftRight,
     Gt,
+    UGt,
     Lt,
+    ULt,
+    Ge,
+    UGe,
+    Le,
+    ULe,
     Eq,
+    Ne,
 }
 
 impl Op {
-    pub fn from_binop(binop: &ast::BinOp) -> Self {
+    pub fn from_binop(binop: &ast::BinOp, signedness: Signedness) -> Self {
+        let signed = signedness.is_signed();
         match binop {
             ast::BinOp::Mul => Op::Mul,
             ast::BinOp::Plus => Op::Plus,
             ast::BinOp::Minus => Op::Minus,
-            ast::BinOp::Div => Op::Div,
-            ast::BinOp::Gt => Op::Gt,
-            ast::BinOp::Lt => Op::Lt,
+            ast::BinOp::Div if signed => Op::Div,
+            ast::BinOp::Div => Op::UDiv,
+            ast::BinOp::Rem if signed => Op::Rem,
+            ast::BinOp::Rem => Op::URem,
+            ast::BinOp::ShiftLeft => Op::ShiftLeft,
+            ast::BinOp::ShiftRight if signed => Op::ShiftRight,
+            ast::BinOp::ShiftRight => Op::UShiftRight,
+            ast::BinOp::Gt if signed => Op::Gt,
+            ast::BinOp::Gt => Op::UGt,
+            ast::BinOp::Lt if signed => Op::Lt,
+            ast::BinOp::Lt => Op::ULt,
+            ast::BinOp::Ge if signed => Op::Ge,
+            ast::BinOp::Ge => Op::UGe,
+            ast::BinOp::Le if signed => Op::Le,
+            ast::BinOp::Le => Op::ULe,
             ast::BinOp::Eq => Op::Eq,
+            ast::BinOp::Ne => Op::Ne,
         }
     }
 
     pub fn is_cmp(&self) -> bool {
+        matches!(
+            self,
+            Self::Eq
+                | Self::Ne
+                | Self::Lt
+                | Self::ULt
+                | Self::Gt
+                | Self::UGt
+                | Self::Le
+                | Self::ULe
+                | Self::Ge
+                | Self::UGe
+        )
+    }
+
+    /// How the operation reads its operands, None when only their bits
+    /// matter
+    pub fn signedness(&self) -> Option<Signedness> {
         match self {
-            Self::Eq | Self::Lt | Self::Gt => true,
-            _ => false,
+            Self::Div | Self::Rem | Self::ShiftRight | Self::Gt | Self::Lt | Self::Ge | Self::Le => {
+                Some(Signedness::Signed)
+            }
+            Self::UDiv
+            | Self::URem
+            | Self::UShiftRight
+            | Self::UGt
+            | Self::ULt
+            | Self::UGe
+            | Self::ULe => Some(Signedness::Unsigned),
+            Self::Plus | Self::Mul | Self::Minus | Self::ShiftLeft | Self::Eq | Self::Ne => None,
         }
     }
 
-    pub fn apply_constant(&self, left: i64, right: i64) -> i64 {
-        match self {
-            Self::Div => left / right,
-            Self::Mul => left * right,
-            Self::Plus => left + right,
-            Self::Minus => left - right,
+    /// Computes the operation on integers of `width`, as the hardware does:
+    /// operands are extended from the width by the signedness of the
+    /// operation, shift amounts are taken modulo the width and the result
+    /// wraps to it. None for a division or remainder by zero.
+    pub fn apply_constant(&self, left: i64, right: i64, width: Width) -> Option<i64> {
+        let signedness = self.signedness().unwrap_or(Signedness::Signed);
+        let (left, right) = (width.extend(left, signedness), width.extend(right, signedness));
+        let (uleft, uright) = (left as u64, right as u64);
+        let shift = (right as u32) & (width.to_bytes() as u32 * 8 - 1);
+        let result = match self {
+            Self::Div | Self::UDiv | Self::Rem | Self::URem if right == 0 => return None,
+            Self::Div => left.wrapping_div(right),
+            Self::UDiv => (uleft / uright) as i64,
+            Self::Rem => left.wrapping_rem(right),
+            Self::URem => (uleft % uright) as i64,
+            Self::Mul => left.wrapping_mul(right),
+            Self::Plus => left.wrapping_add(right),
+            Self::Minus => left.wrapping_sub(right),
+            Self::ShiftLeft => left.wrapping_shl(shift),
+            Self::ShiftRight => left.wrapping_shr(shift),
+            Self::UShiftRight => uleft.wrapping_shr(shift) as i64,
             Self::Eq => (left == right) as i64,
+            Self::Ne => (left != right) as i64,
             Self::Gt => (left > right) as i64,
+            Self::UGt => (uleft > uright) as i64,
             Self::Lt => (left < right) as i64,
-        }
+            Self::ULt => (uleft < uright) as i64,
+            Self::Ge => (left >= right) as i64,
+            Self::UGe => (uleft >= uright) as i64,
+            Self::Le => (left <= right) as i64,
+            Self::ULe => (uleft <= uright) as i64,
+        };
+        Some(width.extend(result, Signedness::Signed))
     }
 }
 
+/// Comparisons of floating operands yield a word, whatever the width
 #[derive(Debug, Clone)]
 pub struct Quadriplet {
     pub width: Width,
@@ -61,7 +143,10 @@ pub struct Quadriplet {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AddressConstant {
     Numeric(i64),
-    StringLiteral(String),
+    // Bits of an f64, so that constants stay hashable
+    Floating(u64),
+    // Address of a function or a data item, named as in the source
+    Symbol(Rc<String>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -84,6 +169,10 @@ impl Address {
         Address::Constant(AddressConstant::Numeric(c))
     }
 
+    pub fn constant_f64(c: f64) -> Self {
+        Address::Constant(AddressConstant::Floating(c.to_bits()))
+    }
+
     pub fn compiler_temp(n: usize) -> Self {
         Address::CompilerTemp(n)
     }
@@ -103,7 +192,7 @@ impl Address {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Label {
     Source(Rc<String>),
     CompilerTemp(usize),
@@ -146,6 +235,62 @@ pub enum Ssa {
 
     Phi(PhiFunction),
 
+    // Width conversions: dest = source sign/zero extended from `from` to `width`
+    Extend {
+        signed: bool,
+        dest: Address,
+        source: Address,
+        from: Width,
+        width: Width,
+    },
+    // dest = low `width` bits of source
+    Truncate {
+        dest: Address,
+        source: Address,
+        width: Width,
+    },
+    // Conversions from, to or between floating widths, `signed` is the
+    // signedness of the integer side
+    Convert {
+        signed: bool,
+        dest: Address,
+        source: Address,
+        from: Width,
+        width: Width,
+    },
+
+    // Variadic arguments: points the va_list stored at `list` to the
+    // first variadic argument of the current function
+    VaStart {
+        list: Address,
+    },
+    // dest = the next `width` wide argument of the va_list stored at
+    // `list`, which moves past it
+    VaArg {
+        dest: Address,
+        list: Address,
+        width: Width,
+    },
+
+    // Memory: dest = address of a new `size` byte stack slot, aligned to
+    // `align`, that lives until the function returns
+    Alloca {
+        dest: Address,
+        size: usize,
+        align: usize,
+    },
+    // dest = the `width` wide value stored at `address`
+    Load {
+        dest: Address,
+        address: Address,
+        width: Width,
+    },
+    // Writes the low `width` bits of `value` to `address`
+    Store {
+        address: Address,
+        value: Address,
+        width: Width,
+    },
 
     // Function calls: dest = call func_name, num_params
     Call {
@@ -173,12 +318,144 @@ pub enum Ssa {
     },
 }
 
+impl Ssa {
+    /// Address written by the instruction
+    pub fn defined_address(&self) -> Option<&Address> {
+        match self {
+            Self::Quadriplet(quad) => Some(&quad.dest),
+            Self::Assignment { dest, .. }
+            | Self::Extend { dest, .. }
+            | Self::Truncate { dest, .. }
+            | Self::Convert { dest, .. }
+            | Self::VaArg { dest, .. }
+            | Self::Alloca { dest, .. }
+            | Self::Load { dest, .. } => Some(dest),
+            Self::Phi(phi) => Some(&phi.dest),
+            Self::Call { dest, .. } => dest.as_ref().map(|(dest, _)| dest),
+            Self::VaStart { .. }
+            | Self::Return { .. }
+            | Self::Store { .. }
+            | Self::Branch { .. }
+            | Self::Label(_)
+            | Self::Jump(_) => None,
+        }
+    }
+
+    /// Addresses read by the instruction, constants included
+    pub fn used_addresses(&self) -> Vec<&Address> {
+        match self {
+            Self::Quadriplet(quad) => [Some(&quad.left), quad.right.as_ref()]
+                .into_iter()
+                .flatten()
+                .collect(),
+            Self::Assignment { source, .. }
+            | Self::Extend { source, .. }
+            | Self::Truncate { source, .. }
+            | Self::Convert { source, .. }
+            | Self::VaStart { list: source }
+            | Self::VaArg { list: source, .. }
+            | Self::Load { address: source, .. } => vec![source],
+            Self::Store { address, value, .. } => vec![address, value],
+            Self::Phi(phi) => phi.merging.iter().map(|(address, _)| address).collect(),
+            Self::Call {
+                parameters, func, ..
+            } => std::iter::once(func)
+                .chain(parameters.iter().map(|p| &p.value))
+                .collect(),
+            Self::Return { value } => value.iter().map(|(address, _)| address).collect(),
+            Self::Branch { cond, .. } => vec![cond],
+            Self::Alloca { .. } | Self::Label(_) | Self::Jump(_) => vec![],
+        }
+    }
+
+    pub fn defined_address_mut(&mut self) -> Option<&mut Address> {
+        match self {
+            Self::Quadriplet(quad) => Some(&mut quad.dest),
+            Self::Assignment { dest, .. }
+            | Self::Extend { dest, .. }
+            | Self::Truncate { dest, .. }
+            | Self::Convert { dest, .. }
+            | Self::VaArg { dest, .. }
+            | Self::Alloca { dest, .. }
+            | Self::Load { dest, .. } => Some(dest),
+            Self::Phi(phi) => Some(&mut phi.dest),
+            Self::Call { dest, .. } => dest.as_mut().map(|(dest, _)| dest),
+            Self::VaStart { .. }
+            | Self::Return { .. }
+            | Self::Store { .. }
+            | Self::Branch { .. }
+            | Self::Label(_)
+            | Self::Jump(_) => None,
+        }
+    }
+
+    pub fn used_addresses_mut(&mut self) -> Vec<&mut Address> {
+        match self {
+            Self::Quadriplet(quad) => [Some(&mut quad.left), quad.right.as_mut()]
+                .into_iter()
+                .flatten()
+                .collect(),
+            Self::Assignment { source, .. }
+            | Self::Extend { source, .. }
+            | Self::Truncate { source, .. }
+            | Self::Convert { source, .. }
+            | Self::VaStart { list: source }
+            | Self::VaArg { list: source, .. }
+            | Self::Load { address: source, .. } => vec![source],
+            Self::Store { address, value, .. } => vec![address, value],
+            Self::Phi(phi) => phi.merging.iter_mut().map(|(address, _)| address).collect(),
+            Self::Call {
+                parameters, func, ..
+            } => std::iter::once(func)
+                .chain(parameters.iter_mut().map(|p| &mut p.value))
+                .collect(),
+            Self::Return { value } => value.iter_mut().map(|(address, _)| address).collect(),
+            Self::Branch { cond, .. } => vec![cond],
+            Self::Alloca { .. } | Self::Label(_) | Self::Jump(_) => vec![],
+        }
+    }
+}
+
+/// An instruction and the position of the source it was lowered from.
+/// None for the ones the compiler adds without a statement behind them.
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub ssa: Ssa,
+    pub location: Option<SourceLocation>,
+}
+
+impl Instruction {
+    pub fn new(ssa: Ssa, location: Option<SourceLocation>) -> Self {
+        Self { ssa, location }
+    }
+
+    /// Location of an instruction standing for both `self` and `other`,
+    /// as when one is folded into the other. Positions that differ leave
+    /// no single line to point at.
+    pub fn merged_location(&self, other: &Instruction) -> Option<SourceLocation> {
+        match (self.location, other.location) {
+            (Some(location), Some(other)) if location != other => None,
+            (location, other) => location.or(other),
+        }
+    }
+}
+
+impl From<Ssa> for Instruction {
+    fn from(ssa: Ssa) -> Self {
+        Self::new(ssa, None)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FunctionDef {
     pub name: String,
-    pub return_width: Width,
+    /// None for functions returning void
+    pub return_width: Option<Width>,
     pub parameters: Vec<(String, Width)>,
-    pub body: Vec<Ssa>,
+    pub body: Cfg,
+    /// False before SSA construction and after phi elimination, where an
+    /// address may be defined more than once
+    pub is_ssa: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -186,7 +463,7 @@ pub enum ToplevelDeclaration {
     Function {
         storage_class: StorageClass,
         name: String,
-        return_width: Width,
+        return_width: Option<Width>,
         parameters: Vec<Width>,
     },
 }
@@ -204,8 +481,73 @@ impl ToplevelDeclaration {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+    /// Visible to other units
+    Export,
+    /// Only referred to from the unit it is in
+    Local,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataInit {
+    Bytes(Vec<u8>),
+    /// Numbers, or addresses of symbols when the width is a long
+    Values(Width, Vec<AddressConstant>),
+    /// Address of a symbol plus a byte offset, in a long
+    Address(Rc<String>, i64),
+    /// A run of zero bytes
+    Zero(usize),
+}
+
+impl DataInit {
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Bytes(bytes) => bytes.len(),
+            Self::Values(width, values) => width.to_bytes() * values.len(),
+            Self::Address(..) => Width::Long.to_bytes(),
+            Self::Zero(size) => *size,
+        }
+    }
+}
+
+/// Memory laid out at compile time: string literals, globals, constant tables
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataDef {
+    pub name: String,
+    pub linkage: Linkage,
+    pub align: usize,
+    /// Never written to, so it can go in a read only section
+    pub constant: bool,
+    pub inits: Vec<DataInit>,
+}
+
+impl DataDef {
+    pub fn size(&self) -> usize {
+        self.inits.iter().map(DataInit::size).sum()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.inits.iter().all(|init| match init {
+            DataInit::Bytes(bytes) => bytes.iter().all(|b| *b == 0),
+            DataInit::Values(_, values) => values.iter().all(|v| *v == AddressConstant::Numeric(0)),
+            DataInit::Address(..) => false,
+            DataInit::Zero(_) => true,
+        })
+    }
+
+    pub fn has_symbols(&self) -> bool {
+        self.inits.iter().any(|init| {
+            matches!(init, DataInit::Values(_, values)
+                if values.iter().any(|v| matches!(v, AddressConstant::Symbol(_))))
+                || matches!(init, DataInit::Address(..))
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ToplevelItem {
     Function(FunctionDef),
     Declaration(ToplevelDeclaration),
+    Data(DataDef),
 }
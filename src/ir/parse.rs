@@ -0,0 +1,633 @@
+use std::rc::Rc;
+
+use thiserror::Error;
+
+use crate::{
+    common::{SourceLocation, StorageClass, Width},
+    ir::{Cfg, nodes, text},
+};
+
+#[derive(Error, Debug)]
+pub enum IrParseError {
+    #[error("line {line}: expected {expected}, found {found:?}")]
+    Unexpected {
+        line: usize,
+        expected: String,
+        found: String,
+    },
+
+    #[error("unexpected end of input, expected {expected}")]
+    UnexpectedEnd { expected: String },
+
+    /// Instructions that do not make up basic blocks
+    #[error(
+        "{}",
+        errors.iter().map(|e| format!("in function '{function}': {e}")).collect::<Vec<_>>().join("\n")
+    )]
+    Blocks { function: String, errors: Vec<String> },
+}
+
+type Result<T> = std::result::Result<T, IrParseError>;
+
+/// Characters ending a word besides whitespace
+const DELIMITERS: &[char] = &[',', '(', ')', ':', '[', ']', '{', '}'];
+
+/// Reads the tokens of one line of IR text
+#[derive(Clone, Copy)]
+struct Cursor<'a> {
+    rest: &'a str,
+    line: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str, line: usize) -> Self {
+        Self { rest: text, line }
+    }
+
+    fn error(&self, expected: &str) -> IrParseError {
+        let found = self.rest.trim();
+        IrParseError::Unexpected {
+            line: self.line,
+            expected: expected.into(),
+            found: if found.is_empty() {
+                "end of line".into()
+            } else {
+                found.into()
+            },
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn is_at_end(&mut self) -> bool {
+        self.skip_spaces();
+        self.rest.is_empty()
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.is_at_end() {
+            Ok(())
+        } else {
+            Err(self.error("end of line"))
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_spaces();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", token)))
+        }
+    }
+
+    /// Next run of characters up to whitespace or a delimiter
+    fn word(&mut self) -> &'a str {
+        self.skip_spaces();
+        let end = self
+            .rest
+            .find(|c: char| c.is_whitespace() || DELIMITERS.contains(&c))
+            .unwrap_or(self.rest.len());
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        word
+    }
+
+    fn width(&mut self) -> Result<Width> {
+        let before = *self;
+        match self.word() {
+            "b" => Ok(Width::Byte),
+            "s" => Ok(Width::Short),
+            "w" => Ok(Width::Word),
+            "l" => Ok(Width::Long),
+            "f" => Ok(Width::Single),
+            "d" => Ok(Width::Double),
+            _ => Err(before.error("a width")),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self, text: &str, expected: &str) -> Result<T> {
+        text.parse().map_err(|_| self.error(expected))
+    }
+
+    /// Double quoted bytes, with the escapes `escape_bytes` writes
+    fn quoted_bytes(&mut self) -> Result<Vec<u8>> {
+        let before = *self;
+        self.expect("\"")?;
+        let mut escaped = false;
+        let end = self
+            .rest
+            .char_indices()
+            .find(|&(_, c)| {
+                let closes = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                closes
+            })
+            .map(|(idx, _)| idx)
+            .ok_or_else(|| before.error("the closing quote of the bytes"))?;
+        let bytes = text::unescape(&self.rest[..end]);
+        self.rest = &self.rest[end + 1..];
+        Ok(bytes)
+    }
+
+    /// Value in a data initializer, a number or the address of a symbol
+    fn data_value(&mut self, width: Width) -> Result<nodes::AddressConstant> {
+        let before = *self;
+        let word = self.word();
+        if let Some(name) = word.strip_prefix('$') {
+            return Ok(nodes::AddressConstant::Symbol(Rc::new(name.into())));
+        }
+        Ok(if width.is_floating() {
+            nodes::AddressConstant::Floating(before.number::<f64>(word, "a floating value")?.to_bits())
+        } else {
+            nodes::AddressConstant::Numeric(before.number(word, "an integer value")?)
+        })
+    }
+
+    fn data_init(&mut self) -> Result<nodes::DataInit> {
+        let before = *self;
+        if self.word() == "z" {
+            let size = self.word();
+            return Ok(nodes::DataInit::Zero(before.number(size, "a size")?));
+        }
+        *self = before;
+        let width = self.width()?;
+        self.skip_spaces();
+        if width == Width::Byte && self.rest.starts_with('"') {
+            return Ok(nodes::DataInit::Bytes(self.quoted_bytes()?));
+        }
+        let mut values = vec![];
+        loop {
+            self.skip_spaces();
+            if self.rest.starts_with([',', '}']) {
+                break;
+            }
+            let value = self.data_value(width)?;
+            self.skip_spaces();
+            // A symbol address with an offset, `l $name + 8`, stands alone
+            if let nodes::AddressConstant::Symbol(name) = &value
+                && values.is_empty()
+                && let Some(sign @ ('+' | '-')) = self.rest.chars().next()
+            {
+                self.rest = &self.rest[1..];
+                let before = *self;
+                let offset: i64 = before.number(self.word(), "an offset")?;
+                let offset = if sign == '-' { -offset } else { offset };
+                return Ok(nodes::DataInit::Address(name.clone(), offset));
+            }
+            values.push(value);
+        }
+        if values.is_empty() {
+            return Err(self.error("a value"));
+        }
+        Ok(nodes::DataInit::Values(width, values))
+    }
+
+    fn address(&mut self) -> Result<nodes::Address> {
+        let before = *self;
+        let word = self.word();
+        if let Some(constant) = word.strip_prefix('#') {
+            let is_floating = constant.contains(['.', 'e', 'E', 'N', 'i']);
+            return Ok(if is_floating {
+                nodes::Address::constant_f64(self.number(constant, "a floating constant")?)
+            } else {
+                nodes::Address::constant_i64(self.number(constant, "an integer constant")?)
+            });
+        }
+        if let Some(name) = word.strip_prefix('$') {
+            return Ok(nodes::Address::constant(nodes::AddressConstant::Symbol(
+                Rc::new(name.into()),
+            )));
+        }
+        let Some(name) = word.strip_prefix('%') else {
+            return Err(before.error("an address"));
+        };
+        // Source variables carry a version, shadowed ones have a dot in the name too
+        if let Some((source, count)) = name.rsplit_once('.') {
+            return Ok(nodes::Address::source_count(
+                source.into(),
+                before.number(count, "a variable version")?,
+            ));
+        }
+        match name.strip_prefix("_t").map(str::parse::<usize>) {
+            Some(Ok(temp)) => Ok(nodes::Address::compiler_temp(temp)),
+            _ => Err(before.error("a versioned variable or a compiler temporary")),
+        }
+    }
+
+    fn label(&mut self) -> Result<nodes::Label> {
+        let word = self.word();
+        if word.is_empty() {
+            return Err(self.error("a label"));
+        }
+        Ok(match word.strip_prefix("_l").map(str::parse::<usize>) {
+            Some(Ok(temp)) => nodes::Label::compiler_temp(temp),
+            _ => nodes::Label::source(word.into()),
+        })
+    }
+
+    fn op(&mut self) -> Result<nodes::Op> {
+        let before = *self;
+        Ok(match self.word() {
+            "+" => nodes::Op::Plus,
+            "*" => nodes::Op::Mul,
+            "-" => nodes::Op::Minus,
+            "/" => nodes::Op::Div,
+            "/u" => nodes::Op::UDiv,
+            "%" => nodes::Op::Rem,
+            "%u" => nodes::Op::URem,
+            "<<" => nodes::Op::ShiftLeft,
+            ">>" => nodes::Op::ShiftRight,
+            ">>u" => nodes::Op::UShiftRight,
+            ">" => nodes::Op::Gt,
+            ">u" => nodes::Op::UGt,
+            "<" => nodes::Op::Lt,
+            "<u" => nodes::Op::ULt,
+            ">=" => nodes::Op::Ge,
+            ">=u" => nodes::Op::UGe,
+            "<=" => nodes::Op::Le,
+            "<=u" => nodes::Op::ULe,
+            "==" => nodes::Op::Eq,
+            "!=" => nodes::Op::Ne,
+            _ => return Err(before.error("an operator")),
+        })
+    }
+
+    /// `call F with (param0 w %a, vparam1 l %b)`, after the destination
+    fn call(&mut self, dest: Option<(nodes::Address, Width)>) -> Result<nodes::Ssa> {
+        let func = self.address()?;
+        self.expect("with")?;
+        self.expect("(")?;
+        let mut parameters = vec![];
+        while !self.eat(")") {
+            if !parameters.is_empty() {
+                self.expect(",")?;
+            }
+            let before = *self;
+            let word = self.word();
+            let (is_variadic, word) = match word.strip_prefix('v') {
+                Some(word) => (true, word),
+                None => (false, word),
+            };
+            let number = word
+                .strip_prefix("param")
+                .ok_or_else(|| before.error("a parameter"))?;
+            parameters.push(nodes::FunctionParameter {
+                number: before.number(number, "a parameter number")?,
+                width: self.width()?,
+                value: self.address()?,
+                is_variadic,
+            });
+        }
+        Ok(nodes::Ssa::Call {
+            num_params: parameters.len(),
+            parameters,
+            dest,
+            func,
+        })
+    }
+
+    /// `dest =w ...`, every instruction writing an address
+    fn definition(&mut self) -> Result<nodes::Ssa> {
+        let dest = self.address()?;
+        self.expect("=")?;
+        let width = self.width()?;
+
+        let before = *self;
+        let keyword = self.word();
+        let ssa = match keyword {
+            "phi" => {
+                let mut merging = vec![];
+                loop {
+                    self.expect("[")?;
+                    let address = self.address()?;
+                    self.expect(",")?;
+                    self.expect("@")?;
+                    merging.push((address, self.label()?));
+                    self.expect("]")?;
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                nodes::Ssa::Phi(nodes::PhiFunction {
+                    dest,
+                    width,
+                    merging,
+                })
+            }
+            "call" => self.call(Some((dest, width)))?,
+            "sext" | "zext" => nodes::Ssa::Extend {
+                signed: keyword == "sext",
+                from: self.width()?,
+                source: self.address()?,
+                dest,
+                width,
+            },
+            "trunc" => nodes::Ssa::Truncate {
+                source: self.address()?,
+                dest,
+                width,
+            },
+            // Conversions between floating widths count as signed
+            "ftof" | "itof" | "ftoi" | "utof" | "ftou" => nodes::Ssa::Convert {
+                signed: matches!(keyword, "ftof" | "itof" | "ftoi"),
+                from: self.width()?,
+                source: self.address()?,
+                dest,
+                width,
+            },
+            "vaarg" => nodes::Ssa::VaArg {
+                list: self.address()?,
+                dest,
+                width,
+            },
+            "alloca" => {
+                let at_size = *self;
+                let size = at_size.number(self.word(), "a slot size")?;
+                self.expect("align")?;
+                let at_align = *self;
+                nodes::Ssa::Alloca {
+                    align: at_align.number(self.word(), "a slot alignment")?,
+                    size,
+                    dest,
+                }
+            }
+            "load" => nodes::Ssa::Load {
+                address: self.address()?,
+                dest,
+                width,
+            },
+            _ => {
+                *self = before;
+                let left = self.address()?;
+                if self.is_at_end() {
+                    nodes::Ssa::Assignment {
+                        dest,
+                        source: left,
+                        width,
+                    }
+                } else {
+                    nodes::Ssa::Quadriplet(nodes::Quadriplet {
+                        op: self.op()?,
+                        right: Some(self.address()?),
+                        left,
+                        dest,
+                        width,
+                    })
+                }
+            }
+        };
+        self.finish()?;
+        Ok(ssa)
+    }
+
+    /// An instruction, and the location `# line:column` after it if any.
+    /// Constants are written `#5`, without a space after the sign.
+    fn located_instruction(&mut self) -> Result<nodes::Instruction> {
+        let mut location = None;
+        if let Some((instruction, comment)) = self.rest.split_once("# ") {
+            let mut comment = Cursor::new(comment, self.line);
+            let line = comment.word();
+            let line = comment.number(line, "the line of a source location")?;
+            comment.expect(":")?;
+            let column = comment.word();
+            let column = comment.number(column, "the column of a source location")?;
+            comment.finish()?;
+            self.rest = instruction;
+            location = Some(SourceLocation { line, column });
+        }
+        Ok(nodes::Instruction::new(self.instruction()?, location))
+    }
+
+    fn instruction(&mut self) -> Result<nodes::Ssa> {
+        self.skip_spaces();
+        if let Some(label) = self.rest.strip_prefix('@') {
+            self.rest = label;
+            let label = self.label()?;
+            self.expect(":")?;
+            self.finish()?;
+            return Ok(nodes::Ssa::Label(label));
+        }
+        let before = *self;
+        let ssa = match self.word() {
+            "return" if self.is_at_end() => nodes::Ssa::Return { value: None },
+            "return" => {
+                let width = self.width()?;
+                nodes::Ssa::Return {
+                    value: Some((self.address()?, width)),
+                }
+            }
+            "jump" => nodes::Ssa::Jump(self.label()?),
+            "call" => self.call(None)?,
+            "vastart" => nodes::Ssa::VaStart { list: self.address()? },
+            "store" => {
+                let width = self.width()?;
+                let value = self.address()?;
+                self.expect(",")?;
+                nodes::Ssa::Store {
+                    address: self.address()?,
+                    value,
+                    width,
+                }
+            }
+            keyword if keyword.starts_with("branch") => {
+                let mut width = Cursor::new(&keyword["branch".len()..], self.line);
+                let width = width.width()?;
+                let cond = self.address()?;
+                self.expect(":")?;
+                nodes::Ssa::Branch {
+                    width,
+                    cond,
+                    true_target: self.label()?,
+                    false_target: self.label()?,
+                }
+            }
+            _ => {
+                *self = before;
+                return self.definition();
+            }
+        };
+        self.finish()?;
+        Ok(ssa)
+    }
+}
+
+/// `auto $f = "f": (w, l) -> w`, without a return width for void functions
+fn declaration(cursor: &mut Cursor) -> Result<nodes::ToplevelDeclaration> {
+    let storage_class = match cursor.word() {
+        "auto" => StorageClass::Auto,
+        "extern" => StorageClass::Extern,
+        "static" => StorageClass::Static,
+        _ => return Err(cursor.error("a storage class")),
+    };
+    cursor.expect("$")?;
+    let name = cursor.word().to_string();
+    cursor.expect("=")?;
+    cursor.expect(&format!("\"{}\"", name))?;
+    cursor.expect(":")?;
+    cursor.expect("(")?;
+    let mut parameters = vec![];
+    while !cursor.eat(")") {
+        if !parameters.is_empty() {
+            cursor.expect(",")?;
+        }
+        parameters.push(cursor.width()?);
+    }
+    let return_width = if cursor.eat("->") {
+        Some(cursor.width()?)
+    } else {
+        None
+    };
+    cursor.finish()?;
+    Ok(nodes::ToplevelDeclaration::Function {
+        storage_class,
+        name,
+        return_width,
+        parameters,
+    })
+}
+
+/// `export const data $t = align 4 { w 1 2, l $f, z 8 }`, where `export`
+/// and `const` are optional
+fn data(cursor: &mut Cursor) -> Result<nodes::DataDef> {
+    let linkage = if cursor.eat("export") {
+        nodes::Linkage::Export
+    } else {
+        nodes::Linkage::Local
+    };
+    let constant = cursor.eat("const");
+    cursor.expect("data")?;
+    cursor.expect("$")?;
+    let name = cursor.word().to_string();
+    cursor.expect("=")?;
+    cursor.expect("align")?;
+    let align = cursor.word();
+    let align = cursor.number(align, "an alignment")?;
+    cursor.expect("{")?;
+    let mut inits = vec![];
+    while !cursor.eat("}") {
+        if !inits.is_empty() {
+            cursor.expect(",")?;
+        }
+        inits.push(cursor.data_init()?);
+    }
+    cursor.finish()?;
+    Ok(nodes::DataDef {
+        name,
+        linkage,
+        align,
+        constant,
+        inits,
+    })
+}
+
+/// Everything of a function definition but its body
+struct FunctionHeader {
+    name: String,
+    return_width: Option<Width>,
+    parameters: Vec<(String, Width)>,
+    is_ssa: bool,
+}
+
+/// `function w f (w %a, l %b) {`, without a return width for void
+/// functions and with `out_of_ssa` before the brace for functions out of
+/// SSA form
+fn function_header(cursor: &mut Cursor) -> Result<FunctionHeader> {
+    cursor.expect("function")?;
+    let first = *cursor;
+    cursor.word();
+    let return_width = if cursor.eat("(") {
+        *cursor = first;
+        None
+    } else {
+        *cursor = first;
+        Some(cursor.width()?)
+    };
+    let name = cursor.word().to_string();
+    if name.is_empty() {
+        return Err(cursor.error("a function name"));
+    }
+    cursor.expect("(")?;
+    let mut parameters = vec![];
+    while !cursor.eat(")") {
+        if !parameters.is_empty() {
+            cursor.expect(",")?;
+        }
+        let width = cursor.width()?;
+        cursor.expect("%")?;
+        parameters.push((cursor.word().to_string(), width));
+    }
+    let is_ssa = !cursor.eat("out_of_ssa");
+    cursor.expect("{")?;
+    cursor.finish()?;
+    Ok(FunctionHeader {
+        name,
+        return_width,
+        parameters,
+        is_ssa,
+    })
+}
+
+/// Reads back the text `into_text` prints
+pub fn from_text(text: &str) -> Result<Vec<nodes::ToplevelItem>> {
+    let mut items = vec![];
+    let mut lines = text.lines().enumerate().map(|(idx, line)| Cursor::new(line, idx + 1));
+
+    while let Some(mut cursor) = lines.next() {
+        if cursor.is_at_end() {
+            continue;
+        }
+        if ["export", "const", "data"].iter().any(|start| cursor.rest.starts_with(start)) {
+            items.push(nodes::ToplevelItem::Data(data(&mut cursor)?));
+            continue;
+        }
+        if !cursor.rest.starts_with("function") {
+            items.push(nodes::ToplevelItem::Declaration(declaration(&mut cursor)?));
+            continue;
+        }
+
+        let FunctionHeader {
+            name,
+            return_width,
+            parameters,
+            is_ssa,
+        } = function_header(&mut cursor)?;
+        let mut instructions = vec![];
+        loop {
+            let Some(mut cursor) = lines.next() else {
+                return Err(IrParseError::UnexpectedEnd {
+                    expected: format!("the end of function {}", name),
+                });
+            };
+            if cursor.eat("}") {
+                cursor.finish()?;
+                break;
+            }
+            instructions.push(cursor.located_instruction()?);
+        }
+        let body = Cfg::from_linear(instructions).map_err(|errors| IrParseError::Blocks {
+            function: name.clone(),
+            errors,
+        })?;
+        items.push(nodes::ToplevelItem::Function(nodes::FunctionDef {
+            name,
+            return_width,
+            parameters,
+            body,
+            is_ssa,
+        }));
+    }
+    Ok(items)
+}
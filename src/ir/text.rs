@@ -14,6 +14,8 @@ impl IrTextRepr for Width {
             Self::Long => "l".into(),
             Self::Short => "s".into(),
             Self::Word => "w".into(),
+            Self::Single => "f".into(),
+            Self::Double => "d".into(),
         }
     }
 }
@@ -25,9 +27,22 @@ impl IrTextRepr for nodes::Op {
             Self::Mul => "*".into(),
             Self::Minus => "-".into(),
             Self::Div => "/".into(),
+            Self::UDiv => "/u".into(),
+            Self::Rem => "%".into(),
+            Self::URem => "%u".into(),
+            Self::ShiftLeft => "<<".into(),
+            Self::ShiftRight => ">>".into(),
+            Self::UShiftRight => ">>u".into(),
             Self::Gt => ">".into(),
+            Self::UGt => ">u".into(),
             Self::Lt => "<".into(),
+            Self::ULt => "<u".into(),
+            Self::Ge => ">=".into(),
+            Self::UGe => ">=u".into(),
+            Self::Le => "<=".into(),
+            Self::ULe => "<=u".into(),
             Self::Eq => "==".into(),
+            Self::Ne => "!=".into(),
         }
     }
 }
@@ -35,7 +50,95 @@ impl IrTextRepr for nodes::AddressConstant {
     fn to_ir_string(&self) -> String {
         match self {
             Self::Numeric(n) => format!("#{n}"),
-            Self::StringLiteral(n) => format!("s'{n}'"),
+            Self::Floating(bits) => format!("#{:?}", f64::from_bits(*bits)),
+            Self::Symbol(name) => format!("${name}"),
+        }
+    }
+}
+
+/// Bytes as they go between double quotes, in the IR text and in the asm
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &byte in bytes {
+        match byte {
+            b'\n' => escaped.push_str("\\n"),
+            b'\t' => escaped.push_str("\\t"),
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    escaped
+}
+
+/// Resolves the C escape sequences of string literals, and those
+/// `escape_bytes` writes
+pub fn unescape(literal: &str) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut chars = literal.bytes().peekable();
+    while let Some(c) = chars.next() {
+        if c != b'\\' {
+            bytes.push(c);
+            continue;
+        }
+        let Some(escaped) = chars.next() else {
+            bytes.push(c);
+            break;
+        };
+        match escaped {
+            b'n' => bytes.push(b'\n'),
+            b't' => bytes.push(b'\t'),
+            b'r' => bytes.push(b'\r'),
+            b'a' => bytes.push(0x07),
+            b'b' => bytes.push(0x08),
+            b'f' => bytes.push(0x0c),
+            b'v' => bytes.push(0x0b),
+            b'x' => {
+                let mut value: u8 = 0;
+                while let Some(digit) = chars.peek().and_then(|&d| (d as char).to_digit(16)) {
+                    value = value.wrapping_mul(16).wrapping_add(digit as u8);
+                    chars.next();
+                }
+                bytes.push(value);
+            }
+            b'0'..=b'7' => {
+                let mut value = escaped - b'0';
+                for _ in 0..2 {
+                    match chars.peek() {
+                        Some(&d @ b'0'..=b'7') => {
+                            value = value.wrapping_mul(8).wrapping_add(d - b'0');
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value);
+            }
+            other => bytes.push(other),
+        }
+    }
+    bytes
+}
+
+impl IrTextRepr for nodes::DataInit {
+    fn to_ir_string(&self) -> String {
+        match self {
+            Self::Bytes(bytes) => format!("b \"{}\"", escape_bytes(bytes)),
+            Self::Values(width, values) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| match value {
+                        nodes::AddressConstant::Numeric(n) => format!("{n}"),
+                        nodes::AddressConstant::Floating(bits) => format!("{:?}", f64::from_bits(*bits)),
+                        nodes::AddressConstant::Symbol(name) => format!("${name}"),
+                    })
+                    .collect();
+                format!("{} {}", width.to_ir_string(), values.join(" "))
+            }
+            Self::Address(name, offset) if *offset < 0 => format!("l ${name} - {}", offset.unsigned_abs()),
+            Self::Address(name, offset) => format!("l ${name} + {offset}"),
+            Self::Zero(size) => format!("z {size}"),
         }
     }
 }
@@ -107,7 +210,7 @@ impl IrTextRepr for nodes::Ssa {
                 Some((addr, width)) => {
                     format!("\treturn {} {}", width.to_ir_string(), addr.to_ir_string())
                 }
-                None => format!("\treturn"),
+                None => "\treturn".to_string(),
             },
             nodes::Ssa::Assignment {
                 dest,
@@ -122,6 +225,95 @@ impl IrTextRepr for nodes::Ssa {
                     source.to_ir_string()
                 )
             }
+            nodes::Ssa::Extend {
+                signed,
+                dest,
+                source,
+                from,
+                width,
+            } => {
+                format!(
+                    "\t{} ={} {} {} {}",
+                    dest.to_ir_string(),
+                    width.to_ir_string(),
+                    if *signed { "sext" } else { "zext" },
+                    from.to_ir_string(),
+                    source.to_ir_string()
+                )
+            }
+            nodes::Ssa::Truncate {
+                dest,
+                source,
+                width,
+            } => {
+                format!(
+                    "\t{} ={} trunc {}",
+                    dest.to_ir_string(),
+                    width.to_ir_string(),
+                    source.to_ir_string()
+                )
+            }
+            nodes::Ssa::Convert {
+                signed,
+                dest,
+                source,
+                from,
+                width,
+            } => {
+                let conversion = match (from.is_floating(), width.is_floating(), signed) {
+                    (true, true, _) => "ftof",
+                    (false, _, true) => "itof",
+                    (false, _, false) => "utof",
+                    (true, false, true) => "ftoi",
+                    (true, false, false) => "ftou",
+                };
+                format!(
+                    "\t{} ={} {} {} {}",
+                    dest.to_ir_string(),
+                    width.to_ir_string(),
+                    conversion,
+                    from.to_ir_string(),
+                    source.to_ir_string()
+                )
+            }
+            nodes::Ssa::VaStart { list } => {
+                format!("\tvastart {}", list.to_ir_string())
+            }
+            nodes::Ssa::VaArg { dest, list, width } => {
+                format!(
+                    "\t{} ={} vaarg {}",
+                    dest.to_ir_string(),
+                    width.to_ir_string(),
+                    list.to_ir_string()
+                )
+            }
+            nodes::Ssa::Alloca { dest, size, align } => {
+                format!("\t{} =l alloca {} align {}", dest.to_ir_string(), size, align)
+            }
+            nodes::Ssa::Load {
+                dest,
+                address,
+                width,
+            } => {
+                format!(
+                    "\t{} ={} load {}",
+                    dest.to_ir_string(),
+                    width.to_ir_string(),
+                    address.to_ir_string()
+                )
+            }
+            nodes::Ssa::Store {
+                address,
+                value,
+                width,
+            } => {
+                format!(
+                    "\tstore {} {}, {}",
+                    width.to_ir_string(),
+                    value.to_ir_string(),
+                    address.to_ir_string()
+                )
+            }
             nodes::Ssa::Call {
                 dest,
                 func,
@@ -184,11 +376,22 @@ impl IrTextRepr for nodes::Ssa {
     }
 }
 
+/// The location follows as a comment, `# line:column`
+impl IrTextRepr for nodes::Instruction {
+    fn to_ir_string(&self) -> String {
+        match self.location {
+            Some(location) => format!("{}\t# {}", self.ssa.to_ir_string(), location),
+            None => self.ssa.to_ir_string(),
+        }
+    }
+}
+
 impl IrTextRepr for StorageClass {
     fn to_ir_string(&self) -> String {
         match self {
             Self::Auto => "auto".into(),
             Self::Extern => "extern".into(),
+            Self::Static => "static".into(),
         }
     }
 }
@@ -204,15 +407,19 @@ impl IrTextRepr for nodes::ToplevelItem {
                     .collect();
                 let parameters = parameters.join(", ");
 
+                // Void functions leave out the return width
+                let return_width = f.return_width.map(|w| format!("{} ", w.to_ir_string()));
+                // Functions out of SSA form are marked, to be verified as such when read back
                 let mut s = format!(
-                    "function {} {} ({}) {{\n",
-                    f.return_width.to_ir_string(),
+                    "function {}{} ({}) {}{{\n",
+                    return_width.unwrap_or_default(),
                     f.name,
-                    parameters
+                    parameters,
+                    if f.is_ssa { "" } else { "out_of_ssa " }
                 );
-                for block in &f.body {
-                    s.push_str(&block.to_ir_string());
-                    s.push_str("\n");
+                for instruction in f.body.to_linear() {
+                    s.push_str(&instruction.to_ir_string());
+                    s.push('\n');
                 }
                 s.push_str("}\n");
                 s
@@ -227,13 +434,26 @@ impl IrTextRepr for nodes::ToplevelItem {
                     let parameters: Vec<String> =
                         parameters.iter().map(|w| w.to_ir_string()).collect();
                     let parameters = parameters.join(", ");
+                    let return_width = return_width
+                        .map(|w| format!(" -> {}", w.to_ir_string()))
+                        .unwrap_or_default();
                     format!(
-                        "{} ${name} = \"{name}\": ({parameters}) -> {}",
+                        "{} ${name} = \"{name}\": ({parameters}){return_width}",
                         storage_class.to_ir_string(),
-                        return_width.to_ir_string()
                     )
                 }
             },
+            nodes::ToplevelItem::Data(data) => {
+                let inits: Vec<String> = data.inits.iter().map(|i| i.to_ir_string()).collect();
+                format!(
+                    "{}{}data ${} = align {} {{ {} }}",
+                    if data.linkage == nodes::Linkage::Export { "export " } else { "" },
+                    if data.constant { "const " } else { "" },
+                    data.name,
+                    data.align,
+                    inits.join(", ")
+                )
+            }
         }
     }
 }
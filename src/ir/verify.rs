@@ -0,0 +1,327 @@
+use std::collections::{HashMap, HashSet};
+
+use thiserror::Error;
+
+use crate::{
+    common::Width,
+    ir::{BlockId, IrTextRepr, nodes},
+};
+
+#[derive(Error, Debug)]
+#[error("in function '{function}': {message}")]
+pub struct VerifyError {
+    pub function: String,
+    pub message: String,
+}
+
+/// Width of the value an instruction defines. Comparisons of floating
+/// operands yield a word.
+fn defined_width(ssa: &nodes::Ssa) -> Option<Width> {
+    match ssa {
+        nodes::Ssa::Quadriplet(quad) if quad.op.is_cmp() && quad.width.is_floating() => {
+            Some(Width::Word)
+        }
+        nodes::Ssa::Quadriplet(quad) => Some(quad.width),
+        nodes::Ssa::Assignment { width, .. }
+        | nodes::Ssa::Extend { width, .. }
+        | nodes::Ssa::Truncate { width, .. }
+        | nodes::Ssa::Convert { width, .. }
+        | nodes::Ssa::VaArg { width, .. }
+        | nodes::Ssa::Load { width, .. } => Some(*width),
+        nodes::Ssa::Phi(phi) => Some(phi.width),
+        nodes::Ssa::Alloca { .. } => Some(Width::Long),
+        nodes::Ssa::Call { dest, .. } => dest.as_ref().map(|(_, width)| *width),
+        nodes::Ssa::VaStart { .. }
+        | nodes::Ssa::Return { .. }
+        | nodes::Ssa::Store { .. }
+        | nodes::Ssa::Branch { .. }
+        | nodes::Ssa::Label(_)
+        | nodes::Ssa::Jump(_) => None,
+    }
+}
+
+/// Width each operand is read with, None where any integer width goes
+fn operand_widths(ssa: &nodes::Ssa) -> Vec<(&nodes::Address, Option<Width>)> {
+    match ssa {
+        nodes::Ssa::Quadriplet(quad) => [Some(&quad.left), quad.right.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|operand| (operand, Some(quad.width)))
+            .collect(),
+        nodes::Ssa::Assignment { source, width, .. } => vec![(source, Some(*width))],
+        nodes::Ssa::Extend { source, from, .. } | nodes::Ssa::Convert { source, from, .. } => {
+            vec![(source, Some(*from))]
+        }
+        // Truncation reads any wider integer
+        nodes::Ssa::Truncate { source, .. } => vec![(source, None)],
+        nodes::Ssa::VaStart { list }
+        | nodes::Ssa::VaArg { list, .. }
+        | nodes::Ssa::Load { address: list, .. } => {
+            vec![(list, Some(Width::Long))]
+        }
+        nodes::Ssa::Store {
+            address,
+            value,
+            width,
+        } => vec![(address, Some(Width::Long)), (value, Some(*width))],
+        nodes::Ssa::Phi(phi) => phi
+            .merging
+            .iter()
+            .map(|(address, _)| (address, Some(phi.width)))
+            .collect(),
+        nodes::Ssa::Call {
+            parameters, func, ..
+        } => std::iter::once((func, Some(Width::Long)))
+            .chain(parameters.iter().map(|p| (&p.value, Some(p.width))))
+            .collect(),
+        nodes::Ssa::Return { value } => value
+            .iter()
+            .map(|(address, width)| (address, Some(*width)))
+            .collect(),
+        nodes::Ssa::Branch { cond, width, .. } => vec![(cond, Some(*width))],
+        nodes::Ssa::Alloca { .. }
+        | nodes::Ssa::Label(_)
+        | nodes::Ssa::Jump(_) => vec![],
+    }
+}
+
+struct Verifier<'a> {
+    function: &'a nodes::FunctionDef,
+    errors: Vec<VerifyError>,
+}
+
+impl Verifier<'_> {
+    fn error(&mut self, message: String) {
+        self.errors.push(VerifyError {
+            function: self.function.name.clone(),
+            message,
+        });
+    }
+
+    /// Every phi names each predecessor of its block exactly once
+    fn check_phis(&mut self) {
+        let cfg = &self.function.body;
+        for (id, block) in cfg.blocks() {
+            let expected: HashSet<String> = cfg
+                .predecessors(id)
+                .iter()
+                .map(|&p| cfg.block(p).label.to_ir_string())
+                .collect();
+            for phi in &block.phis {
+                let labels: Vec<String> = phi.merging.iter().map(|(_, l)| l.to_ir_string()).collect();
+                let unique: HashSet<String> = labels.iter().cloned().collect();
+                if unique.len() != labels.len() || unique != expected {
+                    let mut expected: Vec<String> = expected.iter().cloned().collect();
+                    expected.sort();
+                    self.error(format!(
+                        "phi of {} in block @{} merges from [{}], but the predecessors are [{}]",
+                        phi.dest.to_ir_string(),
+                        block.label.to_ir_string(),
+                        labels.iter().map(|l| format!("@{l}")).collect::<Vec<_>>().join(", "),
+                        expected.iter().map(|l| format!("@{l}")).collect::<Vec<_>>().join(", ")
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Each address is defined once and every use is dominated by its
+    /// definition, operands of phis at the end of the matching predecessor
+    fn check_definitions(&mut self) {
+        let cfg = &self.function.body;
+        let parameters: HashSet<nodes::Address> = self
+            .function
+            .parameters
+            .iter()
+            .map(|(name, _)| nodes::Address::source_count(name.clone(), 0))
+            .collect();
+
+        let blocks: Vec<(BlockId, Vec<nodes::Ssa>)> = cfg
+            .blocks()
+            .map(|(id, block)| (id, block.instructions().into_iter().map(|i| i.ssa).collect()))
+            .collect();
+        let mut definitions: HashMap<&nodes::Address, (BlockId, usize)> = HashMap::new();
+        for (id, block) in &blocks {
+            for (idx, ssa) in block.iter().enumerate() {
+                let Some(dest) = ssa.defined_address() else {
+                    continue;
+                };
+                if parameters.contains(dest) {
+                    self.error(format!("parameter {} is redefined", dest.to_ir_string()));
+                } else if definitions.contains_key(dest) {
+                    self.error(format!("{} is defined more than once", dest.to_ir_string()));
+                } else {
+                    definitions.insert(dest, (*id, idx));
+                }
+            }
+        }
+
+        let dominators = cfg.dominators();
+        let dominates = |definition: (BlockId, usize), block: BlockId, idx: usize| {
+            // Every block dominates unreachable code
+            !dominators.is_reachable(block.index())
+                || if definition.0 == block {
+                    definition.1 < idx
+                } else {
+                    dominators.dominates(definition.0.index(), block.index())
+                }
+        };
+
+        for (id, block) in &blocks {
+            for (idx, ssa) in block.iter().enumerate() {
+                // Phi operands are read at the end of the predecessor they come from
+                let uses: Vec<(&nodes::Address, BlockId, usize)> = match ssa {
+                    nodes::Ssa::Phi(phi) => phi
+                        .merging
+                        .iter()
+                        .filter_map(|(address, label)| {
+                            cfg.predecessors(*id)
+                                .iter()
+                                .find(|&&p| cfg.block(p).label == *label)
+                                .map(|&p| (address, p, usize::MAX))
+                        })
+                        .collect(),
+                    _ => ssa.used_addresses().into_iter().map(|a| (a, *id, idx)).collect(),
+                };
+                for (address, use_block, use_idx) in uses {
+                    if matches!(address, nodes::Address::Constant(_)) || parameters.contains(address) {
+                        continue;
+                    }
+                    match definitions.get(address) {
+                        None => self.error(format!(
+                            "{} is used in `{}` but never defined",
+                            address.to_ir_string(),
+                            ssa.to_ir_string().trim()
+                        )),
+                        Some(&definition) if !dominates(definition, use_block, use_idx) => {
+                            self.error(format!(
+                                "use of {} in `{}` is not dominated by its definition",
+                                address.to_ir_string(),
+                                ssa.to_ir_string().trim()
+                            ))
+                        }
+                        Some(_) => (),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Values are read with the width they were defined with, and
+    /// constants match the kind of width they are read as
+    fn check_widths(&mut self) {
+        let mut widths: HashMap<nodes::Address, Width> = self
+            .function
+            .parameters
+            .iter()
+            .map(|(name, width)| (nodes::Address::source_count(name.clone(), 0), *width))
+            .collect();
+        let instructions: Vec<nodes::Ssa> = self
+            .function
+            .body
+            .blocks()
+            .flat_map(|(_, block)| block.instructions())
+            .map(|instruction| instruction.ssa)
+            .collect();
+        for ssa in &instructions {
+            let (Some(dest), Some(width)) = (ssa.defined_address(), defined_width(ssa)) else {
+                continue;
+            };
+            match widths.get(dest) {
+                Some(&previous) if previous != width => self.error(format!(
+                    "{} is defined as both {} and {}",
+                    dest.to_ir_string(),
+                    previous.to_ir_string(),
+                    width.to_ir_string()
+                )),
+                Some(_) => (),
+                None => {
+                    widths.insert(dest.clone(), width);
+                }
+            }
+        }
+
+        if let Some(return_width) = self.function.return_width {
+            for ssa in &instructions {
+                if let nodes::Ssa::Return { value: Some((_, width)) } = ssa
+                    && *width != return_width
+                {
+                    self.error(format!(
+                        "`{}` in a function returning {}",
+                        ssa.to_ir_string().trim(),
+                        return_width.to_ir_string()
+                    ));
+                }
+            }
+        }
+
+        for ssa in &instructions {
+            if let nodes::Ssa::Return { value: Some(_) } = ssa
+                && self.function.return_width.is_none()
+            {
+                self.error(format!("`{}` in a void function", ssa.to_ir_string().trim()));
+            }
+            for (address, expected) in operand_widths(ssa) {
+                let Some(expected) = expected else {
+                    continue;
+                };
+                let actual = match address {
+                    nodes::Address::Constant(nodes::AddressConstant::Numeric(_)) => {
+                        if expected.is_floating() {
+                            self.error(format!(
+                                "integer constant read as {} in `{}`",
+                                expected.to_ir_string(),
+                                ssa.to_ir_string().trim()
+                            ));
+                        }
+                        continue;
+                    }
+                    nodes::Address::Constant(nodes::AddressConstant::Floating(_)) => {
+                        if !expected.is_floating() {
+                            self.error(format!(
+                                "floating constant read as {} in `{}`",
+                                expected.to_ir_string(),
+                                ssa.to_ir_string().trim()
+                            ));
+                        }
+                        continue;
+                    }
+                    nodes::Address::Constant(_) => Width::Long,
+                    _ => match widths.get(address) {
+                        Some(&width) => width,
+                        None => continue,
+                    },
+                };
+                if actual != expected {
+                    self.error(format!(
+                        "{} of width {} is read as {} in `{}`",
+                        address.to_ir_string(),
+                        actual.to_ir_string(),
+                        expected.to_ir_string(),
+                        ssa.to_ir_string().trim()
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Checks that a function is well formed: consistent widths, and in SSA
+/// form every address defined once, before all of its uses, and phis
+/// naming the predecessors of their block. After phi elimination no phi
+/// may be left. Blocks are checked when the graph is built.
+pub fn verify_function(function: &nodes::FunctionDef) -> Vec<VerifyError> {
+    let mut verifier = Verifier {
+        function,
+        errors: vec![],
+    };
+
+    if function.is_ssa {
+        verifier.check_phis();
+        verifier.check_definitions();
+    } else if function.body.blocks().any(|(_, block)| !block.phis.is_empty()) {
+        verifier.error("phi left after phi elimination".into());
+    }
+    verifier.check_widths();
+    verifier.errors
+}
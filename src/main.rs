@@ -10,12 +10,16 @@ use semantic_analysis::SymbolTable;
 use clap::Parser as ClapParser;
 use std::path::PathBuf;
 
-use std::{error::Error, fs};
+use std::{
+    error::Error,
+    fs,
+    io::Write,
+};
 
 #[derive(ClapParser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Input C source file
+    /// Input C source file, or IR text for files ending in `.ir`
     #[arg(short, long)]
     input: PathBuf,
 
@@ -42,33 +46,93 @@ struct Args {
     /// Type of output to generate
     #[arg(long, value_enum, default_value_t = EmitType::Asm)]
     emit: EmitType,
+
+    /// Verify the IR before optimising and after every optimisation pass
+    #[arg(long)]
+    verify_each: bool,
+
+    /// ABI of the target, which decides how variadic arguments are passed
+    #[arg(long, value_enum, default_value_t = common::Abi::Apple)]
+    abi: common::Abi,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug, PartialEq, Eq)]
 enum EmitType {
     Ir,
     Asm,
+    /// Interpret the optimized IR, printing what the program prints
+    Run,
 }
 
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
-
-    let source_path = args.input;
-
-    let source_code = fs::read_to_string(source_path).expect("Could not read input file");
+/// Runs the C front end, exiting on the first semantic error
+fn compile_c(source_code: &str, dump_ast: bool, abi: common::Abi) -> Vec<ir::nodes::ToplevelItem> {
     let parser = TreeSitterParser::default();
 
-    let unit = parser.parse(&source_code);
-    let symbol_table = SymbolTable::from_translation_unit(&unit);
+    let unit = parser.parse(source_code).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
+    let symbol_table = SymbolTable::from_translation_unit(&unit, abi);
+
+    let mut diagnostics = symbol_table.borrow().diagnostics.borrow().clone();
+    let (type_diagnostics, types) = semantic_analysis::check_types(&unit, symbol_table.clone());
+    diagnostics.extend(type_diagnostics);
+    diagnostics.extend(semantic_analysis::check_reachability(&unit, &symbol_table.borrow()));
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if diagnostics.iter().any(|d| d.is_error()) {
+        std::process::exit(1);
+    }
 
-    if args.dump_ast {
+    if dump_ast {
         println!("--- AST ---");
         println!("Parsed tree: {:#?}", unit);
     }
 
+    let lowered = ir::lower_unit(&unit, symbol_table.clone(), types);
+    let (ssa, undefined) = ir::construct_ssa(&lowered);
+    for diagnostic in ir::check_variables(&ssa, &undefined, symbol_table.clone()) {
+        eprintln!("{}", diagnostic);
+    }
+    ssa
+}
 
-    let ssa = ir::build_ssa(&unit, symbol_table.clone());
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let source_path = args.input;
+
+    let source_code = fs::read_to_string(&source_path).expect("Could not read input file");
+
+    // IR text skips the front end, to test the optimizer and the backend alone
+    let ssa = if source_path.extension().is_some_and(|extension| extension == "ir") {
+        let ssa = ir::from_text(&source_code).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        });
+
+        // Hand-written IR is checked before the optimizer relies on it.
+        // Dumps of `--emit ir` are marked out of SSA form, and checked as such.
+        let errors: Vec<ir::VerifyError> = ssa
+            .iter()
+            .filter_map(|item| match item {
+                ir::nodes::ToplevelItem::Function(f) => Some(f),
+                ir::nodes::ToplevelItem::Declaration(_) | ir::nodes::ToplevelItem::Data(_) => None,
+            })
+            .flat_map(ir::verify_function)
+            .collect();
+        for error in &errors {
+            eprintln!("{}", error);
+        }
+        if !errors.is_empty() {
+            std::process::exit(1);
+        }
+        ssa
+    } else {
+        compile_c(&source_code, args.dump_ast, args.abi)
+    };
 
     if args.dump_ir {
         let ssa_text = ir::into_text(&ssa);
@@ -76,7 +140,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         println!("{}", ssa_text);
     }
 
-    let opt_ssa = opt::run_o1(&ssa);
+    let opt_ssa = opt::run_o1(&ssa, args.verify_each).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    });
 
     if args.graphviz {
         ir::graphviz_unit(&opt_ssa, "./graphviz");
@@ -90,7 +157,6 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     if args.emit == EmitType::Ir {
         let ssa_text = ir::into_text(&opt_ssa);
-        println!("{}", ssa_text);
         if let Some(out_path) = args.output {
             fs::write(out_path, ssa_text).unwrap();
         } else {
@@ -99,7 +165,18 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Ok(())
     }
 
-    let asm = asmgen::convert_unit_to_asm(&opt_ssa);
+    if args.emit == EmitType::Run {
+        let execution = ir::interpret(&opt_ssa).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        });
+        let mut stdout = std::io::stdout();
+        stdout.write_all(&execution.stdout)?;
+        stdout.flush()?;
+        std::process::exit(execution.exit_code);
+    }
+
+    let asm = asmgen::convert_unit_to_asm(&opt_ssa, &source_path.to_string_lossy(), args.abi);
     let asm_text = asmgen::asm_into_text(&asm);
 
     if let Some(out_path) = args.output {
@@ -1,70 +1,89 @@
 use std::collections::HashMap;
 
-use crate::ir::{ir_to_basic_blocks, nodes::{self, Address}};
+use crate::common::Signedness;
+use crate::ir::{Cfg, nodes::{self, Address}};
 
-fn fold_constants_block(block: &[nodes::Ssa]) -> Vec<nodes::Ssa> {
+fn fold_constants_block(block: &[nodes::Instruction]) -> Vec<nodes::Instruction> {
     let mut constants: HashMap<nodes::Address, i64> = HashMap::new();
     let mut out = vec![];
 
-    for i in block.iter() {
-        match i {
+    for instruction in block.iter() {
+        let i = &instruction.ssa;
+        let folded = match i {
             nodes::Ssa::Assignment { dest, source, width } => {
                 if let Address::Constant(nodes::AddressConstant::Numeric(num)) = source {
                     constants.insert(dest.clone(), *num);
-                    out.push(i.clone());
+                    i.clone()
                 } else {
                     if let Some(c) = constants.get(source).copied() {
                         constants.insert(dest.clone(), c);
-                        out.push(nodes::Ssa::Assignment { dest: dest.clone(), source: nodes::Address::constant(nodes::AddressConstant::Numeric(c)), width: *width });
+                        nodes::Ssa::Assignment { dest: dest.clone(), source: nodes::Address::constant(nodes::AddressConstant::Numeric(c)), width: *width }
                     } else {
-                        out.push(i.clone());
+                        i.clone()
                     }
                 }
             },
+            nodes::Ssa::Extend { signed, dest, source, from, width } => {
+                if let Some(c) = constants.get(source).copied() {
+                    let signedness = if *signed { Signedness::Signed } else { Signedness::Unsigned };
+                    let extended = from.extend(c, signedness);
+                    constants.insert(dest.clone(), extended);
+                    nodes::Ssa::Assignment { dest: dest.clone(), source: nodes::Address::constant_i64(extended), width: *width }
+                } else {
+                    i.clone()
+                }
+            },
+            nodes::Ssa::Truncate { dest, source, width } => {
+                if let Some(c) = constants.get(source).copied() {
+                    let truncated = width.extend(c, Signedness::Unsigned);
+                    constants.insert(dest.clone(), truncated);
+                    nodes::Ssa::Assignment { dest: dest.clone(), source: nodes::Address::constant_i64(truncated), width: *width }
+                } else {
+                    i.clone()
+                }
+            },
             nodes::Ssa::Quadriplet(quad) => {
                 let left_c = constants.get(&quad.left);
                 let right_c = constants.get(quad.right.as_ref().unwrap());
-                if let Some(left) = left_c && let Some(right) = right_c {
-                    let const_res = quad.op.apply_constant(*left, *right);
+                // Divisions by zero are left for the program to run
+                if let Some(left) = left_c && let Some(right) = right_c
+                    && let Some(const_res) = quad.op.apply_constant(*left, *right, quad.width) {
                     constants.insert(quad.dest.clone(), const_res);
-                    out.push(nodes::Ssa::Assignment { dest: quad.dest.clone(), source: nodes::Address::constant(nodes::AddressConstant::Numeric(const_res)), width: quad.width });
+                    nodes::Ssa::Assignment { dest: quad.dest.clone(), source: nodes::Address::constant(nodes::AddressConstant::Numeric(const_res)), width: quad.width }
                 } else {
-                    out.push(i.clone());
+                    i.clone()
                 }
             },
             nodes::Ssa::Return { value } => {
                 if let Some((addr, width)) = value &&
                     let Some(const_val) = constants.get(addr) {
-                    out.push(nodes::Ssa::Return { value: Some((Address::constant(nodes::AddressConstant::Numeric(*const_val)), *width)) });                       
+                    nodes::Ssa::Return { value: Some((Address::constant(nodes::AddressConstant::Numeric(*const_val)), *width)) }
 
                 } else {
-                    out.push(i.clone());
+                    i.clone()
                 }
             },
             nodes::Ssa::Branch { width, cond, true_target, false_target } => {
                 if let Some(const_cond) = constants.get(cond) {
-                    out.push(nodes::Ssa::Branch { width: *width, cond: nodes::Address::constant_i64(*const_cond), true_target: true_target.clone(), false_target: false_target.clone() });
+                    nodes::Ssa::Branch { width: *width, cond: nodes::Address::constant_i64(*const_cond), true_target: true_target.clone(), false_target: false_target.clone() }
                 } else {
-                    out.push(i.clone());
+                    i.clone()
                 }
             }
             _ => {
-                out.push(i.clone());
+                i.clone()
             }
-        }
+        };
+        out.push(nodes::Instruction::new(folded, instruction.location));
     }
 
 
     out
 }
 
-pub fn fold_constants(body: &[nodes::Ssa]) -> Vec<nodes::Ssa> {
-    let blocks = ir_to_basic_blocks(&body);
-    let mut out = vec![];
-
-    for b in blocks.iter() {
-        out.extend(fold_constants_block(b))
+pub fn fold_constants(cfg: &mut Cfg) {
+    for (_, block) in cfg.blocks_mut() {
+        let ir = fold_constants_block(&block.instructions());
+        block.set_instructions(ir);
     }
-
-    out
-}
\ No newline at end of file
+}
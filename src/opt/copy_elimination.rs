@@ -1,6 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
-use crate::{ir::nodes::{self, Address}, opt::{controlflow::{BasicBlock, ControlFlowGraph}, dataflow::DataFlowGraph}};
+use crate::{common::Width, ir::{Block, Cfg, nodes::{self, Address}}, opt::dataflow::{self, DataFlowGraph}};
 
 fn eliminate_copy(ssa: &nodes::Ssa, from: nodes::Address, to: nodes::Address) -> nodes::Ssa {
     match ssa {
@@ -8,6 +8,35 @@ fn eliminate_copy(ssa: &nodes::Ssa, from: nodes::Address, to: nodes::Address) ->
             assert_eq!(&from, source);
             nodes::Ssa::Assignment { dest: dest.clone(), source: to, width: *width }
         },
+        nodes::Ssa::Extend { signed, dest, source, from: from_width, width } => {
+            assert_eq!(&from, source);
+            nodes::Ssa::Extend { signed: *signed, dest: dest.clone(), source: to, from: *from_width, width: *width }
+        },
+        nodes::Ssa::Truncate { dest, source, width } => {
+            assert_eq!(&from, source);
+            nodes::Ssa::Truncate { dest: dest.clone(), source: to, width: *width }
+        },
+        nodes::Ssa::Convert { signed, dest, source, from: from_width, width } => {
+            assert_eq!(&from, source);
+            nodes::Ssa::Convert { signed: *signed, dest: dest.clone(), source: to, from: *from_width, width: *width }
+        },
+        nodes::Ssa::VaStart { list } => {
+            assert_eq!(&from, list);
+            nodes::Ssa::VaStart { list: to }
+        },
+        nodes::Ssa::VaArg { dest, list, width } => {
+            assert_eq!(&from, list);
+            nodes::Ssa::VaArg { dest: dest.clone(), list: to, width: *width }
+        },
+        nodes::Ssa::Load { dest, address, width } => {
+            assert_eq!(&from, address);
+            nodes::Ssa::Load { dest: dest.clone(), address: to, width: *width }
+        },
+        nodes::Ssa::Store { address, value, width } => {
+            assert!(address == &from || value == &from, "replace what?");
+            let replace = |operand: &nodes::Address| if operand == &from { to.clone() } else { operand.clone() };
+            nodes::Ssa::Store { address: replace(address), value: replace(value), width: *width }
+        },
         nodes::Ssa::Quadriplet(quad) => {
             if quad.left == from {
                 nodes::Ssa::Quadriplet(nodes::Quadriplet { width: quad.width, dest: quad.dest.clone(), op: quad.op, left: to, right: quad.right.clone() })
@@ -26,6 +55,9 @@ fn eliminate_copy(ssa: &nodes::Ssa, from: nodes::Address, to: nodes::Address) ->
             assert_eq!(value.as_ref().unwrap().0, from);
             nodes::Ssa::Return { value: Some((to, value.as_ref().unwrap().1)) }
         },
+        nodes::Ssa::Call { parameters, dest, func, num_params } if func == &from => {
+            nodes::Ssa::Call { parameters: parameters.clone(), dest: dest.clone(), func: to, num_params: *num_params }
+        },
         nodes::Ssa::Call { parameters, dest, func, num_params } => {
             let mut copy_parameters = parameters.clone();
             let changed = parameters.iter().position(|x| x.value == from).expect("Change what");
@@ -37,36 +69,60 @@ fn eliminate_copy(ssa: &nodes::Ssa, from: nodes::Address, to: nodes::Address) ->
     }
 }
 
-fn eliminate_quadriplets(block: &BasicBlock) -> Vec<nodes::Ssa> {
+/// Widths of the values defined in a block, so that copies converting
+/// a value to another width are not eliminated.
+fn definition_widths(ir: &[nodes::Instruction]) -> HashMap<Address, Width> {
+    ir.iter()
+        .filter_map(|instr| match &instr.ssa {
+            nodes::Ssa::Assignment { dest, source: _, width }
+            | nodes::Ssa::Extend { dest, width, .. }
+            | nodes::Ssa::Truncate { dest, width, .. }
+            | nodes::Ssa::Convert { dest, width, .. }
+            | nodes::Ssa::VaArg { dest, width, .. }
+            | nodes::Ssa::Load { dest, width, .. } => Some((dest.clone(), *width)),
+            nodes::Ssa::Alloca { dest, .. } => Some((dest.clone(), Width::Long)),
+            nodes::Ssa::Quadriplet(quad) => Some((quad.dest.clone(), quad.width)),
+            nodes::Ssa::Call { dest: Some((dest, width)), .. } => Some((dest.clone(), *width)),
+            nodes::Ssa::Phi(phi) => Some((phi.dest.clone(), phi.width)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A quadriplet or call whose result is only copied to another address
+/// writes that address itself. It then stands for the copy as well, the
+/// location is merged.
+fn eliminate_quadriplets(block: &Block) -> Vec<nodes::Instruction> {
     let mut out = vec![];
-    let dataflow = DataFlowGraph::from_basic_block(&block.ir);
+    let ir = block.instructions();
+    let dataflow = DataFlowGraph::from_basic_block(&ir);
 
     // instruction index, Vec<(From, To)>
     let mut ignore = HashSet::new();
 
-    for (instr_idx, instr) in block.ir.iter().enumerate() {
+    for (instr_idx, instr) in ir.iter().enumerate() {
         let mut instr_to_push = instr.clone();
 
-        if let nodes::Ssa::Quadriplet(quad) = &mut instr_to_push {
+        if let nodes::Ssa::Quadriplet(quad) = &mut instr_to_push.ssa {
             let out_children = &dataflow.adjacency().get(&instr_idx);
             if let Some(out_children) = out_children && out_children.len() == 1 {
-                let out_child = &block.ir[out_children[0]];
-                if let nodes::Ssa::Assignment { dest: ass_dist, source: _, width: _ } = out_child {
+                let out_child = &ir[out_children[0]];
+                if let nodes::Ssa::Assignment { dest: ass_dist, source: _, width } = &out_child.ssa && *width == quad.width {
                     quad.dest = ass_dist.clone();
+                    instr_to_push.location = instr.merged_location(out_child);
                     ignore.insert(out_children[0]);
                 }
             }
         }
 
-        if let nodes::Ssa::Call { parameters: _, dest, func: _, num_params: _  } = &mut instr_to_push {
-            if let Some(dest) = dest {
-                let out_children = &dataflow.adjacency().get(&instr_idx);
-                if let Some(out_children) = out_children && out_children.len() == 1 {
-                    let out_child = &block.ir[out_children[0]];
-                    if let nodes::Ssa::Assignment { dest: ass_dist, source: _, width: _ } = out_child {
-                        dest.0 = ass_dist.clone();
-                        ignore.insert(out_children[0]);
-                    }
+        if let nodes::Ssa::Call { parameters: _, dest: Some(dest), func: _, num_params: _ } = &mut instr_to_push.ssa {
+            let out_children = &dataflow.adjacency().get(&instr_idx);
+            if let Some(out_children) = out_children && out_children.len() == 1 {
+                let out_child = &ir[out_children[0]];
+                if let nodes::Ssa::Assignment { dest: ass_dist, source: _, width } = &out_child.ssa && *width == dest.1 {
+                    dest.0 = ass_dist.clone();
+                    instr_to_push.location = instr.merged_location(out_child);
+                    ignore.insert(out_children[0]);
                 }
             }
         }
@@ -80,18 +136,37 @@ fn eliminate_quadriplets(block: &BasicBlock) -> Vec<nodes::Ssa> {
 }
 
 
-fn eliminate_forward_assignments(block: &BasicBlock) -> Vec<nodes::Ssa> {
+fn eliminate_forward_assignments(block: &Block, used_outside: &HashSet<Address>) -> Vec<nodes::Instruction> {
     let mut out = vec![];
-    let dataflow = DataFlowGraph::from_basic_block(&block.ir);
+    let ir = block.instructions();
+    let dataflow = DataFlowGraph::from_basic_block(&ir);
+    let widths = definition_widths(&ir);
 
     // instruction index, Vec<(From, To)>
     let mut replacements: HashMap<usize, Vec<(Address, Address)>> = HashMap::new();
 
-    for (instr_idx, instr) in block.ir.iter().enumerate() {
+    for (instr_idx, instr) in ir.iter().enumerate() {
         let mut instr_to_push = instr.clone();
-        if let nodes::Ssa::Assignment { dest, source, width: _ } = instr {
+
+        // Resolve earlier replacements first, so chains of copies collapse
+        // onto the first source instead of an eliminated temporary
+        if let Some(current_repl) = replacements.get(&instr_idx) {
+            for (from, to) in current_repl {
+                instr_to_push.ssa = eliminate_copy(&instr_to_push.ssa, from.clone(), to.clone());
+            }
+        }
+
+        if let nodes::Ssa::Assignment { dest, source, width } = &instr_to_push.ssa {
             let out_children = &dataflow.adjacency().get(&instr_idx);
-            if let Some(out_children) = out_children && out_children.len() == 1 && !matches!(source, Address::Constant(nodes::AddressConstant::StringLiteral(_))) {
+            let original_source = match &instr.ssa {
+                nodes::Ssa::Assignment { source, .. } => source,
+                _ => unreachable!(),
+            };
+            let changes_width = [source, original_source]
+                .iter()
+                .any(|source| widths.get(*source).is_some_and(|w| w != width));
+            let used_elsewhere = used_outside.contains(dest);
+            if let Some(out_children) = out_children && out_children.len() == 1 && !changes_width && !used_elsewhere && !matches!(source, Address::Constant(nodes::AddressConstant::Floating(_) | nodes::AddressConstant::Symbol(_))) {
                 let other_use = out_children[0];
 
                 replacements.entry(other_use).or_default().push((dest.clone(),  source.clone()));
@@ -99,12 +174,6 @@ fn eliminate_forward_assignments(block: &BasicBlock) -> Vec<nodes::Ssa> {
             }
         }
 
-        if let Some(current_repl) = replacements.get(&instr_idx) {
-            for (from, to) in current_repl {
-                instr_to_push = eliminate_copy(&instr_to_push, from.clone(), to.clone());
-            }
-        } 
-
         out.push(instr_to_push);
     }
 
@@ -112,27 +181,24 @@ fn eliminate_forward_assignments(block: &BasicBlock) -> Vec<nodes::Ssa> {
 }
 
 
-pub fn copy_eliminate_forward_assignments(body: &[nodes::Ssa]) -> Vec<nodes::Ssa> {
-    let control_flow = ControlFlowGraph::new(body);
-    let mut out = vec![];
-    for b in control_flow.blocks() {
-        out.extend(eliminate_forward_assignments(b))
+pub fn copy_eliminate_forward_assignments(cfg: &mut Cfg) {
+    let used_outside = dataflow::used_outside_block(cfg);
+    let no_uses = HashSet::new();
+    for (id, block) in cfg.blocks_mut() {
+        let ir = eliminate_forward_assignments(block, used_outside.get(&id).unwrap_or(&no_uses));
+        block.set_instructions(ir);
     }
-
-    out
 }
 
 
-pub fn copy_eliminate_quadriplets(body: &[nodes::Ssa]) -> Vec<nodes::Ssa> {
-    let control_flow = ControlFlowGraph::new(body);
-    let mut out = vec![];
-    for b in control_flow.blocks() {
-        out.extend(eliminate_quadriplets(b))
+pub fn copy_eliminate_quadriplets(cfg: &mut Cfg) {
+    for (_, block) in cfg.blocks_mut() {
+        let ir = eliminate_quadriplets(block);
+        block.set_instructions(ir);
     }
-
-    out
 }
 
-pub fn copy_eliminate(body: &[nodes::Ssa]) -> Vec<nodes::Ssa> {
-    copy_eliminate_forward_assignments(&copy_eliminate_quadriplets(body))
-}
\ No newline at end of file
+pub fn copy_eliminate(cfg: &mut Cfg) {
+    copy_eliminate_quadriplets(cfg);
+    copy_eliminate_forward_assignments(cfg);
+}
@@ -1,27 +1,68 @@
 use std::collections::{HashMap, HashSet};
 
-use crate::ir::{IrTextRepr, nodes::{self, Address}};
+use crate::ir::{BlockId, Cfg, nodes::{self, Address}};
+
+/// Values each block has to keep for other blocks: the values it defines
+/// that are read elsewhere, and operands it hands to phis
+pub fn used_outside_block(cfg: &Cfg) -> HashMap<BlockId, HashSet<Address>> {
+    let mut definitions = HashMap::new();
+    for (id, block) in cfg.blocks() {
+        for i in block.phis.iter().map(|phi| &phi.dest).chain(block.body.iter().filter_map(|i| i.ssa.defined_address())) {
+            definitions.insert(i.clone(), id);
+        }
+    }
+
+    let mut used: HashMap<BlockId, HashSet<Address>> = HashMap::new();
+
+    // Phi operands are used in the block they come from and the block
+    // defining them when that is another one
+    for (_, block) in cfg.blocks() {
+        for phi in &block.phis {
+            for (merge_addr, merge_label) in &phi.merging {
+                let merge_block = cfg.block_of(merge_label).expect("Phi merging from an unknown block");
+                used.entry(merge_block).or_default().insert(merge_addr.clone());
+                if let Some(&def_block) = definitions.get(merge_addr) && def_block != merge_block {
+                    used.entry(def_block).or_default().insert(merge_addr.clone());
+                }
+            }
+        }
+    }
+
+    // Values read in other blocks
+    for (id, block) in cfg.blocks() {
+        for i in block.body.iter().map(|i| &i.ssa).chain(std::iter::once(block.terminator())) {
+            for addr in i.used_addresses() {
+                if let Some(&def_block) = definitions.get(addr) && def_block != id {
+                    used.entry(def_block).or_default().insert(addr.clone());
+                }
+            }
+        }
+    }
+
+    used
+}
 
 pub struct DataFlowGraph {
-    ir: Vec<nodes::Ssa>,
     adjacency: HashMap<usize, Vec<usize>>,
-    address_assignment: HashMap<Address, usize>,
-    do_not_optimize: Vec<usize>,
 }
 
 impl DataFlowGraph {
-    pub fn from_basic_block(block: &[nodes::Ssa]) -> Self 
+    pub fn from_basic_block(block: &[nodes::Instruction]) -> Self 
     {
         let mut address_assignment: HashMap<Address, usize> = HashMap::new();
         let mut adjacency: HashMap<usize, Vec<usize>> = HashMap::new();
-        let mut do_not_optimize = Vec::new();
 
-        for (idx, b) in block.iter().enumerate() {
+        for (idx, b) in block.iter().map(|i| &i.ssa).enumerate() {
             match b {
                 nodes::Ssa::Phi(phi) => {
                     address_assignment.insert(phi.dest.clone(), idx);
                 },
-                nodes::Ssa::Assignment { dest, source, width: _ } => {
+                nodes::Ssa::Assignment { dest, source, width: _ }
+                | nodes::Ssa::Extend { dest, source, .. }
+                | nodes::Ssa::Truncate { dest, source, .. }
+                | nodes::Ssa::Convert { dest, source, .. }
+                | nodes::Ssa::VaArg { dest, list: source, .. }
+                | nodes::Ssa::Load { dest, address: source, .. } => {
                     address_assignment.insert(dest.clone(), idx);
                     match source {
                         Address::Constant(_) => {},
@@ -32,6 +73,21 @@ impl DataFlowGraph {
                         }
                     }
                 },
+                nodes::Ssa::VaStart { list } => {
+                    if let Some(&list_def) = address_assignment.get(list) {
+                        adjacency.entry(list_def).or_default().push(idx);
+                    }
+                },
+                nodes::Ssa::Alloca { dest, .. } => {
+                    address_assignment.insert(dest.clone(), idx);
+                },
+                nodes::Ssa::Store { address, value, .. } => {
+                    for operand in [address, value] {
+                        if let Some(&operand_def) = address_assignment.get(operand) {
+                            adjacency.entry(operand_def).or_default().push(idx);
+                        }
+                    }
+                },
                 nodes::Ssa::Quadriplet(quad) => {
                     address_assignment.insert(quad.dest.clone(), idx);
                     if let Some(&left) = address_assignment.get(&quad.left) {
@@ -41,88 +97,39 @@ impl DataFlowGraph {
                         adjacency.entry(right).or_default().push(idx);
                     }
                 },
-                nodes::Ssa::Call { parameters, dest, func: _, num_params: _ } => {
+                nodes::Ssa::Call { parameters, dest, func, num_params: _ } => {
                     if let Some((dest, _)) = dest {
                         address_assignment.insert(dest.clone(), idx);
                     }
+                    if let Some(&func_def) = address_assignment.get(func) {
+                        adjacency.entry(func_def).or_default().push(idx);
+                    }
                     for p in parameters {
-                        let parameter_def = address_assignment[&p.value];
-                        adjacency.entry(parameter_def).or_default().push(idx);
+                        if let Some(&parameter_def) = address_assignment.get(&p.value) {
+                            adjacency.entry(parameter_def).or_default().push(idx);
+                        }
+                    }
+                },
+                nodes::Ssa::Branch { cond, .. } => {
+                    if let Some(&cond_def) = address_assignment.get(cond) {
+                        adjacency.entry(cond_def).or_default().push(idx);
                     }
                 },
                 nodes::Ssa::Return { value } => {
                     if let Some((source, _)) = value &&
-                       let Some(source_def) = address_assignment.get(&source) {
+                       let Some(source_def) = address_assignment.get(source) {
                         adjacency.entry(*source_def).or_default().push(idx);
                     }
                 },
-                _ => {
-                    do_not_optimize.push(idx);
-                }
+                _ => {}
             }
         }
 
-        Self { ir: block.to_vec(), adjacency, address_assignment, do_not_optimize }
-    }
-
-    pub fn do_not_optimize(&self) -> &Vec<usize> {
-        &self.do_not_optimize
+        Self { adjacency }
     }
 
     pub fn adjacency(&self) -> &HashMap<usize, Vec<usize>> {
         &self.adjacency
     }
-
-    pub fn connected_components(&self) -> Vec<Vec<usize>> {
-        let mut visited = HashSet::new();
-        let mut components = Vec::new();
-        
-        for &node in self.adjacency.keys() {
-            if !visited.contains(&node) {
-                let mut component = Vec::new();
-                self.dfs(node, &mut visited, &mut component);
-                components.push(component);
-            }
-        }
-        
-        components
-    }
-
-    fn dfs(&self, node: usize, visited: &mut HashSet<usize>, component: &mut Vec<usize>) {
-        visited.insert(node);
-        component.push(node);
-        
-        if let Some(neighbors) = self.adjacency.get(&node) {
-            for &neighbor in neighbors {
-                if !visited.contains(&neighbor) {
-                    self.dfs(neighbor, visited, component);
-                }
-            }
-        }
-    }
-
-    pub fn to_dot(&self) -> String {
-        let mut dot_str = String::new();
-        dot_str.push_str("digraph SSA_Dataflow {\n");
-        dot_str.push_str("rankdir=TB;\n");
-        dot_str.push_str("node [shape=box, fontname=\"Courier\"];\n");
-        dot_str.push_str("edge [fontname=\"Courier\"];\n");
-
-        for (idx, b) in self.ir.iter().enumerate() {
-            if self.do_not_optimize.contains(&idx) {
-                continue;
-            }
-            dot_str.push_str(&format!("block_{} [label=\"{}\"];\n", idx, b.to_ir_string().replace("\t", "") ));
-        }
-
-        for (parent, children) in self.adjacency.iter() {
-            for child in children {
-                dot_str.push_str(&format!("block_{} -> block_{};\n", parent, child));
-            }
-        }
-
-        dot_str.push_str("}\n");
-        dot_str
-    }
 }
 
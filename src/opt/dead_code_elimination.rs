@@ -1,17 +1,22 @@
-use crate::{ir::nodes::{self, Address}, opt::{controlflow::{BasicBlock, ControlFlowGraph}, dataflow::DataFlowGraph}};
+use std::collections::HashSet;
 
+use crate::{ir::{Block, Cfg, nodes::{self, Address}}, opt::dataflow::{self, DataFlowGraph}};
 
-fn eliminate_dead_code_inside_block(block: &BasicBlock) -> Vec<nodes::Ssa> {
-    let mut out = vec![];
-    let dataflow = DataFlowGraph::from_basic_block(&block.ir);
 
-    for (instr_idx, instr) in block.ir.iter().enumerate() {
-        if let nodes::Ssa::Assignment { dest, source: _, width: _ } = instr {
+fn eliminate_dead_code_inside_block(block: &Block, used_outside: &HashSet<Address>) -> Vec<nodes::Instruction> {
+    let mut out = vec![];
+    let ir = block.instructions();
+    let dataflow = DataFlowGraph::from_basic_block(&ir);
+
+    for (instr_idx, instr) in ir.iter().enumerate() {
+        if let nodes::Ssa::Assignment { dest, source: _, width: _ }
+        | nodes::Ssa::Extend { dest, .. }
+        | nodes::Ssa::Truncate { dest, .. }
+        | nodes::Ssa::Convert { dest, .. }
+        | nodes::Ssa::Alloca { dest, .. }
+        | nodes::Ssa::Load { dest, .. } = &instr.ssa {
             let out_children = &dataflow.adjacency().get(&instr_idx);
-            if let Address::CompilerTemp(_) = dest && out_children.is_none() {
-                continue;
-            }
-            if let Address::Source(_) = dest && !block.used_variables.contains_key(dest) && out_children.is_none() {
+            if !used_outside.contains(dest) && out_children.is_none() {
                 continue;
             }
         }
@@ -21,14 +26,12 @@ fn eliminate_dead_code_inside_block(block: &BasicBlock) -> Vec<nodes::Ssa> {
     out
 }
 
-pub fn eliminate_dead_code(body: &[nodes::Ssa]) -> Vec<nodes::Ssa> {
-    let control_flow = ControlFlowGraph::new(body);
+pub fn eliminate_dead_code(cfg: &mut Cfg) {
+    let used_outside = dataflow::used_outside_block(cfg);
+    let no_uses = HashSet::new();
 
-    let mut out = vec![];
-
-    for b in control_flow.blocks() {
-        out.extend(eliminate_dead_code_inside_block(b))
+    for (id, block) in cfg.blocks_mut() {
+        let ir = eliminate_dead_code_inside_block(block, used_outside.get(&id).unwrap_or(&no_uses));
+        block.set_instructions(ir);
     }
-
-    out
-}
\ No newline at end of file
+}
@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    common::Width,
+    ir::{self, BlockId, Cfg, DominatorTree, nodes},
+};
+
+/// Slots that can live in SSA values instead of memory
+struct Slots {
+    // Slot held by each address, the alloca and the copies of its address
+    aliases: HashMap<nodes::Address, nodes::Address>,
+    // Width the slot is loaded and stored with, None when it never is
+    widths: HashMap<nodes::Address, Option<Width>>,
+}
+
+/// Slots whose address, directly or through copies, only goes to loads
+/// and stores of a single width. Anything else lets the address escape
+/// to where the pass cannot follow it.
+fn promotable_slots(cfg: &Cfg) -> Slots {
+    let instructions: Vec<nodes::Ssa> = cfg
+        .blocks()
+        .flat_map(|(_, block)| block.instructions())
+        .map(|instruction| instruction.ssa)
+        .collect();
+
+    let mut aliases: HashMap<nodes::Address, nodes::Address> = instructions
+        .iter()
+        .filter_map(|ssa| match ssa {
+            nodes::Ssa::Alloca { dest, .. } => Some((dest.clone(), dest.clone())),
+            _ => None,
+        })
+        .collect();
+    // A copy may come before the one it copies in the layout
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for ssa in &instructions {
+            if let nodes::Ssa::Assignment {
+                dest,
+                source,
+                width: Width::Long,
+            } = ssa
+                && !aliases.contains_key(dest)
+                && let Some(slot) = aliases.get(source).cloned()
+            {
+                aliases.insert(dest.clone(), slot);
+                changed = true;
+            }
+        }
+    }
+
+    let mut widths: HashMap<nodes::Address, Option<Width>> =
+        aliases.values().map(|slot| (slot.clone(), None)).collect();
+    let mut escaping = HashSet::new();
+    for ssa in &instructions {
+        let (accessed, width) = match ssa {
+            nodes::Ssa::Load { address, width, .. } => (Some(address), Some(*width)),
+            nodes::Ssa::Store {
+                address,
+                value,
+                width,
+            } if address != value => (Some(address), Some(*width)),
+            nodes::Ssa::Assignment { dest, source, .. } if aliases.contains_key(dest) => (Some(source), None),
+            _ => (None, None),
+        };
+        for used in ssa.used_addresses() {
+            let Some(slot) = aliases.get(used) else {
+                continue;
+            };
+            if Some(used) != accessed {
+                escaping.insert(slot.clone());
+                continue;
+            }
+            let Some(width) = width else {
+                continue;
+            };
+            match widths[slot] {
+                Some(known) if known != width => {
+                    escaping.insert(slot.clone());
+                }
+                _ => {
+                    widths.insert(slot.clone(), Some(width));
+                }
+            }
+        }
+    }
+
+    aliases.retain(|_, slot| !escaping.contains(slot));
+    widths.retain(|slot, _| !escaping.contains(slot));
+    Slots { aliases, widths }
+}
+
+struct Promotion<'a> {
+    cfg: &'a mut Cfg,
+    dominators: DominatorTree,
+    slots: Slots,
+    // Value a block leaves in a slot, the last one it stores
+    exit_values: HashMap<(nodes::Address, BlockId), nodes::Address>,
+    // Value of a slot when entering a block
+    entry_values: HashMap<(nodes::Address, BlockId), nodes::Address>,
+}
+
+impl Promotion<'_> {
+    fn read_at_exit(&mut self, slot: &nodes::Address, block: BlockId) -> nodes::Address {
+        match self.exit_values.get(&(slot.clone(), block)) {
+            Some(value) => value.clone(),
+            None => self.read_at_entry(slot, block),
+        }
+    }
+
+    /// Same lookup as SSA construction does for source variables. Loads
+    /// no store reaches, and loads in blocks control never gets to, read zero.
+    fn read_at_entry(&mut self, slot: &nodes::Address, block: BlockId) -> nodes::Address {
+        let key = (slot.clone(), block);
+        if let Some(value) = self.entry_values.get(&key) {
+            return value.clone();
+        }
+
+        let width = self.slots.widths[slot].expect("Loaded slots have a width");
+        let predecessors = self.cfg.predecessors(block).to_vec();
+        let value = match predecessors.as_slice() {
+            _ if !self.dominators.is_reachable(block.index()) => zero(width),
+            [] => zero(width),
+            [predecessor] => self.read_at_exit(slot, *predecessor),
+            _ => {
+                let dest = self.cfg.fresh_temp();
+                self.entry_values.insert(key.clone(), dest.clone());
+                let merging = predecessors
+                    .iter()
+                    .map(|&predecessor| {
+                        (
+                            self.read_at_exit(slot, predecessor),
+                            self.cfg.block(predecessor).label.clone(),
+                        )
+                    })
+                    .collect();
+                self.cfg.block_mut(block).phis.push(nodes::PhiFunction {
+                    dest: dest.clone(),
+                    width,
+                    merging,
+                });
+                dest
+            }
+        };
+        self.entry_values.insert(key, value.clone());
+        value
+    }
+
+    /// Drops the allocas, the copies of their addresses and the stores,
+    /// and turns loads into copies of the value in the slot, at the
+    /// location of the load
+    fn rewrite_blocks(&mut self) {
+        for id in self.cfg.layout().to_vec() {
+            let body = std::mem::take(&mut self.cfg.block_mut(id).body);
+            let mut current: HashMap<nodes::Address, nodes::Address> = HashMap::new();
+            let mut promoted = vec![];
+            for instruction in body {
+                match instruction.ssa {
+                    nodes::Ssa::Alloca { ref dest, .. } | nodes::Ssa::Assignment { ref dest, .. }
+                        if self.slots.aliases.contains_key(dest) => {}
+                    nodes::Ssa::Store {
+                        ref address, value, ..
+                    } if self.slots.aliases.contains_key(address) => {
+                        current.insert(self.slots.aliases[address].clone(), value);
+                    }
+                    nodes::Ssa::Load {
+                        dest,
+                        ref address,
+                        width,
+                    } if self.slots.aliases.contains_key(address) => {
+                        let slot = self.slots.aliases[address].clone();
+                        let source = match current.get(&slot) {
+                            Some(value) => value.clone(),
+                            None => self.read_at_entry(&slot, id),
+                        };
+                        let copy = nodes::Ssa::Assignment { dest, source, width };
+                        promoted.push(nodes::Instruction::new(copy, instruction.location));
+                    }
+                    _ => promoted.push(instruction),
+                }
+            }
+            self.cfg.block_mut(id).body = promoted;
+        }
+    }
+}
+
+fn zero(width: Width) -> nodes::Address {
+    if width.is_floating() {
+        nodes::Address::constant_f64(0.0)
+    } else {
+        nodes::Address::constant_i64(0)
+    }
+}
+
+/// Promotes the stack slots whose address does not escape back to SSA
+/// values ("mem2reg"). Their loads become copies of the value stored last,
+/// with a phi where the stores of several predecessors meet.
+pub fn promote_slots(cfg: &mut Cfg) {
+    let slots = promotable_slots(cfg);
+    if slots.aliases.is_empty() {
+        return;
+    }
+
+    let mut exit_values = HashMap::new();
+    for (id, block) in cfg.blocks() {
+        for instruction in &block.body {
+            if let nodes::Ssa::Store { address, value, .. } = &instruction.ssa
+                && let Some(slot) = slots.aliases.get(address)
+            {
+                exit_values.insert((slot.clone(), id), value.clone());
+            }
+        }
+    }
+
+    let mut promotion = Promotion {
+        dominators: cfg.dominators(),
+        cfg,
+        slots,
+        exit_values,
+        entry_values: HashMap::new(),
+    };
+    promotion.rewrite_blocks();
+    ir::remove_trivial_phis(cfg);
+}
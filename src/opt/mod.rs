@@ -1,26 +1,36 @@
-use crate::{ir::nodes};
+use crate::ir::nodes;
 mod phi_elim;
 mod optimisation;
 mod constant_folding;
 mod dead_code_elimination;
 mod dataflow;
-mod controlflow;
 mod copy_elimination;
+mod mem2reg;
 
-pub fn run_o1(unit: &[nodes::ToplevelItem]) -> Vec<nodes::ToplevelItem> {
+pub use optimisation::PassVerificationError;
+
+/// Optimises every function, verifying the IR around each pass when
+/// `verify_each` is set. Functions out of SSA form are left as they are,
+/// the passes need it.
+pub fn run_o1(
+    unit: &[nodes::ToplevelItem],
+    verify_each: bool,
+) -> Result<Vec<nodes::ToplevelItem>, PassVerificationError> {
     unit.iter()
         .map(|u| match u {
+            nodes::ToplevelItem::Function(f) if !f.is_ssa => Ok(u.clone()),
             nodes::ToplevelItem::Function(f) => {
-                let body = &f.body;
                 let o1 = optimisation::O1;
-                nodes::ToplevelItem::Function(nodes::FunctionDef {
+                Ok(nodes::ToplevelItem::Function(nodes::FunctionDef {
                     name: f.name.clone(),
-                    return_width: f.return_width.clone(),
+                    return_width: f.return_width,
                     parameters: f.parameters.clone(),
-                    body: o1.optimize(&body),
-                })
+                    body: o1.optimize(f, verify_each)?,
+                    // O1 ends with phi elimination
+                    is_ssa: false,
+                }))
             }
-            nodes::ToplevelItem::Declaration(_) => u.clone(),
+            nodes::ToplevelItem::Declaration(_) | nodes::ToplevelItem::Data(_) => Ok(u.clone()),
         })
         .collect()
 }
@@ -1,28 +1,100 @@
-use crate::{ir::nodes, opt::{constant_folding, copy_elimination, dead_code_elimination, phi_elim}};
+use thiserror::Error;
 
-pub type OptimisationPassFn = fn(&[nodes::Ssa]) -> Vec<nodes::Ssa>;
+use crate::{
+    ir::{self, Cfg, nodes},
+    opt::{constant_folding, copy_elimination, dead_code_elimination, mem2reg, phi_elim},
+};
+
+pub type OptimisationPassFn = fn(&mut Cfg);
+
+pub struct OptimisationPass {
+    pub name: &'static str,
+    pub run: OptimisationPassFn,
+    /// Whether the pass leaves the IR in SSA form
+    pub keeps_ssa: bool,
+}
+
+/// Broken IR found by `--verify-each`, after the named pass or in the
+/// pass input when there is none
+#[derive(Error, Debug)]
+#[error(
+    "IR verification failed {}:\n{}",
+    pass.map_or("before optimisation".into(), |pass| format!("after pass '{pass}'")),
+    errors.iter().map(|e| format!("  {e}")).collect::<Vec<_>>().join("\n")
+)]
+pub struct PassVerificationError {
+    pub pass: Option<&'static str>,
+    pub errors: Vec<ir::VerifyError>,
+}
 
 pub struct OptimisationLevel<const N: usize> {
-    passes: [OptimisationPassFn; N],
+    passes: [OptimisationPass; N],
 }
 
 impl<const N: usize> OptimisationLevel<N> {
-    pub fn optimize(&self, ir: &[nodes::Ssa]) -> Vec<nodes::Ssa> {
-        let mut current = ir.to_vec();
-        for &pass in &self.passes {
-            current = pass(&current);
+    /// Runs every pass over the function, checking the IR in between when
+    /// `verify_each` is set
+    pub fn optimize(
+        &self,
+        function: &nodes::FunctionDef,
+        verify_each: bool,
+    ) -> Result<Cfg, PassVerificationError> {
+        let verify = |body: &Cfg, is_ssa: bool, pass: Option<&'static str>| {
+            if !verify_each {
+                return Ok(());
+            }
+            let function = nodes::FunctionDef {
+                body: body.clone(),
+                is_ssa,
+                ..function.clone()
+            };
+            let errors = ir::verify_function(&function);
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(PassVerificationError { pass, errors })
+            }
+        };
+
+        let mut current = function.body.clone();
+        let mut is_ssa = true;
+        verify(&current, is_ssa, None)?;
+        for pass in &self.passes {
+            (pass.run)(&mut current);
+            is_ssa &= pass.keeps_ssa;
+            verify(&current, is_ssa, Some(pass.name))?;
         }
-        current
+        Ok(current)
     }
 }
 
-pub const O1: OptimisationLevel<4> = OptimisationLevel {
+pub const O1: OptimisationLevel<5> = OptimisationLevel {
     passes: [
-        constant_folding::fold_constants,
-        dead_code_elimination::eliminate_dead_code,
-        copy_elimination::copy_eliminate,
-
+        OptimisationPass {
+            name: "mem2reg",
+            run: mem2reg::promote_slots,
+            keeps_ssa: true,
+        },
+        OptimisationPass {
+            name: "constant_folding",
+            run: constant_folding::fold_constants,
+            keeps_ssa: true,
+        },
+        OptimisationPass {
+            name: "dead_code_elimination",
+            run: dead_code_elimination::eliminate_dead_code,
+            keeps_ssa: true,
+        },
+        OptimisationPass {
+            name: "copy_elimination",
+            run: copy_elimination::copy_eliminate,
+            keeps_ssa: true,
+        },
         // Make sure it is always at the end!
-        phi_elim::eliminate_phi_body,
+        OptimisationPass {
+            name: "phi_elimination",
+            run: phi_elim::eliminate_phi_body,
+            keeps_ssa: false,
+        },
     ],
-};
\ No newline at end of file
+};
@@ -1,111 +1,120 @@
-use std::collections::HashMap;
+use crate::{
+    common::Width,
+    ir::{BlockId, Cfg, IrTextRepr, nodes},
+};
 
-use crate::{ir::{self, IrTextRepr, block_label, extract_phi_functions, ir_to_basic_blocks, nodes}};
+/// Copies a phi stands for on one incoming edge: (dest, value, width)
+type EdgeCopies = Vec<(nodes::Address, nodes::Address, Width)>;
 
+/// Phi functions of a block read their operands at the same time, so copies
+/// writing an address another copy reads go through temporaries first.
+/// They run as control leaves `predecessor`, at the location of its terminator.
+fn sequence_copies(copies: &EdgeCopies, predecessor: BlockId, cfg: &mut Cfg) -> Vec<nodes::Instruction> {
+    let location = cfg.block(predecessor).terminator_location();
+    let locate = |ssa| nodes::Instruction::new(ssa, location);
+    let interferes = copies
+        .iter()
+        .any(|(_, value, _)| copies.iter().any(|(dest, _, _)| dest == value));
+    if !interferes {
+        return copies
+            .iter()
+            .map(|(dest, value, width)| nodes::Ssa::Assignment {
+                dest: dest.clone(),
+                source: value.clone(),
+                width: *width,
+            })
+            .map(locate)
+            .collect();
+    }
 
-fn zero_source_variables(mut ssa: nodes::Ssa) -> nodes::Ssa {
-    match &mut ssa {
-        nodes::Ssa::Assignment { dest, source, width: _ } => {
-            if let nodes::Address::Source(s) = dest {
-                s.1 = 0;
-            }
+    let temps: Vec<nodes::Address> = copies.iter().map(|_| cfg.fresh_temp()).collect();
+    let to_temps = copies.iter().zip(&temps).map(|((_, value, width), temp)| nodes::Ssa::Assignment {
+        dest: temp.clone(),
+        source: value.clone(),
+        width: *width,
+    });
+    let from_temps = copies.iter().zip(&temps).map(|((dest, _, width), temp)| nodes::Ssa::Assignment {
+        dest: dest.clone(),
+        source: temp.clone(),
+        width: *width,
+    });
+    to_temps.chain(from_temps).map(locate).collect()
+}
 
-            if let nodes::Address::Source(s) = source {
-                s.1 = 0;
-            }
-        },
-        nodes::Ssa::Call { parameters, dest, func: _, num_params: _ } => {
-            if let Some(dest) = dest {
-                if let nodes::Address::Source(s) = &mut dest.0 {
-                    s.1 = 0;
-                }
-            }
-            for p in parameters {
-                if let nodes::Address::Source(s) = &mut p.value {
-                    s.1 = 0;
+/// Replaces phi functions by copies at the end of the predecessors. Edges
+/// from a branch get a block of their own for the copies, so that they do
+/// not run when the branch goes the other way.
+pub fn eliminate_phi_body(cfg: &mut Cfg) {
+    // Copies to do when going from a block to another one, by predecessor
+    let mut edges: Vec<(BlockId, Vec<(BlockId, EdgeCopies)>)> = vec![];
+    for (target, block) in cfg.blocks() {
+        let mut copies: Vec<(BlockId, EdgeCopies)> = vec![];
+        for phi in &block.phis {
+            for (value, predecessor) in &phi.merging {
+                let predecessor = cfg.block_of(predecessor).expect("Phi merging from an unknown block");
+                let copy = (phi.dest.clone(), value.clone(), phi.width);
+                match copies.iter_mut().find(|(id, _)| *id == predecessor) {
+                    Some((_, edge)) => edge.push(copy),
+                    None => copies.push((predecessor, vec![copy])),
                 }
             }
         }
-        nodes::Ssa::Branch { width: _, cond, true_target: _, false_target: _ } => {
-            if let nodes::Address::Source(s) = cond {
-                s.1 = 0;
+        for (predecessor, edge) in copies {
+            match edges.iter_mut().find(|(id, _)| *id == predecessor) {
+                Some((_, outgoing)) => outgoing.push((target, edge)),
+                None => edges.push((predecessor, vec![(target, edge)])),
             }
-        },
-        nodes::Ssa::Return { value } => {
-            if let Some(value) = value {
-                if let nodes::Address::Source(s) = &mut value.0 {
-                    s.1 = 0;
+        }
+    }
+    for (_, block) in cfg.blocks_mut() {
+        block.phis.clear();
+    }
+
+    for (predecessor, outgoing) in edges {
+        match cfg.block(predecessor).terminator().clone() {
+            nodes::Ssa::Jump(_) => {
+                for (_, copies) in &outgoing {
+                    let copies = sequence_copies(copies, predecessor, cfg);
+                    cfg.block_mut(predecessor).body.extend(copies);
                 }
             }
-        },
-        nodes::Ssa::Quadriplet(quad) => {
-            if let Some(dest) = &mut quad.right {
-                if let nodes::Address::Source(s) = dest {
-                    s.1 = 0;
+            nodes::Ssa::Branch {
+                width,
+                cond,
+                mut true_target,
+                mut false_target,
+            } => {
+                // Split blocks go right after the branch, in the order of their targets
+                let mut last = predecessor;
+                for (target, copies) in &outgoing {
+                    let target_label = cfg.block(*target).label.clone();
+                    let copies = sequence_copies(copies, predecessor, cfg);
+                    let jump = nodes::Instruction::new(
+                        nodes::Ssa::Jump(target_label.clone()),
+                        cfg.block(predecessor).terminator_location(),
+                    );
+                    last = cfg.insert_block_after(last, copies, jump);
+                    let split = cfg.block(last).label.clone();
+                    for branch_target in [&mut true_target, &mut false_target] {
+                        if *branch_target == target_label {
+                            *branch_target = split.clone();
+                        }
+                    }
                 }
+                cfg.set_terminator(
+                    predecessor,
+                    nodes::Ssa::Branch {
+                        width,
+                        cond,
+                        true_target,
+                        false_target,
+                    },
+                );
             }
-            
-            if let nodes::Address::Source(s) = &mut quad.left {
-                s.1 = 0;
-            }
-
-            if let nodes::Address::Source(s) = &mut quad.dest {
-                s.1 = 0;
-            }
+            _ => panic!(
+                "Block {} ending without a jump flows into a phi",
+                cfg.block(predecessor).label.to_ir_string()
+            ),
         }
-        _ => {}
     }
-
-    ssa
 }
-
-// fn eliminate_phi_block(
-//     block: &[nodes::Ssa],
-//     mut added_end: Vec<nodes::Ssa>,
-// ) -> Vec<nodes::Ssa> {
-//     let mut out = vec![];
-
-//     for (idx, b) in block.iter().enumerate() {
-//         if matches!(b, nodes::Ssa::Phi(_)) {
-//             continue;
-//         }
-
-//         let mut need_push = true;
-
-//         if idx == block.len() - 1 {
-//             // last block
-//             match b {
-//                 nodes::Ssa::Return { value: _ }
-//                 | nodes::Ssa::Jump(_)
-//                 | nodes::Ssa::Branch {
-//                     cond: _,
-//                     true_target: _,
-//                     false_target: _,
-//                     width: _,
-//                 } => {
-//                     added_end.push(b.clone());
-//                     need_push = false;
-//                 }
-//                 _ => (),
-//             }
-//         }
-//         if need_push {
-//             out.push(b.clone());
-//         }
-//     }
-//     out.extend(added_end);
-//     out
-// }
-
-pub fn eliminate_phi_body(body: &[nodes::Ssa]) -> Vec<ir::nodes::Ssa> {
-    let mut out = vec![];
-
-    for b in body {
-        if matches!(b, nodes::Ssa::Phi(_)) {
-            continue;
-        }
-        out.push(zero_source_variables(b.clone()));
-    }
-
-    out
-}
\ No newline at end of file
@@ -1,4 +1,9 @@
-use crate::common::StorageClass;
+use std::{
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::common::{Signedness, SourceLocation, StorageClass};
 
 #[derive(Debug)]
 pub enum FunctionDeclaratorField {
@@ -7,22 +12,20 @@ pub enum FunctionDeclaratorField {
 }
 
 impl FunctionDeclaratorField {
-    pub fn parameters(&self) -> impl IntoIterator<Item = &FunctionParameter> {
+    /// The declarator giving the parameters, under the pointers of the
+    /// return type in `int *f(void)` and inside the declarator of a returned
+    /// function pointer in `int (*f(void))(int)`
+    pub fn function_declarator(&self) -> &FunctionDeclarator {
         match self {
-            Self::FunctionDeclarator(fd) => fd.parameters.iter(),
-            Self::PointerDeclarator(pd) => {
-                let mut current = &pd.declarator;
-                loop {
-                    match current.as_ref() {
-                        Declarator::FunctionDeclarator(fd) => {
-                            return fd.parameters.iter();
-                        }
-                        Declarator::PointerDeclarator(pd) => current = &pd.declarator,
-                        _ => panic!("Wrong synax"),
-                    }
-                }
-            }
+            Self::FunctionDeclarator(fd) if matches!(fd.declarator.as_ref(), Declarator::Identifier(..)) => Some(fd),
+            Self::FunctionDeclarator(fd) => fd.declarator.named_function(),
+            Self::PointerDeclarator(pd) => pd.declarator.named_function(),
         }
+        .expect("A function definition declares a function")
+    }
+
+    pub fn parameters(&self) -> impl IntoIterator<Item = &FunctionParameter> {
+        self.function_declarator().parameters.iter()
     }
 
     pub fn get_identifier(&self) -> Identifier {
@@ -31,6 +34,13 @@ impl FunctionDeclaratorField {
             Self::PointerDeclarator(pd) => pd.declarator.get_identifier(),
         }
     }
+
+    pub fn location(&self) -> SourceLocation {
+        match self {
+            Self::FunctionDeclarator(fd) => fd.declarator.location(),
+            Self::PointerDeclarator(pd) => pd.declarator.location(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -49,6 +59,85 @@ pub struct StringLiteral(pub String);
 #[derive(Debug)]
 pub struct NumberLiteral(pub String);
 
+impl NumberLiteral {
+    /// tree-sitter reads a sign directly before a constant as part of it
+    fn unsigned_text(&self) -> &str {
+        self.0.trim_start_matches(['-', '+'])
+    }
+
+    fn is_negative(&self) -> bool {
+        self.0.starts_with('-')
+    }
+
+    fn split_suffix(&self) -> (&str, &str) {
+        let text = self.unsigned_text();
+        let body_len = text.trim_end_matches(['u', 'U', 'l', 'L']).len();
+        text.split_at(body_len)
+    }
+
+    /// Value of the constant without its sign, None if it does not fit
+    /// any integer type
+    pub fn magnitude(&self) -> Option<u64> {
+        let (body, _) = self.split_suffix();
+        let parsed = if let Some(hex) = body.strip_prefix("0x").or(body.strip_prefix("0X")) {
+            u64::from_str_radix(hex, 16)
+        } else if body.len() > 1 && body.starts_with('0') {
+            u64::from_str_radix(&body[1..], 8)
+        } else {
+            body.parse::<u64>()
+        };
+        let magnitude = parsed.ok()?;
+        // Decimal constants without a `u` suffix only have signed types
+        if self.is_decimal() && !self.is_unsigned() && magnitude > i64::MAX as u64 {
+            return None;
+        }
+        Some(magnitude)
+    }
+
+    /// Value of the constant with its sign, wrapped to 64 bits.
+    /// The frontend rejects constants without a magnitude.
+    pub fn value(&self) -> i64 {
+        let magnitude = self.magnitude().unwrap_or_default() as i64;
+        if self.is_negative() {
+            magnitude.wrapping_neg()
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        let (_, suffix) = self.split_suffix();
+        suffix.contains(['u', 'U'])
+    }
+
+    pub fn is_long(&self) -> bool {
+        let (_, suffix) = self.split_suffix();
+        suffix.contains(['l', 'L'])
+    }
+
+    pub fn is_decimal(&self) -> bool {
+        let text = self.unsigned_text();
+        !text.starts_with('0') || text.len() == 1
+    }
+
+    pub fn is_floating(&self) -> bool {
+        let text = self.unsigned_text();
+        !text.starts_with("0x") && !text.starts_with("0X") && text.contains(['.', 'e', 'E'])
+    }
+
+    /// Single precision literals carry an `f` suffix
+    pub fn is_single(&self) -> bool {
+        self.is_floating() && self.0.ends_with(['f', 'F'])
+    }
+
+    pub fn float_value(&self) -> f64 {
+        self.0
+            .trim_end_matches(['f', 'F', 'l', 'L'])
+            .parse()
+            .expect("Invalid floating literal")
+    }
+}
+
 #[derive(Debug)]
 pub struct ParameterDeclaration {
     pub dtype: DataType,
@@ -66,6 +155,9 @@ pub struct FunctionDeclarator {
     pub declarator: Box<Declarator>,
     pub parameters: Vec<FunctionParameter>,
     pub is_variadic: bool,
+    /// False for an empty `()` list, which leaves the parameters unknown,
+    /// unlike `(void)`
+    pub has_prototype: bool,
 }
 
 #[derive(Debug)]
@@ -73,58 +165,230 @@ pub struct PointerDeclarator {
     pub declarator: Box<Declarator>,
 }
 
-impl PointerDeclarator {
-    pub fn get_nest_level(&self) -> usize {
-        let mut level = 1;
-        let mut current = &self.declarator;
-        loop {
-            match current.as_ref() {
-                Declarator::FunctionDeclarator(function_declarator) => {
-                    current = &function_declarator.declarator;
-                }
-                Declarator::PointerDeclarator(pointer_declarator) => {
-                    current = &pointer_declarator.declarator;
-                    level += 1;
-                }
-                Declarator::Identifier(_) => {
-                    break;
-                }
-                Declarator::InitDeclarator(_) => panic!("Syntax"),
-            }
+#[derive(Debug)]
+pub struct ArrayDeclarator {
+    pub declarator: Box<Declarator>,
+    /// None for `[]`, whose length the initializer gives
+    pub size: Option<Expression>,
+}
+
+#[derive(Debug)]
+pub enum Designator {
+    Field(Identifier),
+    Index(Expression),
+}
+
+#[derive(Debug)]
+pub struct InitializerItem {
+    /// Empty for positional items
+    pub designators: Vec<Designator>,
+    pub value: Initializer,
+}
+
+#[derive(Debug)]
+pub struct InitializerList {
+    pub items: Vec<InitializerItem>,
+}
+
+#[derive(Debug)]
+pub enum Initializer {
+    Expression(Expression),
+    List(InitializerList),
+}
+
+/// Why a brace initializer cannot initialize a scalar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarInitializerError {
+    Designator,
+    ExcessElements,
+}
+
+impl Initializer {
+    /// Expression initializing a scalar, which may be wrapped in braces,
+    /// C11 6.7.9p11. None for empty braces, which zero the scalar as
+    /// C23 6.7.10p11 allows.
+    pub fn scalar(&self) -> Result<Option<&Expression>, ScalarInitializerError> {
+        match self {
+            Self::Expression(expression) => Ok(Some(expression)),
+            Self::List(list) => match list.items.as_slice() {
+                [] => Ok(None),
+                [item] if item.designators.is_empty() => item.value.scalar(),
+                [_] => Err(ScalarInitializerError::Designator),
+                _ => Err(ScalarInitializerError::ExcessElements),
+            },
+        }
+    }
+
+    /// Values of the initializer in source order, through nested braces
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            Self::Expression(expression) => vec![expression],
+            Self::List(list) => list.items.iter().flat_map(|item| item.value.expressions()).collect(),
         }
-        level
     }
 }
 
 #[derive(Debug)]
 pub struct InitDeclarator {
     pub declarator: Box<Declarator>,
-    pub value: Expression,
+    pub value: Initializer,
 }
 
 #[derive(Debug)]
 pub enum Declarator {
     FunctionDeclarator(FunctionDeclarator),
     PointerDeclarator(PointerDeclarator),
-    Identifier(Identifier),
+    ArrayDeclarator(ArrayDeclarator),
+    Identifier(Identifier, SourceLocation),
     InitDeclarator(InitDeclarator),
+    /// Where the name would be in an abstract declarator, as in the
+    /// unnamed parameters of a prototype
+    Abstract,
 }
 
 impl Declarator {
     pub fn get_identifier(&self) -> Identifier {
         match self {
             Self::FunctionDeclarator(fd) => fd.declarator.get_identifier(),
-            Self::Identifier(i) => i.clone(),
+            Self::Identifier(i, _) => i.clone(),
             Self::PointerDeclarator(i) => i.declarator.get_identifier(),
+            Self::ArrayDeclarator(i) => i.declarator.get_identifier(),
             Self::InitDeclarator(i) => i.declarator.get_identifier(),
+            Self::Abstract => panic!("Abstract declarators have no name"),
+        }
+    }
+
+    /// The function declarator applied to the declared name, which gives
+    /// the parameters of a declared function. None for other declarators.
+    pub fn named_function(&self) -> Option<&FunctionDeclarator> {
+        match self {
+            Self::FunctionDeclarator(fd) if matches!(fd.declarator.as_ref(), Self::Identifier(..)) => Some(fd),
+            Self::FunctionDeclarator(fd) => fd.declarator.named_function(),
+            Self::PointerDeclarator(pd) => pd.declarator.named_function(),
+            Self::ArrayDeclarator(ad) => ad.declarator.named_function(),
+            Self::InitDeclarator(id) => id.declarator.named_function(),
+            Self::Identifier(..) | Self::Abstract => None,
         }
     }
+
+    /// Declarators without a name, as in `int (*)(char *)`
+    pub fn is_abstract(&self) -> bool {
+        match self {
+            Self::FunctionDeclarator(fd) => fd.declarator.is_abstract(),
+            Self::Identifier(..) => false,
+            Self::PointerDeclarator(i) => i.declarator.is_abstract(),
+            Self::ArrayDeclarator(i) => i.declarator.is_abstract(),
+            Self::InitDeclarator(i) => i.declarator.is_abstract(),
+            Self::Abstract => true,
+        }
+    }
+
+    /// Where the declared name is written
+    pub fn location(&self) -> SourceLocation {
+        match self {
+            Self::FunctionDeclarator(fd) => fd.declarator.location(),
+            Self::Identifier(_, location) => *location,
+            Self::PointerDeclarator(i) => i.declarator.location(),
+            Self::ArrayDeclarator(i) => i.declarator.location(),
+            Self::InitDeclarator(i) => i.declarator.location(),
+            Self::Abstract => panic!("Abstract declarators have no name"),
+        }
+    }
+}
+
+/// Members of a struct sharing a base type, as in `int x, *y;`
+#[derive(Debug)]
+pub struct FieldDeclaration {
+    pub dtype: DataType,
+    pub declarators: Vec<Declarator>,
 }
 
+/// `struct tag { ... }`, or `struct tag` alone naming a struct declared
+/// elsewhere
 #[derive(Debug)]
+pub struct StructSpecifier {
+    /// None for an anonymous struct
+    pub tag: Option<Identifier>,
+    /// None when the specifier only names the struct
+    pub fields: Option<Vec<FieldDeclaration>>,
+    pub location: SourceLocation,
+}
+
+impl StructSpecifier {
+    /// Name the struct is known by in its scope. An anonymous struct is
+    /// named after where it is, which no tag can clash with.
+    pub fn key(&self) -> String {
+        match &self.tag {
+            Some(tag) => tag.0.clone(),
+            None => format!("(unnamed at {})", self.location),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum DataType {
-    Int,
-    Char,
+    Char(Signedness),
+    Short(Signedness),
+    Int(Signedness),
+    Long(Signedness),
+    LongLong(Signedness),
+    Float,
+    Double,
+    /// `va_list`, a pointer to the variadic arguments on the stack
+    VaList,
+    Void,
+    Struct(Rc<StructSpecifier>),
+}
+
+#[derive(Debug)]
+pub struct TypeName {
+    pub dtype: DataType,
+    /// Abstract declarator over the type, as in `int (*)(int)`
+    pub declarator: Box<Declarator>,
+}
+
+#[derive(Debug)]
+pub struct CastExpression {
+    pub type_name: TypeName,
+    pub value: Box<Expression>,
+}
+
+#[derive(Debug)]
+pub enum SizeOfOperand {
+    Type(TypeName),
+    Expression(Box<Expression>),
+}
+
+#[derive(Debug)]
+pub struct SizeOfExpression {
+    pub operand: SizeOfOperand,
+}
+
+#[derive(Debug)]
+pub struct AlignOfExpression {
+    pub type_name: TypeName,
+}
+
+#[derive(Debug)]
+pub enum PointerOp {
+    AddressOf,
+    Dereference,
+}
+
+#[derive(Debug)]
+pub struct PointerExpression {
+    pub op: PointerOp,
+    pub argument: Box<Expression>,
+}
+
+/// The `<stdarg.h>` macros, which the compiler provides as builtins.
+/// A `va_list` has to be a plain variable.
+#[derive(Debug)]
+pub enum VariadicExpression {
+    Start { list: Identifier },
+    Arg { list: Identifier, type_name: TypeName },
+    End { list: Identifier },
+    Copy { dest: Identifier, source: Identifier },
 }
 
 #[derive(Debug)]
@@ -135,8 +399,8 @@ pub struct CallExpression {
 
 impl CallExpression {
     pub fn get_identifier(&self) -> Option<Identifier> {
-        match self.function.as_ref() {
-            Expression::Identifier(id) => Some(id.clone()),
+        match &self.function.kind {
+            ExpressionKind::Identifier(id) => Some(id.clone()),
             _ => None,
         }
     }
@@ -148,24 +412,15 @@ pub enum BinOp {
     Mul,
     Minus,
     Div,
+    Rem,
+    ShiftLeft,
+    ShiftRight,
     Gt,
     Lt,
+    Ge,
+    Le,
     Eq,
-}
-
-impl BinOp {
-    pub fn from_str(value: &str) -> Self {
-        match value {
-            "+" => BinOp::Plus,
-            "*" => BinOp::Mul,
-            "-" => BinOp::Minus,
-            "/" => BinOp::Div,
-            ">" => BinOp::Gt,
-            "<" => BinOp::Lt,
-            "==" => BinOp::Eq,
-            _ => panic!("Unsupported bin op: {}", value),
-        }
-    }
+    Ne,
 }
 
 #[derive(Debug)]
@@ -187,19 +442,12 @@ pub enum AssignmentType {
     SubEq,
     MulEq,
     DivEq,
+    RemEq,
+    ShiftLeftEq,
+    ShiftRightEq,
 }
 
 impl AssignmentType {
-    pub fn from_str(inp: &str) -> Self {
-        match inp {
-            "=" => AssignmentType::Eq,
-            "+=" => AssignmentType::AddEq,
-            "-=" => AssignmentType::SubEq,
-            "*=" => AssignmentType::MulEq,
-            "/=" => AssignmentType::DivEq,
-            _ => todo!(),
-        }
-    }
 
     pub fn to_op(&self) -> Option<BinOp> {
         match self {
@@ -208,13 +456,35 @@ impl AssignmentType {
             Self::SubEq => Some(BinOp::Minus),
             Self::MulEq => Some(BinOp::Mul),
             Self::DivEq => Some(BinOp::Div),
+            Self::RemEq => Some(BinOp::Rem),
+            Self::ShiftLeftEq => Some(BinOp::ShiftLeft),
+            Self::ShiftRightEq => Some(BinOp::ShiftRight),
         }
     }
 }
 
+/// `array[index]`, where either operand may be the pointer
+#[derive(Debug)]
+pub struct SubscriptExpression {
+    pub array: Box<Expression>,
+    pub index: Box<Expression>,
+}
+
+#[derive(Debug)]
+pub struct MemberExpression {
+    pub object: Box<Expression>,
+    pub member: Identifier,
+    /// `->`, where the object is a pointer to the struct
+    pub through_pointer: bool,
+}
+
 #[derive(Debug)]
 pub enum LValue {
     Identifier(Identifier),
+    /// `*pointer`, the object the pointer points to
+    Dereference(Box<Expression>),
+    Subscript(SubscriptExpression),
+    Member(MemberExpression),
 }
 
 #[derive(Debug)]
@@ -224,8 +494,31 @@ pub struct AssignmentExpression {
     pub atype: AssignmentType,
 }
 
+/// Tells expressions apart, so later passes can note things about each
+/// node. Every node gets its own when built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExpressionId(usize);
+
+#[derive(Debug)]
+pub struct Expression {
+    pub id: ExpressionId,
+    pub kind: ExpressionKind,
+    pub location: SourceLocation,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, location: SourceLocation) -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            id: ExpressionId(NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            kind,
+            location,
+        }
+    }
+}
+
 #[derive(Debug)]
-pub enum Expression {
+pub enum ExpressionKind {
     Binary(ExpressionBinary),
     Parenthesized(ParenthesizedExpression),
     Call(CallExpression),
@@ -234,23 +527,35 @@ pub enum Expression {
     NumberLiteral(NumberLiteral),
     Empty,
     Assignment(AssignmentExpression),
+    Cast(CastExpression),
+    SizeOf(SizeOfExpression),
+    AlignOf(AlignOfExpression),
+    Pointer(PointerExpression),
+    Subscript(SubscriptExpression),
+    Member(MemberExpression),
+    Variadic(VariadicExpression),
 }
 
 #[derive(Debug)]
 pub struct ReturnStatement {
     pub expression: Expression,
+    pub location: SourceLocation,
 }
 
 #[derive(Debug)]
 pub struct ExpressionStatement {
     pub expression: Expression,
+    pub location: SourceLocation,
 }
 
 #[derive(Debug)]
 pub struct Declaration {
     pub storage_class: StorageClass,
     pub dtype: DataType,
-    pub declarator: Box<Declarator>,
+    /// Declarators sharing the base type, as in `int a = 1, b, *c;`.
+    /// Empty when only a struct is declared, as in `struct p { int x; };`
+    pub declarators: Vec<Declarator>,
+    pub location: SourceLocation,
 }
 
 #[derive(Debug)]
@@ -258,12 +563,14 @@ pub struct IfStatement {
     pub condition: ParenthesizedExpression,
     pub body: Box<Statement>,
     pub else_body: Option<Box<Statement>>,
+    pub location: SourceLocation,
 }
 
 #[derive(Debug)]
 pub struct WhileStatement {
     pub condition: ParenthesizedExpression,
     pub body: Box<Statement>,
+    pub location: SourceLocation,
 }
 
 #[derive(Debug)]
@@ -276,9 +583,24 @@ pub enum Statement {
     WhileStatement(WhileStatement),
 }
 
+impl Statement {
+    /// Where the statement starts
+    pub fn location(&self) -> SourceLocation {
+        match self {
+            Self::ExpressionStatement(es) => es.location,
+            Self::ReturnStatement(rs) => rs.location,
+            Self::Declaration(d) => d.location,
+            Self::CompoundStatement(cs) => cs.location,
+            Self::IfStatement(ifs) => ifs.location,
+            Self::WhileStatement(ws) => ws.location,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CompoundStatement {
     pub items: Vec<Statement>,
+    pub location: SourceLocation,
 }
 
 #[derive(Debug)]
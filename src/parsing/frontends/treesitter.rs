@@ -1,4 +1,4 @@
-use crate::common::StorageClass;
+use crate::common::{Signedness, SourceLocation, StorageClass};
 use crate::parsing::ast::{
     self, CallExpression, CompoundStatement, Expression, ExpressionStatement, Identifier,
     ParenthesizedExpression, PointerDeclarator, ReturnStatement, TopLevelItem,
@@ -8,6 +8,7 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs;
 use std::os::fd::AsRawFd;
+use std::rc::Rc;
 use thiserror::Error;
 use tree_sitter::{Node, Parser as TsParser};
 
@@ -20,6 +21,38 @@ pub enum NodeConversionError {
 
     #[error("invalid source value (expected {expected:?}, found {found:?})")]
     InvalidSourceValue { expected: String, found: String },
+
+    /// Valid C the compiler does not handle, reported as a diagnostic
+    #[error("{location}: error: {construct} are not supported yet")]
+    Unsupported {
+        construct: &'static str,
+        location: SourceLocation,
+    },
+
+    #[error("{location}: error: integer constant {literal} is too large for its type")]
+    ConstantTooLarge {
+        literal: String,
+        location: SourceLocation,
+    },
+
+    #[error("{location}: error: unknown type name '{name}'")]
+    UnknownTypeName {
+        name: String,
+        location: SourceLocation,
+    },
+}
+
+/// Error for C constructs parsed by tree-sitter but not by the compiler
+fn unsupported(node: &Node) -> Option<NodeConversionError> {
+    let construct = match node.kind() {
+        "union_specifier" => "unions",
+        "enum_specifier" => "enums",
+        _ => return None,
+    };
+    Some(NodeConversionError::Unsupported {
+        construct,
+        location: source_location(node),
+    })
 }
 
 #[derive(Debug, Default)]
@@ -30,16 +63,21 @@ fn children_iter<'a>(parent: &'a Node) -> impl Iterator<Item = Node<'a>> {
     (0..num_children).map(|idx| parent.child(idx).unwrap())
 }
 
+fn source_location(node: &Node) -> SourceLocation {
+    let position = node.start_position();
+    SourceLocation {
+        line: position.row + 1,
+        column: position.column + 1,
+    }
+}
+
 fn named_children_map<'a>(parent: &'a Node) -> HashMap<&'static str, Node<'a>> {
     let num_children = parent.child_count();
     (0..num_children)
         .filter_map(|idx| {
             let child = parent.child(idx).unwrap();
             let name = parent.field_name_for_child(idx as u32);
-            match name {
-                Some(n) => Some((n, child)),
-                None => None,
-            }
+            name.map(|n| (n, child))
         })
         .collect()
 }
@@ -48,22 +86,159 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::DataType {
     type Error = NodeConversionError;
 
     fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
-        if node.kind() != "primitive_type" {
+        match node.kind() {
+            "primitive_type" => {
+                let val = &source[node.start_byte()..node.end_byte()];
+                match val {
+                    "int" => Ok(ast::DataType::Int(Signedness::Signed)),
+                    // char is signed on aarch64 Darwin
+                    "char" => Ok(ast::DataType::Char(Signedness::Signed)),
+                    "float" => Ok(ast::DataType::Float),
+                    "double" => Ok(ast::DataType::Double),
+                    "void" => Ok(ast::DataType::Void),
+                    // tree-sitter knows bool, size_t and the like, which
+                    // come from headers the compiler does not have
+                    _ => Err(NodeConversionError::UnknownTypeName {
+                        name: val.into(),
+                        location: source_location(node),
+                    }),
+                }
+            }
+            "sized_type_specifier" => {
+                let mut signedness = Signedness::Signed;
+                let mut longs = 0;
+                let mut is_short = false;
+                let mut base = None;
+
+                for child in children_iter(node) {
+                    let val = &source[child.start_byte()..child.end_byte()];
+                    match child.kind() {
+                        "primitive_type" => base = Some(ast::DataType::try_from((&child, source))?),
+                        _ => match val {
+                            "unsigned" => signedness = Signedness::Unsigned,
+                            "signed" => signedness = Signedness::Signed,
+                            "long" => longs += 1,
+                            "short" => is_short = true,
+                            _ => {
+                                return Err(NodeConversionError::InvalidSourceValue {
+                                    expected: "unsigned | signed | long | short".into(),
+                                    found: val.into(),
+                                });
+                            }
+                        },
+                    }
+                }
+
+                match (base, is_short, longs) {
+                    (Some(ast::DataType::Char(_)), false, 0) => Ok(ast::DataType::Char(signedness)),
+                    (None | Some(ast::DataType::Int(_)), true, 0) => {
+                        Ok(ast::DataType::Short(signedness))
+                    }
+                    (None | Some(ast::DataType::Int(_)), false, 0) => Ok(ast::DataType::Int(signedness)),
+                    (None | Some(ast::DataType::Int(_)), false, 1) => Ok(ast::DataType::Long(signedness)),
+                    (None | Some(ast::DataType::Int(_)), false, 2) => {
+                        Ok(ast::DataType::LongLong(signedness))
+                    }
+                    // long double is the same as double on aarch64 Darwin
+                    (Some(ast::DataType::Double), false, 1) => Ok(ast::DataType::Double),
+                    _ => Err(NodeConversionError::InvalidSourceValue {
+                        expected: "valid integer type".into(),
+                        found: source[node.start_byte()..node.end_byte()].into(),
+                    }),
+                }
+            }
+            "struct_specifier" => Ok(ast::DataType::Struct(Rc::new(ast::StructSpecifier::try_from((
+                node, source,
+            ))?))),
+            "type_identifier" => match &source[node.start_byte()..node.end_byte()] {
+                "va_list" | "__builtin_va_list" => Ok(ast::DataType::VaList),
+                val => Err(NodeConversionError::UnknownTypeName {
+                    name: val.into(),
+                    location: source_location(node),
+                }),
+            },
+            _ => Err(unsupported(node).unwrap_or(NodeConversionError::InvalidNodeType {
+                expected: "primitive_type | sized_type_specifier | struct_specifier | type_identifier".into(),
+                found: node.kind().into(),
+            })),
+        }
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::StructSpecifier {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "struct_specifier" {
             return Err(NodeConversionError::InvalidNodeType {
-                expected: "primitive_type".into(),
+                expected: "struct_specifier".into(),
                 found: node.kind().into(),
             });
         }
 
-        let val = &source[node.start_byte()..node.end_byte()];
-        match val {
-            "int" => Ok(ast::DataType::Int),
-            "char" => Ok(ast::DataType::Char),
-            _ => Err(NodeConversionError::InvalidSourceValue {
-                expected: "int".into(),
-                found: val.into(),
-            }),
+        let tag = node
+            .child_by_field_name("name")
+            .map(|name| ast::Identifier::try_from((&name, source)))
+            .transpose()?;
+        let fields = node
+            .child_by_field_name("body")
+            .map(|body| {
+                children_iter(&body)
+                    .filter(|child| child.kind() == "field_declaration")
+                    .map(|field| ast::FieldDeclaration::try_from((&field, source)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        Ok(ast::StructSpecifier {
+            tag,
+            fields,
+            location: source_location(node),
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::FieldDeclaration {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "field_declaration" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "field_declaration".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let type_node = node
+            .child_by_field_name("type")
+            .ok_or_else(|| NodeConversionError::MissingChild {
+                parent: "field_declaration".into(),
+                child: "type".into(),
+            })?;
+        let dtype = ast::DataType::try_from((&type_node, source))?;
+
+        if let Some(bitfield) = children_iter(node).find(|child| child.kind() == "bitfield_clause") {
+            return Err(NodeConversionError::Unsupported {
+                construct: "bit-fields",
+                location: source_location(&bitfield),
+            });
+        }
+
+        let mut cursor = node.walk();
+        let declarators = node
+            .children_by_field_name("declarator", &mut cursor)
+            .map(|declarator| ast::Declarator::try_from((&declarator, source)))
+            .collect::<Result<Vec<_>, _>>()?;
+        // A tagged struct without members only declares its tag
+        let is_tagged_struct = matches!(&dtype, ast::DataType::Struct(specifier) if specifier.tag.is_some());
+        if declarators.is_empty() && !is_tagged_struct {
+            return Err(NodeConversionError::Unsupported {
+                construct: "anonymous members",
+                location: source_location(node),
+            });
         }
+
+        Ok(ast::FieldDeclaration { dtype, declarators })
     }
 }
 
@@ -90,7 +265,14 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::NumberLiteral {
 
     fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
         let val = &source[node.start_byte()..node.end_byte()];
-        Ok(ast::NumberLiteral(val.into()))
+        let literal = ast::NumberLiteral(val.into());
+        if !literal.is_floating() && literal.magnitude().is_none() {
+            return Err(NodeConversionError::ConstantTooLarge {
+                literal: val.into(),
+                location: source_location(node),
+            });
+        }
+        Ok(literal)
     }
 }
 
@@ -140,6 +322,171 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::CallExpression {
     }
 }
 
+fn find_descendant<'a>(node: Node<'a>, kind: &str) -> Option<Node<'a>> {
+    if node.kind() == kind {
+        return Some(node);
+    }
+    (0..node.child_count()).find_map(|idx| find_descendant(node.child(idx).unwrap(), kind))
+}
+
+/// The type operand of `va_arg` is not an expression, so tree-sitter
+/// cannot parse it in place. Its text is parsed again where the grammar
+/// expects a type name.
+fn type_name_from_text(text: &str) -> Result<ast::TypeName, NodeConversionError> {
+    let source = format!("int va_arg_type = _Alignof({});", text);
+    let mut parser = TsParser::new();
+    parser
+        .set_language(&tree_sitter_c::LANGUAGE.into())
+        .expect("Error loading C grammar");
+    let tree = parser.parse(&source, None).unwrap();
+    let alignof = find_descendant(tree.root_node(), "alignof_expression").ok_or_else(|| {
+        NodeConversionError::InvalidSourceValue {
+            expected: "type name".into(),
+            found: text.into(),
+        }
+    })?;
+    Ok(ast::AlignOfExpression::try_from((&alignof, source.as_str()))?.type_name)
+}
+
+/// Builtins standing for the `<stdarg.h>` macros
+const VARIADIC_BUILTINS: [&str; 8] = [
+    "va_start",
+    "va_arg",
+    "va_end",
+    "va_copy",
+    "__builtin_va_start",
+    "__builtin_va_arg",
+    "__builtin_va_end",
+    "__builtin_va_copy",
+];
+
+fn is_variadic_builtin(node: &Node, source: &str) -> bool {
+    node.child_by_field_name("function").is_some_and(|function| {
+        function.kind() == "identifier"
+            && VARIADIC_BUILTINS.contains(&&source[function.start_byte()..function.end_byte()])
+    })
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::VariadicExpression {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if !is_variadic_builtin(node, source) {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "call of va_start | va_arg | va_end | va_copy".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let function = node.child_by_field_name("function").unwrap();
+        let name = &source[function.start_byte()..function.end_byte()];
+        let arguments = node.child_by_field_name("arguments").ok_or_else(|| {
+            NodeConversionError::MissingChild {
+                parent: node.kind().into(),
+                child: "arguments".into(),
+            }
+        })?;
+        let identifiers = children_iter(&arguments)
+            .filter(|n| n.kind() == "identifier")
+            .map(|n| ast::Identifier::try_from((&n, source)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let list = identifiers.first().cloned().ok_or_else(|| {
+            NodeConversionError::MissingChild {
+                parent: name.into(),
+                child: "va_list variable".into(),
+            }
+        })?;
+
+        Ok(match name.trim_start_matches("__builtin_") {
+            // The last named parameter is not needed to find the variadic ones
+            "va_start" => ast::VariadicExpression::Start { list },
+            "va_end" => ast::VariadicExpression::End { list },
+            "va_copy" => ast::VariadicExpression::Copy {
+                dest: list,
+                source: identifiers.get(1).cloned().ok_or_else(|| {
+                    NodeConversionError::MissingChild {
+                        parent: name.into(),
+                        child: "source va_list".into(),
+                    }
+                })?,
+            },
+            _ => {
+                let comma = children_iter(&arguments).find(|n| n.kind() == ",").ok_or_else(|| {
+                    NodeConversionError::MissingChild {
+                        parent: name.into(),
+                        child: "type".into(),
+                    }
+                })?;
+                let closing = arguments.child(arguments.child_count() - 1).unwrap();
+                let type_name =
+                    type_name_from_text(source[comma.end_byte()..closing.start_byte()].trim())?;
+                ast::VariadicExpression::Arg { list, type_name }
+            }
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::BinOp {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        let val = &source[node.start_byte()..node.end_byte()];
+        let construct = match val {
+            "+" => return Ok(ast::BinOp::Plus),
+            "*" => return Ok(ast::BinOp::Mul),
+            "-" => return Ok(ast::BinOp::Minus),
+            "/" => return Ok(ast::BinOp::Div),
+            "%" => return Ok(ast::BinOp::Rem),
+            "<<" => return Ok(ast::BinOp::ShiftLeft),
+            ">>" => return Ok(ast::BinOp::ShiftRight),
+            ">" => return Ok(ast::BinOp::Gt),
+            "<" => return Ok(ast::BinOp::Lt),
+            ">=" => return Ok(ast::BinOp::Ge),
+            "<=" => return Ok(ast::BinOp::Le),
+            "==" => return Ok(ast::BinOp::Eq),
+            "!=" => return Ok(ast::BinOp::Ne),
+            "&&" | "||" => "logical operators",
+            "&" | "|" | "^" => "bitwise operators",
+            _ => {
+                return Err(NodeConversionError::InvalidSourceValue {
+                    expected: "binary operator".into(),
+                    found: val.into(),
+                });
+            }
+        };
+        Err(NodeConversionError::Unsupported {
+            construct,
+            location: source_location(node),
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::AssignmentType {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        let val = &source[node.start_byte()..node.end_byte()];
+        match val {
+            "=" => Ok(ast::AssignmentType::Eq),
+            "+=" => Ok(ast::AssignmentType::AddEq),
+            "-=" => Ok(ast::AssignmentType::SubEq),
+            "*=" => Ok(ast::AssignmentType::MulEq),
+            "/=" => Ok(ast::AssignmentType::DivEq),
+            "%=" => Ok(ast::AssignmentType::RemEq),
+            "<<=" => Ok(ast::AssignmentType::ShiftLeftEq),
+            ">>=" => Ok(ast::AssignmentType::ShiftRightEq),
+            "&=" | "|=" | "^=" => Err(NodeConversionError::Unsupported {
+                construct: "bitwise assignments",
+                location: source_location(node),
+            }),
+            _ => Err(NodeConversionError::InvalidSourceValue {
+                expected: "assignment operator".into(),
+                found: val.into(),
+            }),
+        }
+    }
+}
+
 impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::ExpressionBinary {
     type Error = NodeConversionError;
 
@@ -148,8 +495,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::ExpressionBinary {
         let op = node.child(1).unwrap();
         let right = node.child(2).unwrap();
 
-        let op_val = &source[op.start_byte()..op.end_byte()];
-        let op = ast::BinOp::from_str(op_val);
+        let op = ast::BinOp::try_from((&op, source))?;
 
         Ok(ast::ExpressionBinary {
             left: Box::new(TryFrom::try_from((&left, source))?),
@@ -179,14 +525,31 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::LValue {
     type Error = NodeConversionError;
 
     fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
-        if node.kind() != "identifier" {
-            return Err(NodeConversionError::InvalidNodeType {
+        match node.kind() {
+            "identifier" => Ok(ast::LValue::Identifier(Identifier::try_from((node, source))?)),
+            "parenthesized_expression" => ast::LValue::try_from((&node.child(1).unwrap(), source)),
+            "pointer_expression" => match ast::PointerExpression::try_from((node, source))? {
+                ast::PointerExpression {
+                    op: ast::PointerOp::Dereference,
+                    argument,
+                } => Ok(ast::LValue::Dereference(argument)),
+                ast::PointerExpression {
+                    op: ast::PointerOp::AddressOf,
+                    ..
+                } => Err(NodeConversionError::InvalidNodeType {
+                    expected: "lvalue".into(),
+                    found: "address of an object".into(),
+                }),
+            },
+            "subscript_expression" => Ok(ast::LValue::Subscript(ast::SubscriptExpression::try_from((
+                node, source,
+            ))?)),
+            "field_expression" => Ok(ast::LValue::Member(ast::MemberExpression::try_from((node, source))?)),
+            _ => Err(unsupported(node).unwrap_or(NodeConversionError::InvalidNodeType {
                 expected: "identifier".into(),
                 found: node.kind().into(),
-            });
+            })),
         }
-        let identifier = Identifier::try_from((node, source))?;
-        Ok(ast::LValue::Identifier(identifier))
     }
 }
 
@@ -204,8 +567,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::AssignmentExpression {
         let op_node = node.child(1).unwrap();
         let rvalue_node = node.child(2).unwrap();
 
-        let assignment_val = &source[op_node.start_byte()..op_node.end_byte()];
-        let assignment_type = ast::AssignmentType::from_str(assignment_val);
+        let assignment_type = ast::AssignmentType::try_from((&op_node, source))?;
         let lvalue = ast::LValue::try_from((&lvalue_node, source))?;
         let rvalue = ast::Expression::try_from((&rvalue_node, source))?;
         Ok(ast::AssignmentExpression {
@@ -216,44 +578,375 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::AssignmentExpression {
     }
 }
 
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::TypeName {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "type_descriptor" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "type_descriptor".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let named_children = named_children_map(node);
+
+        let dtype = ast::DataType::try_from((
+            named_children
+                .get("type")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "type_descriptor".into(),
+                    child: "type".into(),
+                })?,
+            source,
+        ))?;
+
+        let declarator = match named_children.get("declarator") {
+            Some(declarator) => ast::Declarator::try_from((declarator, source))?,
+            None => ast::Declarator::Abstract,
+        };
+
+        Ok(ast::TypeName {
+            dtype,
+            declarator: Box::new(declarator),
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::CastExpression {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "cast_expression" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "cast_expression".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let named_children = named_children_map(node);
+
+        let type_name = ast::TypeName::try_from((
+            named_children
+                .get("type")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "cast_expression".into(),
+                    child: "type".into(),
+                })?,
+            source,
+        ))?;
+
+        let value = ast::Expression::try_from((
+            named_children
+                .get("value")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "cast_expression".into(),
+                    child: "value".into(),
+                })?,
+            source,
+        ))?;
+
+        Ok(ast::CastExpression {
+            type_name,
+            value: Box::new(value),
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::PointerExpression {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "pointer_expression" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "pointer_expression".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let named_children = named_children_map(node);
+
+        let operator = named_children
+            .get("operator")
+            .ok_or_else(|| NodeConversionError::MissingChild {
+                parent: "pointer_expression".into(),
+                child: "operator".into(),
+            })?;
+        let op = match &source[operator.start_byte()..operator.end_byte()] {
+            "&" => ast::PointerOp::AddressOf,
+            _ => ast::PointerOp::Dereference,
+        };
+
+        let argument = ast::Expression::try_from((
+            named_children
+                .get("argument")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "pointer_expression".into(),
+                    child: "argument".into(),
+                })?,
+            source,
+        ))?;
+
+        Ok(ast::PointerExpression {
+            op,
+            argument: Box::new(argument),
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::SubscriptExpression {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "subscript_expression" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "subscript_expression".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let named_children = named_children_map(node);
+
+        let array = ast::Expression::try_from((
+            named_children
+                .get("argument")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "subscript_expression".into(),
+                    child: "argument".into(),
+                })?,
+            source,
+        ))?;
+
+        let index = ast::Expression::try_from((
+            named_children
+                .get("index")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "subscript_expression".into(),
+                    child: "index".into(),
+                })?,
+            source,
+        ))?;
+
+        Ok(ast::SubscriptExpression {
+            array: Box::new(array),
+            index: Box::new(index),
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::MemberExpression {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "field_expression" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "field_expression".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let named_children = named_children_map(node);
+
+        let object = ast::Expression::try_from((
+            named_children
+                .get("argument")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "field_expression".into(),
+                    child: "argument".into(),
+                })?,
+            source,
+        ))?;
+
+        let field = named_children
+            .get("field")
+            .ok_or_else(|| NodeConversionError::MissingChild {
+                parent: "field_expression".into(),
+                child: "field".into(),
+            })?;
+        let operator = named_children
+            .get("operator")
+            .ok_or_else(|| NodeConversionError::MissingChild {
+                parent: "field_expression".into(),
+                child: "operator".into(),
+            })?;
+
+        Ok(ast::MemberExpression {
+            object: Box::new(object),
+            member: ast::Identifier::try_from((field, source))?,
+            through_pointer: &source[operator.start_byte()..operator.end_byte()] == "->",
+        })
+    }
+}
+
+/// Call read back from a type name. Without typedefs tree-sitter takes
+/// `sizeof(f())` and `sizeof(f(a))` for the type `f` with an abstract
+/// function declarator, while the compiler only has the built-in
+/// `va_list` as a type identifier, so any other name is a function.
+fn type_name_as_call(node: &Node, source: &str) -> Option<ast::CallExpression> {
+    let named_children = named_children_map(node);
+    let function = named_children.get("type")?;
+    let declarator = named_children.get("declarator")?;
+    let name = &source[function.start_byte()..function.end_byte()];
+    if function.kind() != "type_identifier"
+        || matches!(name, "va_list" | "__builtin_va_list")
+        || declarator.kind() != "abstract_function_declarator"
+        || declarator.child_by_field_name("declarator").is_some()
+    {
+        return None;
+    }
+
+    // Each argument is a parameter declaration of a bare type identifier
+    let parameters = declarator.child_by_field_name("parameters")?;
+    let mut cursor = parameters.walk();
+    let arguments = parameters
+        .named_children(&mut cursor)
+        .map(|parameter| {
+            let argument = parameter.child_by_field_name("type")?;
+            (parameter.kind() == "parameter_declaration"
+                && parameter.named_child_count() == 1
+                && argument.kind() == "type_identifier")
+                .then(|| {
+                    let name = &source[argument.start_byte()..argument.end_byte()];
+                    let identifier = ast::ExpressionKind::Identifier(Identifier(name.into()));
+                    ast::Expression::new(identifier, source_location(&argument))
+                })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(ast::CallExpression {
+        function: Box::new(ast::Expression::new(
+            ast::ExpressionKind::Identifier(Identifier(name.into())),
+            source_location(function),
+        )),
+        arguments,
+    })
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::SizeOfExpression {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "sizeof_expression" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "sizeof_expression".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let named_children = named_children_map(node);
+
+        let operand = if let Some(type_node) = named_children.get("type") {
+            match type_name_as_call(type_node, source) {
+                Some(call) => {
+                    let call = ast::Expression::new(ast::ExpressionKind::Call(call), source_location(type_node));
+                    ast::SizeOfOperand::Expression(Box::new(call))
+                }
+                None => ast::SizeOfOperand::Type(ast::TypeName::try_from((type_node, source))?),
+            }
+        } else {
+            let value = ast::Expression::try_from((
+                named_children
+                    .get("value")
+                    .ok_or_else(|| NodeConversionError::MissingChild {
+                        parent: "sizeof_expression".into(),
+                        child: "value".into(),
+                    })?,
+                source,
+            ))?;
+            ast::SizeOfOperand::Expression(Box::new(value))
+        };
+
+        Ok(ast::SizeOfExpression { operand })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::AlignOfExpression {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "alignof_expression" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "alignof_expression".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let named_children = named_children_map(node);
+
+        let type_name = ast::TypeName::try_from((
+            named_children
+                .get("type")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "alignof_expression".into(),
+                    child: "type".into(),
+                })?,
+            source,
+        ))?;
+
+        Ok(ast::AlignOfExpression { type_name })
+    }
+}
+
 impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::Expression {
     type Error = NodeConversionError;
 
     fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
-        match node.kind() {
-            "call_expression" => Ok(ast::Expression::Call(ast::CallExpression::try_from((
+        let kind = match node.kind() {
+            "call_expression" if is_variadic_builtin(node, source) => Ok(
+                ast::ExpressionKind::Variadic(ast::VariadicExpression::try_from((node, source))?),
+            ),
+            "call_expression" => Ok(ast::ExpressionKind::Call(ast::CallExpression::try_from((
                 node, source,
             ))?)),
-            "identifier" => Ok(ast::Expression::Identifier(ast::Identifier::try_from((
+            "identifier" => Ok(ast::ExpressionKind::Identifier(ast::Identifier::try_from((
                 node, source,
             ))?)),
-            "string_literal" => Ok(ast::Expression::StringLiteral(
+            "string_literal" => Ok(ast::ExpressionKind::StringLiteral(
                 ast::StringLiteral::try_from((node, source))?,
             )),
-            "number_literal" => Ok(ast::Expression::NumberLiteral(
+            "number_literal" => Ok(ast::ExpressionKind::NumberLiteral(
                 ast::NumberLiteral::try_from((node, source))?,
             )),
-            "binary_expression" => Ok(ast::Expression::Binary(ast::ExpressionBinary::try_from((
+            "binary_expression" => Ok(ast::ExpressionKind::Binary(ast::ExpressionBinary::try_from((
                 node, source,
             ))?)),
-            "parenthesized_expression" => Ok(ast::Expression::Parenthesized(
+            "parenthesized_expression" => Ok(ast::ExpressionKind::Parenthesized(
                 ast::ParenthesizedExpression::try_from((node, source))?,
             )),
             ";" => {
-                Ok(ast::Expression::Empty)
+                Ok(ast::ExpressionKind::Empty)
             },
             "assignment_expression" => 
                 Ok(
-                    ast::Expression::Assignment(
+                    ast::ExpressionKind::Assignment(
                         ast::AssignmentExpression::try_from((node, source))?
                     )
                 ),
-            _ => Err(NodeConversionError::InvalidNodeType {
+            "cast_expression" => Ok(ast::ExpressionKind::Cast(ast::CastExpression::try_from((
+                node, source,
+            ))?)),
+            "pointer_expression" => Ok(ast::ExpressionKind::Pointer(
+                ast::PointerExpression::try_from((node, source))?,
+            )),
+            "sizeof_expression" => Ok(ast::ExpressionKind::SizeOf(
+                ast::SizeOfExpression::try_from((node, source))?,
+            )),
+            "alignof_expression" => Ok(ast::ExpressionKind::AlignOf(
+                ast::AlignOfExpression::try_from((node, source))?,
+            )),
+            "subscript_expression" => Ok(ast::ExpressionKind::Subscript(
+                ast::SubscriptExpression::try_from((node, source))?,
+            )),
+            "field_expression" => Ok(ast::ExpressionKind::Member(
+                ast::MemberExpression::try_from((node, source))?,
+            )),
+            _ => Err(unsupported(node).unwrap_or(NodeConversionError::InvalidNodeType {
                 expected: "call_expression | identifier | string_literal | binary_expression | parenthesized_expression"
                     .into(),
                 found: node.kind().into(),
-            }),
-        }
+            })),
+        }?;
+        Ok(ast::Expression::new(kind, source_location(node)))
     }
 }
 
@@ -276,6 +969,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::ExpressionStatement {
             })?;
         Ok(ExpressionStatement {
             expression: ast::Expression::try_from((&main_node, source))?,
+            location: source_location(node),
         })
     }
 }
@@ -299,6 +993,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::ReturnStatement {
             })?;
         Ok(ReturnStatement {
             expression: ast::Expression::try_from((&main_node, source))?,
+            location: source_location(node),
         })
     }
 }
@@ -326,37 +1021,54 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::Declaration {
             source,
         ))?;
 
-        let declarator = ast::Declarator::try_from((
-            named_children
-                .get("declarator")
-                .ok_or_else(|| NodeConversionError::MissingChild {
-                    parent: "declaration".into(),
-                    child: "declarator".into(),
-                })?,
-            source,
-        ))?;
+        let mut cursor = node.walk();
+        let declarators = node
+            .children_by_field_name("declarator", &mut cursor)
+            .map(|declarator| ast::Declarator::try_from((&declarator, source)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if declarators.is_empty() && !matches!(data_type, ast::DataType::Struct(_)) {
+            return Err(NodeConversionError::MissingChild {
+                parent: "declaration".into(),
+                child: "declarator".into(),
+            });
+        }
 
-        let storage_specifier = children_iter(node)
-            .find(|n| n.kind() == "storage_class_specifier")
-            .map(|n| {
-                let val = &source[n.start_byte()..n.end_byte()];
-                match val {
-                    "extern" => StorageClass::Extern,
-                    "auto" => StorageClass::Auto,
-                    _ => todo!(),
+        let storage_specifier = match children_iter(node).find(|n| n.kind() == "storage_class_specifier") {
+            None => StorageClass::Auto,
+            Some(specifier) => match &source[specifier.start_byte()..specifier.end_byte()] {
+                "extern" => StorageClass::Extern,
+                // `register` is only a hint to keep the variable in a register
+                "auto" | "register" => StorageClass::Auto,
+                "static" => StorageClass::Static,
+                _ => {
+                    return Err(NodeConversionError::Unsupported {
+                        construct: "inline and thread local specifiers",
+                        location: source_location(&specifier),
+                    });
                 }
-            })
-            .or_else(|| Some(StorageClass::Auto))
-            .unwrap();
+            },
+        };
 
         Ok(ast::Declaration {
             storage_class: storage_specifier,
             dtype: data_type,
-            declarator: Box::new(declarator),
+            declarators,
+            location: source_location(node),
         })
     }
 }
 
+/// `struct p { ... };` alone, a declaration of the struct without declarators.
+/// Its `;` is the next sibling of the specifier.
+fn struct_declaration(node: &Node, source: &str) -> Result<ast::Declaration, NodeConversionError> {
+    Ok(ast::Declaration {
+        storage_class: StorageClass::Auto,
+        dtype: ast::DataType::Struct(Rc::new(ast::StructSpecifier::try_from((node, source))?)),
+        declarators: vec![],
+        location: source_location(node),
+    })
+}
+
 impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::Statement {
     type Error = NodeConversionError;
 
@@ -380,12 +1092,13 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::Statement {
             "while_statement" => Ok(ast::Statement::WhileStatement(
                 ast::WhileStatement::try_from((node, source))?,
             )),
-            _ => Err(NodeConversionError::InvalidNodeType {
+            "struct_specifier" => Ok(ast::Statement::Declaration(struct_declaration(node, source)?)),
+            _ => Err(unsupported(node).unwrap_or(NodeConversionError::InvalidNodeType {
                 expected:
                     "exrpession_statement | return_statement | declaration | compound_statement | if_statement"
                         .into(),
                 found: node.kind().into(),
-            }),
+            })),
         }
     }
 }
@@ -407,8 +1120,10 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::CompoundStatement {
             items: children_iter(node)
                 .skip(1) // ignore opening {
                 .take(num_arguments) // ignore closing }
+                .filter(|c| c.kind() != ";")
                 .map(|c| ast::Statement::try_from((&c, source)))
                 .collect::<Result<Vec<_>, _>>()?,
+            location: source_location(node),
         })
     }
 }
@@ -434,6 +1149,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::IfStatement {
             condition: cond_child,
             body: Box::new(body_child),
             else_body: else_body_child,
+            location: source_location(node),
         })
     }
 }
@@ -455,33 +1171,139 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::WhileStatement {
         Ok(ast::WhileStatement {
             condition: cond_child,
             body: Box::new(body_child),
+            location: source_location(node),
         })
     }
 }
 
+/// Declarator nested in a pointer or function declarator. Abstract
+/// declarators may end without one, where a name would otherwise be.
+fn inner_declarator<'a>(node: &'a Node<'a>, source: &'a str) -> Result<ast::Declarator, NodeConversionError> {
+    match node.child_by_field_name("declarator") {
+        Some(declarator) => ast::Declarator::try_from((&declarator, source)),
+        None if node.kind().starts_with("abstract_") => Ok(ast::Declarator::Abstract),
+        None => Err(NodeConversionError::MissingChild {
+            parent: node.kind().into(),
+            child: "declarator".into(),
+        }),
+    }
+}
+
 impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::PointerDeclarator {
     type Error = NodeConversionError;
 
     fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
-        if node.kind() != "pointer_declarator" {
+        if node.kind() != "pointer_declarator" && node.kind() != "abstract_pointer_declarator" {
             return Err(NodeConversionError::InvalidNodeType {
                 expected: "pointer_declarator".into(),
                 found: node.kind().into(),
             });
         }
 
-        let main_node = node
-            .child(1)
-            .ok_or_else(|| NodeConversionError::MissingChild {
-                parent: node.kind().into(),
-                child: "1".into(),
-            })?;
         Ok(PointerDeclarator {
-            declarator: Box::new(ast::Declarator::try_from((&main_node, source))?),
+            declarator: Box::new(inner_declarator(node, source)?),
+        })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::ArrayDeclarator {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "array_declarator" && node.kind() != "abstract_array_declarator" {
+            return Err(NodeConversionError::InvalidNodeType {
+                expected: "array_declarator".into(),
+                found: node.kind().into(),
+            });
+        }
+
+        let size = node
+            .child_by_field_name("size")
+            .map(|size| ast::Expression::try_from((&size, source)))
+            .transpose()?;
+
+        Ok(ast::ArrayDeclarator {
+            declarator: Box::new(inner_declarator(node, source)?),
+            size,
         })
     }
 }
 
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::Designator {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        match node.kind() {
+            "field_identifier" => Ok(ast::Designator::Field(ast::Identifier(
+                source[node.start_byte()..node.end_byte()].to_owned(),
+            ))),
+            "field_designator" | "subscript_designator" => {
+                let inner = node.named_child(0).ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: node.kind().into(),
+                    child: "0".into(),
+                })?;
+                if node.kind() == "field_designator" {
+                    ast::Designator::try_from((&inner, source))
+                } else {
+                    Ok(ast::Designator::Index(ast::Expression::try_from((&inner, source))?))
+                }
+            }
+            _ => Err(NodeConversionError::InvalidNodeType {
+                expected: "one of 'field_designator' | 'subscript_designator'".into(),
+                found: node.kind().to_owned(),
+            }),
+        }
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::InitializerItem {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "initializer_pair" {
+            return Ok(ast::InitializerItem {
+                designators: vec![],
+                value: ast::Initializer::try_from((node, source))?,
+            });
+        }
+
+        let mut cursor = node.walk();
+        let designators = node
+            .children_by_field_name("designator", &mut cursor)
+            .map(|designator| ast::Designator::try_from((&designator, source)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let value = ast::Initializer::try_from((
+            &node
+                .child_by_field_name("value")
+                .ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: "initializer_pair".into(),
+                    child: "value".into(),
+                })?,
+            source,
+        ))?;
+
+        Ok(ast::InitializerItem { designators, value })
+    }
+}
+
+impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::Initializer {
+    type Error = NodeConversionError;
+
+    fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
+        if node.kind() != "initializer_list" {
+            return Ok(ast::Initializer::Expression(ast::Expression::try_from((node, source))?));
+        }
+
+        let items = children_iter(node)
+            .filter(|child| child.is_named() && child.kind() != "comment")
+            .map(|child| ast::InitializerItem::try_from((&child, source)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ast::Initializer::List(ast::InitializerList { items }))
+    }
+}
+
 impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::InitDeclarator {
     type Error = NodeConversionError;
 
@@ -495,7 +1317,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::InitDeclarator {
 
         let named_children = named_children_map(node);
 
-        let value = ast::Expression::try_from((
+        let value = ast::Initializer::try_from((
             named_children
                 .get("value")
                 .ok_or_else(|| NodeConversionError::MissingChild {
@@ -527,11 +1349,23 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::Declarator {
 
     fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
         match node.kind() {
-            "identifier" => Ok(ast::Declarator::Identifier(ast::Identifier::try_from((node, source))?)),
-            "function_declarator" => Ok(ast::Declarator::FunctionDeclarator(ast::FunctionDeclarator::try_from((node, source))?)),
-            "pointer_declarator" => Ok(ast::Declarator::PointerDeclarator(ast::PointerDeclarator::try_from((node, source))?)),
+            "identifier" | "field_identifier" => Ok(ast::Declarator::Identifier(
+                ast::Identifier::try_from((node, source))?,
+                source_location(node),
+            )),
+            "array_declarator" | "abstract_array_declarator" => Ok(ast::Declarator::ArrayDeclarator(ast::ArrayDeclarator::try_from((node, source))?)),
+            "function_declarator" | "abstract_function_declarator" => Ok(ast::Declarator::FunctionDeclarator(ast::FunctionDeclarator::try_from((node, source))?)),
+            "pointer_declarator" | "abstract_pointer_declarator" => Ok(ast::Declarator::PointerDeclarator(ast::PointerDeclarator::try_from((node, source))?)),
             "init_declarator" => Ok(ast::Declarator::InitDeclarator(ast::InitDeclarator::try_from((node, source))?)),
-            _ => Err(NodeConversionError::InvalidNodeType { expected: "one of 'identifier' | 'function_declarator' | 'init_declarator' | 'pointer_declarator'".into(), found: node.kind().to_owned() })
+            // The parentheses only group, as in `int (*f)(int)`
+            "parenthesized_declarator" | "abstract_parenthesized_declarator" => {
+                let inner = node.named_child(0).ok_or_else(|| NodeConversionError::MissingChild {
+                    parent: node.kind().into(),
+                    child: "0".into(),
+                })?;
+                ast::Declarator::try_from((&inner, source))
+            }
+            _ => Err(unsupported(node).unwrap_or(NodeConversionError::InvalidNodeType { expected: "one of 'identifier' | 'function_declarator' | 'init_declarator' | 'pointer_declarator' | 'array_declarator' | 'parenthesized_declarator'".into(), found: node.kind().to_owned() }))
         }
     }
 }
@@ -559,15 +1393,11 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::ParameterDeclaration {
             source,
         ))?;
 
-        let declarator = ast::Declarator::try_from((
-            named_children
-                .get("declarator")
-                .ok_or_else(|| NodeConversionError::MissingChild {
-                    parent: "parameter_declaration".into(),
-                    child: "type".into(),
-                })?,
-            source,
-        ))?;
+        // Prototypes may leave their parameters unnamed
+        let declarator = match named_children.get("declarator") {
+            Some(declarator) => ast::Declarator::try_from((declarator, source))?,
+            None => ast::Declarator::Abstract,
+        };
 
         Ok(ast::ParameterDeclaration {
             dtype: data_type,
@@ -597,7 +1427,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::FunctionDeclarator {
     type Error = NodeConversionError;
 
     fn try_from((node, source): (&'a Node<'a>, &'a str)) -> Result<Self, Self::Error> {
-        if node.kind() != "function_declarator" {
+        if node.kind() != "function_declarator" && node.kind() != "abstract_function_declarator" {
             return Err(NodeConversionError::InvalidNodeType {
                 expected: "function_declarator".into(),
                 found: node.kind().into(),
@@ -606,15 +1436,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::FunctionDeclarator {
 
         let named_children = named_children_map(node);
 
-        let declarator = ast::Declarator::try_from((
-            named_children
-                .get("declarator")
-                .ok_or_else(|| NodeConversionError::MissingChild {
-                    parent: "function_declarator".into(),
-                    child: "declarator".into(),
-                })?,
-            source,
-        ))?;
+        let declarator = inner_declarator(node, source)?;
 
         let parameters =
             named_children
@@ -625,12 +1447,21 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::FunctionDeclarator {
                 })?;
         let num_parameters = parameters.child_count() - 2;
 
-        let parameters_vec = children_iter(parameters)
+        let mut parameters_vec = children_iter(parameters)
             .skip(1)
             .take(num_parameters)
             .step_by(2)
             .map(|n| ast::FunctionParameter::try_from((&n, source)))
             .collect::<Result<Vec<_>, _>>()?;
+        let has_prototype = !parameters_vec.is_empty();
+
+        // A sole unnamed `void` stands for an empty parameter list
+        if let [ast::FunctionParameter::ParameterDeclaration(pd)] = parameters_vec.as_slice()
+            && matches!(pd.dtype, ast::DataType::Void)
+            && matches!(pd.declarator.as_ref(), ast::Declarator::Abstract)
+        {
+            parameters_vec.clear();
+        }
 
         let is_variadic = parameters_vec
             .iter()
@@ -641,6 +1472,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::FunctionDeclarator {
             declarator: Box::new(declarator),
             parameters: parameters_vec,
             is_variadic,
+            has_prototype,
         })
     }
 }
@@ -675,7 +1507,7 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::FunctionDefinition {
             });
         }
 
-        let named_children = named_children_map(&node);
+        let named_children = named_children_map(node);
 
         let return_type = ast::DataType::try_from((
             named_children
@@ -726,10 +1558,11 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::TopLevelItem {
             "declaration" => Ok(ast::TopLevelItem::Declaration(ast::Declaration::try_from(
                 (node, source),
             )?)),
-            _ => Err(NodeConversionError::InvalidNodeType {
+            "struct_specifier" => Ok(ast::TopLevelItem::Declaration(struct_declaration(node, source)?)),
+            _ => Err(unsupported(node).unwrap_or(NodeConversionError::InvalidNodeType {
                 expected: "one of 'function_definition' | 'declaration'".into(),
                 found: node.kind().to_owned(),
-            }),
+            })),
         }
     }
 }
@@ -745,16 +1578,42 @@ impl<'a> TryFrom<(&'a Node<'a>, &'a str)> for ast::TranslationUnit {
             });
         }
 
-        return Ok(ast::TranslationUnit {
-            items: children_iter(&node)
-                .map(|n| TopLevelItem::try_from((&n, source)))
-                .collect::<Result<Vec<_>, _>>()?,
-        });
+        // There is no preprocessor, only the headers the compiler provides
+        // itself may be included
+        let mut items = vec![];
+        for n in children_iter(node) {
+            // Ends a struct declaration, see `struct_declaration`
+            if n.kind() == ";" {
+                continue;
+            }
+            if n.kind() == "preproc_include" {
+                let path = n.child_by_field_name("path").ok_or_else(|| {
+                    NodeConversionError::MissingChild {
+                        parent: n.kind().into(),
+                        child: "path".into(),
+                    }
+                })?;
+                match &source[path.start_byte()..path.end_byte()] {
+                    "<stdarg.h>" => continue,
+                    val => {
+                        return Err(NodeConversionError::InvalidSourceValue {
+                            expected: "<stdarg.h>".into(),
+                            found: val.into(),
+                        });
+                    }
+                }
+            }
+            items.push(TopLevelItem::try_from((&n, source))?);
+        }
+
+        Ok(ast::TranslationUnit { items })
     }
 }
 
 impl Parser for TreeSitterParser {
-    fn parse(&self, source_code: &str) -> ast::TranslationUnit {
+    type Error = NodeConversionError;
+
+    fn parse(&self, source_code: &str) -> Result<ast::TranslationUnit, NodeConversionError> {
         let mut parser = TsParser::new();
         parser
             .set_language(&tree_sitter_c::LANGUAGE.into())
@@ -762,6 +1621,6 @@ impl Parser for TreeSitterParser {
         let tree = parser.parse(source_code, None).unwrap();
         tree.print_dot_graph(&fs::File::create("foo.dot").unwrap().as_raw_fd());
         let root_node = tree.root_node();
-        ast::TranslationUnit::try_from((&root_node, source_code)).unwrap()
+        ast::TranslationUnit::try_from((&root_node, source_code))
     }
 }
@@ -1,5 +1,7 @@
 use crate::parsing::ast;
 
 pub trait Parser {
-    fn parse(&self, source_code: &str) -> ast::TranslationUnit;
+    type Error: std::error::Error;
+
+    fn parse(&self, source_code: &str) -> Result<ast::TranslationUnit, Self::Error>;
 }
@@ -0,0 +1,277 @@
+use crate::{
+    common::{Signedness, Width},
+    parsing::ast,
+    semantic_analysis::{Symbol, SymbolKind, SymbolTable, SymbolType, symbol_table::table::SemanticError},
+};
+
+impl SymbolType {
+    pub fn is_floating(&self) -> bool {
+        matches!(self, Self::Float | Self::Double)
+    }
+
+    /// Integer conversion rank, C11 6.3.1.1
+    fn rank(&self) -> usize {
+        match self {
+            Self::Char(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) => 3,
+            Self::Long(_) => 4,
+            Self::LongLong(_) => 5,
+            Self::Float
+            | Self::Double
+            | Self::Void
+            | Self::Pointer(_)
+            | Self::Array(..)
+            | Self::Struct(_)
+            | Self::Function { .. } => panic!("Only integers have an integer rank"),
+        }
+    }
+
+    fn with_signedness(&self, signedness: Signedness) -> Self {
+        match self {
+            Self::Char(_) => Self::Char(signedness),
+            Self::Short(_) => Self::Short(signedness),
+            Self::Int(_) => Self::Int(signedness),
+            Self::Long(_) => Self::Long(signedness),
+            Self::LongLong(_) => Self::LongLong(signedness),
+            Self::Float
+            | Self::Double
+            | Self::Void
+            | Self::Pointer(_)
+            | Self::Array(..)
+            | Self::Struct(_)
+            | Self::Function { .. } => self.clone(),
+        }
+    }
+
+    /// Integer promotions: everything ranked below int is computed as int,
+    /// since int can represent every char and short value.
+    pub fn promote(&self) -> Self {
+        match self {
+            Self::Char(_) | Self::Short(_) => Self::Int(Signedness::Signed),
+            _ => self.clone(),
+        }
+    }
+
+    /// Default argument promotions, applied to arguments without a
+    /// prototype parameter such as the variadic ones
+    pub fn default_promote(&self) -> Self {
+        match self {
+            Self::Float => Self::Double,
+            _ => self.promote(),
+        }
+    }
+
+    /// Usual arithmetic conversions, C11 6.3.1.8. A pointer operand keeps
+    /// its type, and the difference of two pointers is a long. Lowering
+    /// scales both to the size of the pointee. Arrays count as the pointer
+    /// they decay to.
+    pub fn common_type(left: &SymbolType, right: &SymbolType) -> SymbolType {
+        let (left, right) = (&left.decay(), &right.decay());
+        match (left, right) {
+            (Self::Pointer(_), Self::Pointer(_)) => return Self::Long(Signedness::Signed),
+            (Self::Pointer(_), _) => return left.clone(),
+            (_, Self::Pointer(_)) => return right.clone(),
+            _ => (),
+        }
+        for floating in [Self::Double, Self::Float] {
+            if *left == floating || *right == floating {
+                return floating;
+            }
+        }
+        let (left, right) = (left.promote(), right.promote());
+        if left == right {
+            return left;
+        }
+        if left.signedness() == right.signedness() {
+            return if left.rank() >= right.rank() { left } else { right };
+        }
+
+        let (unsigned, signed) = if left.signedness().is_signed() {
+            (right, left)
+        } else {
+            (left, right)
+        };
+
+        if unsigned.rank() >= signed.rank() {
+            unsigned
+        } else if signed.size() > unsigned.size() {
+            // Signed type can represent all values of the unsigned one
+            signed
+        } else {
+            signed.with_signedness(Signedness::Unsigned)
+        }
+    }
+
+    /// Converts an integer constant to this type, C11 6.3.1.3
+    pub fn wrap(&self, value: i64) -> i64 {
+        Width::from_type(self).extend(value, self.signedness())
+    }
+
+    pub fn from_type_name(symbol_table: &SymbolTable, type_name: &ast::TypeName) -> Self {
+        Self::try_from_type_name(symbol_table, type_name).expect("Type names are checked by the type checker")
+    }
+
+    pub fn try_from_type_name(symbol_table: &SymbolTable, type_name: &ast::TypeName) -> Result<Self, SemanticError> {
+        let base = SymbolType::try_from((&type_name.dtype, symbol_table))?;
+        SymbolType::try_from((type_name.declarator.as_ref(), &base, symbol_table))
+    }
+}
+
+/// Type of an integer constant, C11 6.4.4.1: the first type of its
+/// candidate list that can represent the value. A sign read as part of
+/// the constant is applied after, so it does not change the type.
+pub fn number_literal_type(nl: &ast::NumberLiteral) -> SymbolType {
+    if nl.is_floating() {
+        return if nl.is_single() {
+            SymbolType::Float
+        } else {
+            SymbolType::Double
+        };
+    }
+    let value = nl
+        .magnitude()
+        .unwrap_or_else(|| panic!("Integer constant {} is too large", nl.0));
+    let fits_int = value <= i32::MAX as u64;
+    let fits_uint = value <= u32::MAX as u64;
+    let fits_long = value <= i64::MAX as u64;
+    let may_be_unsigned = nl.is_unsigned() || !nl.is_decimal();
+
+    match (nl.is_unsigned(), nl.is_long()) {
+        (false, false) if fits_int => SymbolType::Int(Signedness::Signed),
+        (true, false) if fits_uint => SymbolType::Int(Signedness::Unsigned),
+        (false, false) if fits_uint && may_be_unsigned => SymbolType::Int(Signedness::Unsigned),
+        (false, _) if fits_long => SymbolType::Long(Signedness::Signed),
+        _ if may_be_unsigned => SymbolType::Long(Signedness::Unsigned),
+        _ => panic!("Integer constant {} is too large", nl.0),
+    }
+}
+
+impl Symbol {
+    /// Type of an identifier naming the symbol. Function designators decay
+    /// to a pointer to the function.
+    pub fn identifier_type(&self) -> SymbolType {
+        match &self.kind {
+            SymbolKind::Function {
+                parameters,
+                is_variadic,
+                has_prototype,
+                ..
+            } => SymbolType::Pointer(Box::new(SymbolType::Function {
+                return_type: Box::new(self.type_info.clone()),
+                parameters: parameters.clone(),
+                is_variadic: *is_variadic,
+                has_prototype: *has_prototype,
+            })),
+            SymbolKind::Variable { .. } => self.type_info.clone(),
+        }
+    }
+}
+
+impl SymbolType {
+    /// Type of the value of a binary operation on operands of these types
+    pub fn binary_result(op: &ast::BinOp, left: &SymbolType, right: &SymbolType) -> SymbolType {
+        match op {
+            ast::BinOp::Gt | ast::BinOp::Lt | ast::BinOp::Ge | ast::BinOp::Le | ast::BinOp::Eq | ast::BinOp::Ne => {
+                SymbolType::Int(Signedness::Signed)
+            }
+            ast::BinOp::ShiftLeft | ast::BinOp::ShiftRight => left.promote(),
+            ast::BinOp::Plus | ast::BinOp::Minus | ast::BinOp::Mul | ast::BinOp::Div | ast::BinOp::Rem => {
+                SymbolType::common_type(left, right)
+            }
+        }
+    }
+}
+
+/// An expression of function type, C11 6.3.2.1: a function name, or the
+/// dereference of a function pointer
+fn is_function_designator(symbol_table: &SymbolTable, expression: &ast::Expression) -> bool {
+    match &expression.kind {
+        ast::ExpressionKind::Identifier(id) => {
+            symbol_table.query(&id.0).is_some_and(|symbol| matches!(symbol.kind, SymbolKind::Function { .. }))
+        }
+        ast::ExpressionKind::Parenthesized(pe) => is_function_designator(symbol_table, &pe.expression),
+        ast::ExpressionKind::Pointer(pe) => {
+            matches!(pe.op, ast::PointerOp::Dereference)
+                && expression_type(symbol_table, &pe.argument).is_some_and(|t| t.callee_function().is_some())
+        }
+        _ => false,
+    }
+}
+
+/// Type of an expression worked out from the symbol table alone, for the
+/// constant expressions laid out before the type checker runs, such as
+/// array lengths. None for the expressions the checker rejects.
+pub(super) fn expression_type(symbol_table: &SymbolTable, expression: &ast::Expression) -> Option<SymbolType> {
+    let expression_type = match &expression.kind {
+        ast::ExpressionKind::Identifier(id) => symbol_table.query(&id.0)?.identifier_type(),
+        ast::ExpressionKind::Binary(bin) => SymbolType::binary_result(
+            &bin.op,
+            &expression_type(symbol_table, &bin.left)?,
+            &expression_type(symbol_table, &bin.right)?,
+        ),
+        ast::ExpressionKind::Assignment(asn) => match &asn.lvalue {
+            ast::LValue::Identifier(id) => symbol_table.query(&id.0)?.type_info,
+            ast::LValue::Dereference(pointer) => match expression_type(symbol_table, pointer)?.decay() {
+                SymbolType::Pointer(pointee) => *pointee,
+                _ => return None,
+            },
+            ast::LValue::Subscript(subscript) => subscript_type(symbol_table, subscript)?,
+            ast::LValue::Member(member) => member_type(symbol_table, member)?,
+        },
+        ast::ExpressionKind::Call(cl) => match expression_type(symbol_table, &cl.function)?.callee_function() {
+            Some(SymbolType::Function { return_type, .. }) => return_type.as_ref().clone(),
+            _ => return None,
+        },
+        ast::ExpressionKind::Parenthesized(pe) => expression_type(symbol_table, &pe.expression)?,
+        ast::ExpressionKind::NumberLiteral(nl) => number_literal_type(nl),
+        ast::ExpressionKind::StringLiteral(_) => SymbolType::Pointer(Box::new(SymbolType::Char(Signedness::Signed))),
+        ast::ExpressionKind::Cast(cast) => SymbolType::try_from_type_name(symbol_table, &cast.type_name).ok()?,
+        ast::ExpressionKind::Pointer(pe) => {
+            let argument_type = expression_type(symbol_table, &pe.argument)?;
+            let passes_function_pointer = match pe.op {
+                ast::PointerOp::AddressOf => is_function_designator(symbol_table, &pe.argument),
+                ast::PointerOp::Dereference => argument_type.callee_function().is_some(),
+            };
+            match (&pe.op, argument_type) {
+                (_, function_pointer) if passes_function_pointer => function_pointer,
+                (ast::PointerOp::AddressOf, argument_type) => SymbolType::Pointer(Box::new(argument_type)),
+                (ast::PointerOp::Dereference, SymbolType::Array(element, _)) => *element,
+                (ast::PointerOp::Dereference, SymbolType::Pointer(pointee)) => *pointee,
+                (ast::PointerOp::Dereference, _) => return None,
+            }
+        }
+        ast::ExpressionKind::SizeOf(_) | ast::ExpressionKind::AlignOf(_) => SymbolType::Long(Signedness::Unsigned),
+        ast::ExpressionKind::Subscript(subscript) => subscript_type(symbol_table, subscript)?,
+        ast::ExpressionKind::Member(member) => member_type(symbol_table, member)?,
+        ast::ExpressionKind::Variadic(ast::VariadicExpression::Arg { type_name, .. }) => {
+            SymbolType::try_from_type_name(symbol_table, type_name).ok()?
+        }
+        // va_start, va_end and va_copy have no value
+        ast::ExpressionKind::Variadic(_) | ast::ExpressionKind::Empty => return None,
+    };
+    Some(expression_type)
+}
+
+/// Type of the element `array[index]` designates, where either operand
+/// may be the pointer
+fn subscript_type(symbol_table: &SymbolTable, subscript: &ast::SubscriptExpression) -> Option<SymbolType> {
+    let array_type = expression_type(symbol_table, &subscript.array)?.decay();
+    let index_type = expression_type(symbol_table, &subscript.index)?.decay();
+    match (array_type, index_type) {
+        (SymbolType::Pointer(element), _) | (_, SymbolType::Pointer(element)) => Some(*element),
+        _ => None,
+    }
+}
+
+fn member_type(symbol_table: &SymbolTable, member: &ast::MemberExpression) -> Option<SymbolType> {
+    let object_type = match (expression_type(symbol_table, &member.object)?, member.through_pointer) {
+        (SymbolType::Pointer(pointee), true) => *pointee,
+        (object_type, false) => object_type,
+        _ => return None,
+    };
+    match object_type {
+        SymbolType::Struct(struct_type) => Some(struct_type.member(&member.member.0)?.member_type.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+use crate::common::SourceLocation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in the source, errors stop the compilation
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Function the problem was found in
+    pub function: Option<String>,
+    pub location: Option<SourceLocation>,
+    pub message: String,
+    /// Other places involved, such as the previous declaration of a name
+    pub notes: Vec<(SourceLocation, String)>,
+}
+
+impl Diagnostic {
+    pub fn error(function: Option<String>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            function,
+            location: None,
+            message,
+            notes: vec![],
+        }
+    }
+
+    pub fn warning(function: Option<String>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            function,
+            location: None,
+            message,
+            notes: vec![],
+        }
+    }
+
+    pub fn at(mut self, location: SourceLocation) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    pub fn with_note(mut self, location: SourceLocation, note: &str) -> Self {
+        self.notes.push((location, note.into()));
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(function) = &self.function {
+            write!(f, "in function '{}': ", function)?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)?;
+        for (location, note) in &self.notes {
+            write!(f, "\n{}: note: {}", location, note)?;
+        }
+        Ok(())
+    }
+}
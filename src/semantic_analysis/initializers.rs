@@ -0,0 +1,344 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    ir::unescape,
+    parsing::ast,
+    semantic_analysis::{
+        SymbolTable, SymbolType,
+        layout::array_length,
+        symbol_table::table::SemanticError,
+    },
+};
+
+/// What an initializer stores at an offset of the object it initializes
+#[derive(Debug)]
+pub enum InitialValue<'a> {
+    /// An expression converted to the scalar type at the offset
+    Scalar(SymbolType, &'a ast::Expression),
+    /// A struct copied whole from an expression of its type
+    Struct(SymbolType, &'a ast::Expression),
+    /// Characters of a string literal filling a char array, with the null
+    /// terminator when the array has room for it
+    String(Vec<u8>),
+}
+
+impl InitialValue<'_> {
+    /// Bytes of the object the value covers
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Scalar(value_type, _) | Self::Struct(value_type, _) => value_type.size(),
+            Self::String(bytes) => bytes.len(),
+        }
+    }
+}
+
+/// An initializer of an aggregate, read as the values it stores. The
+/// bytes no value covers are zero, C11 6.7.9p21.
+#[derive(Debug, Default)]
+pub struct FlatInitializer<'a> {
+    /// Values by offset, a later initializer of the same object replaces
+    /// the earlier one
+    pub values: BTreeMap<usize, InitialValue<'a>>,
+    /// Elements the initializer gives an array, which is its length when
+    /// declared with `[]`
+    pub length: usize,
+    pub errors: Vec<String>,
+}
+
+/// Reads the initializer of an object of type `object_type`, following the
+/// brace elision and designators of C11 6.7.9. `value_type` gives the type
+/// of an expression that may initialize a whole struct, None for one that
+/// is invalid.
+pub fn flatten<'a>(
+    symbol_table: &SymbolTable,
+    object_type: &SymbolType,
+    initializer: &'a ast::Initializer,
+    value_type: &mut dyn FnMut(&ast::Expression) -> Option<SymbolType>,
+) -> FlatInitializer<'a> {
+    let mut flattener = Flattener {
+        symbol_table,
+        value_type,
+        value_types: HashMap::new(),
+        flat: FlatInitializer::default(),
+    };
+    let length = match initializer {
+        ast::Initializer::List(list) => flattener.list(object_type, 0, &list.items),
+        ast::Initializer::Expression(_) => {
+            flattener.object(object_type, 0, initializer);
+            flattener.flat.values.values().map(InitialValue::size).sum()
+        }
+    };
+    flattener.flat.length = length;
+    flattener.flat
+}
+
+struct Flattener<'a, 't> {
+    symbol_table: &'t SymbolTable,
+    value_type: &'t mut dyn FnMut(&ast::Expression) -> Option<SymbolType>,
+    /// Types asked for so far, each expression is only typed once
+    value_types: HashMap<*const ast::Expression, Option<SymbolType>>,
+    flat: FlatInitializer<'a>,
+}
+
+impl<'a> Flattener<'a, '_> {
+    fn error(&mut self, message: String) {
+        self.flat.errors.push(message);
+    }
+
+    fn value_type(&mut self, expression: &ast::Expression) -> Option<SymbolType> {
+        let key = expression as *const _;
+        if let Some(known) = self.value_types.get(&key) {
+            return known.clone();
+        }
+        let value_type = (self.value_type)(expression);
+        self.value_types.insert(key, value_type.clone());
+        value_type
+    }
+
+    /// Stores a value, dropping the ones it overlaps
+    fn store(&mut self, offset: usize, value: InitialValue<'a>) {
+        self.clear(offset, value.size());
+        self.flat.values.insert(offset, value);
+    }
+
+    /// Drops the values overlapping `size` bytes at `offset`, which are
+    /// zero again
+    fn clear(&mut self, offset: usize, size: usize) {
+        let end = offset + size;
+        let overlapped: Vec<usize> = self
+            .flat
+            .values
+            .range(..end)
+            .filter(|(start, previous)| *start + previous.size() > offset)
+            .map(|(start, _)| *start)
+            .collect();
+        for start in overlapped {
+            self.flat.values.remove(&start);
+        }
+    }
+
+    /// Initializes the object at `offset` with an initializer of its own
+    fn object(&mut self, object_type: &SymbolType, offset: usize, initializer: &'a ast::Initializer) {
+        match (object_type, initializer) {
+            (SymbolType::Array(..), ast::Initializer::Expression(expression)) => {
+                match string_literal(object_type, expression) {
+                    Some(literal) => self.string(object_type, offset, literal),
+                    None => self.error("array initializer must be an initializer list".into()),
+                }
+            }
+            (SymbolType::Struct(_), ast::Initializer::Expression(expression)) => {
+                match self.value_type(expression) {
+                    Some(value_type) if value_type != *object_type => self.error(format!(
+                        "initializing '{}' with an expression of incompatible type '{}'",
+                        object_type, value_type
+                    )),
+                    _ => self.store(offset, InitialValue::Struct(object_type.clone(), expression)),
+                }
+            }
+            (SymbolType::Array(..) | SymbolType::Struct(_), ast::Initializer::List(list)) => {
+                self.list(object_type, offset, &list.items);
+            }
+            _ => match initializer.scalar() {
+                Ok(Some(expression)) => {
+                    self.store(offset, InitialValue::Scalar(object_type.clone(), expression))
+                }
+                Ok(None) => self.clear(offset, object_type.size()),
+                Err(ast::ScalarInitializerError::Designator) => self.error(format!(
+                    "designator in initializer for scalar type '{}'",
+                    object_type
+                )),
+                Err(ast::ScalarInitializerError::ExcessElements) => self.error(format!(
+                    "excess elements in initializer for scalar type '{}'",
+                    object_type
+                )),
+            },
+        }
+    }
+
+    /// Initializes an aggregate from the items of its braces, giving the
+    /// elements they reach
+    fn list(&mut self, object_type: &SymbolType, offset: usize, items: &'a [ast::InitializerItem]) -> usize {
+        let mut position = 0;
+        let length = self.fill(object_type, offset, items, &mut position, true, None);
+        if position < items.len() {
+            let kind = match object_type {
+                SymbolType::Struct(_) => "struct",
+                _ => "array",
+            };
+            self.error(format!("excess elements in {} initializer", kind));
+        }
+        length
+    }
+
+    /// Initializes the subobjects of an aggregate with items from
+    /// `position` on. Without braces of its own, as in the inner arrays of
+    /// `int a[2][2] = {1, 2, 3, 4}`, the aggregate takes the items it has
+    /// room for and stops at a designator, which belongs to the braces
+    /// around it. `designators` are the ones left to apply to the first
+    /// item, after the enclosing aggregates applied theirs.
+    fn fill(
+        &mut self,
+        object_type: &SymbolType,
+        offset: usize,
+        items: &'a [ast::InitializerItem],
+        position: &mut usize,
+        braced: bool,
+        mut designators: Option<&'a [ast::Designator]>,
+    ) -> usize {
+        let mut next = 0;
+        let mut length = 0;
+        while let Some(item) = items.get(*position) {
+            let designators = match designators.take() {
+                Some(designators) => designators,
+                None if braced || item.designators.is_empty() => item.designators.as_slice(),
+                None => break,
+            };
+            let index = match designators.first() {
+                Some(designator) => match self.designated(object_type, designator) {
+                    Some(index) => index,
+                    None => {
+                        *position += 1;
+                        continue;
+                    }
+                },
+                None => next,
+            };
+            let Some((member_type, member_offset)) = subobject(object_type, offset, index) else {
+                break;
+            };
+
+            if designators.len() > 1 {
+                if member_type.is_aggregate() {
+                    let rest = &designators[1..];
+                    self.fill(&member_type, member_offset, items, position, false, Some(rest));
+                } else {
+                    self.error(format!(
+                        "designator in initializer for scalar type '{}'",
+                        member_type
+                    ));
+                    *position += 1;
+                }
+            } else if self.initializes_part(&member_type, &item.value) {
+                self.fill(&member_type, member_offset, items, position, false, Some(&[]));
+            } else {
+                self.object(&member_type, member_offset, &item.value);
+                *position += 1;
+            }
+            next = index + 1;
+            length = length.max(next);
+        }
+        length
+    }
+
+    /// Whether an item is the first of the ones initializing an aggregate
+    /// without braces, rather than the whole of it
+    fn initializes_part(&mut self, object_type: &SymbolType, initializer: &ast::Initializer) -> bool {
+        let ast::Initializer::Expression(expression) = initializer else {
+            return false;
+        };
+        match object_type {
+            SymbolType::Array(..) => string_literal(object_type, expression).is_none(),
+            SymbolType::Struct(_) => self
+                .value_type(expression)
+                .is_some_and(|value_type| value_type != *object_type),
+            _ => false,
+        }
+    }
+
+    /// Index of the subobject a designator names
+    fn designated(&mut self, object_type: &SymbolType, designator: &ast::Designator) -> Option<usize> {
+        match (object_type, designator) {
+            (SymbolType::Struct(struct_type), ast::Designator::Field(field)) => {
+                let position = struct_type
+                    .members()
+                    .and_then(|members| members.iter().position(|member| member.name == field.0));
+                if position.is_none() {
+                    self.error(format!(
+                        "field designator '{}' does not refer to any field in type '{}'",
+                        field.0, object_type
+                    ));
+                }
+                position
+            }
+            (SymbolType::Array(_, length), ast::Designator::Index(index)) => {
+                let index = match array_length(self.symbol_table, index) {
+                    Ok(index) => index,
+                    Err(SemanticError::NegativeArraySize) => {
+                        self.error("array designator value is negative".into());
+                        return None;
+                    }
+                    Err(_) => {
+                        self.error("expression is not an integer constant expression".into());
+                        return None;
+                    }
+                };
+                match length {
+                    Some(length) if index >= *length => {
+                        self.error(format!(
+                            "array designator index ({}) exceeds array bounds ({})",
+                            index, length
+                        ));
+                        None
+                    }
+                    _ => Some(index),
+                }
+            }
+            (_, ast::Designator::Field(field)) => {
+                self.error(format!(
+                    "field designator '{}' used for non-struct type '{}'",
+                    field.0, object_type
+                ));
+                None
+            }
+            (_, ast::Designator::Index(_)) => {
+                self.error(format!(
+                    "array designator used for non-array type '{}'",
+                    object_type
+                ));
+                None
+            }
+        }
+    }
+
+    /// Initializes a char array with a string literal, C11 6.7.9p14. The
+    /// null terminator is left out when only it does not fit.
+    fn string(&mut self, array_type: &SymbolType, offset: usize, literal: &ast::StringLiteral) {
+        let mut bytes = unescape(&literal.0);
+        bytes.push(0);
+        if let SymbolType::Array(_, Some(length)) = array_type {
+            if bytes.len() > length + 1 {
+                self.error("initializer-string for char array is too long".into());
+            }
+            bytes.truncate(*length);
+        }
+        self.store(offset, InitialValue::String(bytes));
+    }
+}
+
+/// Subobject `index` of an aggregate at `offset`, with the offset it is at
+fn subobject(object_type: &SymbolType, offset: usize, index: usize) -> Option<(SymbolType, usize)> {
+    match object_type {
+        SymbolType::Array(element, length) if length.is_none_or(|length| index < length) => {
+            Some((element.as_ref().clone(), offset + index * element.size()))
+        }
+        SymbolType::Struct(struct_type) => {
+            let member = struct_type.members()?.get(index)?;
+            Some((member.member_type.clone(), offset + member.offset))
+        }
+        _ => None,
+    }
+}
+
+/// The string literal an expression is when it initializes a char array
+fn string_literal<'e>(array_type: &SymbolType, expression: &'e ast::Expression) -> Option<&'e ast::StringLiteral> {
+    let SymbolType::Array(element, _) = array_type else {
+        return None;
+    };
+    match &expression.kind {
+        ast::ExpressionKind::StringLiteral(literal) if matches!(element.as_ref(), SymbolType::Char(_)) => {
+            Some(literal)
+        }
+        ast::ExpressionKind::Parenthesized(pe) => string_literal(array_type, &pe.expression),
+        _ => None,
+    }
+}
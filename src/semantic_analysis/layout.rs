@@ -0,0 +1,145 @@
+use crate::{
+    common::Width,
+    ir::nodes::Op,
+    parsing::ast,
+    semantic_analysis::{
+        SymbolTable, SymbolType,
+        conversions::expression_type,
+        symbol_table::table::{Member, SemanticError, StructLayout},
+    },
+};
+
+impl SymbolType {
+    /// Size in bytes, as laid out by the aarch64 Darwin ABI
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Char(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) | Self::Float => 4,
+            Self::Long(_) | Self::LongLong(_) | Self::Double | Self::Pointer(_) => 8,
+            // `sizeof(void)` is 1 in GNU C
+            Self::Void => 1,
+            Self::Array(element, length) => element.size() * length.unwrap_or(0),
+            Self::Struct(struct_type) => struct_type.layout().map_or(0, |layout| layout.size),
+            Self::Function { .. } => panic!("Functions have no size"),
+        }
+    }
+
+    /// Scalars are aligned to their own size, aggregates to their most
+    /// aligned element
+    pub fn alignment(&self) -> usize {
+        match self {
+            Self::Array(element, _) => element.alignment(),
+            Self::Struct(struct_type) => struct_type.layout().map_or(1, |layout| layout.alignment),
+            _ => self.size(),
+        }
+    }
+}
+
+/// Places members in order, each at the next offset aligned for it. The
+/// size is rounded up to the alignment so that arrays of the struct keep
+/// every member aligned.
+pub fn struct_layout(members: Vec<(String, SymbolType)>) -> StructLayout {
+    let mut offset: usize = 0;
+    let mut alignment: usize = 1;
+    let members = members
+        .into_iter()
+        .map(|(name, member_type)| {
+            let member_alignment = member_type.alignment();
+            offset = offset.next_multiple_of(member_alignment);
+            alignment = alignment.max(member_alignment);
+            let member = Member {
+                name,
+                offset,
+                member_type,
+            };
+            offset += member.member_type.size();
+            member
+        })
+        .collect();
+    StructLayout {
+        members,
+        size: offset.next_multiple_of(alignment),
+        alignment,
+    }
+}
+
+/// Length of an array, which must be an integer constant expression since
+/// variable length arrays are not supported
+pub fn array_length(symbol_table: &SymbolTable, size: &ast::Expression) -> Result<usize, SemanticError> {
+    // Checked before typing the size, which may name undeclared variables
+    if !is_constant_form(size) {
+        return Err(SemanticError::VariableLengthArray);
+    }
+    let size_type = expression_type(symbol_table, size).ok_or(SemanticError::VariableLengthArray)?;
+    if size_type.is_floating() || matches!(size_type, SymbolType::Pointer(_)) {
+        return Err(SemanticError::NonIntegerArraySize(size_type));
+    }
+    let length = integer_constant(symbol_table, size).ok_or(SemanticError::VariableLengthArray)?;
+    match size_type.signedness().is_signed() && length < 0 {
+        true => Err(SemanticError::NegativeArraySize),
+        false => Ok(length as usize),
+    }
+}
+
+/// Expressions made only of the operations of a constant expression
+fn is_constant_form(expression: &ast::Expression) -> bool {
+    match &expression.kind {
+        ast::ExpressionKind::NumberLiteral(_) | ast::ExpressionKind::SizeOf(_) | ast::ExpressionKind::AlignOf(_) => true,
+        ast::ExpressionKind::Parenthesized(pe) => is_constant_form(&pe.expression),
+        ast::ExpressionKind::Cast(cast) => is_constant_form(&cast.value),
+        ast::ExpressionKind::Binary(bin) => is_constant_form(&bin.left) && is_constant_form(&bin.right),
+        _ => false,
+    }
+}
+
+/// Value of an integer constant expression, None for anything else and
+/// for a division by zero
+fn integer_constant(symbol_table: &SymbolTable, expression: &ast::Expression) -> Option<i64> {
+    let result_type = expression_type(symbol_table, expression)?;
+    match &expression.kind {
+        ast::ExpressionKind::NumberLiteral(nl) if !nl.is_floating() => Some(result_type.wrap(nl.value())),
+        ast::ExpressionKind::SizeOf(so) => {
+            let operand_type = match &so.operand {
+                ast::SizeOfOperand::Type(type_name) => SymbolType::try_from_type_name(symbol_table, type_name).ok()?,
+                ast::SizeOfOperand::Expression(operand) => expression_type(symbol_table, operand)?,
+            };
+            match operand_type {
+                SymbolType::Function { .. } => None,
+                _ => Some(operand_type.size() as i64),
+            }
+        }
+        ast::ExpressionKind::AlignOf(ao) => {
+            Some(SymbolType::try_from_type_name(symbol_table, &ao.type_name).ok()?.alignment() as i64)
+        }
+        ast::ExpressionKind::Parenthesized(pe) => integer_constant(symbol_table, &pe.expression),
+        ast::ExpressionKind::Cast(cast) if !result_type.is_floating() => {
+            let value_type = expression_type(symbol_table, &cast.value)?;
+            if value_type.is_floating() {
+                return None;
+            }
+            Some(result_type.wrap(integer_constant(symbol_table, &cast.value)?))
+        }
+        ast::ExpressionKind::Binary(bin) => {
+            let left_type = expression_type(symbol_table, &bin.left)?;
+            let right_type = expression_type(symbol_table, &bin.right)?;
+            if left_type.is_floating() || right_type.is_floating() {
+                return None;
+            }
+            let operation_type = match bin.op {
+                ast::BinOp::ShiftLeft | ast::BinOp::ShiftRight => left_type.promote(),
+                _ => SymbolType::common_type(&left_type, &right_type),
+            };
+            let left = integer_constant(symbol_table, &bin.left)?;
+            let right = integer_constant(symbol_table, &bin.right)?;
+            let op = Op::from_binop(&bin.op, operation_type.signedness());
+            let result = op.apply_constant(left, right, Width::from_type(&operation_type))?;
+            Some(result_type.wrap(result))
+        }
+        _ => None,
+    }
+}
+
+pub fn align_of(symbol_table: &SymbolTable, align_of: &ast::AlignOfExpression) -> u64 {
+    SymbolType::from_type_name(symbol_table, &align_of.type_name).alignment() as u64
+}
@@ -1,3 +1,18 @@
+mod conversions;
+mod diagnostics;
+mod initializers;
+mod layout;
+mod reachability;
 mod symbol_table;
+mod type_checker;
 
-pub use symbol_table::{SymbolTable, SymbolTableRef, table::SymbolKind, table::SymbolType};
+pub use conversions::number_literal_type;
+pub use diagnostics::{Diagnostic, Severity};
+pub use initializers::{FlatInitializer, InitialValue, flatten};
+pub use layout::align_of;
+pub use reachability::{check_reachability, compound_falls_through};
+pub use symbol_table::{
+    SymbolTable, SymbolTableRef,
+    table::{Scope, Symbol, SymbolKind, SymbolType},
+};
+pub use type_checker::{ExpressionTypes, check_types};
@@ -0,0 +1,114 @@
+use crate::{
+    parsing::ast,
+    semantic_analysis::{SymbolTable, SymbolType, diagnostics::Diagnostic},
+};
+
+/// Value of a condition that is known without running the program,
+/// as in `while (1)`
+fn constant_condition(condition: &ast::Expression) -> Option<bool> {
+    match &condition.kind {
+        ast::ExpressionKind::NumberLiteral(nl) if nl.is_floating() => Some(nl.float_value() != 0.0),
+        ast::ExpressionKind::NumberLiteral(nl) => Some(nl.value() != 0),
+        ast::ExpressionKind::Parenthesized(pe) => constant_condition(&pe.expression),
+        _ => None,
+    }
+}
+
+/// Whether control can reach the end of the statement. `return` is the
+/// only statement leaving a block early, loops with a constant true
+/// condition never end.
+fn falls_through(statement: &ast::Statement) -> bool {
+    match statement {
+        ast::Statement::ReturnStatement(_) => false,
+        ast::Statement::CompoundStatement(cs) => compound_falls_through(cs),
+        ast::Statement::IfStatement(ifs) => {
+            let else_falls_through = ifs.else_body.as_deref().is_none_or(falls_through);
+            match constant_condition(&ifs.condition.expression) {
+                Some(true) => falls_through(&ifs.body),
+                Some(false) => else_falls_through,
+                None => falls_through(&ifs.body) || else_falls_through,
+            }
+        }
+        ast::Statement::WhileStatement(ws) => constant_condition(&ws.condition.expression) != Some(true),
+        ast::Statement::ExpressionStatement(_) | ast::Statement::Declaration(_) => true,
+    }
+}
+
+pub fn compound_falls_through(compound: &ast::CompoundStatement) -> bool {
+    compound.items.iter().all(falls_through)
+}
+
+/// Declarations without initializers only name storage and run no code
+fn is_executable(statement: &ast::Statement) -> bool {
+    match statement {
+        ast::Statement::Declaration(decl) => decl
+            .declarators
+            .iter()
+            .any(|declarator| matches!(declarator, ast::Declarator::InitDeclarator(_))),
+        _ => true,
+    }
+}
+
+fn check_statement(statement: &ast::Statement, function: &str, diagnostics: &mut Vec<Diagnostic>) {
+    match statement {
+        ast::Statement::CompoundStatement(cs) => check_compound(cs, function, diagnostics),
+        ast::Statement::IfStatement(ifs) => {
+            check_statement(&ifs.body, function, diagnostics);
+            if let Some(else_body) = &ifs.else_body {
+                check_statement(else_body, function, diagnostics);
+            }
+        }
+        ast::Statement::WhileStatement(ws) => check_statement(&ws.body, function, diagnostics),
+        ast::Statement::ReturnStatement(_)
+        | ast::Statement::ExpressionStatement(_)
+        | ast::Statement::Declaration(_) => (),
+    }
+}
+
+/// Reports the first statement of the block that can never run,
+/// the statements after it are part of the same dead code
+fn check_compound(compound: &ast::CompoundStatement, function: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let mut reachable = true;
+    for statement in &compound.items {
+        if !reachable {
+            if is_executable(statement) {
+                diagnostics.push(
+                    Diagnostic::warning(Some(function.into()), "code will never be executed".into())
+                        .at(statement.location()),
+                );
+                return;
+            }
+            continue;
+        }
+        check_statement(statement, function, diagnostics);
+        reachable = falls_through(statement);
+    }
+}
+
+/// Warns about unreachable statements and non-void functions whose end
+/// can be reached. `main` returns 0 there, C11 5.1.2.2.3.
+pub fn check_reachability(unit: &ast::TranslationUnit, symbol_table: &SymbolTable) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for item in &unit.items {
+        let ast::TopLevelItem::FunctionDefinition(fd) = item else {
+            continue;
+        };
+        let function = fd.declarator.get_identifier().0;
+        check_compound(&fd.body, &function, &mut diagnostics);
+
+        let returns_void = matches!(
+            SymbolType::try_from((fd, symbol_table)),
+            Ok(SymbolType::Function { return_type, .. }) if *return_type == SymbolType::Void
+        );
+        if function != "main" && !returns_void && compound_falls_through(&fd.body) {
+            diagnostics.push(
+                Diagnostic::warning(
+                    Some(function.clone()),
+                    "control reaches end of non-void function".into(),
+                )
+                .at(fd.declarator.location()),
+            );
+        }
+    }
+    diagnostics
+}
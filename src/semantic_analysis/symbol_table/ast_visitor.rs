@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::{cell::RefCell, rc::Rc};
 
-use crate::common::StorageClass;
+use crate::common::{SourceLocation, StorageClass};
 use crate::parsing::ast;
+use crate::semantic_analysis::diagnostics::Diagnostic;
 use crate::semantic_analysis::symbol_table::table::{self, Symbol, SymbolTable as SymbolTableRaw};
 
 type SymbolTable = Rc<RefCell<SymbolTableRaw>>;
@@ -17,7 +18,12 @@ impl Visitable for &ast::Statement {
             ast::Statement::CompoundStatement(cs) => cs.visit(table.clone(), None),
             ast::Statement::ExpressionStatement(_) => (),
             ast::Statement::ReturnStatement(_) => (),
-            ast::Statement::IfStatement(ifs) => ifs.body.as_ref().visit(table.clone(), None),
+            ast::Statement::IfStatement(ifs) => {
+                ifs.body.as_ref().visit(table.clone(), None);
+                if let Some(else_body) = &ifs.else_body {
+                    else_body.as_ref().visit(table.clone(), None);
+                }
+            }
             ast::Statement::WhileStatement(ws) => ws.body.as_ref().visit(table.clone(), None),
             ast::Statement::Declaration(d) => d.visit(table.clone(), None),
         };
@@ -26,69 +32,110 @@ impl Visitable for &ast::Statement {
 
 impl Visitable for &ast::Declaration {
     fn visit(&self, table: SymbolTable, _injection: Option<HashMap<String, Symbol>>) {
-        let dtype = &self.dtype;
-        let symbol_type = table::SymbolType::try_from(dtype).unwrap();
-        let identifier = self.declarator.get_identifier();
-        let storage_class = self.storage_class;
-
-        match self.declarator.as_ref() {
-            ast::Declarator::InitDeclarator(init_dec) => {
-                let symbol = Symbol {
-                    name: identifier.0.clone(),
-                    kind: table::SymbolKind::Variable { is_mutable: true },
-                    type_info: table::SymbolType::try_from((
-                        init_dec.declarator.as_ref(),
-                        &symbol_type,
-                    ))
-                    .unwrap(),
-                    storage_class,
-                };
-                table.borrow_mut().add_symbol(symbol);
+        if let ast::DataType::Struct(specifier) = &self.dtype {
+            table.borrow_mut().declare_struct(specifier);
+        }
+        let symbol_type = table::SymbolType::try_from((&self.dtype, &*table.borrow())).unwrap();
+
+        for declarator in &self.declarators {
+            declare(&table, self.storage_class, &symbol_type, declarator);
+        }
+    }
+}
+
+fn declare(
+    table: &SymbolTable,
+    storage_class: StorageClass,
+    symbol_type: &table::SymbolType,
+    declarator: &ast::Declarator,
+) {
+    let identifier = declarator.get_identifier();
+    let location = declarator.location();
+
+    let declared_type = table::SymbolType::try_from((declarator, symbol_type, &*table.borrow()));
+    let declared_type = match declared_type {
+        Ok(declared_type) => declared_type,
+        // Declared as a pointer instead, which uses of the name accept
+        Err(error) => {
+            table
+                .borrow()
+                .report(Diagnostic::error(None, error.to_string()).at(location));
+            table::SymbolType::Pointer(Box::new(symbol_type.clone()))
+        }
+    };
+    check_passing(table, &declared_type, location);
+
+    // Functions keep their return type, the rest of the signature lives in the kind
+    match declared_type {
+        table::SymbolType::Function {
+            return_type,
+            parameters,
+            is_variadic,
+            has_prototype,
+        } => {
+            if let Some(function_declarator) = declarator.named_function() {
+                table
+                    .borrow()
+                    .check_parameters(&function_declarator.parameters);
+            }
+            table.borrow_mut().declare_function(Symbol {
+                name: identifier.0.clone(),
+                kind: table::SymbolKind::Function {
+                    parameters,
+                    is_variadic,
+                    parameter_names: None,
+                    has_prototype,
+                },
+                type_info: *return_type,
+                storage_class,
+                location,
+                ir_name: identifier.0.clone(),
+            })
+        }
+        declared_type => table.borrow_mut().declare_variable(Symbol {
+            name: identifier.0.clone(),
+            kind: table::SymbolKind::Variable {
+                is_mutable: true,
+                is_initialized: matches!(declarator, ast::Declarator::InitDeclarator(_)),
+            },
+            type_info: declared_type,
+            storage_class,
+            location,
+            ir_name: identifier.0.clone(),
+        }),
+    }
+}
+
+/// Structs are only passed to and returned from functions through
+/// pointers, by-value ones have no calling convention yet
+fn check_passing(table: &SymbolTable, declared_type: &table::SymbolType, location: SourceLocation) {
+    match declared_type {
+        table::SymbolType::Pointer(inner) | table::SymbolType::Array(inner, _) => {
+            check_passing(table, inner, location)
+        }
+        table::SymbolType::Function {
+            return_type,
+            parameters,
+            ..
+        } => {
+            if let table::SymbolType::Struct(_) = return_type.as_ref() {
+                table.borrow().report(
+                    Diagnostic::error(None, "struct return values are not supported yet".into())
+                        .at(location),
+                );
             }
-            ast::Declarator::PointerDeclarator(pointer_dec) => {
-                let pointee =
-                    table::SymbolType::try_from((pointer_dec.declarator.as_ref(), &symbol_type))
-                        .unwrap();
-                let symbol = Symbol {
-                    name: identifier.0.clone(),
-                    kind: table::SymbolKind::Variable { is_mutable: true },
-                    type_info: table::SymbolType::Pointer(Box::new(pointee)),
-                    storage_class: storage_class,
-                };
-                table.borrow_mut().add_symbol(symbol);
+            if parameters.iter().any(|p| matches!(p, table::SymbolType::Struct(_))) {
+                table.borrow().report(
+                    Diagnostic::error(None, "struct parameters are not supported yet".into())
+                        .at(location),
+                );
             }
-            ast::Declarator::FunctionDeclarator(func_dec) => {
-                let parameter_types: Vec<table::SymbolType> = func_dec
-                    .parameters
-                    .iter()
-                    .filter_map(|fp| match fp {
-                        ast::FunctionParameter::ParameterDeclaration(_) => {
-                            Some(table::SymbolType::try_from(fp).unwrap())
-                        }
-                        ast::FunctionParameter::VariadicParameter => None,
-                    })
-                    .collect();
-
-                let symbol = Symbol {
-                    name: identifier.0.clone(),
-                    kind: table::SymbolKind::Function {
-                        parameters: parameter_types,
-                        is_variadic: func_dec.is_variadic,
-                        parameter_names: None,
-                    },
-                    type_info: table::SymbolType::try_from((
-                        func_dec.declarator.as_ref(),
-                        &symbol_type,
-                    ))
-                    .unwrap(),
-                    storage_class: storage_class,
-                };
-
-                table.borrow_mut().add_symbol(symbol);
+            check_passing(table, return_type, location);
+            for parameter in parameters {
+                check_passing(table, parameter, location);
             }
-
-            _ => panic!(),
         }
+        _ => (),
     }
 }
 
@@ -113,25 +160,37 @@ impl Visitable for &ast::CompoundStatement {
 impl Visitable for &ast::FunctionDefinition {
     fn visit(&self, table: SymbolTable, _injection: Option<HashMap<String, Symbol>>) {
         let func_name = self.declarator.get_identifier();
+        table.borrow_mut().function = Some(func_name.0.clone());
 
-        let return_type =
-            if let ast::FunctionDeclaratorField::PointerDeclarator(pd) = &self.declarator {
-                let nest = pd.get_nest_level();
-                let base_type = table::SymbolType::try_from(&self.return_type).unwrap();
-                table::SymbolType::make_ptr(base_type, nest)
-            } else {
-                table::SymbolType::try_from(&self.return_type).unwrap()
-            };
+        let location = self.declarator.location();
+        let function_type = table::SymbolType::try_from((*self, &*table.borrow()));
+        let function_type = function_type.unwrap_or_else(|error| {
+            table
+                .borrow()
+                .report(Diagnostic::error(None, error.to_string()).at(location));
+            table::SymbolType::Function {
+                return_type: Box::new(table::SymbolType::try_from((&self.return_type, &*table.borrow())).unwrap()),
+                parameters: vec![],
+                is_variadic: false,
+                has_prototype: false,
+            }
+        });
+        check_passing(&table, &function_type, self.declarator.location());
+        let table::SymbolType::Function { return_type, .. } = function_type else {
+            unreachable!("A function definition declares a function")
+        };
+        let return_type = *return_type;
 
-        let parameter_names: Vec<String> = self
+        let parameter_names: Vec<(String, SourceLocation)> = self
             .declarator
             .parameters()
             .into_iter()
-            .filter_map(|x| match &x {
-                &ast::FunctionParameter::ParameterDeclaration(param) => {
-                    Some(param.declarator.get_identifier().0)
-                }
-                &ast::FunctionParameter::VariadicParameter => None,
+            .filter_map(|x| match x {
+                ast::FunctionParameter::ParameterDeclaration(param) => Some((
+                    param.declarator.get_identifier().0,
+                    param.declarator.location(),
+                )),
+                ast::FunctionParameter::VariadicParameter => None,
             })
             .collect();
 
@@ -140,47 +199,67 @@ impl Visitable for &ast::FunctionDefinition {
             .parameters()
             .into_iter()
             .filter_map(|fp| match fp {
-                ast::FunctionParameter::ParameterDeclaration(_) => {
-                    Some(table::SymbolType::try_from(fp).unwrap())
+                // Already reported with the type of the function
+                ast::FunctionParameter::ParameterDeclaration(pd) => {
+                    let declared = table::SymbolType::try_from((fp, &*table.borrow()));
+                    Some(declared.unwrap_or_else(|_| {
+                        let base = table::SymbolType::try_from((&pd.dtype, &*table.borrow())).unwrap();
+                        table::SymbolType::Pointer(Box::new(base))
+                    }))
                 }
                 ast::FunctionParameter::VariadicParameter => None,
             })
             .collect();
 
 
-        let is_variadic = match &self.declarator {
-            ast::FunctionDeclaratorField::FunctionDeclarator(fd) => fd.is_variadic,
-            _ => panic!(),
-        };
+        let function_declarator = self.declarator.function_declarator();
+        let is_variadic = function_declarator.is_variadic;
+        table
+            .borrow()
+            .check_parameters(&function_declarator.parameters);
 
-        table.borrow_mut().add_symbol(Symbol {
-            name: func_name.0,
+        table.borrow_mut().declare_function(Symbol {
+            name: func_name.0.clone(),
             kind: table::SymbolKind::Function {
                 parameters: parameter_symbols.clone(),
-                is_variadic: is_variadic,
-                parameter_names: Some(parameter_names.clone())
+                is_variadic,
+                parameter_names: Some(parameter_names.iter().map(|(name, _)| name.clone()).collect()),
+                has_prototype: function_declarator.has_prototype,
             },
             type_info: return_type,
             storage_class: StorageClass::Auto,
+            location: self.declarator.location(),
+            ir_name: func_name.0.clone(),
         });
 
         let injected_parameters = parameter_names
             .iter()
             .zip(parameter_symbols.iter())
-            .map(|(name, dtype)| {
+            .map(|((name, location), dtype)| {
                 (
                     name.clone(),
                     Symbol {
                         name: name.clone(),
-                        kind: table::SymbolKind::Variable { is_mutable: true },
+                        kind: table::SymbolKind::Variable {
+                            is_mutable: true,
+                            is_initialized: false,
+                        },
                         type_info: dtype.clone(),
                         storage_class: StorageClass::Auto,
+                        location: *location,
+                        ir_name: name.clone(),
                     },
                 )
             })
             .collect();
 
         (&self.body).visit(table.clone(), Some(injected_parameters));
+        // The body is the scope the visit just added to the file scope
+        let body_scope = table.borrow().current_scope.borrow().children.last().cloned();
+        if let Some(scope) = body_scope {
+            scope.borrow_mut().function = Some((func_name.0, self.declarator.location()));
+        }
+        table.borrow_mut().function = None;
     }
 }
 
@@ -1,22 +1,203 @@
 use crate::{
-    common::StorageClass, parsing::ast, semantic_analysis::symbol_table::ast_visitor::Visitable,
+    common::{Abi, Signedness, SourceLocation, StorageClass},
+    parsing::ast,
+    semantic_analysis::{
+        diagnostics::Diagnostic,
+        layout::{array_length, struct_layout},
+        symbol_table::ast_visitor::Visitable,
+    },
+};
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    fmt,
+    rc::Rc,
 };
-use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 use thiserror::Error;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SymbolType {
-    Int,
-    Char,
+    Char(Signedness),
+    Short(Signedness),
+    Int(Signedness),
+    Long(Signedness),
+    LongLong(Signedness),
+    Float,
+    Double,
+    Void,
     Pointer(Box<SymbolType>),
+    /// Elements and their count, None for the `[]` of an array whose
+    /// length is given elsewhere
+    Array(Box<SymbolType>, Option<usize>),
+    Struct(Rc<StructType>),
+    Function {
+        return_type: Box<SymbolType>,
+        parameters: Vec<SymbolType>,
+        is_variadic: bool,
+        /// Declared with `()`, calls are not checked against the parameters
+        has_prototype: bool,
+    },
 }
 
 impl SymbolType {
+    pub fn signedness(&self) -> Signedness {
+        match self {
+            Self::Char(s) | Self::Short(s) | Self::Int(s) | Self::Long(s) | Self::LongLong(s) => {
+                *s
+            }
+            Self::Float | Self::Double | Self::Void => Signedness::Signed,
+            Self::Pointer(_) | Self::Array(..) | Self::Struct(_) | Self::Function { .. } => {
+                Signedness::Unsigned
+            }
+        }
+    }
+
     pub fn make_ptr(base_type: SymbolType, nest: usize) -> SymbolType {
         if nest == 0 {
             return base_type;
         }
-        return SymbolType::Pointer(Box::new(Self::make_ptr(base_type, nest - 1)));
+        SymbolType::Pointer(Box::new(Self::make_ptr(base_type, nest - 1)))
+    }
+
+    /// Function type a callee of this type calls, function designators
+    /// decay to a pointer so both forms are accepted
+    pub fn callee_function(&self) -> Option<&SymbolType> {
+        match self {
+            Self::Function { .. } => Some(self),
+            Self::Pointer(pointee) if matches!(pointee.as_ref(), Self::Function { .. }) => {
+                Some(pointee)
+            }
+            _ => None,
+        }
+    }
+
+    /// `void *` converts to and from any other object pointer, C11 6.3.2.3
+    pub fn is_void_pointer(&self) -> bool {
+        matches!(self, Self::Pointer(pointee) if **pointee == Self::Void)
+    }
+
+    /// Types two declarations of one variable may have, which differ at
+    /// most by the length an array declaration leaves out, C11 6.2.7
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Array(element, length), Self::Array(other_element, other_length)) => {
+                element.is_compatible(other_element)
+                    && (length.is_none() || other_length.is_none() || length == other_length)
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Arrays and structs are objects made of other objects, which are
+    /// only ever handled through their address
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Self::Array(..) | Self::Struct(_))
+    }
+
+    /// Arrays used as a value convert to a pointer to their first element,
+    /// C11 6.3.2.1p3
+    pub fn decay(&self) -> Self {
+        match self {
+            Self::Array(element, _) => Self::Pointer(element.clone()),
+            _ => self.clone(),
+        }
+    }
+
+    /// Types whose size is known: not `void`, a struct declared but not
+    /// defined, or an array without a length
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Self::Void | Self::Array(_, None) => false,
+            Self::Struct(struct_type) => struct_type.members().is_some(),
+            _ => true,
+        }
+    }
+}
+
+/// Member of a struct, at its offset from the start of the struct
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    pub member_type: SymbolType,
+    pub offset: usize,
+}
+
+/// Members of a defined struct, as laid out in memory
+#[derive(Debug)]
+pub struct StructLayout {
+    pub members: Vec<Member>,
+    pub size: usize,
+    pub alignment: usize,
+}
+
+/// A struct type, shared by every use of its tag in the scope declaring
+/// it. The layout is set once the definition is read, until then the
+/// struct is incomplete and only pointers to it can be used.
+pub struct StructType {
+    /// The tag, or where an anonymous struct is
+    pub name: String,
+    pub location: SourceLocation,
+    layout: OnceCell<StructLayout>,
+}
+
+impl StructType {
+    /// The struct a specifier names before its definition is read
+    fn incomplete(specifier: &ast::StructSpecifier) -> Self {
+        Self {
+            name: specifier.key(),
+            location: specifier.location,
+            layout: OnceCell::new(),
+        }
+    }
+
+    /// The `va_list` of AAPCS64: where the next stacked argument is, the
+    /// tops of the general and floating point register save areas, and
+    /// the negative offsets from them of the next register argument
+    fn aapcs64_va_list() -> Self {
+        let pointer = SymbolType::Pointer(Box::new(SymbolType::Void));
+        let offset = SymbolType::Int(Signedness::Signed);
+        let struct_type = Self {
+            name: "__va_list".into(),
+            location: SourceLocation { line: 0, column: 0 },
+            layout: OnceCell::new(),
+        };
+        let _ = struct_type.layout.set(struct_layout(vec![
+            ("__stack".into(), pointer.clone()),
+            ("__gr_top".into(), pointer.clone()),
+            ("__vr_top".into(), pointer),
+            ("__gr_offs".into(), offset.clone()),
+            ("__vr_offs".into(), offset),
+        ]));
+        struct_type
+    }
+
+    pub fn layout(&self) -> Option<&StructLayout> {
+        self.layout.get()
+    }
+
+    pub fn members(&self) -> Option<&[Member]> {
+        self.layout().map(|layout| layout.members.as_slice())
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members()?.iter().find(|member| member.name == name)
+    }
+}
+
+/// Two structs are the same type only when they come from the same
+/// declaration, whatever their members
+impl PartialEq for StructType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for StructType {}
+
+/// Members are left out, as a struct may point back to itself through them
+impl fmt::Debug for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "struct {}", self.name)
     }
 }
 
@@ -27,64 +208,147 @@ pub enum SemanticError {
 
     #[error("InvalidParameterDeclarator")]
     InvalidParameterDeclarator,
+
+    #[error("variable length arrays are not supported yet")]
+    VariableLengthArray,
+
+    #[error("size of array has non-integer type '{0}'")]
+    NonIntegerArraySize(SymbolType),
+
+    #[error("array has a negative size")]
+    NegativeArraySize,
+
+    #[error("array has incomplete element type '{0}'")]
+    IncompleteElementType(SymbolType),
+
+    #[error("'...' has no type")]
+    VariadicParameterType,
 }
 
-impl TryFrom<&ast::DataType> for SymbolType {
+/// Base type of a declaration. A struct tag names the struct of the
+/// innermost scope declaring it, or an incomplete struct nobody declared.
+impl TryFrom<(&ast::DataType, &SymbolTable)> for SymbolType {
     type Error = SemanticError;
-    fn try_from(value: &ast::DataType) -> Result<Self, Self::Error> {
+    fn try_from((value, table): (&ast::DataType, &SymbolTable)) -> Result<Self, Self::Error> {
         Ok(match value {
-            ast::DataType::Char => Self::Char,
-            ast::DataType::Int => Self::Int,
+            ast::DataType::Char(s) => Self::Char(*s),
+            ast::DataType::Short(s) => Self::Short(*s),
+            ast::DataType::Int(s) => Self::Int(*s),
+            ast::DataType::Long(s) => Self::Long(*s),
+            ast::DataType::LongLong(s) => Self::LongLong(*s),
+            ast::DataType::Float => Self::Float,
+            ast::DataType::Double => Self::Double,
+            ast::DataType::VaList => table.va_list.clone(),
+            ast::DataType::Void => Self::Void,
+            ast::DataType::Struct(specifier) => Self::Struct(
+                table
+                    .query_struct(&specifier.key())
+                    .unwrap_or_else(|| Rc::new(StructType::incomplete(specifier))),
+            ),
         })
     }
 }
 
-impl TryFrom<(&ast::Declarator, &SymbolType)> for SymbolType {
+/// Type of a declarator over the base type of its declaration. Declarators
+/// are read inside out, so `int (*f)(int)` is a pointer to a function and
+/// `int a[2][3]` an array of two arrays of three ints.
+impl TryFrom<(&ast::Declarator, &SymbolType, &SymbolTable)> for SymbolType {
     type Error = SemanticError;
-    fn try_from((value, symb): (&ast::Declarator, &SymbolType)) -> Result<Self, Self::Error> {
+    fn try_from(
+        (value, symb, table): (&ast::Declarator, &SymbolType, &SymbolTable),
+    ) -> Result<Self, Self::Error> {
         match &value {
-            &ast::Declarator::PointerDeclarator(pr) => {
-                let pointee = Box::new(TryFrom::<(&ast::Declarator, &SymbolType)>::try_from((
-                    &pr.declarator,
-                    symb,
-                ))?);
-                Ok(SymbolType::Pointer(pointee))
-            }
-            &ast::Declarator::Identifier(_) => Ok(symb.clone()),
+            &ast::Declarator::PointerDeclarator(pr) => SymbolType::try_from((
+                pr.declarator.as_ref(),
+                &SymbolType::Pointer(Box::new(symb.clone())),
+                table,
+            )),
+            &ast::Declarator::ArrayDeclarator(ad) => {
+                if !symb.is_complete() {
+                    return Err(SemanticError::IncompleteElementType(symb.clone()));
+                }
+                let length = match &ad.size {
+                    Some(size) => Some(array_length(table, size)?),
+                    None => None,
+                };
+                let array = SymbolType::Array(Box::new(symb.clone()), length);
+                SymbolType::try_from((ad.declarator.as_ref(), &array, table))
+            }
+            &ast::Declarator::Identifier(..) | &ast::Declarator::Abstract => Ok(symb.clone()),
             &ast::Declarator::FunctionDeclarator(fd) => {
-                let pointee =
-                    TryFrom::<(&ast::Declarator, &SymbolType)>::try_from((&fd.declarator, symb))?;
-                Ok(pointee)
+                let function = function_type(fd, symb, table)?;
+                SymbolType::try_from((fd.declarator.as_ref(), &function, table))
+            }
+            &ast::Declarator::InitDeclarator(id) => {
+                SymbolType::try_from((id.declarator.as_ref(), symb, table))
             }
-            _ => Err(SemanticError::InvalidPointerDeclarator),
         }
     }
 }
 
-impl TryFrom<&ast::ParameterDeclaration> for SymbolType {
+/// Function returning `return_type` with the parameters of `fd`
+fn function_type(
+    fd: &ast::FunctionDeclarator,
+    return_type: &SymbolType,
+    table: &SymbolTable,
+) -> Result<SymbolType, SemanticError> {
+    let parameters = fd
+        .parameters
+        .iter()
+        .filter_map(|fp| match fp {
+            ast::FunctionParameter::ParameterDeclaration(pd) => Some(SymbolType::try_from((pd, table))),
+            ast::FunctionParameter::VariadicParameter => None,
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(SymbolType::Function {
+        return_type: Box::new(return_type.clone()),
+        parameters,
+        is_variadic: fd.is_variadic,
+        has_prototype: fd.has_prototype,
+    })
+}
+
+/// Type of a defined function, read from its declarator as the type of
+/// a declared one is
+impl TryFrom<(&ast::FunctionDefinition, &SymbolTable)> for SymbolType {
     type Error = SemanticError;
-    fn try_from(value: &ast::ParameterDeclaration) -> Result<Self, Self::Error> {
-        let dtype = TryFrom::try_from(&value.dtype)?;
-        match value.declarator.as_ref() {
-            ast::Declarator::Identifier(_) => Ok(dtype),
-            ast::Declarator::PointerDeclarator(pd) => {
-                let pointee = Box::new(TryFrom::<(&ast::Declarator, &SymbolType)>::try_from((
-                    &pd.declarator,
-                    &dtype,
-                ))?);
-                Ok(SymbolType::Pointer(pointee))
+    fn try_from((value, table): (&ast::FunctionDefinition, &SymbolTable)) -> Result<Self, Self::Error> {
+        let base = SymbolType::try_from((&value.return_type, table))?;
+        match &value.declarator {
+            ast::FunctionDeclaratorField::FunctionDeclarator(fd) => {
+                let function = function_type(fd, &base, table)?;
+                SymbolType::try_from((fd.declarator.as_ref(), &function, table))
             }
-            _ => Err(SemanticError::InvalidPointerDeclarator),
+            ast::FunctionDeclaratorField::PointerDeclarator(pd) => SymbolType::try_from((
+                pd.declarator.as_ref(),
+                &SymbolType::Pointer(Box::new(base)),
+                table,
+            )),
         }
     }
 }
 
-impl TryFrom<&ast::FunctionParameter> for SymbolType {
+impl TryFrom<(&ast::ParameterDeclaration, &SymbolTable)> for SymbolType {
+    type Error = SemanticError;
+    fn try_from((value, table): (&ast::ParameterDeclaration, &SymbolTable)) -> Result<Self, Self::Error> {
+        let dtype = SymbolType::try_from((&value.dtype, table))?;
+        let declared = SymbolType::try_from((value.declarator.as_ref(), &dtype, table))?;
+        // Parameters of function and array type are adjusted to pointers,
+        // C11 6.7.6.3
+        Ok(match declared {
+            SymbolType::Function { .. } => SymbolType::Pointer(Box::new(declared)),
+            SymbolType::Array(..) => declared.decay(),
+            _ => declared,
+        })
+    }
+}
+
+impl TryFrom<(&ast::FunctionParameter, &SymbolTable)> for SymbolType {
     type Error = SemanticError;
-    fn try_from(value: &ast::FunctionParameter) -> Result<Self, Self::Error> {
+    fn try_from((value, table): (&ast::FunctionParameter, &SymbolTable)) -> Result<Self, Self::Error> {
         match value {
-            ast::FunctionParameter::VariadicParameter => panic!("Unsupported"),
-            ast::FunctionParameter::ParameterDeclaration(pd) => TryFrom::try_from(pd),
+            ast::FunctionParameter::VariadicParameter => Err(SemanticError::VariadicParameterType),
+            ast::FunctionParameter::ParameterDeclaration(pd) => SymbolType::try_from((pd, table)),
         }
     }
 }
@@ -93,11 +357,15 @@ impl TryFrom<&ast::FunctionParameter> for SymbolType {
 pub enum SymbolKind {
     Variable {
         is_mutable: bool,
+        /// Declared with an initializer, which makes a file scope
+        /// declaration a definition
+        is_initialized: bool,
     },
     Function {
         parameters: Vec<SymbolType>,
         parameter_names: Option<Vec<String>>,
         is_variadic: bool,
+        has_prototype: bool,
     },
 }
 
@@ -107,14 +375,105 @@ pub struct Symbol {
     pub kind: SymbolKind,
     pub type_info: SymbolType,
     pub storage_class: StorageClass,
+    /// Where the symbol was declared, the definition for functions
+    pub location: SourceLocation,
+    /// Name of the variable in the IR. A declaration hiding an outer
+    /// variable gets a numbered one, so the two stay apart.
+    pub ir_name: String,
+}
+
+impl Symbol {
+    fn is_initialized(&self) -> bool {
+        matches!(
+            self.kind,
+            SymbolKind::Variable {
+                is_initialized: true,
+                ..
+            }
+        )
+    }
+
+    fn is_function_definition(&self) -> bool {
+        matches!(
+            self.kind,
+            SymbolKind::Function {
+                parameter_names: Some(_),
+                ..
+            }
+        )
+    }
+}
+
+/// Types are written the way C spells them, as in `unsigned long` or `int (*)(char *)`
+impl fmt::Display for SymbolType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.declaration(String::new()))
+    }
+}
+
+impl SymbolType {
+    /// The type written as C declares it around `declarator`. Pointers go
+    /// before the declarator and array lengths and parameters after it,
+    /// so a pointer to an array or function is parenthesized, as in
+    /// `int (**)(int)`.
+    fn declaration(&self, declarator: String) -> String {
+        let integer = |signedness: &Signedness, name: &str| match signedness {
+            Signedness::Signed => name.to_string(),
+            Signedness::Unsigned => format!("unsigned {name}"),
+        };
+        let base = match self {
+            Self::Char(s) => integer(s, "char"),
+            Self::Short(s) => integer(s, "short"),
+            Self::Int(s) => integer(s, "int"),
+            Self::Long(s) => integer(s, "long"),
+            Self::LongLong(s) => integer(s, "long long"),
+            Self::Float => "float".into(),
+            Self::Double => "double".into(),
+            Self::Void => "void".into(),
+            Self::Struct(struct_type) => format!("struct {}", struct_type.name),
+            Self::Pointer(pointee) => {
+                return match pointee.as_ref() {
+                    Self::Array(..) | Self::Function { .. } => pointee.declaration(format!("(*{declarator})")),
+                    _ => pointee.declaration(format!("*{declarator}")),
+                };
+            }
+            Self::Array(element, length) => {
+                let length = length.map(|n| n.to_string()).unwrap_or_default();
+                return element.declaration(format!("{declarator}[{length}]"));
+            }
+            Self::Function {
+                return_type,
+                parameters,
+                is_variadic,
+                has_prototype,
+            } => {
+                let mut parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+                if *is_variadic {
+                    parameters.push("...".into());
+                }
+                if parameters.is_empty() && *has_prototype {
+                    parameters.push("void".into());
+                }
+                return return_type.declaration(format!("{declarator}({})", parameters.join(", ")));
+            }
+        };
+        // Array lengths follow the element right away, as in `int[2][3]`
+        if declarator.is_empty() || declarator.starts_with('[') {
+            format!("{base}{declarator}")
+        } else {
+            format!("{base} {declarator}")
+        }
+    }
 }
 
 impl fmt::Debug for Scope {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Scope")
             .field("symbols", &self.symbols)
+            .field("structs", &self.structs)
             .field("children", &self.children) // Show just count
             .field("has_parent", &self.parent.is_some())
+            .field("function", &self.function)
             .finish()
     }
 }
@@ -122,8 +481,14 @@ impl fmt::Debug for Scope {
 #[derive(Default)]
 pub struct Scope {
     pub symbols: HashMap<String, Symbol>,
+    /// Structs declared in the scope, by tag
+    pub structs: HashMap<String, Rc<StructType>>,
     pub parent: Option<Rc<RefCell<Scope>>>,
     pub children: Vec<Rc<RefCell<Scope>>>,
+    /// Name and declarator location of the function whose body the scope
+    /// is, None for the other scopes. The location tells a redefinition
+    /// apart from the first definition
+    pub function: Option<(String, SourceLocation)>,
 }
 
 #[derive(Debug)]
@@ -131,14 +496,24 @@ pub struct SymbolTable {
     pub current_scope: Rc<RefCell<Scope>>,
     pub global_scope: Rc<RefCell<Scope>>,
     pub stack: Rc<RefCell<Vec<usize>>>,
+    /// Redeclaration errors and shadowing warnings found while building the table
+    pub diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    /// Function whose definition is being declared, which the diagnostics
+    /// are reported in. None at file scope.
+    pub function: Option<String>,
+    /// Declarations renamed in the IR so far, the ones hiding an outer
+    /// variable and the static locals
+    shadow_count: usize,
+    pub abi: Abi,
+    /// Type of `va_list` under the ABI, one struct for the whole unit
+    va_list: SymbolType,
 }
 
 impl SymbolTable {
     pub fn enter_scope_mut(&mut self) {
         let new_scope = Rc::new(RefCell::new(Scope {
-            symbols: HashMap::new(),
             parent: Some(self.current_scope.clone()),
-            children: vec![],
+            ..Default::default()
         }));
 
         self.current_scope
@@ -153,17 +528,27 @@ impl SymbolTable {
             current_scope: scope,
             global_scope: self.global_scope.clone(),
             stack: Rc::new(RefCell::new(vec![])),
+            diagnostics: self.diagnostics.clone(),
+            function: None,
+            shadow_count: 0,
+            abi: self.abi,
+            va_list: self.va_list.clone(),
         }))
     }
 
     pub fn fake_parent(&self) -> SymbolTableRef {
         Rc::new(RefCell::new(SymbolTable {
-            current_scope: Rc::new(RefCell::new(Scope { symbols: HashMap::new(),
-                 parent: None,
-                children: vec![self.current_scope.clone()]
+            current_scope: Rc::new(RefCell::new(Scope {
+                children: vec![self.current_scope.clone()],
+                ..Default::default()
             })),
             global_scope: self.global_scope.clone(),
             stack: Rc::new(RefCell::new(vec![])),
+            diagnostics: self.diagnostics.clone(),
+            function: None,
+            shadow_count: 0,
+            abi: self.abi,
+            va_list: self.va_list.clone(),
         }))
     }
 
@@ -174,6 +559,247 @@ impl SymbolTable {
             .insert(symbol.name.clone(), symbol);
     }
 
+    pub(super) fn report(&self, mut diagnostic: Diagnostic) {
+        diagnostic.function = self.function.clone();
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
+
+    /// Adds a variable to the current scope. Redeclaring a name of the same
+    /// scope is an error, except for compatible file scope and `extern`
+    /// declarations, and hiding a variable of an outer scope is a warning.
+    pub fn declare_variable(&mut self, mut symbol: Symbol) {
+        let is_extern = matches!(symbol.storage_class, StorageClass::Extern);
+        let is_initialized = matches!(symbol.kind, SymbolKind::Variable { is_initialized: true, .. });
+        match &symbol.type_info {
+            // The type checker gives the array the length of its initializer
+            SymbolType::Array(_, None) if is_initialized => (),
+            SymbolType::Array(_, None) if !is_extern => self.report(
+                Diagnostic::error(
+                    None,
+                    "definition of variable with array type needs an explicit size or an initializer"
+                        .into(),
+                )
+                .at(symbol.location),
+            ),
+            incomplete if !incomplete.is_complete() && (!is_extern || *incomplete == SymbolType::Void) => {
+                self.report(
+                    Diagnostic::error(
+                        None,
+                        format!("variable '{}' has incomplete type '{}'", symbol.name, incomplete),
+                    )
+                    .at(symbol.location),
+                )
+            }
+            _ => (),
+        }
+        let previous = self.current_scope.borrow().symbols.get(&symbol.name).cloned();
+        let is_file_scope = self.current_scope.borrow().parent.is_none();
+
+        let Some(previous) = previous else {
+            if let Some(shadowed) = self.query(&symbol.name)
+                && let SymbolKind::Variable { .. } = shadowed.kind
+                && !matches!(symbol.storage_class, StorageClass::Extern)
+            {
+                let shadowed_scope = match self.query_outer_local(&symbol.name) {
+                    Some(_) => "a previous local",
+                    None => "a global declaration",
+                };
+                self.report(
+                    Diagnostic::warning(
+                        None,
+                        format!("declaration of '{}' shadows {}", symbol.name, shadowed_scope),
+                    )
+                    .at(symbol.location)
+                    .with_note(shadowed.location, "shadowed declaration is here"),
+                );
+                self.shadow_count += 1;
+                symbol.ir_name = format!("{}.{}", symbol.name, self.shadow_count);
+            }
+            // A static local is a data item of the unit, named apart from
+            // the globals and the statics of other functions
+            if !is_file_scope
+                && matches!(symbol.storage_class, StorageClass::Static)
+                && symbol.ir_name == symbol.name
+            {
+                self.shadow_count += 1;
+                symbol.ir_name = format!("{}.{}", symbol.name, self.shadow_count);
+            }
+            return self.add_symbol(symbol);
+        };
+
+        let error = match previous.kind {
+            SymbolKind::Function { .. } => Some(format!(
+                "redefinition of '{}' as different kind of symbol",
+                symbol.name
+            )),
+            SymbolKind::Variable { .. } if !previous.type_info.is_compatible(&symbol.type_info) => Some(format!(
+                "redefinition of '{}' with a different type: '{}' vs '{}'",
+                symbol.name, symbol.type_info, previous.type_info
+            )),
+            SymbolKind::Variable { .. } => match (previous.storage_class, symbol.storage_class) {
+                (StorageClass::Extern, StorageClass::Extern) => None,
+                _ if is_file_scope && previous.is_initialized() && symbol.is_initialized() => {
+                    Some(format!("redefinition of '{}'", symbol.name))
+                }
+                _ if is_file_scope => None,
+                _ => Some(format!("redefinition of '{}'", symbol.name)),
+            },
+        };
+
+        match error {
+            Some(message) => self.report(
+                Diagnostic::error(None, message)
+                    .at(symbol.location)
+                    .with_note(previous.location, "previous definition is here"),
+            ),
+            // A definition completes an earlier `extern` declaration or
+            // tentative definition
+            None if matches!(previous.storage_class, StorageClass::Extern) || symbol.is_initialized() => {
+                self.add_symbol(symbol)
+            }
+            None => (),
+        }
+    }
+
+    /// A symbol of the scopes between the current one and the file scope
+    fn query_outer_local(&self, name: &str) -> Option<Symbol> {
+        let mut scope = self.current_scope.borrow().parent.clone();
+        while let Some(current) = scope {
+            current.borrow().parent.as_ref()?;
+            if let Some(symbol) = current.borrow().symbols.get(name) {
+                return Some(symbol.clone());
+            }
+            scope = current.borrow().parent.clone();
+        }
+        None
+    }
+
+    /// Adds a function to the current scope, merging it with an earlier
+    /// declaration of the same function so that prototypes and definitions
+    /// can come in any order. The parameter names of a definition are kept,
+    /// and so are the parameters of a prototype over a `()` declaration.
+    pub fn declare_function(&mut self, symbol: Symbol) {
+        let previous = self.current_scope.borrow().symbols.get(&symbol.name).cloned();
+        let Some(previous) = previous else {
+            return self.add_symbol(symbol);
+        };
+
+        let is_definition = symbol.is_function_definition();
+        let (
+            SymbolKind::Function {
+                parameters,
+                parameter_names,
+                is_variadic,
+                has_prototype,
+            },
+            SymbolKind::Function {
+                parameters: previous_parameters,
+                parameter_names: previous_names,
+                is_variadic: previous_variadic,
+                has_prototype: previous_prototype,
+            },
+        ) = (symbol.kind, previous.kind.clone())
+        else {
+            return self.report(
+                Diagnostic::error(
+                    None,
+                    format!("redefinition of '{}' as different kind of symbol", symbol.name),
+                )
+                .at(symbol.location)
+                .with_note(previous.location, "previous definition is here"),
+            );
+        };
+
+        // A definition fixes the parameters even when written with `()`
+        let same_parameters = parameters == previous_parameters && is_variadic == previous_variadic;
+        let known_parameters = has_prototype || is_definition;
+        let previous_known_parameters = previous_prototype || previous.is_function_definition();
+        if symbol.type_info != previous.type_info
+            || (known_parameters && previous_known_parameters && !same_parameters)
+        {
+            return self.report(
+                Diagnostic::error(None, format!("conflicting types for '{}'", symbol.name))
+                    .at(symbol.location)
+                    .with_note(previous.location, "previous declaration is here"),
+            );
+        }
+
+        if is_definition && previous.is_function_definition() {
+            return self.report(
+                Diagnostic::error(None, format!("redefinition of '{}'", symbol.name))
+                    .at(symbol.location)
+                    .with_note(previous.location, "previous definition is here"),
+            );
+        }
+
+        let storage_class = match (symbol.storage_class, previous.storage_class) {
+            (StorageClass::Extern, _) | (_, StorageClass::Extern) => StorageClass::Extern,
+            _ => symbol.storage_class,
+        };
+
+        let (parameters, is_variadic) = if has_prototype {
+            (parameters, is_variadic)
+        } else {
+            (previous_parameters, previous_variadic)
+        };
+        self.add_symbol(Symbol {
+            ir_name: symbol.name.clone(),
+            name: symbol.name,
+            kind: SymbolKind::Function {
+                parameters,
+                parameter_names: parameter_names.or(previous_names),
+                is_variadic,
+                has_prototype: has_prototype || previous_prototype,
+            },
+            type_info: symbol.type_info,
+            storage_class,
+            location: if is_definition {
+                symbol.location
+            } else {
+                previous.location
+            },
+        });
+    }
+
+    /// Reports parameters of one function declarator sharing a name or
+    /// having type `void`, which only a sole unnamed parameter may have
+    pub fn check_parameters(&self, parameters: &[ast::FunctionParameter]) {
+        let mut seen: HashMap<String, SourceLocation> = HashMap::new();
+        for parameter in parameters {
+            let ast::FunctionParameter::ParameterDeclaration(pd) = parameter else {
+                continue;
+            };
+            let is_void = SymbolType::try_from((pd, self)).is_ok_and(|dtype| dtype == SymbolType::Void);
+            if pd.declarator.is_abstract() {
+                if is_void {
+                    self.report(Diagnostic::error(
+                        None,
+                        "'void' must be the first and only parameter if specified".into(),
+                    ));
+                }
+                continue;
+            }
+            let name = pd.declarator.get_identifier().0;
+            let location = pd.declarator.location();
+            if is_void {
+                self.report(
+                    Diagnostic::error(None, format!("parameter '{}' has incomplete type 'void'", name))
+                        .at(location),
+                );
+            }
+            match seen.get(&name) {
+                Some(previous) => self.report(
+                    Diagnostic::error(None, format!("redefinition of parameter '{}'", name))
+                        .at(location)
+                        .with_note(*previous, "previous declaration is here"),
+                ),
+                None => {
+                    seen.insert(name, location);
+                }
+            }
+        }
+    }
+
     pub fn exit_scope_mut(&mut self) {
         let maybe_parent = self
             .current_scope
@@ -187,12 +813,22 @@ impl SymbolTable {
         }
     }
 
-    pub fn from_translation_unit(unit: &ast::TranslationUnit) -> Rc<RefCell<Self>> {
+    pub fn from_translation_unit(unit: &ast::TranslationUnit, abi: Abi) -> Rc<RefCell<Self>> {
         let global_scope = Rc::new(RefCell::new(Scope::default()));
+        // Apple's arm64 ABI passes every variadic argument on the stack
+        let va_list = match abi {
+            Abi::Apple => SymbolType::Pointer(Box::new(SymbolType::Char(Signedness::Signed))),
+            Abi::Aapcs64 => SymbolType::Struct(Rc::new(StructType::aapcs64_va_list())),
+        };
         let table = Rc::new(RefCell::new(SymbolTable {
             current_scope: global_scope.clone(),
             global_scope: global_scope.clone(),
             stack: Rc::new(RefCell::new(vec![])),
+            diagnostics: Rc::new(RefCell::new(vec![])),
+            function: None,
+            shadow_count: 0,
+            abi,
+            va_list,
         }));
         unit.visit(table.clone(), None);
 
@@ -216,18 +852,156 @@ impl SymbolTable {
         }
     }
 
+    /// The struct a tag names, in the innermost scope declaring it
+    pub fn query_struct(&self, key: &str) -> Option<Rc<StructType>> {
+        let mut scope = Some(self.current_scope.clone());
+        while let Some(current) = scope {
+            if let Some(struct_type) = current.borrow().structs.get(key) {
+                return Some(struct_type.clone());
+            }
+            scope = current.borrow().parent.clone();
+        }
+        None
+    }
+
+    /// Declares the struct a specifier names. A tag nobody declared yet
+    /// names a new incomplete struct of the current scope, which its
+    /// definition completes. The struct is declared before its members
+    /// are read, so that they can point to it.
+    pub fn declare_struct(&mut self, specifier: &ast::StructSpecifier) {
+        let key = specifier.key();
+        let Some(fields) = &specifier.fields else {
+            if self.query_struct(&key).is_none() {
+                let struct_type = Rc::new(StructType::incomplete(specifier));
+                self.current_scope.borrow_mut().structs.insert(key, struct_type);
+            }
+            return;
+        };
+
+        let previous = self.current_scope.borrow().structs.get(&key).cloned();
+        let struct_type = match previous {
+            Some(previous) if previous.layout().is_some() => {
+                return self.report(
+                    Diagnostic::error(None, format!("redefinition of 'struct {}'", key))
+                        .at(specifier.location)
+                        .with_note(previous.location, "previous definition is here"),
+                );
+            }
+            Some(previous) => previous,
+            None => {
+                let struct_type = Rc::new(StructType::incomplete(specifier));
+                self.current_scope
+                    .borrow_mut()
+                    .structs
+                    .insert(key, struct_type.clone());
+                struct_type
+            }
+        };
+
+        let mut members: Vec<(String, SymbolType)> = vec![];
+        let mut locations: HashMap<String, SourceLocation> = HashMap::new();
+        for field in fields {
+            if let ast::DataType::Struct(inner) = &field.dtype {
+                self.declare_struct(inner);
+            }
+            let Ok(base) = SymbolType::try_from((&field.dtype, &*self)) else {
+                continue;
+            };
+            for declarator in &field.declarators {
+                let name = declarator.get_identifier().0;
+                let location = declarator.location();
+                let member_type = match SymbolType::try_from((declarator, &base, &*self)) {
+                    Ok(member_type) => member_type,
+                    Err(error) => {
+                        self.report(Diagnostic::error(None, error.to_string()).at(location));
+                        continue;
+                    }
+                };
+                if let SymbolType::Function { .. } = member_type {
+                    self.report(
+                        Diagnostic::error(None, format!("field '{}' declared as a function", name))
+                            .at(location),
+                    );
+                    continue;
+                }
+                if !member_type.is_complete() {
+                    self.report(
+                        Diagnostic::error(
+                            None,
+                            format!("field '{}' has incomplete type '{}'", name, member_type),
+                        )
+                        .at(location),
+                    );
+                    continue;
+                }
+                if let Some(previous) = locations.get(&name) {
+                    self.report(
+                        Diagnostic::error(None, format!("duplicate member '{}'", name))
+                            .at(location)
+                            .with_note(*previous, "previous declaration is here"),
+                    );
+                    continue;
+                }
+                locations.insert(name.clone(), location);
+                members.push((name, member_type));
+            }
+        }
+        let _ = struct_type.layout.set(struct_layout(members));
+    }
+
+    /// Scope of the body of the function `name` defined at `location`
+    pub fn function_scope(&self, name: &str, location: SourceLocation) -> Option<Rc<RefCell<Scope>>> {
+        self.global_scope
+            .borrow()
+            .children
+            .iter()
+            .find(|scope| {
+                scope.borrow().function.as_ref().is_some_and(|(function, defined_at)| {
+                    function == name && *defined_at == location
+                })
+            })
+            .cloned()
+    }
+
+    /// Whether `name` resolves to a declaration of the file scope,
+    /// rather than to a local one hiding it
+    pub fn is_file_scope(&self, name: &str) -> bool {
+        let mut scope = Some(self.current_scope.clone());
+        while let Some(current) = scope {
+            if current.borrow().symbols.contains_key(name) {
+                return current.borrow().parent.is_none();
+            }
+            scope = current.borrow().parent.clone();
+        }
+        false
+    }
+
+    /// Enters the next child of the current scope. Scopes are replayed in
+    /// the order the visitor created them, so the stack holds the index of
+    /// the next child to enter at every level.
     pub fn compound_enter(&mut self) {
-        self.stack.borrow_mut().push(0);
-        let current_child = *self.stack.borrow().last().unwrap();
-        let new_scope = self.current_scope.borrow().children[current_child].clone(); 
+        let current_child = {
+            let mut stack = self.stack.borrow_mut();
+            if stack.is_empty() {
+                stack.push(0);
+            }
+            let next = stack.last_mut().unwrap();
+            let current_child = *next;
+            *next += 1;
+            stack.push(0);
+            current_child
+        };
+        let new_scope = self.current_scope.borrow().children[current_child].clone();
         self.current_scope = new_scope;
     }
 
-    pub fn compound_next(&mut self) {
-        *self.stack.borrow_mut().last_mut().unwrap() += 1;
-        let current_child = *self.stack.borrow().last().unwrap();
-        let new_scope = self.current_scope.borrow().children[current_child].clone(); 
-        self.current_scope = new_scope;
+    /// Position of the scope replay, to visit a part of the tree again
+    pub fn scope_position(&self) -> Vec<usize> {
+        self.stack.borrow().clone()
+    }
+
+    pub fn rewind_scope_position(&self, position: Vec<usize>) {
+        *self.stack.borrow_mut() = position;
     }
 
     pub fn compound_exit(&mut self) {
@@ -0,0 +1,1153 @@
+use std::collections::HashMap;
+
+use crate::{
+    common::{Signedness, SourceLocation, StorageClass, Width},
+    ir::nodes::Op,
+    parsing::ast,
+    semantic_analysis::{
+        InitialValue, SymbolKind, SymbolTable, SymbolTableRef, SymbolType, align_of,
+        diagnostics::Diagnostic, flatten, number_literal_type,
+    },
+};
+
+/// Types the type checker gave the expressions of the AST. Arrays keep
+/// their type, before they decay to a pointer.
+#[derive(Debug, Default)]
+pub struct ExpressionTypes(HashMap<ast::ExpressionId, SymbolType>);
+
+impl ExpressionTypes {
+    fn annotate(&mut self, expression: &ast::Expression, expression_type: SymbolType) {
+        self.0.insert(expression.id, expression_type);
+    }
+
+    fn get(&self, expression: &ast::Expression) -> Option<&SymbolType> {
+        self.0.get(&expression.id)
+    }
+
+    /// Type of an expression the type checker accepted
+    pub fn of(&self, expression: &ast::Expression) -> SymbolType {
+        self.get(expression)
+            .cloned()
+            .expect("Lowered expressions are typed by the type checker")
+    }
+
+    /// Value of a `sizeof`. The operand is only typed, never evaluated.
+    pub fn size_of(&self, symbol_table: &SymbolTable, size_of: &ast::SizeOfExpression) -> u64 {
+        let operand_type = match &size_of.operand {
+            ast::SizeOfOperand::Type(type_name) => SymbolType::from_type_name(symbol_table, type_name),
+            ast::SizeOfOperand::Expression(expression) => self.of(expression),
+        };
+        operand_type.size() as u64
+    }
+
+    /// `&` of a function designator and `*` of a function pointer give back
+    /// the function pointer, as the designator decays right back to it.
+    /// Only these have the type of their operand.
+    pub fn passes_function_pointer(&self, expression: &ast::Expression) -> bool {
+        match &expression.kind {
+            ast::ExpressionKind::Pointer(pe) => {
+                self.get(expression).is_some_and(|pointer| self.get(&pe.argument) == Some(pointer))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Where a value is converted to another type, used to word diagnostics
+#[derive(Debug, Clone, Copy)]
+enum Conversion {
+    Passing,
+    Returning,
+    Assigning,
+    Initializing,
+}
+
+impl Conversion {
+    fn describe(&self, from: &SymbolType, to: &SymbolType) -> String {
+        match self {
+            Self::Passing => format!("passing '{}' to parameter of type '{}'", from, to),
+            Self::Returning => {
+                format!(
+                    "returning '{}' from a function with result type '{}'",
+                    from, to
+                )
+            }
+            Self::Assigning => format!("assigning to '{}' from '{}'", to, from),
+            Self::Initializing => {
+                format!(
+                    "initializing '{}' with an expression of type '{}'",
+                    to, from
+                )
+            }
+        }
+    }
+}
+
+/// Checks the types of every expression of the function bodies against
+/// the symbol table. Expressions with an error get no type, so a single
+/// mistake is only reported once.
+struct TypeChecker {
+    table: SymbolTableRef,
+    /// None for the declarations of the file scope
+    function: Option<String>,
+    return_type: SymbolType,
+    is_variadic: bool,
+    /// Statement or declarator being checked, expressions have no location
+    location: SourceLocation,
+    diagnostics: Vec<Diagnostic>,
+    types: ExpressionTypes,
+}
+
+impl TypeChecker {
+    fn error(&mut self, message: String) {
+        let error = Diagnostic::error(self.function.clone(), message).at(self.location);
+        self.diagnostics.push(error);
+    }
+
+    fn warning(&mut self, message: String) {
+        let warning = Diagnostic::warning(self.function.clone(), message).at(self.location);
+        self.diagnostics.push(warning);
+    }
+
+    fn check_compound(&mut self, compound: &ast::CompoundStatement) {
+        self.table.borrow_mut().compound_enter();
+        for statement in &compound.items {
+            self.check_statement(statement);
+        }
+        self.table.borrow_mut().compound_exit();
+    }
+
+    fn check_statement(&mut self, statement: &ast::Statement) {
+        let function = self.function.clone().unwrap_or_default();
+        if !matches!(statement, ast::Statement::CompoundStatement(_)) {
+            self.location = statement.location();
+        }
+        match statement {
+            ast::Statement::CompoundStatement(cs) => self.check_compound(cs),
+            ast::Statement::ExpressionStatement(es) => {
+                self.check_expression(&es.expression);
+            }
+            ast::Statement::ReturnStatement(rs) => {
+                let returns_value = !matches!(rs.expression.kind, ast::ExpressionKind::Empty);
+                if self.return_type == SymbolType::Void {
+                    self.check_expression(&rs.expression);
+                    if returns_value {
+                        let message =
+                            format!("void function '{}' should not return a value", function);
+                        self.error(message);
+                    }
+                } else if !returns_value {
+                    let message = format!(
+                        "non-void function '{}' should return a value",
+                        function
+                    );
+                    self.warning(message);
+                } else if let Some(value_type) = self.check_value(&rs.expression) {
+                    let return_type = self.return_type.clone();
+                    self.check_conversion(
+                        &rs.expression,
+                        &value_type,
+                        &return_type,
+                        Conversion::Returning,
+                    );
+                }
+            }
+            ast::Statement::IfStatement(ifs) => {
+                self.check_condition(&ifs.condition);
+                self.check_statement(&ifs.body);
+                if let Some(else_body) = &ifs.else_body {
+                    self.check_statement(else_body);
+                }
+            }
+            ast::Statement::WhileStatement(ws) => {
+                self.check_condition(&ws.condition);
+                self.check_statement(&ws.body);
+            }
+            ast::Statement::Declaration(decl) => {
+                for declarator in &decl.declarators {
+                    let ast::Declarator::InitDeclarator(id) = declarator else {
+                        continue;
+                    };
+                    let is_static = matches!(decl.storage_class, StorageClass::Static);
+                    self.check_initializer(declarator, &id.value, is_static);
+                }
+            }
+        }
+    }
+
+    /// Variables with static storage are initialized at compile time, with
+    /// a constant
+    fn check_initializer(
+        &mut self,
+        declarator: &ast::Declarator,
+        initializer: &ast::Initializer,
+        is_static: bool,
+    ) {
+        self.location = declarator.location();
+        let name = declarator.get_identifier().0;
+        let declared_type = self.table.borrow().query(&name).unwrap().type_info;
+        if declared_type.is_aggregate() {
+            return self.check_aggregate_initializer(&name, initializer, &declared_type, is_static);
+        }
+        let Some(value) = self.check_scalar_initializer(initializer, &declared_type) else {
+            return;
+        };
+        if let Some(value_type) = self.check_value(value) {
+            self.check_initial_value(value, &value_type, &declared_type, is_static);
+        }
+    }
+
+    fn check_initial_value(
+        &mut self,
+        value: &ast::Expression,
+        value_type: &SymbolType,
+        declared_type: &SymbolType,
+        is_static: bool,
+    ) {
+        self.check_conversion(value, value_type, declared_type, Conversion::Initializing);
+        if is_static && !self.is_constant(value) {
+            self.error("initializer element is not a compile-time constant".into());
+        } else if is_static && is_arithmetic_constant(&self.table.borrow(), value) {
+            self.fold_constant(value, declared_type);
+        }
+    }
+
+    /// Checks the values an initializer gives the elements and members of
+    /// an aggregate. An array declared with `[]` gets the length of its
+    /// initializer.
+    fn check_aggregate_initializer(
+        &mut self,
+        name: &str,
+        initializer: &ast::Initializer,
+        declared_type: &SymbolType,
+        is_static: bool,
+    ) {
+        let table = self.table.clone();
+        let mut checked: HashMap<*const ast::Expression, Option<SymbolType>> = HashMap::new();
+        let flat = flatten(&table.borrow(), declared_type, initializer, &mut |expression| {
+            let value_type = self.check_value(expression);
+            checked.insert(expression as *const _, value_type.clone());
+            value_type
+        });
+        for error in flat.errors {
+            self.error(error);
+        }
+        if let SymbolType::Array(element, None) = declared_type {
+            let completed = SymbolType::Array(element.clone(), Some(flat.length));
+            let scope = self.table.borrow().current_scope.clone();
+            if let Some(symbol) = scope.borrow_mut().symbols.get_mut(name) {
+                symbol.type_info = completed;
+            }
+        }
+
+        for value in flat.values.values() {
+            match value {
+                InitialValue::Scalar(member_type, expression) => {
+                    let value_type = match checked.get(&(*expression as *const _)) {
+                        Some(value_type) => value_type.clone(),
+                        None => self.check_value(expression),
+                    };
+                    if let Some(value_type) = value_type {
+                        self.check_initial_value(expression, &value_type, member_type, is_static);
+                    }
+                }
+                InitialValue::Struct(..) if is_static => {
+                    self.error("initializer element is not a compile-time constant".into());
+                }
+                InitialValue::Struct(..) | InitialValue::String(_) => (),
+            }
+        }
+    }
+
+    /// Value of an arithmetic constant expression converted to `target`,
+    /// folded as lowering does. None once it divides by zero, which is
+    /// reported.
+    fn fold_constant(&mut self, expression: &ast::Expression, target: &SymbolType) -> Option<Constant> {
+        let source_type = self.types.of(expression);
+        let value = match &expression.kind {
+            ast::ExpressionKind::NumberLiteral(nl) if nl.is_floating() => Constant::Floating(nl.float_value()),
+            ast::ExpressionKind::NumberLiteral(nl) => {
+                let literal_type = number_literal_type(nl);
+                let value = Constant::Integer(literal_type.wrap(nl.value()));
+                return Some(value.convert(&literal_type, target));
+            }
+            ast::ExpressionKind::SizeOf(so) => Constant::Integer(self.types.size_of(&self.table.borrow(), so) as i64),
+            ast::ExpressionKind::AlignOf(ao) => Constant::Integer(align_of(&self.table.borrow(), ao) as i64),
+            ast::ExpressionKind::Parenthesized(pe) => self.fold_constant(&pe.expression, &source_type)?,
+            ast::ExpressionKind::Cast(cast) => self.fold_constant(&cast.value, &source_type)?,
+            ast::ExpressionKind::Binary(bin) => {
+                let left_type = self.types.of(&bin.left);
+                let right_type = self.types.of(&bin.right);
+                let operation_type = match bin.op {
+                    ast::BinOp::ShiftLeft | ast::BinOp::ShiftRight => left_type.promote(),
+                    _ => SymbolType::common_type(&left_type, &right_type),
+                };
+                let left = self.fold_constant(&bin.left, &operation_type)?;
+                let right = self.fold_constant(&bin.right, &operation_type)?;
+                let op = Op::from_binop(&bin.op, operation_type.signedness());
+                let result = match (left, right) {
+                    (Constant::Integer(left), Constant::Integer(right)) => {
+                        let Some(result) = op.apply_constant(left, right, Width::from_type(&operation_type)) else {
+                            let operation = match bin.op {
+                                ast::BinOp::Rem => "remainder",
+                                _ => "division",
+                            };
+                            self.error(format!("{} by zero in a constant expression", operation));
+                            return None;
+                        };
+                        Constant::Integer(operation_type.wrap(result))
+                    }
+                    // Floating division by zero is an infinity or a NaN
+                    (Constant::Floating(left), Constant::Floating(right)) => match op {
+                        Op::Plus => Constant::Floating(left + right),
+                        Op::Minus => Constant::Floating(left - right),
+                        Op::Mul => Constant::Floating(left * right),
+                        Op::Div | Op::UDiv => Constant::Floating(left / right),
+                        Op::Eq => Constant::Integer((left == right) as i64),
+                        Op::Ne => Constant::Integer((left != right) as i64),
+                        Op::Lt | Op::ULt => Constant::Integer((left < right) as i64),
+                        Op::Le | Op::ULe => Constant::Integer((left <= right) as i64),
+                        Op::Gt | Op::UGt => Constant::Integer((left > right) as i64),
+                        Op::Ge | Op::UGe => Constant::Integer((left >= right) as i64),
+                        _ => unreachable!("Floating operands only take arithmetic and comparisons"),
+                    },
+                    _ => unreachable!("Both operands are converted to the operation type"),
+                };
+                // Comparisons give an int, whatever type they compare in
+                let result_type = if op.is_cmp() { &source_type } else { &operation_type };
+                return Some(result.convert(result_type, target));
+            }
+            _ => unreachable!("Only arithmetic constant expressions are folded"),
+        };
+        Some(value.convert(&source_type, target))
+    }
+
+    /// Constant expressions of C11 6.6: arithmetic ones, and the address
+    /// of a function, of a string literal or of an object with static
+    /// storage, give or take an integer constant
+    fn is_constant(&self, expression: &ast::Expression) -> bool {
+        is_arithmetic_constant(&self.table.borrow(), expression) || self.is_address_constant(expression)
+    }
+
+    fn is_address_constant(&self, expression: &ast::Expression) -> bool {
+        let is_function = |e| self.types.get(e).is_some_and(|t| t.callee_function().is_some());
+        let is_integer = |e| self.is_integer_constant(e);
+        match &expression.kind {
+            ast::ExpressionKind::StringLiteral(_) => true,
+            // Arrays decay to the address of their first element
+            ast::ExpressionKind::Identifier(_) | ast::ExpressionKind::Subscript(_) | ast::ExpressionKind::Member(_)
+                if matches!(self.types.get(expression), Some(SymbolType::Array(..))) =>
+            {
+                self.is_static_object(expression)
+            }
+            ast::ExpressionKind::Identifier(_) => is_function(expression),
+            ast::ExpressionKind::Parenthesized(pe) => self.is_address_constant(&pe.expression),
+            // Addresses only fit pointers and integers of their size
+            ast::ExpressionKind::Cast(cast) => {
+                SymbolType::from_type_name(&self.table.borrow(), &cast.type_name).size() == 8
+                    && self.is_address_constant(&cast.value)
+            }
+            ast::ExpressionKind::Pointer(pe) if self.types.passes_function_pointer(expression) => {
+                self.is_address_constant(&pe.argument)
+            }
+            ast::ExpressionKind::Pointer(pe) => {
+                matches!(pe.op, ast::PointerOp::AddressOf) && self.is_static_object(&pe.argument)
+            }
+            ast::ExpressionKind::Binary(bin) => match bin.op {
+                ast::BinOp::Plus => {
+                    (self.is_address_constant(&bin.left) && is_integer(&bin.right))
+                        || (is_integer(&bin.left) && self.is_address_constant(&bin.right))
+                }
+                ast::BinOp::Minus => self.is_address_constant(&bin.left) && is_integer(&bin.right),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn is_integer_constant(&self, expression: &ast::Expression) -> bool {
+        is_arithmetic_constant(&self.table.borrow(), expression)
+            && self.types.get(expression).is_some_and(|t| !t.is_floating())
+    }
+
+    /// Lvalues designating an object with static storage, or part of one,
+    /// at a constant offset
+    fn is_static_object(&self, expression: &ast::Expression) -> bool {
+        match &expression.kind {
+            ast::ExpressionKind::Identifier(id) => self.has_static_storage(&id.0),
+            ast::ExpressionKind::Parenthesized(pe) => self.is_static_object(&pe.expression),
+            ast::ExpressionKind::Subscript(subscript) => {
+                (self.is_address_constant(&subscript.array) && self.is_integer_constant(&subscript.index))
+                    || (self.is_integer_constant(&subscript.array) && self.is_address_constant(&subscript.index))
+            }
+            ast::ExpressionKind::Member(member) if member.through_pointer => self.is_address_constant(&member.object),
+            ast::ExpressionKind::Member(member) => self.is_static_object(&member.object),
+            ast::ExpressionKind::Pointer(pe) => {
+                matches!(pe.op, ast::PointerOp::Dereference) && self.is_address_constant(&pe.argument)
+            }
+            _ => false,
+        }
+    }
+
+    /// Variables whose address is known at link time
+    fn has_static_storage(&self, name: &str) -> bool {
+        let table = self.table.borrow();
+        table.query(name).is_some_and(|symbol| {
+            matches!(symbol.storage_class, StorageClass::Extern | StorageClass::Static)
+                || table.is_file_scope(name)
+        })
+    }
+
+    /// Checks the braces around the initializer of a scalar, C11 6.7.9p11,
+    /// giving the expression they hold. None for errors and for the empty
+    /// braces of C23 6.7.10p11.
+    fn check_scalar_initializer<'a>(
+        &mut self,
+        initializer: &'a ast::Initializer,
+        declared_type: &SymbolType,
+    ) -> Option<&'a ast::Expression> {
+        match initializer.scalar() {
+            Ok(value) => value,
+            Err(ast::ScalarInitializerError::Designator) => {
+                self.error(format!(
+                    "designator in initializer for scalar type '{}'",
+                    declared_type
+                ));
+                None
+            }
+            Err(ast::ScalarInitializerError::ExcessElements) => {
+                self.error(format!(
+                    "excess elements in initializer for scalar type '{}'",
+                    declared_type
+                ));
+                None
+            }
+        }
+    }
+
+    fn check_condition(&mut self, condition: &ast::ParenthesizedExpression) {
+        let condition_type = self.check_value(&condition.expression);
+        if let Some(SymbolType::Struct(_)) = condition_type {
+            self.error(format!(
+                "statement requires expression of scalar type ('{}' invalid)",
+                condition_type.unwrap()
+            ));
+        } else if let Some(condition_type) = condition_type
+            && condition_type.callee_function().is_some()
+        {
+            self.warning(format!(
+                "address of function will always evaluate to 'true' in a condition of type '{}'",
+                condition_type
+            ));
+        }
+    }
+
+    /// Checks an expression whose value is used. Arrays give the pointer
+    /// to their first element.
+    fn check_value(&mut self, expression: &ast::Expression) -> Option<SymbolType> {
+        match &expression.kind {
+            ast::ExpressionKind::Parenthesized(pe) => {
+                let value_type = self.check_value(&pe.expression)?;
+                // Keeps the type before decay, which `sizeof` and `&` use
+                let inner_type = self.types.get(&pe.expression).cloned()?;
+                self.types.annotate(expression, inner_type);
+                return Some(value_type);
+            }
+            ast::ExpressionKind::Variadic(variadic)
+                if !matches!(variadic, ast::VariadicExpression::Arg { .. }) =>
+            {
+                self.check_expression(expression);
+                self.error("va_start, va_end and va_copy have no value".into());
+                return None;
+            }
+            _ => (),
+        }
+        let value_type = self.check_expression(expression)?;
+        if value_type == SymbolType::Void {
+            self.error("void value not ignored as it ought to be".into());
+            return None;
+        }
+        Some(value_type.decay())
+    }
+
+    /// Checks an expression, annotating it with its type when valid
+    fn check_expression(&mut self, expression: &ast::Expression) -> Option<SymbolType> {
+        let expression_type = self.check_expression_kind(expression)?;
+        self.types.annotate(expression, expression_type.clone());
+        Some(expression_type)
+    }
+
+    fn check_expression_kind(&mut self, expression: &ast::Expression) -> Option<SymbolType> {
+        match &expression.kind {
+            ast::ExpressionKind::Identifier(id) => {
+                let Some(symbol) = self.table.borrow().query(&id.0) else {
+                    self.error(format!("use of undeclared identifier '{}'", id.0));
+                    return None;
+                };
+                Some(symbol.identifier_type())
+            }
+            ast::ExpressionKind::NumberLiteral(nl) => Some(number_literal_type(nl)),
+            ast::ExpressionKind::StringLiteral(_) => {
+                Some(SymbolType::Pointer(Box::new(SymbolType::Char(Signedness::Signed))))
+            }
+            ast::ExpressionKind::AlignOf(ao) => {
+                let type_name = self.check_type_name(&ao.type_name)?;
+                self.check_sized(&type_name, "_Alignof")?;
+                Some(SymbolType::Long(Signedness::Unsigned))
+            }
+            ast::ExpressionKind::SizeOf(so) => {
+                let operand_type = match &so.operand {
+                    ast::SizeOfOperand::Expression(operand) => {
+                        self.check_value(operand)?;
+                        self.types.of(operand)
+                    }
+                    ast::SizeOfOperand::Type(type_name) => self.check_type_name(type_name)?,
+                };
+                self.check_sized(&operand_type, "sizeof")?;
+                Some(SymbolType::Long(Signedness::Unsigned))
+            }
+            ast::ExpressionKind::Subscript(subscript) => self.check_subscript(subscript),
+            ast::ExpressionKind::Member(member) => self.check_member(member),
+            ast::ExpressionKind::Parenthesized(pe) => self.check_expression(&pe.expression),
+            ast::ExpressionKind::Binary(bin) => {
+                let left = self.check_value(&bin.left);
+                let right = self.check_value(&bin.right);
+                let (left, right) = (left?, right?);
+                self.check_binary(&bin.op, &left, &right, &bin.left, &bin.right)?;
+                Some(SymbolType::binary_result(&bin.op, &left, &right))
+            }
+            ast::ExpressionKind::Assignment(asn) => {
+                let lvalue_type = self.check_lvalue(&asn.lvalue);
+                let rvalue = self.check_value(&asn.rvalue);
+                let (lvalue_type, rvalue) = (lvalue_type?, rvalue?);
+                match asn.atype.to_op() {
+                    None => self.check_conversion(
+                        &asn.rvalue,
+                        &rvalue,
+                        &lvalue_type,
+                        Conversion::Assigning,
+                    ),
+                    Some(op) => {
+                        // Stands for the object, never a null pointer constant
+                        let lvalue = ast::Expression::new(ast::ExpressionKind::Empty, self.location);
+                        self.check_binary(&op, &lvalue_type, &rvalue, &lvalue, &asn.rvalue)?;
+                    }
+                }
+                Some(lvalue_type)
+            }
+            ast::ExpressionKind::Call(ce) => self.check_call(ce),
+            ast::ExpressionKind::Cast(cast) => {
+                let cast_type = self.check_type_name(&cast.type_name)?;
+                // Any value, even a void one, can be discarded
+                if cast_type == SymbolType::Void {
+                    self.check_expression(&cast.value);
+                    return Some(cast_type);
+                }
+                if let SymbolType::Function { .. } | SymbolType::Array(..) | SymbolType::Struct(_) = cast_type {
+                    self.check_expression(&cast.value);
+                    self.error(format!(
+                        "used type '{}' where arithmetic or pointer type is required",
+                        cast_type
+                    ));
+                    return None;
+                }
+                let value_type = self.check_value(&cast.value)?;
+                if let SymbolType::Struct(_) = value_type {
+                    self.error(format!(
+                        "operand of type '{}' where arithmetic or pointer type is required",
+                        value_type
+                    ));
+                    return None;
+                }
+                if (value_type.is_floating() && matches!(cast_type, SymbolType::Pointer(_)))
+                    || (cast_type.is_floating() && matches!(value_type, SymbolType::Pointer(_)))
+                {
+                    self.error(format!("cannot cast '{}' to '{}'", value_type, cast_type));
+                    return None;
+                }
+                Some(cast_type)
+            }
+            ast::ExpressionKind::Pointer(pe) => {
+                let argument_type = self.check_value(&pe.argument)?;
+                match &pe.op {
+                    ast::PointerOp::Dereference => match self.check_dereference(&argument_type)? {
+                        // `*fp` designates the function, which decays right back
+                        SymbolType::Function { .. } => Some(argument_type),
+                        pointee => Some(pointee),
+                    },
+                    ast::PointerOp::AddressOf => {
+                        // `f` and `*fp` designate functions, whose address is taken like an lvalue's
+                        if !is_lvalue(&pe.argument) {
+                            self.error("cannot take the address of an rvalue".into());
+                            return None;
+                        }
+                        if self.is_function_designator(&pe.argument) {
+                            return Some(argument_type);
+                        }
+                        Some(SymbolType::Pointer(Box::new(self.types.of(&pe.argument))))
+                    }
+                }
+            }
+            ast::ExpressionKind::Variadic(variadic) => self.check_variadic(variadic),
+            ast::ExpressionKind::Empty => None,
+        }
+    }
+
+    /// Checks the object an assignment writes to, giving its type
+    fn check_lvalue(&mut self, lvalue: &ast::LValue) -> Option<SymbolType> {
+        match lvalue {
+            ast::LValue::Identifier(id) => {
+                let Some(symbol) = self.table.borrow().query(&id.0) else {
+                    self.error(format!("use of undeclared identifier '{}'", id.0));
+                    return None;
+                };
+                if let SymbolKind::Function { .. } = symbol.kind {
+                    self.error(format!("cannot assign to function '{}'", id.0));
+                    return None;
+                }
+                self.check_assignable(symbol.type_info)
+            }
+            ast::LValue::Subscript(subscript) => {
+                let element = self.check_subscript(subscript)?;
+                self.check_assignable(element)
+            }
+            ast::LValue::Member(member) => {
+                let member_type = self.check_member(member)?;
+                self.check_assignable(member_type)
+            }
+            ast::LValue::Dereference(pointer) => {
+                let pointer_type = self.check_value(pointer)?;
+                let pointee = self.check_dereference(&pointer_type)?;
+                if let SymbolType::Function { .. } = pointee {
+                    self.error(format!("non-object type '{}' is not assignable", pointee));
+                    return None;
+                }
+                Some(pointee)
+            }
+        }
+    }
+
+    /// Arrays are objects, but not ones an assignment can write
+    fn check_assignable(&mut self, object_type: SymbolType) -> Option<SymbolType> {
+        if let SymbolType::Array(..) = object_type {
+            self.error(format!("array type '{}' is not assignable", object_type));
+            return None;
+        }
+        Some(object_type)
+    }
+
+    /// `array[index]`, which is `*(array + index)` so either operand may
+    /// be the pointer, C11 6.5.2.1
+    fn check_subscript(&mut self, subscript: &ast::SubscriptExpression) -> Option<SymbolType> {
+        let array = self.check_value(&subscript.array);
+        let index = self.check_value(&subscript.index);
+        let (array, index) = (array?, index?);
+        let (pointer, index) = match (&array, &index) {
+            (SymbolType::Pointer(_), _) => (&array, &index),
+            (_, SymbolType::Pointer(_)) => (&index, &array),
+            _ => {
+                self.error("subscripted value is not an array or pointer".into());
+                return None;
+            }
+        };
+        if index.is_floating() || matches!(index, SymbolType::Pointer(_) | SymbolType::Struct(_)) {
+            self.error("array subscript is not an integer".into());
+            return None;
+        }
+        let element = self.check_dereference(pointer)?;
+        if !element.is_complete() || matches!(element, SymbolType::Function { .. }) {
+            self.error(format!(
+                "subscript of pointer to incomplete type '{}'",
+                element
+            ));
+            return None;
+        }
+        Some(element)
+    }
+
+    /// `object.member` of a struct, or `pointer->member` of the struct a
+    /// pointer points to
+    fn check_member(&mut self, member: &ast::MemberExpression) -> Option<SymbolType> {
+        let object_type = self.check_expression(&member.object)?;
+        let base_type = match (&object_type, member.through_pointer) {
+            (SymbolType::Pointer(pointee), true) => pointee.as_ref().clone(),
+            (SymbolType::Pointer(_), false) => {
+                self.error(format!(
+                    "member reference type '{}' is a pointer; did you mean to use '->'?",
+                    object_type
+                ));
+                return None;
+            }
+            (_, true) => {
+                self.error(format!(
+                    "member reference type '{}' is not a pointer",
+                    object_type.decay()
+                ));
+                return None;
+            }
+            (_, false) => object_type,
+        };
+        let SymbolType::Struct(struct_type) = &base_type else {
+            self.error(format!(
+                "member reference base type '{}' is not a structure or union",
+                base_type.decay()
+            ));
+            return None;
+        };
+        if struct_type.members().is_none() {
+            self.error(format!("incomplete definition of type 'struct {}'", struct_type.name));
+            return None;
+        }
+        match struct_type.member(&member.member.0) {
+            Some(found) => Some(found.member_type.clone()),
+            None => {
+                self.error(format!(
+                    "no member named '{}' in 'struct {}'",
+                    member.member.0, struct_type.name
+                ));
+                None
+            }
+        }
+    }
+
+    /// Type of the object `*` gives from a value of type `pointer_type`
+    fn check_dereference(&mut self, pointer_type: &SymbolType) -> Option<SymbolType> {
+        match pointer_type {
+            _ if pointer_type.is_void_pointer() => {
+                self.error(format!(
+                    "indirection requires a pointer to an object type ('{}' invalid)",
+                    pointer_type
+                ));
+                None
+            }
+            SymbolType::Pointer(pointee) => Some(pointee.as_ref().clone()),
+            _ => {
+                self.error(format!(
+                    "indirection requires pointer operand ('{}' invalid)",
+                    pointer_type
+                ));
+                None
+            }
+        }
+    }
+
+    fn check_binary(
+        &mut self,
+        op: &ast::BinOp,
+        left: &SymbolType,
+        right: &SymbolType,
+        left_expression: &ast::Expression,
+        right_expression: &ast::Expression,
+    ) -> Option<()> {
+        let is_pointer = |t: &SymbolType| matches!(t, SymbolType::Pointer(_));
+        let is_struct = |t: &SymbolType| matches!(t, SymbolType::Struct(_));
+        let invalid = is_struct(left) || is_struct(right) || match op {
+            ast::BinOp::Plus | ast::BinOp::Minus => {
+                let is_minus = matches!(op, ast::BinOp::Minus);
+                (is_pointer(left) && right.is_floating())
+                    || (is_pointer(right) && left.is_floating())
+                    || (!is_minus && is_pointer(left) && is_pointer(right))
+                    // Only pointers to the same type are subtracted, and
+                    // nothing is subtracted from an integer
+                    || (is_minus && is_pointer(left) && is_pointer(right) && left != right)
+                    || (is_minus && !is_pointer(left) && is_pointer(right))
+            }
+            ast::BinOp::Mul | ast::BinOp::Div => is_pointer(left) || is_pointer(right),
+            ast::BinOp::Rem | ast::BinOp::ShiftLeft | ast::BinOp::ShiftRight => [left, right]
+                .iter()
+                .any(|t| is_pointer(t) || t.is_floating()),
+            ast::BinOp::Gt
+            | ast::BinOp::Lt
+            | ast::BinOp::Ge
+            | ast::BinOp::Le
+            | ast::BinOp::Eq
+            | ast::BinOp::Ne => {
+                if (is_pointer(left) && right.is_floating())
+                    || (is_pointer(right) && left.is_floating())
+                {
+                    true
+                } else {
+                    if is_pointer(left) != is_pointer(right)
+                        && !is_null_pointer_constant(left_expression)
+                        && !is_null_pointer_constant(right_expression)
+                    {
+                        self.warning(format!(
+                            "comparison between pointer and integer ('{}' and '{}')",
+                            left, right
+                        ));
+                    } else if is_pointer(left) && is_pointer(right) && !compatible_pointers(left, right) {
+                        self.warning(format!(
+                            "comparison of distinct pointer types ('{}' and '{}')",
+                            left, right
+                        ));
+                    }
+                    false
+                }
+            }
+        };
+
+        if invalid {
+            self.error(format!(
+                "invalid operands to binary expression ('{}' and '{}')",
+                left, right
+            ));
+            return None;
+        }
+        // Pointer arithmetic counts in pointees, which functions have no size for
+        if matches!(op, ast::BinOp::Plus | ast::BinOp::Minus)
+            && let Some(function) = [left, right].into_iter().find_map(|t| match t {
+                SymbolType::Pointer(pointee) if matches!(pointee.as_ref(), SymbolType::Function { .. }) => {
+                    Some(pointee)
+                }
+                _ => None,
+            })
+        {
+            self.error(format!("arithmetic on a pointer to the function type '{}'", function));
+            return None;
+        }
+        // nor do structs declared but not defined
+        if matches!(op, ast::BinOp::Plus | ast::BinOp::Minus)
+            && let Some(incomplete) = [left, right].into_iter().find_map(|t| match t {
+                SymbolType::Pointer(pointee) if matches!(pointee.as_ref(), SymbolType::Struct(_)) && !pointee.is_complete() => {
+                    Some(pointee)
+                }
+                _ => None,
+            })
+        {
+            self.error(format!("arithmetic on a pointer to an incomplete type '{}'", incomplete));
+            return None;
+        }
+        Some(())
+    }
+
+    /// Function types have no size nor alignment to take
+    fn check_sized(&mut self, operand_type: &SymbolType, operator: &str) -> Option<()> {
+        if let SymbolType::Function { .. } = operand_type {
+            self.error(format!("invalid application of '{}' to a function type", operator));
+            return None;
+        }
+        // `sizeof(void)` is 1 in GNU C
+        if !operand_type.is_complete() && *operand_type != SymbolType::Void {
+            self.error(format!(
+                "invalid application of '{}' to an incomplete type '{}'",
+                operator, operand_type
+            ));
+            return None;
+        }
+        Some(())
+    }
+
+    /// Type a type name spells, whose arrays must have a constant length
+    fn check_type_name(&mut self, type_name: &ast::TypeName) -> Option<SymbolType> {
+        let table = self.table.clone();
+        let table = table.borrow();
+        let base = SymbolType::try_from((&type_name.dtype, &*table)).unwrap();
+        match SymbolType::try_from((type_name.declarator.as_ref(), &base, &*table)) {
+            Ok(spelled) => Some(spelled),
+            Err(error) => {
+                self.error(error.to_string());
+                None
+            }
+        }
+    }
+
+    fn check_call(&mut self, ce: &ast::CallExpression) -> Option<SymbolType> {
+        let callee = match ce.get_identifier() {
+            Some(id) if self.table.borrow().query(&id.0).is_none() => {
+                self.error(format!("call to undeclared function '{}'", id.0));
+                None
+            }
+            _ => self.check_value(&ce.function),
+        };
+        let arguments: Vec<_> = ce
+            .arguments
+            .iter()
+            .map(|arg| self.check_value(arg))
+            .collect();
+        let callee = callee?;
+
+        let Some(SymbolType::Function {
+            return_type,
+            parameters,
+            is_variadic,
+            has_prototype,
+        }) = callee.callee_function().cloned()
+        else {
+            self.error(format!(
+                "called object type '{}' is not a function or function pointer",
+                callee
+            ));
+            return None;
+        };
+
+        if !has_prototype {
+            // The parameters are unknown without a prototype
+        } else if ce.arguments.len() < parameters.len() {
+            self.error(format!(
+                "too few arguments to function call, expected {}{}, have {}",
+                if is_variadic { "at least " } else { "" },
+                parameters.len(),
+                ce.arguments.len()
+            ));
+        } else if !is_variadic && ce.arguments.len() > parameters.len() {
+            self.error(format!(
+                "too many arguments to function call, expected {}, have {}",
+                parameters.len(),
+                ce.arguments.len()
+            ));
+        }
+
+        for ((argument, argument_type), parameter) in ce
+            .arguments
+            .iter()
+            .zip(arguments.iter())
+            .zip(parameters.iter())
+        {
+            if let Some(argument_type) = argument_type {
+                self.check_conversion(argument, argument_type, parameter, Conversion::Passing);
+            }
+        }
+        // Parameters of struct type are reported with the declaration
+        let unmatched = arguments.iter().skip(if has_prototype { parameters.len() } else { 0 });
+        if unmatched.flatten().any(|argument| matches!(argument, SymbolType::Struct(_))) {
+            self.error("struct arguments are not supported yet".into());
+        }
+
+        Some(*return_type)
+    }
+
+    fn check_variadic(&mut self, variadic: &ast::VariadicExpression) -> Option<SymbolType> {
+        let lists = match variadic {
+            ast::VariadicExpression::Start { list }
+            | ast::VariadicExpression::Arg { list, .. }
+            | ast::VariadicExpression::End { list } => vec![list],
+            ast::VariadicExpression::Copy { dest, source } => vec![dest, source],
+        };
+        let va_list = SymbolType::try_from((&ast::DataType::VaList, &*self.table.borrow())).unwrap();
+        let mut valid = true;
+        for list in lists {
+            let symbol = self.table.borrow().query(&list.0);
+            match symbol {
+                None => {
+                    self.error(format!("use of undeclared identifier '{}'", list.0));
+                    valid = false;
+                }
+                Some(symbol) if symbol.type_info != va_list => {
+                    self.error(format!("'{}' is not a va_list", list.0));
+                    valid = false;
+                }
+                Some(_) => (),
+            }
+        }
+
+        match variadic {
+            ast::VariadicExpression::Start { .. } if !self.is_variadic => {
+                self.error("'va_start' used in function with fixed arguments".into());
+                None
+            }
+            ast::VariadicExpression::Arg { type_name, .. } if valid => {
+                let value_type = self.check_type_name(type_name)?;
+                if value_type == SymbolType::Float || value_type.promote() != value_type {
+                    self.warning(format!(
+                        "second argument to 'va_arg' is of promotable type '{}', \
+                         the behavior is undefined",
+                        value_type
+                    ));
+                }
+                Some(value_type)
+            }
+            _ => None,
+        }
+    }
+
+    /// Implicit conversion of a value of type `from` to `to`, C11 6.5.16.1
+    fn check_conversion(
+        &mut self,
+        expression: &ast::Expression,
+        from: &SymbolType,
+        to: &SymbolType,
+        conversion: Conversion,
+    ) {
+        let context = conversion.describe(from, to);
+        match (from, to) {
+            (SymbolType::Struct(_), _) | (_, SymbolType::Struct(_)) if from != to => {
+                self.error(format!("incompatible types {}", context));
+            }
+            (SymbolType::Pointer(_), SymbolType::Pointer(_)) if !compatible_pointers(from, to) => {
+                self.warning(format!("incompatible pointer types {}", context));
+            }
+            (SymbolType::Pointer(_), SymbolType::Pointer(_)) => (),
+            (SymbolType::Pointer(_), _) if to.is_floating() => {
+                self.error(format!("incompatible types {}", context));
+            }
+            (_, SymbolType::Pointer(_)) if from.is_floating() => {
+                self.error(format!("incompatible types {}", context));
+            }
+            (SymbolType::Pointer(_), _) => {
+                self.error(format!(
+                    "incompatible pointer to integer conversion {}",
+                    context
+                ));
+            }
+            (_, SymbolType::Pointer(_)) if !is_null_pointer_constant(expression) => {
+                self.error(format!(
+                    "incompatible integer to pointer conversion {}",
+                    context
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    /// An expression of function type, C11 6.3.2.1: a function name, or the
+    /// dereference of a function pointer. Both are typed as the pointer
+    /// they decay to.
+    fn is_function_designator(&self, expression: &ast::Expression) -> bool {
+        match &expression.kind {
+            ast::ExpressionKind::Identifier(id) => self
+                .table
+                .borrow()
+                .query(&id.0)
+                .is_some_and(|symbol| matches!(symbol.kind, SymbolKind::Function { .. })),
+            ast::ExpressionKind::Parenthesized(pe) => self.is_function_designator(&pe.expression),
+            ast::ExpressionKind::Pointer(pe) => {
+                matches!(pe.op, ast::PointerOp::Dereference) && self.types.passes_function_pointer(expression)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Pointers that convert to each other without a cast: the same type, or
+/// `void *` and an object pointer, C11 6.3.2.3
+fn compatible_pointers(left: &SymbolType, right: &SymbolType) -> bool {
+    let is_object_pointer = |t: &SymbolType| t.callee_function().is_none();
+    left == right
+        || (left.is_void_pointer() && is_object_pointer(right))
+        || (right.is_void_pointer() && is_object_pointer(left))
+}
+
+/// Arithmetic constant expression, C11 6.6p8
+fn is_arithmetic_constant(symbol_table: &SymbolTable, expression: &ast::Expression) -> bool {
+    match &expression.kind {
+        ast::ExpressionKind::NumberLiteral(_)
+        | ast::ExpressionKind::SizeOf(_)
+        | ast::ExpressionKind::AlignOf(_) => true,
+        ast::ExpressionKind::Parenthesized(pe) => is_arithmetic_constant(symbol_table, &pe.expression),
+        ast::ExpressionKind::Cast(cast) => {
+            !matches!(SymbolType::from_type_name(symbol_table, &cast.type_name), SymbolType::Pointer(_))
+                && is_arithmetic_constant(symbol_table, &cast.value)
+        }
+        ast::ExpressionKind::Binary(bin) => {
+            is_arithmetic_constant(symbol_table, &bin.left) && is_arithmetic_constant(symbol_table, &bin.right)
+        }
+        _ => false,
+    }
+}
+
+/// Value of an arithmetic constant expression
+#[derive(Debug, Clone, Copy)]
+enum Constant {
+    Integer(i64),
+    Floating(f64),
+}
+
+impl Constant {
+    /// Converts the value from `from` to `to`, as `convert_constant` of
+    /// lowering does
+    fn convert(self, from: &SymbolType, to: &SymbolType) -> Self {
+        let floating = |value: f64| match to {
+            SymbolType::Float => Self::Floating(value as f32 as f64),
+            _ => Self::Floating(value),
+        };
+        match self {
+            Self::Integer(n) if to.is_floating() => match from.signedness() {
+                Signedness::Signed => floating(n as f64),
+                Signedness::Unsigned => floating(n as u64 as f64),
+            },
+            Self::Integer(n) => Self::Integer(to.wrap(n)),
+            Self::Floating(value) if to.is_floating() => floating(value),
+            Self::Floating(value) => match to.signedness() {
+                Signedness::Signed => Self::Integer(to.wrap(value as i64)),
+                Signedness::Unsigned => Self::Integer(to.wrap(value as u64 as i64)),
+            },
+        }
+    }
+}
+
+/// Expressions designating an object, whose address can be taken
+fn is_lvalue(expression: &ast::Expression) -> bool {
+    match &expression.kind {
+        ast::ExpressionKind::Identifier(_) | ast::ExpressionKind::Subscript(_) | ast::ExpressionKind::Member(_) => true,
+        ast::ExpressionKind::Pointer(pe) => matches!(pe.op, ast::PointerOp::Dereference),
+        ast::ExpressionKind::Parenthesized(pe) => is_lvalue(&pe.expression),
+        _ => false,
+    }
+}
+
+/// An integer constant with the value 0, C11 6.3.2.3
+fn is_null_pointer_constant(expression: &ast::Expression) -> bool {
+    match &expression.kind {
+        ast::ExpressionKind::NumberLiteral(nl) => !nl.is_floating() && nl.value() == 0,
+        ast::ExpressionKind::Parenthesized(pe) => is_null_pointer_constant(&pe.expression),
+        _ => false,
+    }
+}
+
+/// Type checks the function bodies of the unit and the initializers of
+/// its globals, giving the types of the checked expressions
+pub fn check_types(
+    unit: &ast::TranslationUnit,
+    symbol_table: SymbolTableRef,
+) -> (Vec<Diagnostic>, ExpressionTypes) {
+    let global_scope = symbol_table.borrow().global_scope.clone();
+    let mut globals = TypeChecker {
+        table: symbol_table.borrow().new_with_scope(global_scope),
+        function: None,
+        return_type: SymbolType::Void,
+        is_variadic: false,
+        location: SourceLocation { line: 1, column: 1 },
+        diagnostics: vec![],
+        types: ExpressionTypes::default(),
+    };
+    for item in &unit.items {
+        let ast::TopLevelItem::Declaration(decl) = item else {
+            continue;
+        };
+        for declarator in &decl.declarators {
+            if let ast::Declarator::InitDeclarator(id) = declarator {
+                globals.check_initializer(declarator, &id.value, true);
+            }
+        }
+    }
+
+    let mut diagnostics = globals.diagnostics;
+    let mut types = globals.types;
+    for item in &unit.items {
+        let ast::TopLevelItem::FunctionDefinition(fd) = item else {
+            continue;
+        };
+        let function = fd.declarator.get_identifier().0;
+        let context = symbol_table.borrow().function_scope(&function, fd.declarator.location()).unwrap();
+
+        let return_type = symbol_table.borrow().query(&function).unwrap().type_info;
+        let is_variadic = fd.declarator.function_declarator().is_variadic;
+        let mut checker = TypeChecker {
+            table: symbol_table
+                .borrow()
+                .new_with_scope(context)
+                .borrow()
+                .fake_parent(),
+            function: Some(function),
+            return_type,
+            is_variadic,
+            location: fd.declarator.location(),
+            diagnostics: vec![],
+            types,
+        };
+        checker.check_compound(&fd.body);
+        diagnostics.extend(checker.diagnostics);
+        types = checker.types;
+    }
+    (diagnostics, types)
+}
@@ -4,25 +4,33 @@ from dataclasses import dataclass
 from tempfile import TemporaryDirectory
 import subprocess
 import argparse
+import platform
 
 parser = argparse.ArgumentParser()
 parser.add_argument("--program")
+parser.add_argument("--interpret", action="store_true", help="run programs with the IR interpreter")
 args = parser.parse_args()
 
+# Without an arm64 Mac to run the assembly on, programs are interpreted instead
+interpret = args.interpret or not (platform.system() == "Darwin" and platform.machine() == "arm64")
+
 programs = Path("programs")
 compiler_bin = "../target/release/c-compiler"
 
 @dataclass
 class Program:
     source: str
-    output: str
+    output: str | None
+    diagnostics: str | None
 
 def parse_program(inp: Path) -> Program:
     c_source = ""
-    expected_output = ""
+    expected_output = None
+    expected_diagnostics = None
 
     reading_output = False
     reading_source = False
+    reading_diagnostics = False
 
     with open(inp) as f:
         for line in f:
@@ -35,24 +43,59 @@ def parse_program(inp: Path) -> Program:
                 continue
             if stripped == "// === Output ===":
                 reading_output = True
+                expected_output = ""
                 continue
             if stripped == "// === End Output ===":
                 reading_output = False
                 continue
+            if stripped == "// === Diagnostics ===":
+                reading_diagnostics = True
+                expected_diagnostics = ""
+                continue
+            if stripped == "// === End Diagnostics ===":
+                reading_diagnostics = False
+                continue
             if reading_output:
                 expected_output += line.lstrip("// ")
+            if reading_diagnostics:
+                expected_diagnostics += line.removeprefix("// ")
             if reading_source:
                 c_source += line
-    return Program(c_source, expected_output)
+    return Program(c_source, expected_output, expected_diagnostics)
 
-def compile(source_file, asm_file) -> int:
+def compile(source_file, asm_file) -> tuple[int, str]:
     result = subprocess.run(
         [compiler_bin, "-i", source_file, "-o", asm_file],
         capture_output=True,
         text=True
     )
+    return result.returncode, result.stderr
+
+def emit_ir(source_file, ir_file) -> int:
+    result = subprocess.run(
+        [compiler_bin, "-i", source_file, "--emit", "ir", "-o", ir_file],
+        capture_output=True,
+        text=True
+    )
     return result.returncode
 
+def round_trip_ir(source_file, td) -> str | None:
+    """Reads back the IR dump of a program, which must print the same"""
+    dump_file = Path(td) / "dump.ir"
+    if emit_ir(source_file, dump_file) != 0:
+        return "failed to dump its IR"
+    result = subprocess.run(
+        [compiler_bin, "-i", dump_file, "--dump-ir", "-o", Path(td) / "dump.asm"],
+        capture_output=True,
+        text=True
+    )
+    if result.returncode != 0:
+        return f"failed to read back its IR dump: {result.stderr}"
+    read_back = result.stdout.split("--- IR ---\n")[1].split("--- IR OPT ---")[0]
+    if read_back != dump_file.read_text() + "\n":
+        return "changed its IR dump when reading it back"
+    return None
+
 def compile_asm(asm_file, exe_file) -> int:
     result = subprocess.run(
         ["clang", asm_file, "-o", exe_file],
@@ -61,10 +104,10 @@ def compile_asm(asm_file, exe_file) -> int:
     )
     return result.returncode
 
-def compare_output(exe_file, expected_output):
+def compare_output(command, expected_output):
     try:
         result = subprocess.run(
-            [exe_file],
+            command,
             capture_output=True,
             text=True,
             timeout=10  # Prevent infinite loops
@@ -81,7 +124,7 @@ def compare_output(exe_file, expected_output):
     except subprocess.TimeoutExpired:
         return False, "Execution timed out", ""
     except FileNotFoundError:
-        return False, f"Executable not found: {exe_file}", ""
+        return False, f"Executable not found: {command[0]}", ""
     except Exception as e:
         return False, f"Execution failed: {e}", ""
 
@@ -96,7 +139,8 @@ for p in progs:
     error = ""
 
     with TemporaryDirectory() as td:
-        source_file = Path(td) / "source.c"
+        # IR programs go straight to the optimizer and the backend
+        source_file = Path(td) / ("source" + Path(p).suffix)
         out_file = Path(td) / "out.txt"
         asm_file = Path(td) / "out.asm"
         exe_file = Path(td) / "out.exe"
@@ -105,17 +149,41 @@ for p in progs:
         with open(out_file, "w") as f: 
             f.write(program.source)
 
-        if compile(source_file, asm_file) != 0:
+        returncode, diagnostics = compile(source_file, asm_file)
+
+        if program.diagnostics is not None and diagnostics != program.diagnostics:
+            print(f"❌ Program {p} reported unexpected diagnostics")
+            print("Expected diagnostics")
+            print(program.diagnostics)
+            print("Diagnostics")
+            print(diagnostics)
+            failed += 1
+            continue
+
+        # Programs without an output are expected to be rejected
+        if program.output is None:
+            if returncode == 0:
+                print(f"❌ Program {p} compiled, but should have failed")
+                failed += 1
+                continue
+            print(f"✅ Test succeded: {p}")
+            continue
+
+        if returncode != 0:
             print(f"❌ Program {p} failed to compile c")
             failed += 1
             continue
 
-        if compile_asm(asm_file, exe_file) != 0:
+        if interpret:
+            command = [compiler_bin, "-i", source_file, "--emit", "run"]
+        elif compile_asm(asm_file, exe_file) != 0:
             print(f"❌ Program {p} failed to compile assembly")
             failed += 1
             continue
+        else:
+            command = [exe_file]
 
-        ok, err, output = compare_output(exe_file, program.output)
+        ok, err, output = compare_output(command, program.output)
         if not ok:
             print(f"❌ Program {p} failed to output, because", err)
             print("Expected output")
@@ -124,6 +192,17 @@ for p in progs:
             print(output)
             failed += 1
             continue
+
+        # Every IR dump reads back to the same program
+        err = round_trip_ir(source_file, td)
+        if err is None:
+            command = [compiler_bin, "-i", Path(td) / "dump.ir", "--emit", "run"]
+            ok, err, _ = compare_output(command, program.output)
+            err = None if ok else f"gave a wrong output from its IR dump: {err}"
+        if err is not None:
+            print(f"❌ Program {p} {err}")
+            failed += 1
+            continue
         print(f"✅ Test succeded: {p}")
 
 if failed > 0:
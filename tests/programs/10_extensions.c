@@ -0,0 +1,40 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+long widen(int value) {
+    return value;
+}
+
+unsigned long widen_unsigned(unsigned int value) {
+    return value;
+}
+
+int low_byte(int value) {
+    unsigned char byte = value;
+    return byte;
+}
+
+int signed_byte(int value) {
+    char byte = value;
+    return byte;
+}
+
+int main() {
+    int minus_five = 0;
+    minus_five = minus_five - 5;
+    printf("widened is %ld\n", widen(minus_five));
+    printf("unsigned is %lu\n", widen_unsigned(minus_five));
+    printf("low byte is %d\n", low_byte(511));
+    printf("signed byte is %d\n", signed_byte(200));
+    printf("folded is %d\n", (char)200 + (unsigned char)511);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// widened is -5
+// unsigned is 4294967291
+// low byte is 255
+// signed byte is -56
+// folded is 199
+// === End Output ===
@@ -0,0 +1,50 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+double average(int a, int b) {
+    return (a + b) / 2.0;
+}
+
+double scale(double value, float factor) {
+    return value * factor;
+}
+
+int truncate(double value) {
+    return value;
+}
+
+int is_less(double a, double b) {
+    return a < b;
+}
+
+float half(float value) {
+    return value / 2;
+}
+
+int main() {
+    double total = 0;
+    int steps = 0;
+    while (steps < 10) {
+        total = total + 0.25;
+        steps = steps + 1;
+    }
+    printf("total is %f\n", total);
+    printf("average is %f\n", average(3, 4));
+    printf("scaled is %f\n", scale(1.5, 2.5f));
+    printf("truncated is %d\n", truncate(-7.9));
+    printf("less is %d, %d\n", is_less(1.0, 2.0), is_less(2.0, 1.0));
+    printf("half is %f\n", half(5));
+    printf("folded is %f\n", 1 + 2.5 * 2);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// total is 2.500000
+// average is 3.500000
+// scaled is 3.750000
+// truncated is -7
+// less is 1, 0
+// half is 2.500000
+// folded is 6.000000
+// === End Output ===
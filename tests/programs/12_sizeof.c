@@ -0,0 +1,44 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int count(int value) {
+    printf("evaluated %d\n", value);
+    return value;
+}
+
+short level(void) {
+    return count(3);
+}
+
+int main() {
+    char c = 1;
+    short s = 2;
+    long l = 3;
+    double d = 4.0;
+    printf("char is %lu, short is %lu\n", sizeof(char), sizeof s);
+    printf("int is %lu, long is %lu\n", sizeof(int), sizeof l);
+    printf("float is %lu, double is %lu\n", sizeof(float), sizeof(d));
+    printf("pointer is %lu\n", sizeof(char *));
+    printf("promoted is %lu\n", sizeof(c + c));
+    printf("unevaluated is %lu\n", sizeof(count(7)));
+    printf("calls are %lu, %lu\n", sizeof(level()), sizeof(count(c)));
+    printf("aligned is %lu, %lu\n", _Alignof(short), _Alignof(double));
+    int total = sizeof(long) + 1;
+    printf("total is %d\n", total);
+    printf("below zero is %d\n", sizeof(int) - 5 > 0);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// char is 1, short is 2
+// int is 4, long is 8
+// float is 4, double is 8
+// pointer is 8
+// promoted is 4
+// unevaluated is 4
+// calls are 2, 4
+// aligned is 2, 8
+// total is 9
+// below zero is 1
+// === End Output ===
@@ -0,0 +1,43 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int add(int a, int b) {
+    return a + b;
+}
+
+int mul(int a, int b) {
+    return a * b;
+}
+
+int apply(int (*operation)(int, int), int a, int b) {
+    return operation(a, b);
+}
+
+long twice(long (*step)(long), long value) {
+    return (*step)(step(value));
+}
+
+long increment(long value) {
+    return value + 1;
+}
+
+int main() {
+    int (*op)(int, int) = add;
+    printf("add is %d\n", op(3, 4));
+    op = &mul;
+    printf("mul is %d\n", (*op)(3, 4));
+    printf("applied is %d\n", apply(add, 10, 20));
+    printf("twice is %ld\n", twice(increment, 40));
+    int (*print)(const char *, ...) = printf;
+    print("through a pointer %d\n", 5);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// add is 7
+// mul is 12
+// applied is 30
+// twice is 42
+// through a pointer 5
+// === End Output ===
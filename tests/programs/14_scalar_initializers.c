@@ -0,0 +1,25 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int main() {
+    int braced = {42};
+    long nested = {{7}};
+    double real = {2.5};
+    int zero = {0};
+    char letter = {65 + 1};
+    printf("braced is %d\n", braced);
+    printf("nested is %ld\n", nested);
+    printf("real is %f\n", real);
+    printf("zero is %d\n", zero);
+    printf("letter is %d\n", letter);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// braced is 42
+// nested is 7
+// real is 2.500000
+// zero is 0
+// letter is 66
+// === End Output ===
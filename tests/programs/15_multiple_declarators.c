@@ -0,0 +1,22 @@
+// === Source ===
+int printf(const char *, ...), puts(const char *);
+
+int main() {
+    int a = 1, b, c = a + 2;
+    long d = 7, *e;
+    b = 5;
+    printf("%d %d %d\n", a, b, c);
+    printf("%ld\n", d + c);
+    int x = 10, y = x * 2, z = y - x;
+    printf("%d %d %d\n", x, y, z);
+    puts("done");
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 1 5 3
+// 10
+// 10 20 10
+// done
+// === End Output ===
@@ -0,0 +1,36 @@
+// === Source ===
+int printf(const char *, ...);
+int is_even(int);
+int is_odd(int n);
+long twice(long);
+
+int main() {
+    int later(int a, int b);
+    printf("is_even(10) is %d\n", is_even(10));
+    printf("is_odd(7) is %d\n", is_odd(7));
+    printf("twice(21) is %ld\n", twice(21));
+    printf("later(2, 3) is %d\n", later(2, 3));
+    return 0;
+}
+
+int is_even(int n) {
+    if (n == 0) return 1;
+    return is_odd(n - 1);
+}
+
+int is_odd(int n) {
+    if (n == 0) return 0;
+    return is_even(n - 1);
+}
+
+long twice(long value) { return value * 2; }
+int later(int x, int y) { return x * 10 + y; }
+long twice(long);
+// === End Source ===
+
+// === Output ===
+// is_even(10) is 1
+// is_odd(7) is 1
+// twice(21) is 42
+// later(2, 3) is 23
+// === End Output ===
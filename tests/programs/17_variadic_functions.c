@@ -0,0 +1,60 @@
+// === Source ===
+#include <stdarg.h>
+int printf(const char *, ...);
+
+int sum(int count, ...) {
+    va_list args;
+    va_start(args, count);
+    int total = 0;
+    while (count > 0) {
+        total += va_arg(args, int);
+        count -= 1;
+    }
+    va_end(args);
+    return total;
+}
+
+double average(int count, ...) {
+    va_list args;
+    va_list again;
+    va_start(args, count);
+    va_copy(again, args);
+    double total = 0.0;
+    int left = count;
+    while (left > 0) {
+        total += va_arg(args, double);
+        left -= 1;
+    }
+    va_end(args);
+    double first = va_arg(again, double);
+    va_end(again);
+    printf("first is %f\n", first);
+    return total / count;
+}
+
+long pick(int which, ...) {
+    va_list args;
+    va_start(args, which);
+    long value = va_arg(args, long);
+    while (which > 0) {
+        value = va_arg(args, long);
+        which -= 1;
+    }
+    va_end(args);
+    return value;
+}
+
+int main() {
+    printf("sum is %d\n", sum(4, 1, 2, 3, 4));
+    printf("average is %f\n", average(3, 1.5, 2.5, 5.0));
+    printf("pick is %ld\n", pick(2, 10l, 20l, 30l));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// sum is 10
+// first is 1.500000
+// average is 3.000000
+// pick is 30
+// === End Output ===
@@ -0,0 +1,83 @@
+// === Source ===
+#include <stdarg.h>
+int printf(const char *fmt, ...);
+int add(int a, int b) { return a + b; }
+long ptr(long *p);
+int main() {
+    int x = 1;
+    int *p = 0;
+    char *s = "hi";
+    double d = 1.5;
+    p = s;
+    p = x;
+    p = d;
+    x = p;
+    add(1);
+    add(1, 2, 3);
+    printf();
+    missing(3);
+    y = 2;
+    x = *x;
+    p = p * 2;
+    x = x % d;
+    ptr(p);
+    x();
+    return s;
+}
+int w() { return; }
+int fixed(int n) {
+    va_list ap;
+    int k = 0;
+    va_start(ap, n);
+    k = va_arg(k, int);
+    return va_end(ap);
+}
+int stores(int x, void *v, int (*fp)(int)) {
+    *x = 1;
+    *v = 1;
+    *fp = 0;
+    return x;
+}
+long differences(int *p, long *l, int (*fp)(void)) {
+    long d = p - l;
+    p = 1 - p;
+    fp = fp + 1;
+    return d;
+}
+long sizes(long a) {
+    a = sizeof(int (int));
+    a = _Alignof(void (void));
+    return (long)(int (int))a;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'main': 10:5: warning: incompatible pointer types assigning to 'int *' from 'char *'
+// in function 'main': 11:5: error: incompatible integer to pointer conversion assigning to 'int *' from 'int'
+// in function 'main': 12:5: error: incompatible types assigning to 'int *' from 'double'
+// in function 'main': 13:5: error: incompatible pointer to integer conversion assigning to 'int' from 'int *'
+// in function 'main': 14:5: error: too few arguments to function call, expected 2, have 1
+// in function 'main': 15:5: error: too many arguments to function call, expected 2, have 3
+// in function 'main': 16:5: error: too few arguments to function call, expected at least 1, have 0
+// in function 'main': 17:5: error: call to undeclared function 'missing'
+// in function 'main': 18:5: error: use of undeclared identifier 'y'
+// in function 'main': 19:5: error: indirection requires pointer operand ('int' invalid)
+// in function 'main': 20:5: error: invalid operands to binary expression ('int *' and 'int')
+// in function 'main': 21:5: error: invalid operands to binary expression ('int' and 'double')
+// in function 'main': 22:5: warning: incompatible pointer types passing 'int *' to parameter of type 'long *'
+// in function 'main': 23:5: error: called object type 'int' is not a function or function pointer
+// in function 'main': 24:5: error: incompatible pointer to integer conversion returning 'char *' from a function with result type 'int'
+// in function 'w': 26:11: warning: non-void function 'w' should return a value
+// in function 'fixed': 30:5: error: 'va_start' used in function with fixed arguments
+// in function 'fixed': 31:5: error: 'k' is not a va_list
+// in function 'fixed': 32:5: error: va_start, va_end and va_copy have no value
+// in function 'stores': 35:5: error: indirection requires pointer operand ('int' invalid)
+// in function 'stores': 36:5: error: indirection requires a pointer to an object type ('void *' invalid)
+// in function 'stores': 37:5: error: non-object type 'int (int)' is not assignable
+// in function 'differences': 41:10: error: invalid operands to binary expression ('int *' and 'long *')
+// in function 'differences': 42:5: error: invalid operands to binary expression ('int' and 'int *')
+// in function 'differences': 43:5: error: arithmetic on a pointer to the function type 'int (void)'
+// in function 'sizes': 47:5: error: invalid application of 'sizeof' to a function type
+// in function 'sizes': 48:5: error: invalid application of '_Alignof' to a function type
+// in function 'sizes': 49:5: error: used type 'int (int)' where arithmetic or pointer type is required
+// === End Diagnostics ===
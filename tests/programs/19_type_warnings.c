@@ -0,0 +1,20 @@
+// === Source ===
+int printf(const char *, ...);
+int twice(int x) { return x * 2; }
+int main() {
+    long *p = 0;
+    char *s = "ok";
+    p = s;
+    printf("%s %d\n", s, twice(4));
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'main': 6:5: warning: incompatible pointer types assigning to 'long *' from 'char *'
+// in function 'main': 4:11: warning: variable 'p' set but not used
+// === End Diagnostics ===
+
+// === Output ===
+// ok 8
+// === End Output ===
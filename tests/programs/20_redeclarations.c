@@ -0,0 +1,66 @@
+// === Source ===
+int printf(const char *, ...);
+int g;
+int g;
+extern int e;
+int e;
+int twice(int x);
+long twice(int x);
+int add(int a, int a) { return a; }
+int add2(int a, int b) { return a + b; }
+int add2(int a, int b) { return a - b; }
+int proto(int p, int p);
+int main() {
+    int x = 1;
+    int x = 2;
+    long y;
+    int y;
+    int twice;
+    {
+        int x = 3;
+        int g = 4;
+        {
+            int x = 5;
+        }
+    }
+    return 0;
+}
+int f(int n) {
+    int n = 2;
+    return n;
+}
+int main;
+int h = 1;
+int h;
+int h = 2;
+int t;
+int t = 3;
+int t;
+// === End Source ===
+
+// === Diagnostics ===
+// 7:6: error: conflicting types for 'twice'
+// 6:5: note: previous declaration is here
+// in function 'add': 8:20: error: redefinition of parameter 'a'
+// 8:13: note: previous declaration is here
+// in function 'add2': 10:5: error: redefinition of 'add2'
+// 9:5: note: previous definition is here
+// 11:22: error: redefinition of parameter 'p'
+// 11:15: note: previous declaration is here
+// in function 'main': 14:9: error: redefinition of 'x'
+// 13:9: note: previous definition is here
+// in function 'main': 16:9: error: redefinition of 'y' with a different type: 'int' vs 'long'
+// 15:10: note: previous definition is here
+// in function 'main': 19:13: warning: declaration of 'x' shadows a previous local
+// 13:9: note: shadowed declaration is here
+// in function 'main': 20:13: warning: declaration of 'g' shadows a global declaration
+// 2:5: note: shadowed declaration is here
+// in function 'main': 22:17: warning: declaration of 'x' shadows a previous local
+// 19:13: note: shadowed declaration is here
+// in function 'f': 28:9: error: redefinition of 'n'
+// 27:11: note: previous definition is here
+// 31:5: error: redefinition of 'main' as different kind of symbol
+// 12:5: note: previous definition is here
+// 34:5: error: redefinition of 'h'
+// 32:5: note: previous definition is here
+// === End Diagnostics ===
@@ -0,0 +1,45 @@
+// === Source ===
+int printf(const char *, ...);
+int scale(int value) {
+    int count = value * 2;
+    {
+        int count = value * 3;
+        printf("inner %d\n", count);
+    }
+    return count;
+}
+int main() {
+    int x = 1;
+    if (x) {
+        int x = 10;
+        printf("then %d\n", x);
+    } else {
+        long x = 20;
+        printf("else %ld\n", x);
+    }
+    while (x < 3) {
+        int y = x * 100;
+        x = x + 1;
+        printf("loop %d %d\n", x, y);
+    }
+    printf("outer %d %d\n", x, scale(5));
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'scale': 5:13: warning: declaration of 'count' shadows a previous local
+// 3:9: note: shadowed declaration is here
+// in function 'main': 13:13: warning: declaration of 'x' shadows a previous local
+// 11:9: note: shadowed declaration is here
+// in function 'main': 16:14: warning: declaration of 'x' shadows a previous local
+// 11:9: note: shadowed declaration is here
+// === End Diagnostics ===
+
+// === Output ===
+// then 10
+// loop 2 100
+// loop 3 200
+// inner 15
+// outer 3 10
+// === End Output ===
@@ -0,0 +1,55 @@
+// === Source ===
+int printf(const char *, ...);
+
+int sign(int x) {
+    if (x < 0) {
+        return 0 - 1;
+    } else if (x > 0) {
+        return 1;
+    } else {
+        return 0;
+    }
+}
+
+int first_above(int limit) {
+    int n = 1;
+    while (1) {
+        if (n > limit) {
+            return n;
+        }
+        n = n * 2;
+    }
+}
+
+int report(int x) {
+    if (x) {
+        printf("report %d\n", x);
+        return x;
+    }
+}
+
+int early(int x) {
+    return x + 1;
+    printf("never\n");
+    x = x * 2;
+}
+
+int main() {
+    printf("%d %d %d\n", sign(0 - 5), sign(0), sign(7));
+    printf("%d\n", first_above(100));
+    report(3);
+    printf("%d\n", early(41));
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'report': 23:5: warning: control reaches end of non-void function
+// in function 'early': 32:5: warning: code will never be executed
+// === End Diagnostics ===
+
+// === Output ===
+// -1 0 1
+// 128
+// report 3
+// 42
+// === End Output ===
@@ -0,0 +1,47 @@
+// === Source ===
+int printf(const char *, ...);
+int f(int c, int unused) {
+    int x;
+    int y;
+    int z = 5;
+    int never;
+    int dead = 3;
+    if (c) {
+        x = 1;
+    }
+    y = x + 2;
+    y = 7;
+    while (c < 10) {
+        c = c + z;
+    }
+    dead = 4;
+    return y + c;
+}
+int g(int c) {
+    int u;
+    if (c > 100) {
+        return u;
+    }
+    return c;
+}
+int h(int c) {
+    int w = c;
+    w = 2;
+    return w;
+}
+int main() { printf("%d %d\n", f(1, 2), g(3)); return 0; }
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'f': 2:18: warning: unused parameter 'unused'
+// in function 'f': 6:9: warning: unused variable 'never'
+// in function 'f': 7:9: warning: variable 'dead' set but not used
+// in function 'f': 11:5: warning: value assigned to 'y' is never read
+// in function 'f': 11:9: warning: variable 'x' may be used uninitialized
+// in function 'g': 22:16: warning: variable 'u' is used uninitialized
+// in function 'h': 27:5: warning: value assigned to 'w' is never read
+// === End Diagnostics ===
+
+// === Output ===
+// 18 3
+// === End Output ===
@@ -0,0 +1,58 @@
+// === Source ===
+int printf(const char *, ...);
+
+
+void report(int value) {
+    printf("value %d\n", value);
+}
+
+void nothing(void) {
+    return;
+}
+
+int twice();
+int add();
+
+int twice(int x) {
+    return x + x;
+}
+
+void early(int x) {
+    if (x > 2) {
+        printf("big\n");
+        return;
+    }
+    printf("small\n");
+}
+
+int main(void) {
+    void *p;
+    char *s = "text";
+    p = s;
+    s = p;
+    report(3);
+    nothing();
+    (void)twice(4);
+    (void)report;
+    early(1);
+    early(5);
+    report(twice(21));
+    printf("%s %d\n", s, add(1, 2));
+    return 0;
+}
+
+int add(int a, int b) {
+    return a + b;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// === End Diagnostics ===
+
+// === Output ===
+// value 3
+// small
+// big
+// value 42
+// text 3
+// === End Output ===
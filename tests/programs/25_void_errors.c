@@ -0,0 +1,35 @@
+// === Source ===
+int printf(const char *, ...);
+void f(void);
+void g(int, void);
+void h(void x);
+int k(void) { return 1; }
+void m(void) { return 1; }
+int main(void) {
+    void v;
+    void *p = 0;
+    int x = f();
+    int y = *p;
+    x = (f());
+    k(3);
+    printf("%d\n", f());
+    if (f()) { }
+    (void)(f());
+    (f());
+    return f();
+}
+// === End Source ===
+
+// === Diagnostics ===
+// error: 'void' must be the first and only parameter if specified
+// 4:13: error: parameter 'x' has incomplete type 'void'
+// in function 'main': 8:10: error: variable 'v' has incomplete type 'void'
+// in function 'm': 6:16: error: void function 'm' should not return a value
+// in function 'main': 10:9: error: void value not ignored as it ought to be
+// in function 'main': 11:9: error: indirection requires a pointer to an object type ('void *' invalid)
+// in function 'main': 12:5: error: void value not ignored as it ought to be
+// in function 'main': 13:5: error: too many arguments to function call, expected 0, have 1
+// in function 'main': 14:5: error: void value not ignored as it ought to be
+// in function 'main': 15:5: error: void value not ignored as it ought to be
+// in function 'main': 18:5: error: void value not ignored as it ought to be
+// === End Diagnostics ===
@@ -0,0 +1,35 @@
+// === Source ===
+int printf(const char *, ...);
+int puts(const char *);
+int putchar(int);
+void *malloc(unsigned long);
+
+int main() {
+    printf("[%5d] [%-5d] [%05d] [%+d] [% d] [%.3d] [%x] [%#x] [%X] [%o] [%#o]\n", 42, 42, 0 - 42, 7, 7, 5, 255, 255, 255, 8, 8);
+    printf("[%u] [%lu] [%ld] [%lld] [%hd] [%hhd] [%c] [%5s] [%-5s|] [%.2s] [%%]\n", 0 - 1, 0l - 1, 0l - 5, 123456789012l, 70000, 300, 65, "ab", "ab", "abcdef");
+    printf("[%f] [%.2f] [%10.3f] [%-10.1f|] [%e] [%.3E] [%g] [%g] [%g] [%g] [%G]\n", 3.14159, 2.5, 0.0 - 1.0, 9.99, 12345.678, 0.000123, 0.0001, 123456.0, 1234567.0, 100.0, 0.00001234);
+    printf("[%08.3f] [%+.1f] [%.0f] [%.0f] [%#.0f] [%g] [%#g] [%*d] [%.*f]\n", 0.0 - 3.5, 2.0, 0.5, 1.5, 3.0, 0.0, 1.0, 6, 12, 2, 1.005);
+    float f = 1.1;
+    printf("%f %.10f\n", f, 1.0 / 3.0);
+    puts("puts works");
+    putchar(111);
+    putchar(107);
+    putchar(10);
+    void *p = malloc(16);
+    printf("%d\n", p != 0);
+    printf("tab\there \\ \"q\" \x41\101\n");
+    return 3;
+}
+// === End Source ===
+
+// === Output ===
+// [   42] [42   ] [-0042] [+7] [ 7] [005] [ff] [0xff] [FF] [10] [010]
+// [4294967295] [18446744073709551615] [-5] [123456789012] [4464] [44] [A] [   ab] [ab   |] [ab] [%]
+// [3.141590] [2.50] [    -1.000] [10.0      |] [1.234568e+04] [1.230E-04] [0.0001] [123456] [1.23457e+06] [100] [1.234E-05]
+// [-003.500] [+2.0] [0] [2] [3.] [0] [1.00000] [    12] [1.00]
+// 1.100000 0.3333333333
+// puts works
+// ok
+// 1
+// tab	here \ "q" AA
+// === End Output ===
@@ -0,0 +1,84 @@
+// === Source ===
+int printf(const char *format, ...);
+
+int fib(int n) {
+   int a = 0;
+   int b = 1;
+   int i = 0;
+   while (i < n) {
+      int t = a;
+      a = b;
+      b = t + b;
+      i += 1;
+   }
+   return a;
+}
+
+int collatz(int n) {
+   int steps = 0;
+   while (n != 1) {
+      if (n - n / 2 * 2 == 0) {
+         n = n / 2;
+      } else {
+         n = 3 * n + 1;
+      }
+      steps += 1;
+   }
+   return steps;
+}
+
+int grid(int rows, int columns) {
+   int total = 0;
+   int row = 0;
+   while (row < rows) {
+      int column = 0;
+      while (column < columns) {
+         if (column > row) {
+            total += column;
+         }
+         column += 1;
+      }
+      row += 1;
+   }
+   return total;
+}
+
+int sign(int x) {
+   int s = 0;
+   if (x > 0) {
+      s = 1;
+   }
+   if (x < 0) {
+      s = 0 - 1;
+   }
+   return s;
+}
+
+int main() {
+   int i = 0;
+   while (i < 10) {
+      printf("fib(%d) = %d\n", i, fib(i));
+      i += 1;
+   }
+   printf("collatz(27) = %d\n", collatz(27));
+   printf("grid(4, 6) = %d\n", grid(4, 6));
+   printf("%d %d %d\n", sign(5), sign(0), sign(0 - 5));
+   return 0;
+}
+// === End Source ===
+
+// === Output ===
+// fib(0) = 0
+// fib(1) = 1
+// fib(2) = 1
+// fib(3) = 2
+// fib(4) = 3
+// fib(5) = 5
+// fib(6) = 8
+// fib(7) = 13
+// fib(8) = 21
+// fib(9) = 34
+// collatz(27) = 111
+// grid(4, 6) = 50
+// 1 0 -1
+// === End Output ===
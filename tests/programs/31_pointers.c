@@ -0,0 +1,63 @@
+// === Source ===
+int printf(const char *format, ...);
+
+int read(int *p) {
+   return *p;
+}
+
+long twice(long *p) {
+   return *p + *p;
+}
+
+int count(int n) {
+   int total = 0;
+   int *t = &total;
+   while (n > 0) {
+      total = *t + n;
+      n = n - 1;
+   }
+   return *t;
+}
+
+int parameter(int n) {
+   int *p = &n;
+   n = n * 2;
+   return *p + 1;
+}
+
+double scale(double d) {
+   double *p = &d;
+   double **pp = &p;
+   return **pp * 2.5;
+}
+
+int main(void) {
+   int x = 7;
+   int *p = &x;
+   printf("%d %d\n", *p, read(&x));
+   x = 9;
+   printf("%d %d\n", *p, read(p));
+   long y = 21;
+   printf("%ld\n", twice(&y));
+   printf("%d\n", *&x + *(&x));
+   printf("%d\n", count(10));
+   printf("%d\n", parameter(20));
+   printf("%f\n", scale(4.0));
+   int u;
+   int *q = &u;
+   u = 3;
+   printf("%d\n", *q);
+   return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 7 7
+// 9 9
+// 42
+// 18
+// 55
+// 41
+// 10.000000
+// 3
+// === End Output ===
@@ -0,0 +1,63 @@
+// === Source ===
+int printf(const char *format, ...);
+
+int rem(int a, int b) {
+   return a % b;
+}
+
+unsigned int urem(unsigned int a, unsigned int b) {
+   return a % b;
+}
+
+int digits(int n) {
+   int sum = 0;
+   while (n != 0) {
+      sum += n % 10;
+      n /= 10;
+   }
+   return sum;
+}
+
+int main(void) {
+   int seven = 7;
+   int three = 3;
+   printf("%d %d %d %d\n", rem(7, 3), rem(0 - 7, 3), rem(7, 0 - 3), rem(0 - 7, 0 - 3));
+   printf("%d %d\n", seven % three, (0 - seven) % three);
+   printf("%u %u\n", urem(4294967295u, 10u), urem(0u - 7u, 3u));
+   printf("%d\n", digits(98765));
+   int x = 100;
+   x %= 7;
+   printf("%d\n", x);
+
+   unsigned int big = 4294967295u;
+   unsigned int wrapped = big + 1u;
+   printf("%u %u\n", wrapped, big * 3u);
+   printf("%u %d\n", big / 2u, (0 - seven) / 2);
+   printf("%u %d\n", big >> 4, (0 - 64) >> 2);
+   unsigned int small = 1u;
+   printf("%d %d\n", small < big, 0 - 1 < 1);
+   unsigned char c = 250;
+   c = c + 10;
+   printf("%d\n", c);
+   long l = 9223372036854775807;
+   printf("%ld\n", l % 1000);
+   unsigned long ul = 0ul - 1ul;
+   printf("%lu %lu\n", ul / 3ul, ul % 1000ul);
+   return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 1 -1 1 -1
+// 1 -1
+// 5 0
+// 35
+// 2
+// 0 4294967293
+// 2147483647 -3
+// 268435455 -16
+// 1 1
+// 4
+// 807
+// 6148914691236517205 615
+// === End Output ===
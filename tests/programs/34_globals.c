@@ -0,0 +1,72 @@
+// === Source ===
+int printf(const char *, ...);
+
+int counter;
+int start = 5;
+long big = 1L << 40;
+unsigned int wrapped = 0 - 1;
+unsigned char small = 300;
+short mixed = (3 + 4) * 2 % 5 - sizeof(long);
+double ratio = 1 / 4.0;
+float third = 1.0 / 3;
+int truncated = 7.9;
+char *greeting = "hello";
+int *where = &counter;
+int add(int a, int b);
+int (*op)(int, int) = add;
+int counter = 7;
+int counter;
+
+int add(int a, int b) {
+    return a + b;
+}
+
+void bump(int by) {
+    counter = counter + by;
+}
+
+int get(int *p) {
+    return *p;
+}
+
+int shadow() {
+    int start = 100;
+    return start;
+}
+
+int twice() {
+    extern int start;
+    start *= 2;
+    return start;
+}
+
+int main() {
+    printf("%d %d %ld %u %d %d\n", counter, start, big, wrapped, small, mixed);
+    printf("%f %f %d %s\n", ratio, third, truncated, greeting);
+    bump(3);
+    bump(counter);
+    printf("%d %d\n", counter, *where);
+    start = 11;
+    printf("%d %d %d\n", get(&start), get(where), op(counter, start));
+    op = 0;
+    printf("%d ", shadow());
+    printf("%d ", twice());
+    printf("%d\n", start);
+    printf("%d\n", op == 0);
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'shadow': 32:9: warning: declaration of 'start' shadows a global declaration
+// 4:5: note: shadowed declaration is here
+// === End Diagnostics ===
+
+// === Output ===
+// 7 5 1099511627776 4294967295 44 -4
+// 0.250000 0.333333 7 hello
+// 20 20
+// 11 20 31
+// 100 22 22
+// 1
+// === End Output ===
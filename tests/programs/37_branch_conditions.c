@@ -0,0 +1,57 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int count_down(unsigned long u) {
+    int steps = 0;
+    while (u > 0) {
+        u = u - 1;
+        steps = steps + 1;
+    }
+    return steps;
+}
+
+int main() {
+    long n = 0;
+    long big = 4294967296;
+    int x = 4;
+    int *p = &x;
+    int *null = 0;
+    double d = 0.5;
+
+    if (n == 0)
+        printf("n is zero\n");
+    if (big != 0)
+        printf("big is not zero\n");
+    if (big)
+        printf("big is true\n");
+    if (p == 0)
+        printf("p is null\n");
+    else
+        printf("p points to %d\n", *p);
+    if (null == 0)
+        printf("null is null\n");
+    if (p)
+        printf("p is true\n");
+    if (d)
+        printf("d is true\n");
+    printf("%d steps\n", count_down(5));
+
+    while (n < big) {
+        n = n + 1073741824;
+    }
+    printf("%ld\n", n);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// n is zero
+// big is not zero
+// big is true
+// p points to 4
+// null is null
+// p is true
+// d is true
+// 5 steps
+// 4294967296
+// === End Output ===
@@ -0,0 +1,27 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+double d_global = 0x10;
+unsigned long u_global = 1e19;
+
+int main() {
+    double a = 1u;
+    double b = 0x10;
+    double c = 010;
+    double e = 18446744073709551615u;
+    float f = 0xffu;
+    unsigned long u = 1e19;
+    unsigned int w = 3e9;
+    long l = 2.5e3;
+    printf("%f %f %f %f %f\n", a, b, c, e, f);
+    printf("%lu %u %ld\n", u, w, l);
+    printf("%f %lu\n", d_global, u_global);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 1.000000 16.000000 8.000000 18446744073709551616.000000 255.000000
+// 10000000000000000000 3000000000 2500
+// 16.000000 10000000000000000000
+// === End Output ===
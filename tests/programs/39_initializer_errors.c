@@ -0,0 +1,20 @@
+// === Source ===
+int g = {1, 2};
+int h = {.x = 3};
+long k = {{4}};
+
+int main() {
+    int x = {1, 2};
+    double y = {[0] = 1.0};
+    int z = {};
+    int w = {{5}};
+    return x + w + z;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 1:5: error: excess elements in initializer for scalar type 'int'
+// 2:5: error: designator in initializer for scalar type 'int'
+// in function 'main': 6:9: error: excess elements in initializer for scalar type 'int'
+// in function 'main': 7:12: error: designator in initializer for scalar type 'double'
+// === End Diagnostics ===
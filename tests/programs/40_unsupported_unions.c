@@ -0,0 +1,14 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int main() {
+    union number { int i; float f; } n;
+    n.i = 1;
+    printf("%d\n", n.i);
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 4:5: error: unions are not supported yet
+// === End Diagnostics ===
@@ -0,0 +1,19 @@
+// === Source ===
+int g = "hi";
+int *p = 5;
+long *q = "x";
+double d = "d";
+int *null = 0;
+char *s = "ok";
+
+int main() {
+    return g;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 1:5: error: incompatible pointer to integer conversion initializing 'int' with an expression of type 'char *'
+// 2:6: error: incompatible integer to pointer conversion initializing 'int *' with an expression of type 'int'
+// 3:7: warning: incompatible pointer types initializing 'long *' with an expression of type 'char *'
+// 4:8: error: incompatible types initializing 'double' with an expression of type 'char *'
+// === End Diagnostics ===
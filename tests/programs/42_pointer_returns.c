@@ -0,0 +1,46 @@
+// === Source ===
+#include <stdarg.h>
+extern int printf(const char *format, ...);
+
+int g = 5;
+long l = 7;
+
+int *global_address(void) {
+    return &g;
+}
+
+long **through(long **pp) {
+    return pp;
+}
+
+char *pick(int n, ...) {
+    va_list ap;
+    char *chosen = 0;
+    va_start(ap, n);
+    while (n > 0) {
+        chosen = va_arg(ap, char *);
+        n = n - 1;
+    }
+    va_end(ap);
+    return chosen;
+}
+
+void *nothing(void) {
+    return 0;
+}
+
+int main() {
+    long *p = &l;
+    int *q = global_address();
+    printf("%d %ld\n", *q, **through(&p));
+    printf("%s\n", pick(2, "first", "second"));
+    printf("%d\n", nothing() == 0);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 5 7
+// second
+// 1
+// === End Output ===
@@ -0,0 +1,62 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int g = 1;
+
+void set(int *out, int value) {
+    *out = value;
+}
+
+void swap(long *a, long *b) {
+    long t = *a;
+    *a = *b;
+    *b = t;
+}
+
+void scale(double *d, char *c) {
+    *d *= 2;
+    *c += 100;
+    *c += 100;
+}
+
+int *pick(int *a, int *b, int first) {
+    if (first) {
+        return a;
+    }
+    return b;
+}
+
+int main() {
+    int x = 0;
+    int y = 0;
+    long a = 3;
+    long b = 4;
+    double d = 1.25;
+    char c = 10;
+    int *p = &x;
+    int **pp = &p;
+    int z;
+
+    set(&x, 5);
+    swap(&a, &b);
+    scale(&d, &c);
+    printf("%d %ld %ld %f %d\n", x, a, b, d, c);
+
+    **pp += 2;
+    (*p) = *p * 3;
+    z = *p = 8;
+    printf("%d %d\n", x, z);
+
+    *pick(&x, &y, 0) = 9;
+    *pick(&x, &y, 1) -= 1;
+    set(&g, g + 41);
+    printf("%d %d %d\n", x, y, g);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 5 4 3 2.500000 -46
+// 8 8
+// 7 9 42
+// === End Output ===
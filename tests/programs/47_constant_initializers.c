@@ -0,0 +1,37 @@
+// === Source ===
+int f(void);
+int a = 1;
+int b = a;
+int c = f();
+int *d = &a;
+int e = (a + 1);
+long l = (long)&a;
+char k = (char)&a;
+int (*fp)(void) = f;
+int *n = 0;
+double r = 1.5 * 2;
+int z = 1 / (2 - 2);
+double inf = 1.0 / 0;
+
+int main() {
+    int x = 2;
+    static int s = x;
+    static int *t = &x;
+    static int *u = &a;
+    static int v = 3 * 4;
+    static int *w = &v;
+    static int y = 4 % 0;
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 3:5: error: initializer element is not a compile-time constant
+// 4:5: error: initializer element is not a compile-time constant
+// 6:5: error: initializer element is not a compile-time constant
+// 8:6: error: initializer element is not a compile-time constant
+// 12:5: error: division by zero in a constant expression
+// in function 'main': 17:16: error: initializer element is not a compile-time constant
+// in function 'main': 18:17: error: initializer element is not a compile-time constant
+// in function 'main': 22:16: error: remainder by zero in a constant expression
+// === End Diagnostics ===
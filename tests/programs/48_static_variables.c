@@ -0,0 +1,57 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+static int hidden = 5;
+static long total;
+static int *where = &hidden;
+
+static int twice(int n) {
+    return 2 * n;
+}
+
+int counter(void) {
+    static int calls;
+    calls += 1;
+    return calls;
+}
+
+int other(void) {
+    static int calls = 100;
+    calls = calls + 1;
+    return calls;
+}
+
+int *remember(void) {
+    static int hidden = 7;
+    static int *self = &hidden;
+    return self;
+}
+
+void add(long n) {
+    total += n;
+}
+
+int main() {
+    int i = 0;
+    while (i < 3) {
+        counter();
+        add(i);
+        i = i + 1;
+    }
+    other();
+    *remember() += 1;
+    printf("%d %d %d\n", counter(), other(), *remember());
+    printf("%d %d %ld %d\n", hidden, *where, total, twice(hidden));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 4 102 8
+// 5 5 3 10
+// === End Output ===
+
+// === Diagnostics ===
+// in function 'remember': 24:16: warning: declaration of 'hidden' shadows a global declaration
+// 3:12: note: shadowed declaration is here
+// === End Diagnostics ===
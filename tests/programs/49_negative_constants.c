@@ -0,0 +1,17 @@
+// === Source ===
+extern int printf(const char *format, ...);
+int g = -1;
+long h = -9223372036854775807L;
+int main() {
+    int x = -7;
+    unsigned u = -1u;
+    double d = -3;
+    long big = -2147483648;
+    printf("%d %d %ld %u %.1f %ld %d\n", g, x, h, u, d, big, 0x10 + -0x10);
+    return -1 + 1;
+}
+// === End Source ===
+
+// === Output ===
+// -1 -7 -9223372036854775807 4294967295 -3.0 -2147483648 0
+// === End Output ===
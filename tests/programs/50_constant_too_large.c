@@ -0,0 +1,10 @@
+// === Source ===
+int main() {
+    long x = 99999999999999999999;
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 2:14: error: integer constant 99999999999999999999 is too large for its type
+// === End Diagnostics ===
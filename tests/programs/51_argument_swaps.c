@@ -0,0 +1,24 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+double sub(double a, double b) { return a - b; }
+double swap(double a, double b) { return sub(b, a); }
+
+int isub(int a, int b) { return a - b; }
+int iswap(int a, int b) { return isub(b, a); }
+
+int rot(int a, int b, int c) { return a * 100 + b * 10 + c; }
+int rotate(int a, int b, int c) { return rot(b, c, a); }
+
+double mix(int a, double x, int b, double y) { return (a - b) * 10 + (x - y); }
+double mix_swap(int a, double x, int b, double y) { return mix(b, y, a, x); }
+
+int main() {
+    printf("%.1f %d %d %.1f\n", swap(1.0, 3.0), iswap(1, 3), rotate(1, 2, 3), mix_swap(1, 0.5, 4, 2.0));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 2.0 2 231 31.5
+// === End Output ===
@@ -0,0 +1,32 @@
+// === Source ===
+extern int printf(const char *format, ...);
+extern void *malloc(unsigned long size);
+extern void free(void *pointer);
+
+long at(long *p, int i) { return *(p + i); }
+long distance(short *from, short *to) { return to - from; }
+
+int main() {
+    long *p = malloc(32);
+    *p = 42;
+    *(p + 1) = 7;
+    *(2 + p) = 9;
+    long *q = p + 3;
+    *q = 11;
+    q -= 1;
+    p += 1;
+    char *c = (char *)p;
+    printf("%ld %ld %ld %ld\n", *(p - 1), *p, *q, at(p, 2));
+    printf("%ld %ld %ld\n", q - p, (p + 2) - q, (c + 3) - c);
+    short *s = (short *)p;
+    printf("%ld\n", distance(s, s + 5));
+    free(p - 1);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 42 7 9 11
+// 1 1 3
+// 5
+// === End Output ===
@@ -0,0 +1,30 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int add(int a, int b) { return a + b; }
+int sub(int a, int b) { return a - b; }
+int (*pick(int w))(int, int);
+
+void show(int x) { printf("%d\n", x); }
+void (*shower(void))(int) { return show; }
+
+int main() {
+    void *raw = (void *)pick(0);
+    int (*f)(int, int) = (int (*)(int, int))raw;
+    shower()(f(4, 5));
+    printf("%d\n", pick(1)(4, 5));
+    printf("%ld %ld\n", sizeof(void (*(*)(void))(int)), _Alignof(int (*)(int)));
+    return 0;
+}
+
+int (*pick(int w))(int, int) {
+    if (w) return add;
+    return sub;
+}
+// === End Source ===
+
+// === Output ===
+// -1
+// 9
+// 8 8
+// === End Output ===
@@ -0,0 +1,10 @@
+// === Source ===
+int main() {
+    _Bool flag = 1;
+    return flag;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 2:5: error: unknown type name '_Bool'
+// === End Diagnostics ===
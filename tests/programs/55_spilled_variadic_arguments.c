@@ -0,0 +1,24 @@
+// === Source ===
+int printf(const char *, ...);
+
+long next(long value) { return value + 1; }
+
+int main() {
+    long a = next(0);
+    long b = next(a);
+    long c = next(b);
+    long d = next(c);
+    long e = next(d);
+    long f = next(e);
+    printf("%ld %ld %ld\n", a, b, c);
+    printf("%ld %ld %ld\n", d, e, f);
+    printf("%ld\n", a + b + c + d + e + f);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 1 2 3
+// 4 5 6
+// 21
+// === End Output ===
@@ -0,0 +1,16 @@
+// === Source ===
+int printf(const char *, ...);
+
+int main(int argc, char **argv) {
+    printf("argc is %d\n", argc);
+    printf("argv[0] is null: %d\n", *argv == 0);
+    printf("argv[argc] is null: %d\n", *(argv + argc) == 0);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// argc is 1
+// argv[0] is null: 0
+// argv[argc] is null: 1
+// === End Output ===
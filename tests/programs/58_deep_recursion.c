@@ -0,0 +1,17 @@
+// === Source ===
+int printf(const char *, ...);
+
+long depth(long n) {
+    if (n == 0) return 0;
+    return depth(n - 1) + 1;
+}
+
+int main() {
+    printf("%ld\n", depth(9000));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 9000
+// === End Output ===
@@ -0,0 +1,11 @@
+// === Source ===
+int main() {
+    int flags = 6;
+    flags |= 1;
+    return flags && 1;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 3:11: error: bitwise assignments are not supported yet
+// === End Diagnostics ===
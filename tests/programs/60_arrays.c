@@ -0,0 +1,55 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int squares[4];
+
+int sum(int *values, int count) {
+    int total = 0;
+    int i = 0;
+    while (i < count) {
+        total += values[i];
+        i += 1;
+    }
+    return total;
+}
+
+int main() {
+    int i = 0;
+    while (i < 4) {
+        squares[i] = i * i;
+        i += 1;
+    }
+    printf("sum of squares is %d\n", sum(squares, 4));
+
+    long grid[2][3];
+    i = 0;
+    while (i < 6) {
+        grid[i / 3][i % 3] = i * 10;
+        i += 1;
+    }
+    printf("grid[1][2] is %ld\n", grid[1][2]);
+
+    long (*row)[3] = grid + 1;
+    printf("(*row)[0] is %ld\n", (*row)[0]);
+
+    char letters[3];
+    letters[0] = 111;
+    1[letters] = 107;
+    *(letters + 2) = 0;
+    printf("letters are %s\n", letters);
+
+    int *last = &squares[3];
+    printf("last is %d, before it %d\n", *last, last[-1]);
+    printf("sizes are %d %d %d\n", (int)sizeof(squares), (int)sizeof(grid), (int)sizeof(grid[0]));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// sum of squares is 14
+// grid[1][2] is 50
+// (*row)[0] is 30
+// letters are ok
+// last is 9, before it 4
+// sizes are 16 48 24
+// === End Output ===
@@ -0,0 +1,72 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+struct point {
+    int x;
+    int y;
+};
+
+struct segment {
+    char label;
+    struct point from;
+    struct point to;
+    double length;
+};
+
+struct node {
+    int value;
+    struct node *next;
+};
+
+struct segment shared;
+
+void stretch(struct segment *segment, int by) {
+    segment->to.x += by;
+    segment->length = segment->length * 2.0;
+}
+
+int main() {
+    struct segment s;
+    s.label = 97;
+    s.from.x = 1;
+    s.from.y = 2;
+    s.to = s.from;
+    s.to.y = 5;
+    s.length = 1.5;
+    stretch(&s, 3);
+    printf("%c from (%d, %d) to (%d, %d), length %f\n", s.label, s.from.x, s.from.y, s.to.x, s.to.y, s.length);
+
+    shared = s;
+    shared.from.x = 9;
+    printf("copy starts at %d, original at %d\n", shared.from.x, s.from.x);
+
+    struct node last;
+    struct node first;
+    last.value = 2;
+    last.next = 0;
+    first.value = 1;
+    first.next = &last;
+    struct node *current = &first;
+    int total = 0;
+    while (current) {
+        total += current->value;
+        current = current->next;
+    }
+    printf("total is %d\n", total);
+
+    struct point points[2];
+    points[1].y = 7;
+    (*points).x = 4;
+    printf("points %d %d\n", points[0].x, points[1].y);
+    printf("sizes are %d %d %d\n", (int)sizeof(struct point), (int)sizeof(struct segment), (int)_Alignof(struct segment));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// a from (1, 2) to (4, 5), length 3.000000
+// copy starts at 9, original at 1
+// total is 3
+// points 4 7
+// sizes are 8 32 8
+// === End Output ===
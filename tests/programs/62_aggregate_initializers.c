@@ -0,0 +1,61 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+struct point {
+    int x;
+    int y;
+};
+
+struct shape {
+    char name[8];
+    struct point corners[3];
+    long area;
+};
+
+int primes[5] = {2, 3, 5};
+int sparse[] = {[3] = 4, 1};
+char greeting[] = "hi";
+struct shape triangle = {"tri", {{0, 0}, [2] = {3, 4}}, .area = 6};
+int *first = primes;
+
+int main() {
+    int digits[4] = {1, 2};
+    int matrix[2][3] = {1, 2, 3, 4};
+    int braced[2][3] = {{1}, {4, 5}};
+    struct point p = {.y = 2, .x = 1};
+    struct point q = p;
+    struct shape square = {.corners[1].y = 8, .name = "sq", 16};
+    char word[4] = "abcd";
+    static long totals[3] = {[1] = 10};
+    double halves[] = {0.5, 1.5, 2.5};
+
+    printf("primes %d %d %d %d\n", primes[0], primes[2], primes[3], first[1]);
+    printf("sparse has %d elements, %d %d %d\n", (int)(sizeof(sparse) / sizeof(int)), sparse[0], sparse[3], sparse[4]);
+    printf("%s has %d bytes\n", greeting, (int)sizeof(greeting));
+    printf("%s corner (%d, %d) area %ld\n", triangle.name, triangle.corners[2].x, triangle.corners[2].y, triangle.area);
+    printf("digits %d %d %d %d\n", digits[0], digits[1], digits[2], digits[3]);
+    printf("matrix %d %d %d\n", matrix[1][0], matrix[1][1], matrix[1][2]);
+    printf("braced %d %d %d %d\n", braced[0][0], braced[0][1], braced[1][1], braced[1][2]);
+    printf("p (%d, %d) q (%d, %d)\n", p.x, p.y, q.x, q.y);
+    printf("%s corners (%d, %d) (%d, %d) area %ld\n", square.name, square.corners[0].x, square.corners[0].y, square.corners[1].x, square.corners[1].y, square.area);
+    printf("word %c%c%c%c\n", word[0], word[1], word[2], word[3]);
+    printf("totals %ld %ld %ld\n", totals[0], totals[1], totals[2]);
+    printf("%d halves, the last %f\n", (int)(sizeof(halves) / sizeof(double)), halves[2]);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// primes 2 5 0 3
+// sparse has 5 elements, 0 4 1
+// hi has 3 bytes
+// tri corner (3, 4) area 6
+// digits 1 2 0 0
+// matrix 4 0 0
+// braced 1 0 5 0
+// p (1, 2) q (1, 2)
+// sq corners (16, 0) (0, 8) area 0
+// word abcd
+// totals 0 10 0
+// 3 halves, the last 2.500000
+// === End Output ===
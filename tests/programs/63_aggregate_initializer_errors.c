@@ -0,0 +1,43 @@
+// === Source ===
+struct point {
+    int x;
+    int y;
+};
+
+int global[2] = {1, 2, 3};
+struct point origin = {.z = 0};
+char name[2] = "long";
+
+int main() {
+    int values[3] = 4;
+    int indexed[2] = {[2] = 1};
+    int negative[2] = {[-1] = 1};
+    struct point p = {1, 2, 3};
+    struct point q = {[0] = 1};
+    int plain = {.x = 1};
+    int size = 2;
+    int sized[size];
+    int typeless[sizeof(missing)];
+    struct point r = values;
+    int unsized[];
+    static struct point copy = p;
+    return values[0] + indexed[0] + negative[0] + p.x + q.x + plain + sized[0] + r.x + copy.x;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'main': 18:9: error: variable length arrays are not supported yet
+// in function 'main': 19:9: error: variable length arrays are not supported yet
+// in function 'main': 21:9: error: definition of variable with array type needs an explicit size or an initializer
+// 6:5: error: excess elements in array initializer
+// 7:14: error: field designator 'z' does not refer to any field in type 'struct point'
+// 8:6: error: initializer-string for char array is too long
+// in function 'main': 11:9: error: array initializer must be an initializer list
+// in function 'main': 12:9: error: array designator index (2) exceeds array bounds (2)
+// in function 'main': 13:9: error: array designator value is negative
+// in function 'main': 14:18: error: excess elements in struct initializer
+// in function 'main': 15:18: error: array designator used for non-array type 'struct point'
+// in function 'main': 16:9: error: designator in initializer for scalar type 'int'
+// in function 'main': 20:18: error: initializing 'struct point' with an expression of incompatible type 'int *'
+// in function 'main': 22:25: error: initializer element is not a compile-time constant
+// === End Diagnostics ===
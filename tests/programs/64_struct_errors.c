@@ -0,0 +1,50 @@
+// === Source ===
+struct point {
+    int x;
+    int x;
+    void nothing;
+};
+struct point {
+    int z;
+};
+struct later;
+struct later incomplete;
+struct pair { int a; } make(void);
+
+int main() {
+    struct point p;
+    struct point *pp = &p;
+    struct later *l = 0;
+    int number = 1;
+    p.z = 1;
+    pp.x = 2;
+    p->x = 3;
+    number.x = 4;
+    l->x = 5;
+    number[0] = 6;
+    p = 7;
+    if (p) {
+        return 1;
+    }
+    return p + 1;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 3:9: error: duplicate member 'x'
+// 2:9: note: previous declaration is here
+// 4:10: error: field 'nothing' has incomplete type 'void'
+// 6:1: error: redefinition of 'struct point'
+// 1:1: note: previous definition is here
+// 10:14: error: variable 'incomplete' has incomplete type 'struct later'
+// 11:24: error: struct return values are not supported yet
+// in function 'main': 18:5: error: no member named 'z' in 'struct point'
+// in function 'main': 19:5: error: member reference type 'struct point *' is a pointer; did you mean to use '->'?
+// in function 'main': 20:5: error: member reference type 'struct point' is not a pointer
+// in function 'main': 21:5: error: member reference base type 'int' is not a structure or union
+// in function 'main': 22:5: error: incomplete definition of type 'struct later'
+// in function 'main': 23:5: error: subscripted value is not an array or pointer
+// in function 'main': 24:5: error: incompatible types assigning to 'struct point' from 'int'
+// in function 'main': 25:5: error: statement requires expression of scalar type ('struct point' invalid)
+// in function 'main': 28:5: error: invalid operands to binary expression ('struct point' and 'int')
+// === End Diagnostics ===
@@ -0,0 +1,18 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int main() {
+    int *low = (int *)16;
+    int *high = (int *)-16;
+    printf("%d %d %d %d\n", high > low, high >= low, low < high, low <= high);
+    printf("%d %d\n", low > high, high < low);
+    printf("%ld %ld\n", low - high, high - low);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 1 1 1 1
+// 0 0
+// 8 -8
+// === End Output ===
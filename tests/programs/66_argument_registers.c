@@ -0,0 +1,23 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int weigh(int a, int b, int c, int d, long e) { return a + 2 * b + 3 * c + 4 * d + 5 * e; }
+double mix(double a, double b, double c, double d, float e) { return a + b * c - d / e; }
+long both(int a, double x, int b, double y, int c, double z, int d, double w, int e, float v) {
+    return a + b + c + d + e + (long)(x + y + z + w + v);
+}
+
+int main() {
+    int (*indirect)(int, int, int, int, long) = weigh;
+    printf("%d %d\n", weigh(1, 2, 3, 4, 5), indirect(5, 4, 3, 2, 1));
+    printf("%.2f\n", mix(1.0, 2.0, 3.0, 4.0, 8.0f));
+    printf("%ld\n", both(1, 0.5, 2, 1.5, 3, 2.5, 4, 3.5, 5, 4.0f));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 55 35
+// 6.50
+// 27
+// === End Output ===
@@ -0,0 +1,39 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int six(int a, int b, int c, int d, int e, int f) { return a + b + c + d + e + f; }
+long ten(int a, int b, int c, int d, int e, int f, int g, int h, char i, long j) {
+    return a + b + c + d + e + f + g + h + 100 * i + 1000 * j;
+}
+double floats(float a, float b, float c, float d, float e, float f, float g, float h, float i, double j) {
+    return a + b + c + d + e + f + g + h + 10 * i + 100 * j;
+}
+long mixed(int a, double x, int b, double y, int c, double z, int d, double w, int e, double v, int f,
+           double u, int g, double t, int h, double s, short i, float r, long j, double q) {
+    return a + b + c + d + e + f + g + h + i + j + (long)(x + y + z + w + v + u + t + s + r + q);
+}
+int unknown();
+int unknown(int a, int b, int c, int d, int e, int f, int g, int h, int i) {
+    return 10 * i - a - b - c - d - e - f - g - h;
+}
+
+int main() {
+    long (*indirect)(int, int, int, int, int, int, int, int, char, long) = ten;
+    printf("%d\n", six(1, 2, 3, 4, 5, 6));
+    printf("%ld %ld\n", ten(1, 1, 1, 1, 1, 1, 1, 1, 2, 3), indirect(0, 0, 0, 0, 0, 0, 0, 1, 1, 1));
+    printf("%.1f\n", floats(1, 1, 1, 1, 1, 1, 1, 1, 2.5f, 0.5));
+    printf("%ld\n", mixed(1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 0.5f, 3, 2.5));
+    printf("%d\n", unknown(1, 2, 3, 4, 5, 6, 7, 8, 9));
+    printf("%d %d %d %d %d %d %d %d %d %d\n", 1, 2, 3, 4, 5, 6, 7, 8, 9, 10);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 21
+// 3208 1101
+// 83.0
+// 24
+// 54
+// 1 2 3 4 5 6 7 8 9 10
+// === End Output ===
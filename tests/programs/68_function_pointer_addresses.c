@@ -0,0 +1,33 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int inc(int x) { return x + 1; }
+int dec(int x) { return x - 1; }
+int (*pick(int up))(int) {
+    if (up)
+        return inc;
+    return &dec;
+}
+
+int (*table[2])(int) = {inc, &dec};
+int (**first)(int) = table;
+
+int main() {
+    int (*fp)(int) = *inc;
+    int (**pp)(int) = &fp;
+    int (***ppp)(int) = &pp;
+    *pp = dec;
+    printf("%d %d %d\n", fp(1), (**ppp)(5), (*pp)(3));
+    int (*(*tp)[2])(int) = &table;
+    printf("%d %d %d\n", (*tp)[0](7), (**pp)(9), (*first)(1));
+    **ppp = pick(1);
+    printf("%d %d\n", fp(1), (&*pick(0))(1));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 0 4 2
+// 8 8 2
+// 2 0
+// === End Output ===
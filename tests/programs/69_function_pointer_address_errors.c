@@ -0,0 +1,19 @@
+// === Source ===
+int inc(int x) { return x + 1; }
+int (*pick(void))(int) { return inc; }
+
+int main() {
+    int (*fp)(int) = inc;
+    int (**pp)(int) = &fp;
+    int *ip = pp;
+    int (**wrong)(int) = fp;
+    &pick();
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'main': 7:10: warning: incompatible pointer types initializing 'int *' with an expression of type 'int (**)(int)'
+// in function 'main': 8:12: warning: incompatible pointer types initializing 'int (**)(int)' with an expression of type 'int (*)(int)'
+// in function 'main': 9:5: error: cannot take the address of an rvalue
+// === End Diagnostics ===
@@ -0,0 +1,35 @@
+// === Source ===
+extern int printf(const char *format, ...);
+struct point { int x; int y; };
+struct shape { char name[4]; struct point corners[3]; };
+int a[4] = {10, 20, 30, 40};
+int m[2][3] = {{1, 2, 3}, {4, 5, 6}};
+struct shape sq = {"sq", {{1, 2}, {3, 4}, {5, 6}}};
+struct point pt = {7, 8};
+int *second = &a[1];
+int *third = a + 2;
+int *last = 3 + a;
+int *before_last = &a[4] - 1;
+int *row = m[1];
+int *cell = &m[1][2];
+int *py = &pt.y;
+int *corner = &sq.corners[2].y;
+char *letter = sq.name + 1;
+int *through = &(&sq)->corners[1].x;
+char *text = "hello" + 1;
+long as_integer = (long)&a[2];
+struct fixed { int *p; int *q; } both = {&a[3], a + 1};
+int main() {
+    static int *local = &a[2];
+    printf("%d %d %d %d %d %d\n", *second, *third, *last, *before_last, *row, *cell);
+    printf("%d %d %c %d %s\n", *py, *corner, *letter, *through, text);
+    printf("%d %d %d %d\n", *(int *)as_integer, *both.p, *both.q, *local);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 20 30 40 40 4 6
+// 8 6 q 3 ello
+// 30 40 20 30
+// === End Output ===
@@ -0,0 +1,27 @@
+// === Source ===
+int a[4];
+int *p = a;
+int i = 1;
+int *q = &a[i];
+int *r = &p[1];
+int *s = a * 2;
+int *t = 2 - a;
+int *u = a + 1.5;
+long d = &a[2] - &a[0];
+
+int main() {
+    int local[2];
+    static int *v = &local[1];
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// 4:6: error: initializer element is not a compile-time constant
+// 5:6: error: initializer element is not a compile-time constant
+// 6:6: error: invalid operands to binary expression ('int *' and 'int')
+// 7:6: error: invalid operands to binary expression ('int' and 'int *')
+// 8:6: error: invalid operands to binary expression ('int *' and 'double')
+// 9:6: error: initializer element is not a compile-time constant
+// in function 'main': 13:17: error: initializer element is not a compile-time constant
+// === End Diagnostics ===
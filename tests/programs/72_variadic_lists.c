@@ -0,0 +1,80 @@
+// === Source ===
+#include <stdarg.h>
+int printf(const char *, ...);
+
+va_list saved;
+
+int vsum(int count, va_list args) {
+    int total = 0;
+    while (count > 0) {
+        total += va_arg(args, int);
+        count -= 1;
+    }
+    return total;
+}
+
+int sum_twice(int count, ...) {
+    va_list args;
+    va_list again;
+    va_start(args, count);
+    va_copy(again, args);
+    int first = vsum(count, args);
+    int second = vsum(count, again);
+    va_end(again);
+    va_end(args);
+    return first * 100 + second;
+}
+
+double skip_first(int count, ...) {
+    va_list args;
+    va_start(args, count);
+    va_arg(args, double);
+    double rest = 0;
+    while (count > 1) {
+        rest += va_arg(args, double);
+        count -= 1;
+    }
+    va_end(args);
+    return rest;
+}
+
+int in_global(int count, ...) {
+    va_start(saved, count);
+    int last = 0;
+    while (count > 0) {
+        last = va_arg(saved, int);
+        count -= 1;
+    }
+    va_end(saved);
+    return last;
+}
+
+int mixed(int count, ...) {
+    va_list args;
+    va_start(args, count);
+    int total = 0;
+    while (count > 0) {
+        int whole = va_arg(args, int);
+        double part = va_arg(args, double);
+        total += whole * 10 + (int)(part * 10);
+        count -= 1;
+    }
+    va_end(args);
+    return total;
+}
+
+int main() {
+    printf("%d\n", sum_twice(3, 1, 2, 3));
+    printf("%.2f\n", skip_first(3, 100.0, 0.25, 0.5));
+    printf("%d\n", mixed(2, 1, 0.5, 2, 0.25));
+    printf("%d\n", in_global(4, 7, 8, 9, 10));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 606
+// 0.75
+// 37
+// 10
+// === End Output ===
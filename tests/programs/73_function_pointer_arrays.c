@@ -0,0 +1,24 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int inc(int x) { return x + 1; }
+int dec(int x) { return x - 1; }
+
+int first() {
+    int (*fps[2])(int);
+    fps[0] = inc;
+    return fps[0](1);
+}
+
+int main() {
+    int (*fps[2])(int);
+    fps[0] = inc;
+    fps[1] = dec;
+    printf("%d %d %d\n", first(), fps[0](5), fps[1](5));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 2 6 4
+// === End Output ===
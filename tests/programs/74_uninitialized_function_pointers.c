@@ -0,0 +1,38 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+int inc(int x) { return x + 1; }
+int dec(int x) { return x - 1; }
+
+int through_pointer() {
+    int (*fp)(int);
+    int (**pp)(int) = &fp;
+    *pp = inc;
+    return fp(1);
+}
+
+int remembered(int up) {
+    static int (*last)(int);
+    int (*previous)(int) = last;
+    if (up)
+        last = inc;
+    else
+        last = dec;
+    if (previous == 0)
+        return 0;
+    return previous(10);
+}
+
+int main() {
+    int (*fp)(int);
+    fp = dec;
+    printf("%d %d\n", through_pointer(), fp(1));
+    printf("%d %d %d\n", remembered(1), remembered(0), remembered(1));
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// 2 0
+// 0 11 9
+// === End Output ===
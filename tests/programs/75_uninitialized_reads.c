@@ -0,0 +1,39 @@
+// === Source ===
+int printf(const char *, ...);
+int first(void) {
+    int x;
+    int y = x;
+    x = 4;
+    return y;
+}
+int overwritten(void) {
+    int x = 1;
+    x = 2;
+    x = 3;
+    return x;
+}
+int twice(int c) {
+    int x;
+    if (c) {
+        x = 1;
+    }
+    return x + x;
+}
+int main() {
+    printf("%d %d %d\n", first(), overwritten(), twice(1));
+    return 0;
+}
+// === End Source ===
+
+// === Diagnostics ===
+// in function 'first': 4:13: warning: variable 'x' is used uninitialized
+// in function 'first': 5:5: warning: value assigned to 'x' is never read
+// in function 'overwritten': 9:5: warning: value assigned to 'x' is never read
+// in function 'overwritten': 10:5: warning: value assigned to 'x' is never read
+// in function 'twice': 19:12: warning: variable 'x' may be used uninitialized
+// in function 'twice': 19:16: warning: variable 'x' may be used uninitialized
+// === End Diagnostics ===
+
+// === Output ===
+// 0 3 2
+// === End Output ===
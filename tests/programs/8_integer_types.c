@@ -0,0 +1,49 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+unsigned int halve(unsigned int value) {
+    return value / 2;
+}
+
+long quarter(long value) {
+    return value >> 2;
+}
+
+unsigned long high_nibble(unsigned long value) {
+    return value >> 4;
+}
+
+int is_big(unsigned int value) {
+    return value > 3000000000;
+}
+
+int at_most(int value, int limit) {
+    return value <= limit;
+}
+
+short bump(short value) {
+    return value + 1;
+}
+
+int main() {
+    printf("half is %u\n", halve(4000000000));
+    printf("quarter is %ld\n", quarter(5000000000));
+    printf("nibble is %lu\n", high_nibble(240));
+    printf("big is %d\n", is_big(4000000000));
+    printf("at most is %d\n", at_most(300, 300));
+    printf("bumped is %d\n", bump(299));
+    long long ll = 123456789012;
+    printf("ll is %lld\n", ll);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// half is 2000000000
+// quarter is 1250000000
+// nibble is 15
+// big is 1
+// at most is 1
+// bumped is 300
+// ll is 123456789012
+// === End Output ===
\ No newline at end of file
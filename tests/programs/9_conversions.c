@@ -0,0 +1,42 @@
+// === Source ===
+extern int printf(const char *format, ...);
+
+long widen(int value) {
+    long wide = value;
+    return wide * 100000;
+}
+
+long product(int a, int b) {
+    return (long)a * b;
+}
+
+int sum_mixed(char a, short b, int c) {
+    return a + b + c;
+}
+
+int below(int a, unsigned int b) {
+    return a < b;
+}
+
+int main() {
+    char truncated = (char)300;
+    printf("truncated is %d\n", truncated);
+    printf("widened is %ld\n", widen(70000));
+    printf("product is %ld\n", product(100000, 100000));
+    printf("sum is %d\n", sum_mixed(100, 30000, 5));
+    int minus_one = 0;
+    minus_one = minus_one - 1;
+    printf("below is %d\n", below(minus_one, 1));
+    printf("shifted is %ld\n", (long)1 << 40);
+    return 0;
+}
+// === End Source ===
+
+// === Output ===
+// truncated is 44
+// widened is 7000000000
+// product is 10000000000
+// sum is 30105
+// below is 0
+// shifted is 1099511627776
+// === End Output ===